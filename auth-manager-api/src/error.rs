@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::password_policy::PasswordViolation;
+
 /// Public API error response format
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ErrorResponse {
@@ -7,4 +9,7 @@ pub struct ErrorResponse {
     pub message: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub details: Option<String>,
+    /// Failed password rules, for `WEAK_PASSWORD` errors
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub violations: Option<Vec<PasswordViolation>>,
 }
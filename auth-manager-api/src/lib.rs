@@ -10,6 +10,7 @@
 //! - Response DTOs (UserResponse, LoginResponse, etc.)
 //! - Error response format (ErrorResponse)
 //! - Generic response wrapper (AppResponse)
+//! - Password policy and strength estimation (`PasswordPolicy`)
 //!
 //! ## Example
 //!
@@ -23,12 +24,14 @@
 //! ```
 
 pub mod error;
+pub mod password_policy;
 pub mod requests;
 pub mod responses;
 pub mod result;
 
 // Re-exports for convenient access
 pub use error::ErrorResponse;
+pub use password_policy::{CharacterClass, PasswordPolicy, PasswordViolation};
 pub use requests::*;
 pub use responses::*;
 pub use result::{AppResponse, StatusCode};
@@ -0,0 +1,463 @@
+//! Password policy shared by the server and the frontend.
+//!
+//! The server serves its active [`PasswordPolicy`] on `GET /auth/password-policy`;
+//! a client can deserialize it and call [`PasswordPolicy::check`] on every keystroke
+//! to show the same hints the server will enforce.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Shortest email/username fragment considered when looking for personal info
+const MIN_USER_INPUT_LEN: usize = 3;
+
+/// Character classes a policy can require.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CharacterClass {
+    Uppercase,
+    Lowercase,
+    Digit,
+    /// Anything that is not a letter or a digit
+    Symbol,
+}
+
+impl CharacterClass {
+    fn matches(self, c: char) -> bool {
+        match self {
+            Self::Uppercase => c.is_uppercase(),
+            Self::Lowercase => c.is_lowercase(),
+            Self::Digit => c.is_ascii_digit(),
+            Self::Symbol => !c.is_alphanumeric(),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Uppercase => "an uppercase letter",
+            Self::Lowercase => "a lowercase letter",
+            Self::Digit => "a digit",
+            Self::Symbol => "a symbol",
+        }
+    }
+}
+
+/// Rules a new password must satisfy.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct PasswordPolicy {
+    /// Minimum length, in characters
+    pub min_length: usize,
+    /// Maximum length, in characters
+    pub max_length: usize,
+    pub required_classes: Vec<CharacterClass>,
+    /// Minimum [`strength_score`] (0 to 4)
+    pub min_strength_score: u8,
+    /// Reject passwords containing the email or username
+    pub forbid_user_info: bool,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: 8,
+            max_length: 128,
+            required_classes: vec![
+                CharacterClass::Uppercase,
+                CharacterClass::Lowercase,
+                CharacterClass::Digit,
+            ],
+            min_strength_score: 2,
+            forbid_user_info: true,
+        }
+    }
+}
+
+/// A rule the password failed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum PasswordViolation {
+    TooShort {
+        min_length: usize,
+    },
+    TooLong {
+        max_length: usize,
+    },
+    MissingCharacterClass {
+        class: CharacterClass,
+    },
+    TooWeak {
+        score: u8,
+        min_score: u8,
+    },
+    ContainsUserInfo,
+    /// Found in a breached-password corpus; only reported by the server
+    Breached {
+        count: u64,
+    },
+    /// Same as the current or a recent password; only reported by the server
+    RecentlyUsed,
+}
+
+impl fmt::Display for PasswordViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooShort { min_length } => {
+                write!(f, "Password must be at least {min_length} characters")
+            }
+            Self::TooLong { max_length } => {
+                write!(f, "Password must be at most {max_length} characters")
+            }
+            Self::MissingCharacterClass { class } => {
+                write!(f, "Password must contain {}", class.name())
+            }
+            Self::TooWeak { .. } => write!(f, "Password is too easy to guess"),
+            Self::ContainsUserInfo => {
+                write!(f, "Password must not contain your email or username")
+            }
+            Self::Breached { count } => write!(
+                f,
+                "This password has appeared in a data breach ({count} times) and must not be used"
+            ),
+            Self::RecentlyUsed => write!(f, "Password must differ from your recent passwords"),
+        }
+    }
+}
+
+impl PasswordPolicy {
+    /// Returns every rule `password` breaks, in policy order; empty if it is accepted.
+    ///
+    /// `user_inputs` are the account's email and username: they are forbidden as
+    /// substrings when [`forbid_user_info`](Self::forbid_user_info) is set, and make
+    /// the password weaker in any case.
+    #[must_use]
+    pub fn check(&self, password: &str, user_inputs: &[&str]) -> Vec<PasswordViolation> {
+        let mut violations = Vec::new();
+
+        let length = password.chars().count();
+        if length < self.min_length {
+            violations.push(PasswordViolation::TooShort {
+                min_length: self.min_length,
+            });
+        }
+        if length > self.max_length {
+            violations.push(PasswordViolation::TooLong {
+                max_length: self.max_length,
+            });
+        }
+        for class in &self.required_classes {
+            if !password.chars().any(|c| class.matches(c)) {
+                violations.push(PasswordViolation::MissingCharacterClass { class: *class });
+            }
+        }
+        if self.forbid_user_info && contains_user_info(password, user_inputs) {
+            violations.push(PasswordViolation::ContainsUserInfo);
+        }
+        let score = strength_score(password, user_inputs);
+        if score < self.min_strength_score {
+            violations.push(PasswordViolation::TooWeak {
+                score,
+                min_score: self.min_strength_score,
+            });
+        }
+        violations
+    }
+}
+
+/// Lowercased fragments of the user inputs: each input, plus the local part of emails.
+fn user_fragments(user_inputs: &[&str]) -> Vec<String> {
+    let mut fragments = Vec::new();
+    for input in user_inputs {
+        let input = input.trim().to_lowercase();
+        if let Some((local, _)) = input.split_once('@') {
+            fragments.push(local.to_string());
+        }
+        fragments.push(input);
+    }
+    fragments.retain(|f| f.chars().count() >= MIN_USER_INPUT_LEN);
+    fragments
+}
+
+fn contains_user_info(password: &str, user_inputs: &[&str]) -> bool {
+    let password = password.to_lowercase();
+    user_fragments(user_inputs)
+        .iter()
+        .any(|fragment| password.contains(fragment.as_str()))
+}
+
+/// Common passwords and words, most frequent first (the rank sets the guess count).
+const COMMON_WORDS: &[&str] = &[
+    "password", "123456", "qwerty", "azerty", "letmein", "welcome", "admin", "dragon", "monkey",
+    "football", "baseball", "iloveyou", "master", "sunshine", "princess", "shadow", "superman",
+    "trustno1", "login", "abc123", "starwars", "secret", "hello", "freedom", "whatever", "soleil",
+    "bonjour", "chocolat", "summer", "winter", "spring", "autumn", "qwertz", "asdf", "zxcv",
+    "passwd", "pass", "love", "test", "user",
+];
+
+/// Estimates how hard `password` is to guess, from 0 (trivial) to 4 (strong).
+///
+/// Follows the zxcvbn approach on a small scale: the password is split into
+/// recognisable patterns (common words, possibly in leetspeak, personal info,
+/// repeats, sequences, years) and brute-forced characters, and the summed
+/// guess estimate (in bits) is bucketed like zxcvbn's score.
+#[must_use]
+pub fn strength_score(password: &str, user_inputs: &[&str]) -> u8 {
+    match guesses_log2(password, user_inputs) {
+        bits if bits < 10.0 => 0, // < 10^3 guesses
+        bits if bits < 20.0 => 1, // < 10^6
+        bits if bits < 26.6 => 2, // < 10^8
+        bits if bits < 33.2 => 3, // < 10^10
+        _ => 4,
+    }
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn guesses_log2(password: &str, user_inputs: &[&str]) -> f64 {
+    let chars: Vec<char> = password.chars().collect();
+    let lower: Vec<char> = chars.iter().map(|c| to_lower(*c)).collect();
+    let unleet: Vec<char> = lower.iter().map(|c| unleet(*c)).collect();
+    let fragments: Vec<Vec<char>> = user_fragments(user_inputs)
+        .iter()
+        .map(|f| f.chars().collect())
+        .collect();
+
+    let mut bits = 0.0;
+    // Nombre de segments : motifs reconnus et suites de caractères quelconques
+    let mut segments = 0u32;
+    let mut in_bruteforce = false;
+    let mut i = 0;
+    while i < chars.len() {
+        if let Some((len, pattern_bits)) =
+            longest_pattern(&chars[i..], &lower[i..], &unleet[i..], &fragments)
+        {
+            bits += pattern_bits;
+            segments += 1;
+            in_bruteforce = false;
+            i += len;
+        } else {
+            bits += cardinality(chars[i]).log2();
+            if !in_bruteforce {
+                segments += 1;
+                in_bruteforce = true;
+            }
+            i += 1;
+        }
+    }
+    // Comme zxcvbn, l'attaquant doit aussi deviner l'ordre des segments (n!)
+    bits + (2..=segments).map(|n| f64::from(n).log2()).sum::<f64>()
+}
+
+/// Longest known pattern at the start of the slices, as `(length, bits)`.
+#[allow(clippy::cast_precision_loss)]
+fn longest_pattern(
+    chars: &[char],
+    lower: &[char],
+    unleet: &[char],
+    fragments: &[Vec<char>],
+) -> Option<(usize, f64)> {
+    let case_bits = |len: usize| {
+        if chars[..len].iter().any(|c| c.is_uppercase()) {
+            1.0
+        } else {
+            0.0
+        }
+    };
+    let mut candidates = Vec::new();
+
+    for fragment in fragments {
+        if lower.starts_with(fragment) {
+            candidates.push((fragment.len(), 1.0 + case_bits(fragment.len())));
+        }
+    }
+    for (rank, word) in COMMON_WORDS.iter().enumerate() {
+        let word: Vec<char> = word.chars().collect();
+        let leet_bits = if lower.starts_with(&word) {
+            0.0
+        } else if unleet.starts_with(&word) {
+            1.0
+        } else {
+            continue;
+        };
+        let len = word.len();
+        candidates.push((len, ((rank + 1) as f64).log2() + case_bits(len) + leet_bits));
+    }
+
+    // Même caractère répété : "aaaa", "1111"
+    let repeat = lower.iter().take_while(|c| **c == lower[0]).count();
+    if repeat >= 3 {
+        candidates.push((
+            repeat,
+            cardinality(chars[0]).log2() + (repeat as f64).log2(),
+        ));
+    }
+
+    // Suite ascendante ou descendante : "abcd", "4321"
+    if lower.len() >= 3 {
+        let step = i64::from(u32::from(lower[1])) - i64::from(u32::from(lower[0]));
+        if step.abs() == 1 {
+            let run = 1 + lower
+                .windows(2)
+                .take_while(|w| i64::from(u32::from(w[1])) - i64::from(u32::from(w[0])) == step)
+                .count();
+            if run >= 3 {
+                candidates.push((
+                    run,
+                    cardinality(chars[0]).log2() + (run as f64).log2() + 1.0,
+                ));
+            }
+        }
+    }
+
+    // Année récente : 1900-2099
+    if lower.len() >= 4 {
+        let year: String = lower[..4].iter().collect();
+        if year.parse::<u32>().is_ok_and(|y| (1900..2100).contains(&y)) {
+            candidates.push((4, 200f64.log2()));
+        }
+    }
+
+    candidates
+        .into_iter()
+        .max_by(|a, b| a.0.cmp(&b.0).then(b.1.total_cmp(&a.1)))
+}
+
+/// Size of the alphabet an attacker brute-forcing `c` has to try.
+fn cardinality(c: char) -> f64 {
+    if c.is_ascii_digit() {
+        10.0
+    } else if c.is_ascii_lowercase() || c.is_ascii_uppercase() {
+        26.0
+    } else if c.is_ascii() {
+        33.0
+    } else {
+        100.0
+    }
+}
+
+fn to_lower(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+fn unleet(c: char) -> char {
+    match c {
+        '@' | '4' => 'a',
+        '3' => 'e',
+        '1' | '!' => 'i',
+        '0' => 'o',
+        '$' | '5' => 's',
+        '7' => 't',
+        _ => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_policy_reports_every_failed_rule() {
+        let violations = PasswordPolicy::default().check("abc", &[]);
+
+        assert!(violations.contains(&PasswordViolation::TooShort { min_length: 8 }));
+        assert!(
+            violations.contains(&PasswordViolation::MissingCharacterClass {
+                class: CharacterClass::Uppercase
+            })
+        );
+        assert!(
+            violations.contains(&PasswordViolation::MissingCharacterClass {
+                class: CharacterClass::Digit
+            })
+        );
+        assert!(matches!(
+            violations.last(),
+            Some(PasswordViolation::TooWeak {
+                score: 0,
+                min_score: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn strong_password_passes_default_policy() {
+        assert!(
+            PasswordPolicy::default()
+                .check("Correct123!", &[])
+                .is_empty()
+        );
+        assert!(
+            PasswordPolicy::default()
+                .check("OldPass123!", &[])
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn common_patterns_score_low() {
+        assert!(strength_score("Password123", &[]) <= 1);
+        assert_eq!(strength_score("P@ssw0rd", &[]), 0);
+        assert!(strength_score("aaaaaaaaaaaa", &[]) <= 1);
+        assert!(strength_score("abcdefgh1990", &[]) <= 1);
+        assert_eq!(strength_score("Tr0ub4dour&3-horse", &[]), 4);
+    }
+
+    #[test]
+    fn email_and_username_are_rejected_as_substrings() {
+        let policy = PasswordPolicy::default();
+        let inputs = ["Jean.Dupont@example.com", "jdupont"];
+
+        let violations = policy.check("Jean.Dupont2024!", &inputs);
+        assert!(violations.contains(&PasswordViolation::ContainsUserInfo));
+        assert!(
+            policy
+                .check("XJDupontX99!z", &inputs)
+                .contains(&PasswordViolation::ContainsUserInfo)
+        );
+
+        let lenient = PasswordPolicy {
+            forbid_user_info: false,
+            min_strength_score: 0,
+            ..PasswordPolicy::default()
+        };
+        assert!(lenient.check("Jean.Dupont2024!", &inputs).is_empty());
+    }
+
+    #[test]
+    fn length_is_counted_in_characters_and_capped() {
+        let policy = PasswordPolicy {
+            min_length: 4,
+            max_length: 10,
+            required_classes: vec![],
+            min_strength_score: 0,
+            forbid_user_info: false,
+        };
+        assert!(policy.check("éàüö", &[]).is_empty());
+        assert_eq!(
+            policy.check("abcdefghijk", &[]),
+            vec![PasswordViolation::TooLong { max_length: 10 }]
+        );
+    }
+
+    #[test]
+    fn policy_and_violations_round_trip_as_json() {
+        let json = serde_json::to_value(PasswordPolicy::default()).unwrap();
+        assert_eq!(json["required_classes"][0], "uppercase");
+        assert_eq!(
+            serde_json::from_value::<PasswordPolicy>(serde_json::json!({ "min_length": 12 }))
+                .unwrap(),
+            PasswordPolicy {
+                min_length: 12,
+                ..PasswordPolicy::default()
+            }
+        );
+
+        let violation = serde_json::to_value(PasswordViolation::MissingCharacterClass {
+            class: CharacterClass::Symbol,
+        })
+        .unwrap();
+        assert_eq!(
+            violation,
+            serde_json::json!({ "rule": "missing_character_class", "class": "symbol" })
+        );
+    }
+}
@@ -1,4 +1,6 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 // -------- REQUEST DTOs --------
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -24,3 +26,65 @@ pub struct ChangePasswordRequest {
     pub old_password: String,
     pub new_password: String,
 }
+
+/// Confirms a self-service account deletion request
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DeleteAccountRequest {
+    pub password: String, // Plain text
+}
+
+/// Query string for paginated login history (`?cursor=...&limit=...`)
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct LoginHistoryQuery {
+    /// Opaque cursor taken from the previous page's `next_cursor`
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// Admin lookup of login attempts by attempted email (`?email_hash=...` or `?email=...`)
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct LoginAttemptsByEmailQuery {
+    /// Hex SHA-256 of the lowercased, normalised email
+    pub email_hash: Option<String>,
+    /// Plain email, hashed server-side
+    pub email: Option<String>,
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// Body of `POST /auth/unlock`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UnlockAccountRequest {
+    /// Token from the lock notification email
+    pub token: String,
+}
+
+/// "This wasn't me" link from a suspicious-login email
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RevokeSessionsRequest {
+    /// Token from the suspicious-login email
+    pub token: String,
+}
+
+/// Body of `POST /auth/confirm-deletion`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ConfirmAccountDeletionRequest {
+    /// Token from the deletion confirmation email
+    pub token: String,
+}
+
+/// Admin audit log filters (`GET /admin/audit-events` and its JSON Lines export)
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct AuditEventQuery {
+    /// One of `AuditEventType::as_str`
+    pub event_type: Option<String>,
+    pub actor_id: Option<Uuid>,
+    pub target_id: Option<Uuid>,
+    /// Inclusive lower bound on `occurred_at`
+    pub from: Option<DateTime<Utc>>,
+    /// Exclusive upper bound on `occurred_at`
+    pub to: Option<DateTime<Utc>>,
+    /// Opaque cursor taken from the previous page's `next_cursor`
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
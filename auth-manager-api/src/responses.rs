@@ -11,6 +11,12 @@ pub struct UserResponse {
     pub is_active: bool,
     pub is_admin: bool,
     pub created_at: DateTime<Utc>,
+    /// Set while an account deletion is pending; logging in cancels it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deletion_scheduled_at: Option<DateTime<Utc>>,
+    /// Last password change; absent for accounts without a password
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password_changed_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -19,6 +25,9 @@ pub struct LoginResponse {
     pub refresh_token: String,
     pub user: UserResponse,
     pub expires_in: i64,
+    /// The password has expired: the access token only allows changing it
+    #[serde(default)]
+    pub password_change_required: bool,
 }
 
 /// Public login response (does not include refresh token)
@@ -27,6 +36,8 @@ pub struct PublicLoginResponse {
     pub access_token: String,
     pub user: UserResponse,
     pub expires_in: i64,
+    #[serde(default)]
+    pub password_change_required: bool,
 }
 
 impl From<LoginResponse> for PublicLoginResponse {
@@ -35,6 +46,7 @@ impl From<LoginResponse> for PublicLoginResponse {
             access_token: src.access_token,
             user: src.user,
             expires_in: src.expires_in,
+            password_change_required: src.password_change_required,
         }
     }
 }
@@ -43,4 +55,256 @@ impl From<LoginResponse> for PublicLoginResponse {
 pub struct RefreshTokenResponse {
     pub access_token: String,
     pub expires_in: i64,
+    /// See [`LoginResponse::password_change_required`]
+    #[serde(default)]
+    pub password_change_required: bool,
+}
+
+/// Returned when an account deletion has been scheduled
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AccountDeletionResponse {
+    pub deletion_scheduled_at: DateTime<Utc>,
+}
+
+// -------- DATA EXPORT (GDPR) --------
+
+/// Complete user record as stored, minus credentials
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UserExport {
+    pub id: Uuid,
+    pub email: String,
+    pub username: String,
+    pub email_verified: bool,
+    pub is_active: bool,
+    pub is_admin: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub last_login_at: Option<DateTime<Utc>>,
+    pub deletion_scheduled_at: Option<DateTime<Utc>>,
+}
+
+/// External identity (Google, GitHub, ...) linked to the account
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct IdentityExport {
+    pub provider: String,
+    pub provider_user_id: String,
+    pub email: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+/// Refresh-token session (the token itself is never exported)
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SessionExport {
+    pub id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    /// Opened by a login from a new device or otherwise suspicious
+    #[serde(default)]
+    pub risk_flagged: bool,
+}
+
+/// Login attempt recorded for the account
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LoginAttemptExport {
+    pub attempted_at: DateTime<Utc>,
+    pub success: bool,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure_reason: Option<LoginFailureReason>,
+}
+
+/// JSON archive returned by `GET /users/me/export`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AccountExport {
+    pub exported_at: DateTime<Utc>,
+    pub user: UserExport,
+    pub identities: Vec<IdentityExport>,
+    pub sessions: Vec<SessionExport>,
+    pub login_history: Vec<LoginAttemptExport>,
+}
+
+// -------- LOGIN HISTORY --------
+
+/// Coarse device category derived from the User-Agent
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DeviceType {
+    Desktop,
+    Mobile,
+    Tablet,
+    Bot,
+    Unknown,
+}
+
+/// Browser and OS families parsed from the User-Agent
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DeviceInfo {
+    pub browser: Option<String>,
+    pub os: Option<String>,
+    pub device_type: DeviceType,
+}
+
+/// Why a login attempt failed
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LoginFailureReason {
+    UnknownEmail,
+    InvalidPassword,
+    AccountLocked,
+    PasswordNotSet,
+    RateLimited,
+}
+
+impl LoginFailureReason {
+    pub const ALL: &[Self] = &[
+        Self::UnknownEmail,
+        Self::InvalidPassword,
+        Self::AccountLocked,
+        Self::PasswordNotSet,
+        Self::RateLimited,
+    ];
+
+    /// Stable identifier, identical to the serde representation
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::UnknownEmail => "unknown_email",
+            Self::InvalidPassword => "invalid_password",
+            Self::AccountLocked => "account_locked",
+            Self::PasswordNotSet => "password_not_set",
+            Self::RateLimited => "rate_limited",
+        }
+    }
+
+    #[must_use]
+    pub fn from_str_opt(value: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|r| r.as_str() == value)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LoginHistoryEntry {
+    pub id: Uuid,
+    pub attempted_at: DateTime<Utc>,
+    pub success: bool,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub device: DeviceInfo,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure_reason: Option<LoginFailureReason>,
+}
+
+/// One page of login history, newest first
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LoginHistoryPage {
+    pub items: Vec<LoginHistoryEntry>,
+    /// Pass as `cursor` to fetch the next page; absent on the last page
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+/// Kind of security event recorded in the audit log
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditEventType {
+    UserRegistered,
+    LoginSucceeded,
+    LoginFailed,
+    Logout,
+    PasswordChanged,
+    /// Password set by an administrator (`auth-manager reset-password`)
+    PasswordReset,
+    AccountLocked,
+    AccountUnlocked,
+    AccountDeletionRequested,
+    AccountDeletionCancelled,
+    AccountPurged,
+    SuspiciousLogin,
+    SessionsRevoked,
+    AdminLoginHistoryViewed,
+    AdminLoginAttemptsSearched,
+    AdminAuditLogViewed,
+    AdminAuditLogExported,
+    /// Administrator account created from the command line
+    AdminCreated,
+}
+
+impl AuditEventType {
+    pub const ALL: &[Self] = &[
+        Self::UserRegistered,
+        Self::LoginSucceeded,
+        Self::LoginFailed,
+        Self::Logout,
+        Self::PasswordChanged,
+        Self::PasswordReset,
+        Self::AccountLocked,
+        Self::AccountUnlocked,
+        Self::AccountDeletionRequested,
+        Self::AccountDeletionCancelled,
+        Self::AccountPurged,
+        Self::SuspiciousLogin,
+        Self::SessionsRevoked,
+        Self::AdminLoginHistoryViewed,
+        Self::AdminLoginAttemptsSearched,
+        Self::AdminAuditLogViewed,
+        Self::AdminAuditLogExported,
+        Self::AdminCreated,
+    ];
+
+    /// Stable identifier, identical to the serde representation
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::UserRegistered => "user_registered",
+            Self::LoginSucceeded => "login_succeeded",
+            Self::LoginFailed => "login_failed",
+            Self::Logout => "logout",
+            Self::PasswordChanged => "password_changed",
+            Self::PasswordReset => "password_reset",
+            Self::AccountLocked => "account_locked",
+            Self::AccountUnlocked => "account_unlocked",
+            Self::AccountDeletionRequested => "account_deletion_requested",
+            Self::AccountDeletionCancelled => "account_deletion_cancelled",
+            Self::AccountPurged => "account_purged",
+            Self::SuspiciousLogin => "suspicious_login",
+            Self::SessionsRevoked => "sessions_revoked",
+            Self::AdminLoginHistoryViewed => "admin_login_history_viewed",
+            Self::AdminLoginAttemptsSearched => "admin_login_attempts_searched",
+            Self::AdminAuditLogViewed => "admin_audit_log_viewed",
+            Self::AdminAuditLogExported => "admin_audit_log_exported",
+            Self::AdminCreated => "admin_created",
+        }
+    }
+
+    #[must_use]
+    pub fn from_str_opt(value: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|t| t.as_str() == value)
+    }
+}
+
+/// One audit log entry. Also the line format of the JSON Lines export.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AuditEventEntry {
+    pub id: Uuid,
+    pub occurred_at: DateTime<Utc>,
+    /// [`AuditEventType::as_str`]; kept as a string so newer event types still parse
+    pub event_type: String,
+    /// Who acted (`None` for the system, e.g. scheduled purges)
+    pub actor_id: Option<Uuid>,
+    /// Account the event is about
+    pub target_id: Option<Uuid>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub request_id: Option<String>,
+    pub metadata: serde_json::Value,
+}
+
+/// One page of audit events, newest first
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AuditEventPage {
+    pub items: Vec<AuditEventEntry>,
+    /// Pass as `cursor` to fetch the next page; absent on the last page
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
 }
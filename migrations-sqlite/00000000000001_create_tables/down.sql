@@ -0,0 +1,9 @@
+DROP TABLE IF EXISTS known_devices;
+DROP TABLE IF EXISTS audit_events;
+DROP TABLE IF EXISTS password_history;
+DROP TABLE IF EXISTS user_tokens;
+DROP TABLE IF EXISTS rate_limit_buckets;
+DROP TABLE IF EXISTS user_identities;
+DROP TABLE IF EXISTS login_attempts;
+DROP TABLE IF EXISTS refresh_tokens;
+DROP TABLE IF EXISTS users;
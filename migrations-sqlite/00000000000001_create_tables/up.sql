@@ -0,0 +1,168 @@
+-- Schéma SQLite équivalent aux migrations PostgreSQL (`migrations/`)
+--
+-- UUID : texte (forme avec tirets), générés par l'application.
+-- Horodatages : texte UTC "YYYY-MM-DD HH:MM:SS.fff+00:00", comparables comme des chaînes.
+-- Booléens : 0/1. Les clés étrangères exigent `PRAGMA foreign_keys = ON` (fait par le pool).
+
+CREATE TABLE users (
+    id TEXT PRIMARY KEY NOT NULL,
+    email TEXT NOT NULL UNIQUE,
+    username TEXT NOT NULL UNIQUE,
+    password_hash TEXT,
+    email_verified BOOLEAN NOT NULL DEFAULT 0,
+    is_active BOOLEAN NOT NULL DEFAULT 1,
+    is_admin BOOLEAN NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f+00:00', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f+00:00', 'now')),
+    last_login_at TEXT,
+    username_canonical TEXT NOT NULL,
+    deletion_scheduled_at TEXT,
+    failed_login_count INTEGER NOT NULL DEFAULT 0,
+    lockout_level INTEGER NOT NULL DEFAULT 0,
+    locked_until TEXT,
+    locked_permanently BOOLEAN NOT NULL DEFAULT 0,
+    last_failed_login_at TEXT,
+    password_pepper_version INTEGER,
+    password_changed_at TEXT,
+    -- Email en minuscules (Unicode) calculé par l'application : lower() de SQLite
+    -- ne connaît que l'ASCII
+    email_lower TEXT NOT NULL
+);
+
+CREATE UNIQUE INDEX users_username_canonical_key ON users (username_canonical);
+CREATE UNIQUE INDEX users_email_lower_key ON users (email_lower);
+CREATE INDEX users_deletion_scheduled_at_idx ON users (deletion_scheduled_at)
+    WHERE deletion_scheduled_at IS NOT NULL;
+
+CREATE TABLE refresh_tokens (
+    id TEXT PRIMARY KEY NOT NULL,
+    user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    token_hash TEXT NOT NULL UNIQUE,
+    expires_at TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f+00:00', 'now')),
+    updated_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f+00:00', 'now')),
+    risk_flagged BOOLEAN NOT NULL DEFAULT 0
+);
+
+CREATE INDEX refresh_tokens_user_id_idx ON refresh_tokens (user_id);
+
+-- Historique anonymisé plutôt que supprimé avec le compte
+CREATE TABLE login_attempts (
+    id TEXT PRIMARY KEY NOT NULL,
+    user_id TEXT REFERENCES users(id) ON DELETE SET NULL,
+    success BOOLEAN NOT NULL,
+    attempted_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f+00:00', 'now')),
+    user_agent TEXT,
+    ip_address TEXT,
+    email_hash TEXT,
+    failure_reason TEXT,
+    request_id TEXT
+);
+
+CREATE INDEX login_attempts_user_history_idx
+    ON login_attempts (user_id, attempted_at DESC, id DESC);
+CREATE INDEX login_attempts_email_hash_idx
+    ON login_attempts (email_hash, attempted_at DESC, id DESC)
+    WHERE email_hash IS NOT NULL;
+CREATE INDEX login_attempts_ip_idx
+    ON login_attempts (ip_address, attempted_at DESC)
+    WHERE ip_address IS NOT NULL;
+
+CREATE TABLE user_identities (
+    id TEXT PRIMARY KEY NOT NULL,
+    user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    provider TEXT NOT NULL,
+    provider_user_id TEXT NOT NULL,
+    email TEXT,
+    created_at TEXT DEFAULT (strftime('%Y-%m-%d %H:%M:%f+00:00', 'now')),
+    UNIQUE (provider, provider_user_id)
+);
+
+CREATE TABLE rate_limit_buckets (
+    key TEXT PRIMARY KEY NOT NULL,
+    window_start TEXT NOT NULL,
+    hits INTEGER NOT NULL
+);
+
+CREATE INDEX rate_limit_buckets_window_start_idx ON rate_limit_buckets (window_start);
+
+CREATE TABLE user_tokens (
+    id TEXT PRIMARY KEY NOT NULL,
+    user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    purpose TEXT NOT NULL,
+    token_hash TEXT NOT NULL UNIQUE,
+    expires_at TEXT NOT NULL,
+    used_at TEXT,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f+00:00', 'now'))
+);
+
+CREATE INDEX user_tokens_user_id_idx ON user_tokens (user_id);
+
+CREATE TABLE password_history (
+    id TEXT PRIMARY KEY NOT NULL,
+    user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    password_hash TEXT NOT NULL,
+    password_pepper_version INTEGER,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f+00:00', 'now'))
+);
+
+CREATE INDEX password_history_user_idx ON password_history (user_id, created_at DESC);
+
+-- Journal d'audit : pas de clé étrangère, les événements survivent aux comptes
+CREATE TABLE audit_events (
+    id TEXT PRIMARY KEY NOT NULL,
+    occurred_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f+00:00', 'now')),
+    event_type TEXT NOT NULL,
+    actor_id TEXT,
+    target_id TEXT,
+    ip_address TEXT,
+    user_agent TEXT,
+    request_id TEXT,
+    metadata TEXT NOT NULL DEFAULT '{}'
+);
+
+CREATE INDEX audit_events_occurred_at_idx ON audit_events (occurred_at DESC, id DESC);
+CREATE INDEX audit_events_actor_idx ON audit_events (actor_id, occurred_at DESC, id DESC);
+CREATE INDEX audit_events_target_idx ON audit_events (target_id, occurred_at DESC, id DESC);
+CREATE INDEX audit_events_type_idx ON audit_events (event_type, occurred_at DESC, id DESC);
+
+CREATE TABLE known_devices (
+    id TEXT PRIMARY KEY NOT NULL,
+    user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    fingerprint TEXT NOT NULL,
+    browser TEXT,
+    os TEXT,
+    ip_prefix TEXT,
+    first_seen_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f+00:00', 'now')),
+    last_seen_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f+00:00', 'now')),
+    UNIQUE (user_id, fingerprint)
+);
+
+CREATE INDEX known_devices_user_idx ON known_devices (user_id, last_seen_at DESC);
+
+-- Équivalent des triggers `updated_at` PostgreSQL : l'application fournit la valeur,
+-- le trigger ne la pose que si une mise à jour l'a laissée inchangée
+CREATE TRIGGER update_users_updated_at AFTER UPDATE ON users
+    FOR EACH ROW WHEN NEW.updated_at = OLD.updated_at
+BEGIN
+    UPDATE users SET updated_at = strftime('%Y-%m-%d %H:%M:%f+00:00', 'now')
+        WHERE id = NEW.id;
+END;
+
+CREATE TRIGGER update_refresh_tokens_updated_at AFTER UPDATE ON refresh_tokens
+    FOR EACH ROW WHEN NEW.updated_at = OLD.updated_at
+BEGIN
+    UPDATE refresh_tokens SET updated_at = strftime('%Y-%m-%d %H:%M:%f+00:00', 'now')
+        WHERE id = NEW.id;
+END;
+
+-- Les événements d'audit ne sont jamais modifiés ni supprimés
+CREATE TRIGGER audit_events_append_only_update BEFORE UPDATE ON audit_events
+BEGIN
+    SELECT RAISE(ABORT, 'audit_events is append-only');
+END;
+
+CREATE TRIGGER audit_events_append_only_delete BEFORE DELETE ON audit_events
+BEGIN
+    SELECT RAISE(ABORT, 'audit_events is append-only');
+END;
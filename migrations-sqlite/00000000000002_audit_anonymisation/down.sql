@@ -0,0 +1,8 @@
+DROP TRIGGER audit_events_append_only_update;
+
+CREATE TRIGGER audit_events_append_only_update BEFORE UPDATE ON audit_events
+BEGIN
+    SELECT RAISE(ABORT, 'audit_events is append-only');
+END;
+
+DROP TABLE IF EXISTS audit_purged_users;
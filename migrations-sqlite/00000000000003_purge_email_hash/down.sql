@@ -0,0 +1,23 @@
+DROP TRIGGER audit_events_append_only_update;
+
+CREATE TRIGGER audit_events_append_only_update BEFORE UPDATE ON audit_events
+    FOR EACH ROW WHEN NOT (
+        EXISTS (
+            SELECT 1 FROM audit_purged_users
+            WHERE user_id = OLD.actor_id OR user_id = OLD.target_id
+        )
+        AND NEW.ip_address IS NULL
+        AND NEW.user_agent IS NULL
+        AND (NEW.actor_id IS OLD.actor_id
+            OR EXISTS (SELECT 1 FROM audit_purged_users WHERE user_id = OLD.actor_id))
+        AND (NEW.target_id IS OLD.target_id
+            OR EXISTS (SELECT 1 FROM audit_purged_users WHERE user_id = OLD.target_id))
+        AND NEW.id IS OLD.id
+        AND NEW.occurred_at IS OLD.occurred_at
+        AND NEW.event_type IS OLD.event_type
+        AND NEW.request_id IS OLD.request_id
+        AND NEW.metadata IS OLD.metadata
+    )
+BEGIN
+    SELECT RAISE(ABORT, 'audit_events is append-only');
+END;
@@ -0,0 +1,28 @@
+-- La purge d'un compte efface aussi l'empreinte d'email de ses événements d'audit
+-- (`metadata.email_hash` des échecs de connexion) : seule cette clé peut passer à null,
+-- le reste des métadonnées est inchangé.
+DROP TRIGGER audit_events_append_only_update;
+
+CREATE TRIGGER audit_events_append_only_update BEFORE UPDATE ON audit_events
+    FOR EACH ROW WHEN NOT (
+        EXISTS (
+            SELECT 1 FROM audit_purged_users
+            WHERE user_id = OLD.actor_id OR user_id = OLD.target_id
+        )
+        AND NEW.ip_address IS NULL
+        AND NEW.user_agent IS NULL
+        AND (NEW.actor_id IS OLD.actor_id
+            OR EXISTS (SELECT 1 FROM audit_purged_users WHERE user_id = OLD.actor_id))
+        AND (NEW.target_id IS OLD.target_id
+            OR EXISTS (SELECT 1 FROM audit_purged_users WHERE user_id = OLD.target_id))
+        AND NEW.id IS OLD.id
+        AND NEW.occurred_at IS OLD.occurred_at
+        AND NEW.event_type IS OLD.event_type
+        AND NEW.request_id IS OLD.request_id
+        AND (NEW.metadata IS OLD.metadata
+            OR (json_type(OLD.metadata, '$.email_hash') IS NOT NULL
+                AND NEW.metadata IS json_set(OLD.metadata, '$.email_hash', NULL)))
+    )
+BEGIN
+    SELECT RAISE(ABORT, 'audit_events is append-only');
+END;
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS users_email_lower_key;
+DROP INDEX IF EXISTS users_username_canonical_key;
+ALTER TABLE users DROP COLUMN IF EXISTS username_canonical;
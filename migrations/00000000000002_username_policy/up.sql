@@ -0,0 +1,36 @@
+-- Politique de noms d'utilisateur et unicité insensible à la casse
+
+-- Les index uniques ci-dessous échoueraient sur des doublons à la casse près : la
+-- migration s'arrête en les listant, à fusionner ou renommer avant de la relancer
+DO $$
+DECLARE
+    duplicates TEXT;
+BEGIN
+    SELECT string_agg(names, '; ') INTO duplicates FROM (
+        SELECT 'username ' || string_agg(username, ', ' ORDER BY username) AS names
+        FROM users GROUP BY lower(username) HAVING count(*) > 1
+        UNION ALL
+        SELECT 'email ' || string_agg(email, ', ' ORDER BY email)
+        FROM users GROUP BY lower(email) HAVING count(*) > 1
+    ) AS groups;
+    IF duplicates IS NOT NULL THEN
+        RAISE EXCEPTION 'Case-insensitive duplicates, merge or rename them first: %', duplicates;
+    END IF;
+END $$;
+
+-- Forme canonique du username (minuscules + squelette de confusables UTS #39),
+-- calculée par l'application. Les lignes existantes sont initialisées en minuscules,
+-- puis `auth-manager migrate` y écrit le squelette dans la même transaction
+-- (`db::migrations::backfill_username_canonical`).
+--
+-- `diesel migration run` ne joue que ce fichier : les comptes existants gardent
+-- `lower(username)`, sans squelette ni détection des usernames semblables. Sur une base
+-- qui contient déjà des comptes, migrer avec `auth-manager migrate`.
+ALTER TABLE users ADD COLUMN username_canonical VARCHAR(255);
+UPDATE users SET username_canonical = lower(username);
+ALTER TABLE users ALTER COLUMN username_canonical SET NOT NULL;
+
+CREATE UNIQUE INDEX users_username_canonical_key ON users (username_canonical);
+
+-- Email unique sans tenir compte de la casse
+CREATE UNIQUE INDEX users_email_lower_key ON users (lower(email));
@@ -0,0 +1,6 @@
+ALTER TABLE login_attempts DROP CONSTRAINT login_attempts_user_id_fkey;
+ALTER TABLE login_attempts ADD CONSTRAINT login_attempts_user_id_fkey
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE;
+
+DROP INDEX IF EXISTS users_deletion_scheduled_at_idx;
+ALTER TABLE users DROP COLUMN IF EXISTS deletion_scheduled_at;
@@ -0,0 +1,12 @@
+-- Suppression de compte différée (RGPD)
+
+-- Date à laquelle le compte sera effectivement supprimé (NULL = pas de suppression prévue)
+ALTER TABLE users ADD COLUMN deletion_scheduled_at TIMESTAMP WITH TIME ZONE;
+
+CREATE INDEX users_deletion_scheduled_at_idx ON users (deletion_scheduled_at)
+    WHERE deletion_scheduled_at IS NOT NULL;
+
+-- L'historique de connexion est anonymisé plutôt que supprimé en cascade
+ALTER TABLE login_attempts DROP CONSTRAINT login_attempts_user_id_fkey;
+ALTER TABLE login_attempts ADD CONSTRAINT login_attempts_user_id_fkey
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE SET NULL;
@@ -0,0 +1,4 @@
+DROP INDEX IF EXISTS login_attempts_email_hash_idx;
+DROP INDEX IF EXISTS login_attempts_user_history_idx;
+ALTER TABLE login_attempts DROP COLUMN IF EXISTS email_hash;
+ALTER TABLE login_attempts DROP COLUMN IF EXISTS ip_address;
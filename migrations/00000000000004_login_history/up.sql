@@ -0,0 +1,13 @@
+-- Historique de connexion
+
+-- Adresse IP du client (IPv4 ou IPv6 textuelle)
+ALTER TABLE login_attempts ADD COLUMN ip_address VARCHAR(45);
+-- SHA-256 (hex) de l'email tenté, pour retrouver les tentatives sur un email inconnu
+ALTER TABLE login_attempts ADD COLUMN email_hash VARCHAR(64);
+
+-- Pagination par curseur (attempted_at, id) décroissant
+CREATE INDEX login_attempts_user_history_idx
+    ON login_attempts (user_id, attempted_at DESC, id DESC);
+CREATE INDEX login_attempts_email_hash_idx
+    ON login_attempts (email_hash, attempted_at DESC, id DESC)
+    WHERE email_hash IS NOT NULL;
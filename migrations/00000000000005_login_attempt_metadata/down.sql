@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS login_attempts_ip_idx;
+ALTER TABLE login_attempts DROP COLUMN IF EXISTS request_id;
+ALTER TABLE login_attempts DROP COLUMN IF EXISTS failure_reason;
@@ -0,0 +1,11 @@
+-- Métadonnées des tentatives de connexion
+
+-- Cause d'un échec (unknown_email, invalid_password, ...), NULL pour un succès
+ALTER TABLE login_attempts ADD COLUMN failure_reason VARCHAR(32);
+-- Identifiant de requête (API Gateway ou X-Request-Id) pour corréler avec les logs
+ALTER TABLE login_attempts ADD COLUMN request_id VARCHAR(128);
+
+-- Recherche des tentatives récentes par IP (limitation de débit)
+CREATE INDEX login_attempts_ip_idx
+    ON login_attempts (ip_address, attempted_at DESC)
+    WHERE ip_address IS NOT NULL;
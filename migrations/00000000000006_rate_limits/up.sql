@@ -0,0 +1,11 @@
+-- Compteurs de limitation de débit (fenêtre fixe), partagés entre instances Lambda
+
+CREATE TABLE rate_limit_buckets (
+    -- "ip:<addr>", "email:<sha256>" ou "ip_email:<addr>:<sha256>"
+    key VARCHAR(255) PRIMARY KEY,
+    window_start TIMESTAMPTZ NOT NULL,
+    hits INTEGER NOT NULL
+);
+
+-- Nettoyage des fenêtres expirées
+CREATE INDEX rate_limit_buckets_window_start_idx ON rate_limit_buckets (window_start);
@@ -0,0 +1,25 @@
+-- Verrouillage progressif des comptes
+
+-- Échecs consécutifs depuis le dernier verrouillage ou succès
+ALTER TABLE users ADD COLUMN failed_login_count INTEGER NOT NULL DEFAULT 0;
+-- Nombre de verrouillages successifs (palier courant)
+ALTER TABLE users ADD COLUMN lockout_level INTEGER NOT NULL DEFAULT 0;
+-- Connexion refusée jusqu'à cette date (délai exponentiel ou palier)
+ALTER TABLE users ADD COLUMN locked_until TIMESTAMPTZ;
+-- Verrouillage définitif : déblocage par un admin ou par lien email
+ALTER TABLE users ADD COLUMN locked_permanently BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE users ADD COLUMN last_failed_login_at TIMESTAMPTZ;
+
+-- Jetons à usage unique envoyés par email (déblocage de compte, ...)
+CREATE TABLE user_tokens (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    purpose VARCHAR(32) NOT NULL,
+    -- SHA-256 (hex) du jeton ; le jeton en clair n'est jamais stocké
+    token_hash VARCHAR(64) NOT NULL UNIQUE,
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    used_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX user_tokens_user_id_idx ON user_tokens (user_id);
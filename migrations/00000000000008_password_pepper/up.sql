@@ -0,0 +1,2 @@
+-- Version du pepper (clé HMAC hors base) appliqué avant hachage ; NULL = sans pepper
+ALTER TABLE users ADD COLUMN password_pepper_version INTEGER;
@@ -0,0 +1,16 @@
+-- Historique des mots de passe et expiration
+
+-- Date du dernier changement de mot de passe ; NULL pour un compte sans mot de passe
+ALTER TABLE users ADD COLUMN password_changed_at TIMESTAMP WITH TIME ZONE DEFAULT NOW();
+UPDATE users SET password_changed_at = CASE WHEN password_hash IS NULL THEN NULL ELSE created_at END;
+
+-- Anciens hachages, pour refuser la réutilisation d'un mot de passe récent
+CREATE TABLE password_history (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    password_hash VARCHAR(255) NOT NULL,
+    password_pepper_version INTEGER,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX password_history_user_idx ON password_history (user_id, created_at DESC);
@@ -0,0 +1,33 @@
+-- Journal d'audit de sécurité (ajout seul)
+
+CREATE TABLE audit_events (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    occurred_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    -- Voir AuditEventType (auth-manager-api)
+    event_type VARCHAR(64) NOT NULL,
+    -- Pas de clé étrangère : les événements survivent à la suppression des comptes
+    actor_id UUID,
+    target_id UUID,
+    ip_address VARCHAR(45),
+    user_agent TEXT,
+    request_id VARCHAR(128),
+    metadata JSONB NOT NULL DEFAULT '{}'
+);
+
+-- Pagination par curseur (occurred_at, id) décroissant, avec ou sans filtre
+CREATE INDEX audit_events_occurred_at_idx ON audit_events (occurred_at DESC, id DESC);
+CREATE INDEX audit_events_actor_idx ON audit_events (actor_id, occurred_at DESC, id DESC);
+CREATE INDEX audit_events_target_idx ON audit_events (target_id, occurred_at DESC, id DESC);
+CREATE INDEX audit_events_type_idx ON audit_events (event_type, occurred_at DESC, id DESC);
+
+-- Les événements ne sont jamais modifiés ni supprimés
+CREATE OR REPLACE FUNCTION audit_events_append_only()
+RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'audit_events is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER audit_events_append_only
+    BEFORE UPDATE OR DELETE ON audit_events
+    FOR EACH ROW EXECUTE FUNCTION audit_events_append_only();
@@ -0,0 +1,2 @@
+ALTER TABLE refresh_tokens DROP COLUMN IF EXISTS risk_flagged;
+DROP TABLE IF EXISTS known_devices;
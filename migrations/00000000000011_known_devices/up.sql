@@ -0,0 +1,19 @@
+-- Appareils connus et sessions à risque
+
+-- Appareils déjà vus pour un compte : empreinte (famille de navigateur/OS + préfixe IP)
+CREATE TABLE known_devices (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    fingerprint VARCHAR(64) NOT NULL,
+    browser VARCHAR(64),
+    os VARCHAR(64),
+    ip_prefix VARCHAR(64),
+    first_seen_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    last_seen_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    UNIQUE (user_id, fingerprint)
+);
+
+CREATE INDEX known_devices_user_idx ON known_devices (user_id, last_seen_at DESC);
+
+-- Session ouverte par une connexion jugée suspecte
+ALTER TABLE refresh_tokens ADD COLUMN risk_flagged BOOLEAN NOT NULL DEFAULT FALSE;
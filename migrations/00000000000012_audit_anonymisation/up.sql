@@ -0,0 +1,25 @@
+-- Anonymisation du journal d'audit à la purge d'un compte
+
+-- `UserStore::purge` pose `auth_manager.purged_user` pour sa seule transaction
+-- (set_config(..., true)) : une mise à jour n'est acceptée que sur les événements de
+-- ce compte, pour remplacer son identifiant et effacer IP et user agent. Tout le reste
+-- du journal reste en ajout seul.
+CREATE OR REPLACE FUNCTION audit_events_append_only()
+RETURNS TRIGGER AS $$
+DECLARE
+    purged UUID := NULLIF(current_setting('auth_manager.purged_user', true), '')::UUID;
+BEGIN
+    IF TG_OP = 'UPDATE'
+        AND purged IN (OLD.actor_id, OLD.target_id)
+        AND NEW.ip_address IS NULL
+        AND NEW.user_agent IS NULL
+        AND (NEW.actor_id IS NOT DISTINCT FROM OLD.actor_id OR OLD.actor_id = purged)
+        AND (NEW.target_id IS NOT DISTINCT FROM OLD.target_id OR OLD.target_id = purged)
+        AND (NEW.id, NEW.occurred_at, NEW.event_type, NEW.request_id, NEW.metadata)
+            IS NOT DISTINCT FROM (OLD.id, OLD.occurred_at, OLD.event_type, OLD.request_id, OLD.metadata)
+    THEN
+        RETURN NEW;
+    END IF;
+    RAISE EXCEPTION 'audit_events is append-only';
+END;
+$$ LANGUAGE plpgsql;
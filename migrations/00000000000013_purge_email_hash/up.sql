@@ -0,0 +1,26 @@
+-- La purge d'un compte efface aussi l'empreinte d'email de ses événements d'audit
+-- (`metadata.email_hash` des échecs de connexion), qui permettrait de le retrouver via
+-- une recherche par email : seule cette clé peut passer à null, le reste des
+-- métadonnées est inchangé.
+CREATE OR REPLACE FUNCTION audit_events_append_only()
+RETURNS TRIGGER AS $$
+DECLARE
+    purged UUID := NULLIF(current_setting('auth_manager.purged_user', true), '')::UUID;
+BEGIN
+    IF TG_OP = 'UPDATE'
+        AND purged IN (OLD.actor_id, OLD.target_id)
+        AND NEW.ip_address IS NULL
+        AND NEW.user_agent IS NULL
+        AND (NEW.actor_id IS NOT DISTINCT FROM OLD.actor_id OR OLD.actor_id = purged)
+        AND (NEW.target_id IS NOT DISTINCT FROM OLD.target_id OR OLD.target_id = purged)
+        AND (NEW.metadata IS NOT DISTINCT FROM OLD.metadata
+            OR (OLD.metadata ? 'email_hash'
+                AND NEW.metadata = OLD.metadata || '{"email_hash": null}'::jsonb))
+        AND (NEW.id, NEW.occurred_at, NEW.event_type, NEW.request_id)
+            IS NOT DISTINCT FROM (OLD.id, OLD.occurred_at, OLD.event_type, OLD.request_id)
+    THEN
+        RETURN NEW;
+    END IF;
+    RAISE EXCEPTION 'audit_events is append-only';
+END;
+$$ LANGUAGE plpgsql;
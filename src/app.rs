@@ -3,83 +3,84 @@
 use axum::{
     Router,
     extract::Extension,
-    http::{HeaderValue, Method, header},
     routing::{delete, get, post},
 };
 use std::sync::Arc;
-use tower_http::{cors::CorsLayer, trace::TraceLayer};
+use tower_http::trace::TraceLayer;
 
-use crate::auth::jwt::JwtManager;
-use crate::auth::services::AuthService;
-use crate::handlers::auth::{login, logout, refresh_token, register};
+use crate::auth::client_info::TrustedProxies;
+use crate::cors::AllowedOrigins;
+use crate::handlers::admin::{
+    export_audit_events, get_audit_events, get_login_attempts_by_email, get_user_login_history,
+    unlock_user,
+};
+use crate::handlers::auth::{
+    confirm_account_deletion, get_password_policy, login, logout, not_me, refresh_token, register,
+    unlock_account,
+};
 use crate::handlers::health::health;
-use crate::handlers::user::{change_password, delete_user, get_current_user, get_user_by_id};
+use crate::handlers::user::{
+    change_password, delete_user, export_current_user, get_current_user, get_login_history,
+    get_user_by_id, send_deletion_confirmation,
+};
+use crate::state::AppState;
 
 /// Configure les routes d'authentification.
-/// `auth_service` est fourni en Extension à tous les handlers.
-/// Les routes protégées utilisent `jwt_manager` en State pour l'extracteur `AuthClaims`.
-pub fn auth_routes(jwt_manager: JwtManager, auth_service: Arc<AuthService>) -> Router {
-    let public = Router::new()
+pub fn auth_routes() -> Router<AppState> {
+    Router::new()
         .route("/register", post(register))
         .route("/login", post(login))
-        .route("/refresh", post(refresh_token));
-
-    let protected = Router::new()
+        .route("/refresh", post(refresh_token))
+        .route("/unlock", post(unlock_account))
+        .route("/not-me", post(not_me))
+        .route("/confirm-deletion", post(confirm_account_deletion))
+        .route("/password-policy", get(get_password_policy))
         .route("/logout", post(logout))
-        .with_state(jwt_manager);
-
-    public.merge(protected).layer(Extension(auth_service))
 }
 
 /// Configure les routes utilisateur.
-/// `jwt_manager` en State pour `AuthClaims`; les handlers appellent `AuthService` directement.
-pub fn user_routes(jwt_manager: JwtManager) -> Router {
+/// Les routes protégées s'appuient sur l'extracteur `AuthClaims`.
+pub fn user_routes() -> Router<AppState> {
     Router::new()
         .route("/me", get(get_current_user))
+        .route("/me/export", get(export_current_user))
+        .route("/me/login-history", get(get_login_history))
         .route("/{id}", get(get_user_by_id))
         .route("/{id}", delete(delete_user))
+        .route(
+            "/{id}/deletion-confirmation",
+            post(send_deletion_confirmation),
+        )
         .route("/{id}/change-password", post(change_password))
-        .with_state(jwt_manager)
 }
 
-/// Construit l'application complète
-pub fn build_router(jwt_manager: JwtManager) -> Router {
-    let auth_service = Arc::new(AuthService::new(jwt_manager.clone()));
-
-    let allowed_origins: Vec<HeaderValue> = crate::config::Environment::detect()
-        .cors_origins()
-        .iter()
-        .map(|o| o.parse().expect("Invalid CORS origin"))
-        .collect();
-
-    let cors = CorsLayer::new()
-        .allow_origin(allowed_origins)
-        .allow_methods([
-            Method::GET,
-            Method::POST,
-            Method::PUT,
-            Method::DELETE,
-            Method::OPTIONS,
-            Method::PATCH,
-        ])
-        .allow_headers([
-            header::CONTENT_TYPE,
-            header::AUTHORIZATION,
-            header::ACCEPT,
-            header::ORIGIN,
-            header::ACCESS_CONTROL_REQUEST_METHOD,
-            header::ACCESS_CONTROL_REQUEST_HEADERS,
-        ])
-        .expose_headers([header::CONTENT_TYPE, header::AUTHORIZATION])
-        .allow_credentials(true)
-        .max_age(std::time::Duration::from_secs(3600));
+/// Configure les routes d'administration.
+/// L'extracteur `AdminClaims` vérifie le rôle administrateur en base.
+pub fn admin_routes() -> Router<AppState> {
+    Router::new()
+        .route("/users/{id}/login-history", get(get_user_login_history))
+        .route("/users/{id}/unlock", post(unlock_user))
+        .route("/login-attempts", get(get_login_attempts_by_email))
+        .route("/audit-events", get(get_audit_events))
+        .route("/audit-events/export", get(export_audit_events))
+}
 
+/// Construit l'application complète
+pub fn build_router(
+    state: AppState,
+    trusted_proxies: TrustedProxies,
+    allowed_origins: AllowedOrigins,
+) -> Router {
     Router::new()
         .route("/health", get(health))
-        .nest("/auth", auth_routes(jwt_manager.clone(), auth_service))
-        .nest("/users", user_routes(jwt_manager))
+        .nest("/auth", auth_routes())
+        .nest("/users", user_routes())
+        .nest("/admin", admin_routes())
+        .with_state(state)
+        // Proxies de confiance pour l'extracteur `ClientInfo`
+        .layer(Extension(Arc::new(trusted_proxies)))
         // Middleware CORS (doit être avant TraceLayer)
-        .layer(cors)
+        .layer(allowed_origins.layer())
         // Middleware global de tracing
         .layer(TraceLayer::new_for_http())
 }
@@ -87,20 +88,27 @@ pub fn build_router(jwt_manager: JwtManager) -> Router {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::db::connection::init_test_pool;
+    use crate::auth::jwt::JwtManager;
+    use crate::auth::services::AuthService;
+    use crate::db::store::test_repositories;
     use axum::body::Body;
-    use axum::http::{Request, StatusCode};
+    use axum::http::{Request, StatusCode, header};
     use lambda_http::tower::ServiceExt; // for oneshot
 
     fn test_jwt() -> JwtManager {
-        init_test_pool();
         JwtManager::new("test_secret_for_auth_routes", 1)
     }
 
+    fn test_state(service: AuthService) -> AppState {
+        AppState::new(service, test_jwt(), test_repositories())
+    }
+
+    fn test_service() -> AuthService {
+        AuthService::new(test_jwt(), test_repositories())
+    }
+
     fn test_auth_routes() -> Router {
-        let jwt = test_jwt();
-        let service = Arc::new(AuthService::new(jwt.clone()));
-        auth_routes(jwt, service)
+        auth_routes().with_state(test_state(test_service()))
     }
 
     #[tokio::test]
@@ -117,11 +125,32 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
     }
 
+    #[tokio::test]
+    async fn password_policy_is_public() {
+        let policy = auth_manager_api::PasswordPolicy {
+            min_length: 14,
+            ..auth_manager_api::PasswordPolicy::default()
+        };
+        let app = auth_routes().with_state(test_state(test_service().with_password_policy(policy)));
+
+        let req = Request::builder()
+            .uri("/password-policy")
+            .body(Body::empty())
+            .unwrap();
+
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["min_length"], 14);
+    }
+
     #[tokio::test]
     async fn logout_succeeds_with_valid_bearer_token() {
         use crate::auth::password::PasswordManager;
         use crate::db::models::user::NewUser;
-        use crate::db::repositories::user_repository::UserRepository;
 
         let jwt = test_jwt();
 
@@ -130,9 +159,14 @@ mod tests {
         let new_user = NewUser {
             email: format!("logout_test_{}@example.com", uuid::Uuid::new_v4()),
             username: "logout_user".to_string(),
-            password_hash: Some(hash),
+            password_hash: Some(hash.hash),
+            password_pepper_version: hash.pepper_version,
+            username_canonical: "logout_user".to_string(),
         };
-        let user = UserRepository::create(&new_user).expect("create user");
+        let user = test_repositories()
+            .users
+            .create(&new_user)
+            .expect("create user");
         let token = jwt.generate_token(user.id, 1).expect("token");
 
         let app = test_auth_routes();
@@ -147,6 +181,352 @@ mod tests {
         let resp = app.clone().oneshot(req).await.unwrap();
         assert_eq!(resp.status(), StatusCode::OK);
 
-        let _ = UserRepository::delete(user.id);
+        let _ = test_repositories().users.delete(user.id);
+    }
+
+    #[tokio::test]
+    async fn password_change_token_only_reaches_change_password() {
+        let jwt = test_jwt();
+        let token = jwt
+            .generate_password_change_token(uuid::Uuid::new_v4())
+            .expect("token");
+        let app = user_routes().with_state(test_state(test_service()));
+
+        let req = Request::builder()
+            .uri("/me")
+            .header("Authorization", format!("Bearer {token}"))
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        // Accepté par l'extracteur : l'erreur vient du handler (autre utilisateur)
+        let req = Request::builder()
+            .uri(format!("/{}/change-password", uuid::Uuid::new_v4()))
+            .method("POST")
+            .header("Authorization", format!("Bearer {token}"))
+            .header("Content-Type", "application/json")
+            .body(Body::from(
+                r#"{"old_password":"OldPass123!","new_password":"NewPass456!"}"#,
+            ))
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn admin_routes_reject_non_admin_users() {
+        use crate::auth::password::PasswordManager;
+        use crate::db::models::user::NewUser;
+
+        let jwt = test_jwt();
+        let username = format!(
+            "nonadmin_{}",
+            &uuid::Uuid::new_v4().simple().to_string()[..16]
+        );
+        let user = test_repositories()
+            .users
+            .create(&NewUser {
+                email: format!("{username}@example.com"),
+                username_canonical: username.clone(),
+                username,
+                password_hash: Some(PasswordManager::hash("NotAdmin123!").expect("hash").hash),
+                password_pepper_version: None,
+            })
+            .expect("create user");
+        let token = jwt.generate_token(user.id, 1).expect("token");
+
+        let app = admin_routes().with_state(test_state(test_service()));
+        let req = Request::builder()
+            .uri(format!("/users/{}/login-history", user.id))
+            .header("Authorization", format!("Bearer {token}"))
+            .body(Body::empty())
+            .unwrap();
+
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        let _ = test_repositories().users.delete(user.id);
+    }
+
+    #[tokio::test]
+    async fn admin_actions_are_audited_and_exportable() {
+        use crate::auth::password::PasswordManager;
+        use crate::db::models::user::{NewUser, UpdateUser};
+
+        let jwt = test_jwt();
+        let username = format!(
+            "auditor_{}",
+            &uuid::Uuid::new_v4().simple().to_string()[..16]
+        );
+        let admin = test_repositories()
+            .users
+            .create(&NewUser {
+                email: format!("{username}@example.com"),
+                username_canonical: username.clone(),
+                username,
+                password_hash: Some(PasswordManager::hash("Auditor123!").expect("hash").hash),
+                password_pepper_version: None,
+            })
+            .expect("create user");
+        test_repositories()
+            .users
+            .update(
+                admin.id,
+                &UpdateUser {
+                    is_admin: Some(true),
+                    ..UpdateUser::default()
+                },
+            )
+            .expect("promote");
+        let token = jwt.generate_token(admin.id, 1).expect("token");
+        let app = admin_routes().with_state(test_state(test_service()));
+        let get = |uri: String| {
+            Request::builder()
+                .uri(uri)
+                .header("Authorization", format!("Bearer {token}"))
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let req = Request::builder()
+            .uri(format!("/users/{}/unlock", admin.id))
+            .method("POST")
+            .header("Authorization", format!("Bearer {token}"))
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let filter = format!("target_id={}&event_type=account_unlocked", admin.id);
+        let resp = app
+            .clone()
+            .oneshot(get(format!("/audit-events?{filter}")))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let page: auth_manager_api::AuditEventPage = serde_json::from_slice(&body).unwrap();
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].actor_id, Some(admin.id));
+        assert_eq!(page.items[0].metadata["method"], "admin");
+
+        let resp = app
+            .clone()
+            .oneshot(get(format!("/audit-events/export?{filter}")))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()[header::CONTENT_TYPE], "application/x-ndjson");
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let lines: Vec<auth_manager_api::AuditEventEntry> = std::str::from_utf8(&body)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].id, page.items[0].id);
+
+        // Consultations et exports sont journalisés à leur tour, avec le filtre appliqué
+        for event_type in ["admin_audit_log_viewed", "admin_audit_log_exported"] {
+            let resp = app
+                .clone()
+                .oneshot(get(format!(
+                    "/audit-events?actor_id={}&event_type={event_type}",
+                    admin.id
+                )))
+                .await
+                .unwrap();
+            let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let reads: auth_manager_api::AuditEventPage = serde_json::from_slice(&body).unwrap();
+            assert_eq!(reads.items.len(), 1, "{event_type}");
+            assert_eq!(reads.items[0].metadata["target_id"], admin.id.to_string());
+            assert_eq!(reads.items[0].metadata["event_type"], "account_unlocked");
+        }
+
+        let resp = app
+            .oneshot(get("/audit-events?event_type=nope".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let _ = test_repositories().users.delete(admin.id);
+    }
+
+    #[tokio::test]
+    async fn memory_storage_serves_register_login_and_profile() {
+        let repositories = test_repositories();
+        let service = AuthService::new(test_jwt(), repositories.clone());
+        let app = build_router(
+            AppState::new(service, test_jwt(), repositories),
+            TrustedProxies::default(),
+            AllowedOrigins::default(),
+        );
+        let post = |uri: &str, body: serde_json::Value| {
+            Request::builder()
+                .uri(uri)
+                .method("POST")
+                .header("Content-Type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        let credentials = serde_json::json!({
+            "email": "memory@example.com",
+            "password": "Sturdy-Lantern-42",
+        });
+
+        let resp = app
+            .clone()
+            .oneshot(post(
+                "/auth/register",
+                serde_json::json!({
+                    "email": "memory@example.com",
+                    "username": "memory_user",
+                    "password": "Sturdy-Lantern-42",
+                }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::CREATED);
+
+        let resp = app
+            .clone()
+            .oneshot(post("/auth/login", credentials))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(resp.headers().contains_key(header::SET_COOKIE));
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let login: auth_manager_api::PublicLoginResponse = serde_json::from_slice(&body).unwrap();
+
+        let req = Request::builder()
+            .uri("/users/me")
+            .header("Authorization", format!("Bearer {}", login.access_token))
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let me: auth_manager_api::UserResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(me.id, login.user.id);
+        assert_eq!(me.username, "memory_user");
+    }
+
+    #[tokio::test]
+    async fn configured_cors_origins_and_cookie_are_applied() {
+        let repositories = test_repositories();
+        let service = AuthService::new(test_jwt(), repositories.clone());
+        let cookies = crate::auth::cookie::CookieConfig {
+            name: "rt".to_string(),
+            path: "/".to_string(),
+            ..Default::default()
+        };
+        let app = build_router(
+            AppState::new(service, test_jwt(), repositories).with_cookie_config(cookies),
+            TrustedProxies::default(),
+            AllowedOrigins::parse("https://*.example.com").unwrap(),
+        );
+        let post = |uri: &str, body: serde_json::Value| {
+            Request::builder()
+                .uri(uri)
+                .method("POST")
+                .header("Content-Type", "application/json")
+                .header("Origin", "https://app.example.com")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let resp = app
+            .clone()
+            .oneshot(post(
+                "/auth/register",
+                serde_json::json!({
+                    "email": "cookie@example.com",
+                    "username": "cookie_user",
+                    "password": "Sturdy-Lantern-42",
+                }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(
+            resp.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://app.example.com"
+        );
+
+        let resp = app
+            .clone()
+            .oneshot(post(
+                "/auth/login",
+                serde_json::json!({
+                    "email": "cookie@example.com",
+                    "password": "Sturdy-Lantern-42",
+                }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let set_cookie = resp.headers()[header::SET_COOKIE].to_str().unwrap();
+        assert!(set_cookie.starts_with("rt="), "{set_cookie}");
+        assert!(set_cookie.ends_with("; Path=/"), "{set_cookie}");
+        let pair = set_cookie.split(';').next().unwrap().to_string();
+
+        let req = Request::builder()
+            .uri("/auth/refresh")
+            .method("POST")
+            .header(header::COOKIE, pair)
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let req = Request::builder()
+            .uri("/health")
+            .header("Origin", "https://example.com.evil.net")
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        assert!(
+            !resp
+                .headers()
+                .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+        );
+    }
+
+    #[tokio::test]
+    async fn health_reports_draining_during_shutdown() {
+        let shutdown = crate::shutdown::Shutdown::default();
+        let app = build_router(
+            test_state(test_service()).with_shutdown(shutdown.clone()),
+            TrustedProxies::default(),
+            AllowedOrigins::default(),
+        );
+        let health = || {
+            Request::builder()
+                .uri("/health")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let resp = app.clone().oneshot(health()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        shutdown.begin();
+        let resp = app.oneshot(health()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["status"], "draining");
     }
 }
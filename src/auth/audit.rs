@@ -0,0 +1,183 @@
+//! Journal d'audit de sécurité (table `audit_events`, en ajout seul).
+
+use auth_manager_api::{AuditEventEntry, AuditEventPage, AuditEventQuery, AuditEventType};
+use uuid::Uuid;
+
+use crate::auth::client_info::ClientInfo;
+use crate::auth::services::{decode_history_cursor, encode_history_cursor};
+use crate::db::models::audit_event::NewAuditEvent;
+use crate::db::repositories::audit_event_repository::AuditEventFilter;
+use crate::db::store::AuditEventStore;
+use crate::error::AppError;
+
+const AUDIT_PAGE_DEFAULT_LIMIT: i64 = 50;
+const AUDIT_PAGE_MAX_LIMIT: i64 = 200;
+const AUDIT_EXPORT_BATCH_SIZE: i64 = 500;
+/// Rows per export response; the rest is fetched with the returned cursor
+const AUDIT_EXPORT_MAX_ROWS: usize = 10_000;
+
+/// Audit event being built, written by [`Audit::record`].
+///
+/// ```ignore
+/// Audit::new(AuditEventType::PasswordChanged)
+///     .actor(user_id)
+///     .target(user_id)
+///     .client(&client)
+///     .record(repositories.audit_events.as_ref());
+/// ```
+#[derive(Debug, Clone)]
+pub struct Audit(NewAuditEvent);
+
+impl Audit {
+    pub fn new(event_type: AuditEventType) -> Self {
+        Self(NewAuditEvent {
+            event_type: event_type.as_str().to_string(),
+            metadata: serde_json::json!({}),
+            ..NewAuditEvent::default()
+        })
+    }
+
+    /// Who performed the action; left empty for system actions
+    pub fn actor(mut self, actor_id: Uuid) -> Self {
+        self.0.actor_id = Some(actor_id);
+        self
+    }
+
+    /// Account the event is about
+    pub fn target(mut self, target_id: Uuid) -> Self {
+        self.0.target_id = Some(target_id);
+        self
+    }
+
+    /// Shorthand for an action a user performs on their own account
+    pub fn user(self, user_id: Uuid) -> Self {
+        self.actor(user_id).target(user_id)
+    }
+
+    pub fn client(mut self, client: &ClientInfo) -> Self {
+        self.0.ip_address = client.ip.map(|ip| ip.to_string());
+        self.0.user_agent.clone_from(&client.user_agent);
+        self.0.request_id.clone_from(&client.request_id);
+        self
+    }
+
+    pub fn metadata(mut self, metadata: serde_json::Value) -> Self {
+        self.0.metadata = metadata;
+        self
+    }
+
+    /// Appends the event. Best effort: an audit failure is logged and never fails
+    /// the request that triggered it.
+    pub fn record(self, store: &dyn AuditEventStore) {
+        let _ = store.create(&self.0).inspect_err(|e| {
+            tracing::warn!(event_type = %self.0.event_type, "Failed to write audit event: {e}");
+        });
+    }
+}
+
+/// Returns one page of audit events matching `query`, newest first.
+///
+/// `limit` defaults to 50 and is capped at 200.
+///
+/// # Errors
+///
+/// - [`AppError::InvalidInput`] if the event type is unknown or the cursor is malformed.
+/// - [`AppError::DatabaseError`] on persistence failures.
+pub fn find_page(
+    store: &dyn AuditEventStore,
+    query: &AuditEventQuery,
+) -> Result<AuditEventPage, AppError> {
+    let filter = filter_from(query)?;
+    let cursor = query
+        .cursor
+        .as_deref()
+        .map(decode_history_cursor)
+        .transpose()?;
+    let limit = query
+        .limit
+        .unwrap_or(AUDIT_PAGE_DEFAULT_LIMIT)
+        .clamp(1, AUDIT_PAGE_MAX_LIMIT);
+
+    let mut rows = store.find_page(&filter, cursor, limit + 1)?;
+    let limit = usize::try_from(limit).unwrap_or(usize::MAX);
+    let next_cursor = if rows.len() > limit {
+        rows.truncate(limit);
+        rows.last()
+            .map(|last| encode_history_cursor(last.occurred_at, last.id))
+    } else {
+        None
+    };
+
+    Ok(AuditEventPage {
+        items: rows.into_iter().map(Into::into).collect(),
+        next_cursor,
+    })
+}
+
+/// Exports the events matching `query` as JSON Lines, newest first.
+///
+/// At most 10 000 events are returned at once; when more remain, the second element
+/// is the cursor to resume from. `limit` is ignored.
+///
+/// # Errors
+///
+/// - [`AppError::InvalidInput`] if the event type is unknown or the cursor is malformed.
+/// - [`AppError::DatabaseError`] on persistence failures.
+pub fn export_json_lines(
+    store: &dyn AuditEventStore,
+    query: &AuditEventQuery,
+) -> Result<(String, Option<String>), AppError> {
+    let filter = filter_from(query)?;
+    let mut cursor = query
+        .cursor
+        .as_deref()
+        .map(decode_history_cursor)
+        .transpose()?;
+
+    let mut body = String::new();
+    let mut exported = 0;
+    loop {
+        let batch = store.find_page(&filter, cursor, AUDIT_EXPORT_BATCH_SIZE)?;
+        let Some(last) = batch.last() else {
+            return Ok((body, None));
+        };
+        cursor = Some((last.occurred_at, last.id));
+        let full_batch = i64::try_from(batch.len()).unwrap_or(i64::MAX) == AUDIT_EXPORT_BATCH_SIZE;
+
+        for event in batch {
+            let line = serde_json::to_string(&AuditEventEntry::from(event))
+                .map_err(|e| AppError::internal(e.to_string()))?;
+            body.push_str(&line);
+            body.push('\n');
+            exported += 1;
+        }
+
+        if !full_batch {
+            return Ok((body, None));
+        }
+        if exported >= AUDIT_EXPORT_MAX_ROWS {
+            let next = cursor.map(|(occurred_at, id)| encode_history_cursor(occurred_at, id));
+            return Ok((body, next));
+        }
+    }
+}
+
+fn filter_from(query: &AuditEventQuery) -> Result<AuditEventFilter, AppError> {
+    let event_type = query
+        .event_type
+        .as_deref()
+        .map(|value| {
+            AuditEventType::from_str_opt(value)
+                .map(|t| t.as_str().to_string())
+                .ok_or_else(|| AppError::invalid_input("Unknown audit event type"))
+        })
+        .transpose()?;
+
+    Ok(AuditEventFilter {
+        event_type,
+        actor_id: query.actor_id,
+        target_id: query.target_id,
+        from: query.from,
+        to: query.to,
+    })
+}
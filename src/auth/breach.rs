@@ -0,0 +1,199 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use auth_manager_api::PasswordViolation;
+use sha1::{Digest, Sha1};
+
+use crate::error::AppError;
+
+/// Length of the SHA-1 hex prefix sent to a range source (k-anonymity).
+const PREFIX_LEN: usize = 5;
+
+#[derive(Debug, thiserror::Error)]
+#[error("Breached password source unavailable: {0}")]
+pub struct BreachSourceError(pub String);
+
+/// Breached-password corpus queried by SHA-1 prefix, in the Have I Been Pwned
+/// "range" model: only the first five hex characters of the hash leave the caller,
+/// so a remote implementation never learns the password.
+pub trait BreachedPasswordSource: Send + Sync {
+    /// Returns the hash suffixes (uppercase hex, without the prefix) starting with
+    /// `prefix`, each with the number of times it was seen in breaches.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`BreachSourceError`] if the corpus cannot be read.
+    fn range(&self, prefix: &str) -> Result<Vec<(String, u64)>, BreachSourceError>;
+}
+
+/// Local copy of the corpus: one `<PREFIX>.txt` file per prefix, each line being
+/// `SUFFIX:COUNT` (the layout produced by the official HIBP downloader).
+pub struct FileRangeSource {
+    dir: PathBuf,
+}
+
+impl FileRangeSource {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+impl BreachedPasswordSource for FileRangeSource {
+    fn range(&self, prefix: &str) -> Result<Vec<(String, u64)>, BreachSourceError> {
+        let path = self.dir.join(format!("{prefix}.txt"));
+        match std::fs::read_to_string(&path) {
+            Ok(body) => Ok(parse_range(&body)),
+            // Pas de fichier pour ce préfixe : aucun hash connu
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(BreachSourceError(format!("{}: {e}", path.display()))),
+        }
+    }
+}
+
+/// Parses a range body, skipping malformed lines and padding entries (count 0).
+fn parse_range(body: &str) -> Vec<(String, u64)> {
+    body.lines()
+        .filter_map(|line| {
+            let (suffix, count) = line.trim().split_once(':')?;
+            let count = count.trim().parse::<u64>().ok().filter(|c| *c > 0)?;
+            Some((suffix.trim().to_ascii_uppercase(), count))
+        })
+        .collect()
+}
+
+/// Rejects passwords found in a breached-password corpus.
+#[derive(Clone)]
+pub struct BreachChecker {
+    source: Arc<dyn BreachedPasswordSource>,
+}
+
+impl BreachChecker {
+    pub fn new(source: Arc<dyn BreachedPasswordSource>) -> Self {
+        Self { source }
+    }
+
+    /// Number of times `password` was seen in breaches (0 if never).
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`BreachSourceError`] if the source is unavailable.
+    pub fn breach_count(&self, password: &str) -> Result<u64, BreachSourceError> {
+        let digest = hex::encode_upper(Sha1::digest(password.as_bytes()));
+        let (prefix, suffix) = digest.split_at(PREFIX_LEN);
+        Ok(self
+            .source
+            .range(prefix)?
+            .into_iter()
+            .find(|(candidate, _)| candidate == suffix)
+            .map_or(0, |(_, count)| count))
+    }
+
+    /// Fails with [`AppError::WeakPassword`] if `password` is breached. An
+    /// unavailable source is logged and lets the password through, so an outage of
+    /// the corpus does not block registrations.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::WeakPassword`] if the password appears in the corpus.
+    pub fn check(&self, password: &str) -> Result<(), AppError> {
+        match self.breach_count(password) {
+            Ok(0) => Ok(()),
+            Ok(count) => Err(AppError::WeakPassword(vec![PasswordViolation::Breached {
+                count,
+            }])),
+            Err(e) => {
+                tracing::warn!("{e}; skipping breached password check");
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Corpus built from plain passwords, standing in for a real source in tests.
+#[cfg(test)]
+#[derive(Default)]
+pub struct InMemoryRangeSource {
+    hashes: std::collections::HashMap<String, u64>,
+}
+
+#[cfg(test)]
+impl InMemoryRangeSource {
+    pub fn with_passwords(passwords: &[&str]) -> Self {
+        Self {
+            hashes: passwords
+                .iter()
+                .map(|password| (hex::encode_upper(Sha1::digest(password.as_bytes())), 1))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+impl BreachedPasswordSource for InMemoryRangeSource {
+    fn range(&self, prefix: &str) -> Result<Vec<(String, u64)>, BreachSourceError> {
+        Ok(self
+            .hashes
+            .iter()
+            .filter_map(|(hash, count)| {
+                hash.strip_prefix(prefix)
+                    .map(|suffix| (suffix.to_string(), *count))
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FailingSource;
+
+    impl BreachedPasswordSource for FailingSource {
+        fn range(&self, _prefix: &str) -> Result<Vec<(String, u64)>, BreachSourceError> {
+            Err(BreachSourceError("offline".to_string()))
+        }
+    }
+
+    #[test]
+    fn breached_password_is_rejected_as_weak() {
+        let checker = BreachChecker::new(Arc::new(InMemoryRangeSource::with_passwords(&[
+            "Password123",
+        ])));
+
+        assert!(matches!(
+            checker.check("Password123"),
+            Err(AppError::WeakPassword(violations))
+                if violations == [PasswordViolation::Breached { count: 1 }]
+        ));
+        assert!(checker.check("Unbreached-Horse-Battery-9").is_ok());
+    }
+
+    #[test]
+    fn file_source_reads_prefix_files() {
+        let dir = std::env::temp_dir().join(format!("hibp_{}", uuid::Uuid::new_v4().simple()));
+        std::fs::create_dir_all(&dir).unwrap();
+        // SHA-1("password") = 5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8
+        std::fs::write(
+            dir.join("5BAA6.txt"),
+            "0018A45C4D1DEF81644B54AB7F969B88D65:1\r\n\
+             1E4C9B93F3F0682250B6CF8331B7EE68FD8:9545824\r\n\
+             garbage\r\n\
+             00000000000000000000000000000000000:0\r\n",
+        )
+        .unwrap();
+
+        let checker = BreachChecker::new(Arc::new(FileRangeSource::new(&dir)));
+        assert_eq!(checker.breach_count("password").unwrap(), 9_545_824);
+        assert_eq!(checker.breach_count("not in any file").unwrap(), 0);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn unavailable_source_fails_open() {
+        let checker = BreachChecker::new(Arc::new(FailingSource));
+
+        assert!(checker.breach_count("password").is_err());
+        assert!(checker.check("password").is_ok());
+    }
+}
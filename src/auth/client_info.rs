@@ -0,0 +1,300 @@
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+use axum::extract::{ConnectInfo, FromRequestParts};
+use axum::http::{HeaderMap, header, request::Parts};
+use ipnet::IpNet;
+use lambda_http::request::RequestContext;
+
+use crate::error::AppError;
+
+/// Longest request id accepted from an `X-Request-Id` header.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Proxies whose `X-Forwarded-For` entries are believed.
+///
+/// Installed as a router `Extension`; when absent, no proxy is trusted and the
+/// connecting peer is taken as the client.
+#[derive(Debug, Clone, Default)]
+pub struct TrustedProxies(pub Vec<IpNet>);
+
+impl TrustedProxies {
+    /// Parses a comma-separated list of IPs or CIDR ranges.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first entry that is neither an IP address nor a CIDR range.
+    pub fn parse(list: &str) -> Result<Self, String> {
+        list.split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                entry
+                    .parse::<IpNet>()
+                    .or_else(|_| entry.parse::<IpAddr>().map(IpNet::from))
+                    .map_err(|_| entry.to_string())
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Self)
+    }
+
+    fn contains(&self, ip: &IpAddr) -> bool {
+        self.0.iter().any(|net| net.contains(ip))
+    }
+
+    fn resolve(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        self.walk(peer, &forwarded_hops(headers))
+    }
+
+    /// Behind an ALB, whose address is unknown to us: the right-most entry, appended
+    /// by the ALB, is the peer it saw.
+    fn resolve_behind_alb(&self, headers: &HeaderMap) -> Option<IpAddr> {
+        let hops = forwarded_hops(headers);
+        let (last, earlier) = hops.split_last()?;
+        let peer = last.parse().ok()?;
+        Some(self.walk(peer, earlier))
+    }
+
+    /// Walks `hops` from the right, starting at `peer`, while the current address is a
+    /// trusted proxy. The result is the first address not under our control, which is
+    /// the only one a client cannot forge; an unparsable hop ends the walk, as whatever
+    /// lies left of it was written by the client.
+    fn walk(&self, peer: IpAddr, hops: &[&str]) -> IpAddr {
+        let mut client = peer;
+        for hop in hops.iter().rev() {
+            if !self.contains(&client) {
+                break;
+            }
+            match hop.parse() {
+                Ok(ip) => client = ip,
+                Err(_) => break,
+            }
+        }
+        client
+    }
+}
+
+/// `X-Forwarded-For` entries, left to right, across every occurrence of the header.
+fn forwarded_hops(headers: &HeaderMap) -> Vec<&str> {
+    headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .collect()
+}
+
+/// Request metadata recorded alongside login attempts.
+#[derive(Debug, Clone, Default)]
+pub struct ClientInfo {
+    pub ip: Option<IpAddr>,
+    pub user_agent: Option<String>,
+    pub request_id: Option<String>,
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for ClientInfo {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let trusted = parts
+            .extensions
+            .get::<Arc<TrustedProxies>>()
+            .map(Arc::clone)
+            .unwrap_or_default();
+        let lambda_context = parts.extensions.get::<RequestContext>();
+
+        // Sur Lambda, l'IP source vient du contexte API Gateway ; en local, du socket
+        let peer = lambda_context.and_then(lambda_source_ip).or_else(|| {
+            parts
+                .extensions
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| addr.ip())
+        });
+
+        let user_agent = parts
+            .headers
+            .get(header::USER_AGENT)
+            .and_then(|h| h.to_str().ok())
+            .map(str::to_owned);
+
+        let request_id = lambda_context
+            .and_then(lambda_request_id)
+            .or_else(|| header_request_id(&parts.headers));
+
+        let ip = match peer {
+            Some(peer) => Some(trusted.resolve(peer, &parts.headers)),
+            None if matches!(lambda_context, Some(RequestContext::Alb(_))) => {
+                trusted.resolve_behind_alb(&parts.headers)
+            }
+            None => None,
+        };
+
+        Ok(ClientInfo {
+            ip,
+            user_agent,
+            request_id,
+        })
+    }
+}
+
+fn lambda_source_ip(context: &RequestContext) -> Option<IpAddr> {
+    let source_ip = match context {
+        RequestContext::ApiGatewayV1(ctx) => ctx.identity.source_ip.as_deref(),
+        RequestContext::ApiGatewayV2(ctx) => ctx.http.source_ip.as_deref(),
+        RequestContext::WebSocket(ctx) => ctx.identity.source_ip.as_deref(),
+        // ALB n'expose pas l'IP source : elle n'arrive que via X-Forwarded-For
+        // (voir `TrustedProxies::resolve_behind_alb`)
+        _ => None,
+    };
+    source_ip.and_then(|ip| ip.parse().ok())
+}
+
+fn lambda_request_id(context: &RequestContext) -> Option<String> {
+    match context {
+        RequestContext::ApiGatewayV1(ctx) => ctx.request_id.clone(),
+        RequestContext::ApiGatewayV2(ctx) => ctx.request_id.clone(),
+        RequestContext::WebSocket(ctx) => ctx.request_id.clone(),
+        _ => None,
+    }
+}
+
+fn header_request_id(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get("x-request-id")
+        .and_then(|h| h.to_str().ok())
+        .map(str::trim)
+        .filter(|id| {
+            !id.is_empty()
+                && id.len() <= MAX_REQUEST_ID_LEN
+                && id.chars().all(|c| c.is_ascii_graphic())
+        })
+        .map(str::to_owned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::{HeaderValue, Request};
+
+    fn headers(xff: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", HeaderValue::from_str(xff).unwrap());
+        headers
+    }
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_accepts_ips_and_cidrs() {
+        let proxies = TrustedProxies::parse("10.0.0.0/8, 192.0.2.1,2001:db8::/32").unwrap();
+        assert_eq!(proxies.0.len(), 3);
+        assert!(proxies.contains(&ip("10.1.2.3")));
+        assert!(proxies.contains(&ip("192.0.2.1")));
+        assert!(!proxies.contains(&ip("192.0.2.2")));
+        assert_eq!(
+            TrustedProxies::parse("10.0.0.0/8,nope").unwrap_err(),
+            "nope"
+        );
+    }
+
+    #[test]
+    fn forwarded_for_is_ignored_without_trusted_proxies() {
+        let proxies = TrustedProxies::default();
+        let client = proxies.resolve(ip("198.51.100.7"), &headers("203.0.113.9"));
+        assert_eq!(client, ip("198.51.100.7"));
+    }
+
+    #[test]
+    fn forwarded_for_is_walked_through_trusted_hops_only() {
+        let proxies = TrustedProxies::parse("10.0.0.0/8").unwrap();
+        // Le client a forgé "1.1.1.1"; seul 203.0.113.9 a été ajouté par notre proxy
+        let client = proxies.resolve(ip("10.0.0.2"), &headers("1.1.1.1, 203.0.113.9, 10.0.0.1"));
+        assert_eq!(client, ip("203.0.113.9"));
+    }
+
+    #[test]
+    fn forwarded_for_stops_at_malformed_entry() {
+        let proxies = TrustedProxies::parse("10.0.0.0/8").unwrap();
+        // "garbage" vient du client, notre proxy a ajouté sa vraie adresse après
+        let client = proxies.resolve(ip("10.0.0.2"), &headers("garbage, 203.0.113.9, 10.0.0.1"));
+        assert_eq!(client, ip("203.0.113.9"));
+
+        // Rien d'exploitable derrière le dernier proxy de confiance : on s'arrête à lui
+        let client = proxies.resolve(ip("10.0.0.2"), &headers("203.0.113.9, garbage"));
+        assert_eq!(client, ip("10.0.0.2"));
+    }
+
+    #[tokio::test]
+    async fn extractor_uses_connect_info_and_request_id_header() {
+        let mut request = Request::builder()
+            .header("user-agent", "TestAgent/1.0")
+            .header("x-request-id", "req-123")
+            .header("x-forwarded-for", "203.0.113.9")
+            .body(())
+            .unwrap();
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from(([10, 0, 0, 1], 4000))));
+        request
+            .extensions_mut()
+            .insert(Arc::new(TrustedProxies::parse("10.0.0.0/8").unwrap()));
+        let (mut parts, ()) = request.into_parts();
+
+        let info = ClientInfo::from_request_parts(&mut parts, &())
+            .await
+            .unwrap();
+        assert_eq!(info.ip, Some(ip("203.0.113.9")));
+        assert_eq!(info.user_agent.as_deref(), Some("TestAgent/1.0"));
+        assert_eq!(info.request_id.as_deref(), Some("req-123"));
+    }
+
+    #[tokio::test]
+    async fn extractor_prefers_api_gateway_context() {
+        use lambda_http::aws_lambda_events::apigw::ApiGatewayV2httpRequestContext;
+
+        let mut context = ApiGatewayV2httpRequestContext::default();
+        context.http.source_ip = Some("198.51.100.7".to_string());
+        context.request_id = Some("apigw-req".to_string());
+
+        let mut request = Request::builder()
+            .header("x-request-id", "spoofed")
+            .body(())
+            .unwrap();
+        request
+            .extensions_mut()
+            .insert(RequestContext::ApiGatewayV2(context));
+        let (mut parts, ()) = request.into_parts();
+
+        let info = ClientInfo::from_request_parts(&mut parts, &())
+            .await
+            .unwrap();
+        assert_eq!(info.ip, Some(ip("198.51.100.7")));
+        assert_eq!(info.request_id.as_deref(), Some("apigw-req"));
+    }
+
+    #[tokio::test]
+    async fn extractor_reads_forwarded_for_behind_alb() {
+        use lambda_http::aws_lambda_events::alb::AlbTargetGroupRequestContext;
+
+        let mut request = Request::builder()
+            .header("x-forwarded-for", "garbage, 1.1.1.1, 203.0.113.9, 10.0.0.1")
+            .body(())
+            .unwrap();
+        request
+            .extensions_mut()
+            .insert(RequestContext::Alb(AlbTargetGroupRequestContext::default()));
+        request
+            .extensions_mut()
+            .insert(Arc::new(TrustedProxies::parse("10.0.0.0/8").unwrap()));
+        let (mut parts, ()) = request.into_parts();
+
+        let info = ClientInfo::from_request_parts(&mut parts, &())
+            .await
+            .unwrap();
+        // L'ALB a vu 10.0.0.1 (proxy de confiance), qui a vu 203.0.113.9
+        assert_eq!(info.ip, Some(ip("203.0.113.9")));
+    }
+}
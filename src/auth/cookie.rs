@@ -0,0 +1,303 @@
+//! Cookie du refresh token : attributs configurables (`COOKIE_*`) et construction typée
+//! de l'en-tête `Set-Cookie`.
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use axum::http::{HeaderMap, HeaderValue, header};
+
+use crate::error::AppError;
+
+/// `SameSite` attribute of a cookie.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SameSite {
+    Strict,
+    Lax,
+    /// Sent on cross-site requests too (frontend on another site than the API)
+    #[default]
+    None,
+}
+
+impl SameSite {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Strict => "Strict",
+            Self::Lax => "Lax",
+            Self::None => "None",
+        }
+    }
+}
+
+impl FromStr for SameSite {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "strict" => Ok(Self::Strict),
+            "lax" => Ok(Self::Lax),
+            "none" => Ok(Self::None),
+            _ => Err(format!("expected `strict`, `lax` or `none`, got {s:?}")),
+        }
+    }
+}
+
+/// A `Set-Cookie` header value, built attribute by attribute.
+///
+/// ```ignore
+/// SetCookie::new("refresh_token", hash)
+///     .http_only()
+///     .secure()
+///     .same_site(SameSite::Lax)
+///     .path("/auth/refresh")
+///     .header_value()?
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SetCookie {
+    name: String,
+    value: String,
+    domain: Option<String>,
+    path: Option<String>,
+    max_age: Option<Duration>,
+    same_site: Option<SameSite>,
+    http_only: bool,
+    secure: bool,
+}
+
+impl SetCookie {
+    pub fn new(name: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            value: value.into(),
+            domain: None,
+            path: None,
+            max_age: None,
+            same_site: None,
+            http_only: false,
+            secure: false,
+        }
+    }
+
+    pub fn domain(mut self, domain: impl Into<String>) -> Self {
+        self.domain = Some(domain.into());
+        self
+    }
+
+    pub fn path(mut self, path: impl Into<String>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    pub fn same_site(mut self, same_site: SameSite) -> Self {
+        self.same_site = Some(same_site);
+        self
+    }
+
+    /// Hidden from JavaScript
+    pub fn http_only(mut self) -> Self {
+        self.http_only = true;
+        self
+    }
+
+    /// Only sent over HTTPS (required by browsers with `SameSite=None`)
+    pub fn secure(mut self) -> Self {
+        self.secure = true;
+        self
+    }
+
+    /// The header value.
+    ///
+    /// # Errors
+    ///
+    /// Returns an internal error if an attribute holds characters a header cannot carry.
+    pub fn header_value(&self) -> Result<HeaderValue, AppError> {
+        HeaderValue::from_str(&self.to_string())
+            .map_err(|_| AppError::internal("Failed to set cookie"))
+    }
+}
+
+impl fmt::Display for SetCookie {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.name, self.value)?;
+        if self.http_only {
+            f.write_str("; HttpOnly")?;
+        }
+        if self.secure {
+            f.write_str("; Secure")?;
+        }
+        if let Some(same_site) = self.same_site {
+            write!(f, "; SameSite={}", same_site.as_str())?;
+        }
+        if let Some(domain) = &self.domain {
+            write!(f, "; Domain={domain}")?;
+        }
+        if let Some(path) = &self.path {
+            write!(f, "; Path={path}")?;
+        }
+        if let Some(max_age) = self.max_age {
+            write!(f, "; Max-Age={}", max_age.as_secs())?;
+        }
+        Ok(())
+    }
+}
+
+/// Attributes of the refresh token cookie.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CookieConfig {
+    pub name: String,
+    /// `None`: host-only cookie (sent back to the API host only)
+    pub domain: Option<String>,
+    pub path: String,
+    pub same_site: SameSite,
+    /// `None`: session cookie, dropped when the browser closes
+    pub max_age: Option<Duration>,
+}
+
+impl Default for CookieConfig {
+    fn default() -> Self {
+        Self {
+            name: "refresh_token".to_string(),
+            domain: None,
+            path: "/auth/refresh".to_string(),
+            same_site: SameSite::None,
+            max_age: None,
+        }
+    }
+}
+
+impl CookieConfig {
+    /// Checks that the attributes can be written in a `Set-Cookie` header.
+    ///
+    /// # Errors
+    ///
+    /// Returns a message naming the invalid attribute.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.is_empty() || !self.name.chars().all(is_token_char) {
+            return Err(format!("COOKIE_NAME: invalid cookie name {:?}", self.name));
+        }
+        if let Some(domain) = &self.domain {
+            let labels = domain.strip_prefix('.').unwrap_or(domain);
+            if labels.is_empty()
+                || !labels.split('.').all(|label| {
+                    !label.is_empty()
+                        && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+                })
+            {
+                return Err(format!("COOKIE_DOMAIN: invalid domain {domain:?}"));
+            }
+        }
+        if !self.path.starts_with('/')
+            || self
+                .path
+                .chars()
+                .any(|c| c == ';' || c.is_whitespace() || c.is_control())
+        {
+            return Err(format!("COOKIE_PATH: invalid path {:?}", self.path));
+        }
+        Ok(())
+    }
+
+    /// Cookie holding the refresh token hash: `HttpOnly` and `Secure`, never in the body.
+    pub fn refresh_cookie(&self, refresh_hash: &str) -> SetCookie {
+        let mut cookie = SetCookie::new(&self.name, refresh_hash)
+            .http_only()
+            .secure()
+            .same_site(self.same_site)
+            .path(&self.path);
+        if let Some(domain) = &self.domain {
+            cookie = cookie.domain(domain);
+        }
+        if let Some(max_age) = self.max_age {
+            cookie = cookie.max_age(max_age);
+        }
+        cookie
+    }
+
+    /// Value of the refresh token cookie in the request's `Cookie` headers.
+    pub fn find(&self, headers: &HeaderMap) -> Option<String> {
+        headers
+            .get_all(header::COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(';'))
+            .find_map(|pair| match pair.trim().split_once('=') {
+                Some((name, value)) if name.trim() == self.name => Some(value.trim().to_string()),
+                _ => None,
+            })
+    }
+}
+
+/// RFC 6265 `token`: visible ASCII except separators
+fn is_token_char(c: char) -> bool {
+    c.is_ascii_graphic() && !"()<>@,;:\\\"/[]?={}".contains(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refresh_cookie_carries_configured_attributes() {
+        let default = CookieConfig::default().refresh_cookie("abc");
+        assert_eq!(
+            default.to_string(),
+            "refresh_token=abc; HttpOnly; Secure; SameSite=None; Path=/auth/refresh"
+        );
+
+        let config = CookieConfig {
+            name: "rt".to_string(),
+            domain: Some(".example.com".to_string()),
+            path: "/".to_string(),
+            same_site: SameSite::Lax,
+            max_age: Some(Duration::from_secs(86_400)),
+        };
+        assert_eq!(
+            config.refresh_cookie("abc").to_string(),
+            "rt=abc; HttpOnly; Secure; SameSite=Lax; Domain=.example.com; Path=/; Max-Age=86400"
+        );
+    }
+
+    #[test]
+    fn find_reads_the_configured_cookie() {
+        let config = CookieConfig {
+            name: "rt".to_string(),
+            ..CookieConfig::default()
+        };
+        let mut headers = HeaderMap::new();
+        headers.append(header::COOKIE, HeaderValue::from_static("theme=dark"));
+        headers.append(
+            header::COOKIE,
+            HeaderValue::from_static("a=1; rt=hash; b=2"),
+        );
+
+        assert_eq!(config.find(&headers).as_deref(), Some("hash"));
+        assert_eq!(CookieConfig::default().find(&headers), None);
+    }
+
+    #[test]
+    fn validate_rejects_attributes_that_break_the_header() {
+        for config in [
+            CookieConfig {
+                name: "refresh token".to_string(),
+                ..CookieConfig::default()
+            },
+            CookieConfig {
+                domain: Some("example.com; Secure".to_string()),
+                ..CookieConfig::default()
+            },
+            CookieConfig {
+                path: "auth".to_string(),
+                ..CookieConfig::default()
+            },
+        ] {
+            assert!(config.validate().is_err(), "{config:?}");
+        }
+        assert!(CookieConfig::default().validate().is_ok());
+        assert_eq!("LAX".parse(), Ok(SameSite::Lax));
+    }
+}
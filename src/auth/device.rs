@@ -0,0 +1,150 @@
+use auth_manager_api::{DeviceInfo, DeviceType};
+
+/// Browser families, most specific first: Edge and Opera embed "Chrome",
+/// Chrome embeds "Safari".
+const BROWSERS: &[(&str, &str)] = &[
+    ("Edg/", "Edge"),
+    ("EdgA/", "Edge"),
+    ("OPR/", "Opera"),
+    ("SamsungBrowser/", "Samsung Internet"),
+    ("Firefox/", "Firefox"),
+    ("FxiOS/", "Firefox"),
+    ("CriOS/", "Chrome"),
+    ("Chrome/", "Chrome"),
+    ("Safari/", "Safari"),
+];
+
+/// OS families, most specific first: Android and iOS user agents mention "Linux"
+/// and "Mac OS X" respectively.
+const OPERATING_SYSTEMS: &[(&str, &str)] = &[
+    ("Android", "Android"),
+    ("iPhone", "iOS"),
+    ("iPad", "iPadOS"),
+    ("Windows", "Windows"),
+    ("CrOS", "ChromeOS"),
+    ("Mac OS X", "macOS"),
+    ("Macintosh", "macOS"),
+    ("Linux", "Linux"),
+];
+
+const BOT_MARKERS: &[&str] = &[
+    "bot",
+    "crawler",
+    "spider",
+    "curl/",
+    "wget/",
+    "python-requests",
+];
+
+/// Coarse, dependency-free User-Agent parsing for display in the login history.
+///
+/// This only distinguishes the major families; it is not meant for analytics.
+pub fn parse_user_agent(user_agent: Option<&str>) -> DeviceInfo {
+    let Some(ua) = user_agent.filter(|ua| !ua.trim().is_empty()) else {
+        return DeviceInfo {
+            browser: None,
+            os: None,
+            device_type: DeviceType::Unknown,
+        };
+    };
+
+    let lowered = ua.to_ascii_lowercase();
+    if BOT_MARKERS.iter().any(|marker| lowered.contains(marker)) {
+        return DeviceInfo {
+            browser: None,
+            os: None,
+            device_type: DeviceType::Bot,
+        };
+    }
+
+    let browser = first_match(ua, BROWSERS);
+    let os = first_match(ua, OPERATING_SYSTEMS);
+
+    let device_type = if ua.contains("iPad") || ua.contains("Tablet") {
+        DeviceType::Tablet
+    } else if ua.contains("Mobi") || ua.contains("iPhone") {
+        DeviceType::Mobile
+    } else if ua.contains("Android") {
+        // Android without "Mobile" is a tablet per Google's UA guidelines
+        DeviceType::Tablet
+    } else if os.is_some() {
+        DeviceType::Desktop
+    } else {
+        DeviceType::Unknown
+    };
+
+    DeviceInfo {
+        browser,
+        os,
+        device_type,
+    }
+}
+
+fn first_match(ua: &str, table: &[(&str, &str)]) -> Option<String> {
+    table
+        .iter()
+        .find(|(needle, _)| ua.contains(needle))
+        .map(|(_, name)| (*name).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_desktop_chrome_on_windows() {
+        let info = parse_user_agent(Some(
+            "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
+        ));
+        assert_eq!(info.browser.as_deref(), Some("Chrome"));
+        assert_eq!(info.os.as_deref(), Some("Windows"));
+        assert_eq!(info.device_type, DeviceType::Desktop);
+    }
+
+    #[test]
+    fn parses_edge_before_chrome() {
+        let info = parse_user_agent(Some(
+            "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36 Edg/120.0.0.0",
+        ));
+        assert_eq!(info.browser.as_deref(), Some("Edge"));
+    }
+
+    #[test]
+    fn parses_mobile_safari_on_iphone() {
+        let info = parse_user_agent(Some(
+            "Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.0 Mobile/15E148 Safari/604.1",
+        ));
+        assert_eq!(info.browser.as_deref(), Some("Safari"));
+        assert_eq!(info.os.as_deref(), Some("iOS"));
+        assert_eq!(info.device_type, DeviceType::Mobile);
+    }
+
+    #[test]
+    fn parses_firefox_on_android_phone() {
+        let info = parse_user_agent(Some(
+            "Mozilla/5.0 (Android 14; Mobile; rv:121.0) Gecko/121.0 Firefox/121.0",
+        ));
+        assert_eq!(info.browser.as_deref(), Some("Firefox"));
+        assert_eq!(info.os.as_deref(), Some("Android"));
+        assert_eq!(info.device_type, DeviceType::Mobile);
+    }
+
+    #[test]
+    fn detects_bots_and_cli_clients() {
+        assert_eq!(
+            parse_user_agent(Some("curl/8.4.0")).device_type,
+            DeviceType::Bot
+        );
+        assert_eq!(
+            parse_user_agent(Some("Googlebot/2.1 (+http://www.google.com/bot.html)")).device_type,
+            DeviceType::Bot
+        );
+    }
+
+    #[test]
+    fn missing_user_agent_is_unknown() {
+        let info = parse_user_agent(None);
+        assert_eq!(info.device_type, DeviceType::Unknown);
+        assert!(info.browser.is_none());
+    }
+}
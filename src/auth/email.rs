@@ -0,0 +1,302 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use sha2::{Digest, Sha256};
+use unicode_normalization::UnicodeNormalization;
+
+/// RFC 5321 limits: 64 octets for the local part, 254 for the whole path.
+const MAX_LOCAL_PART_LEN: usize = 64;
+const MAX_EMAIL_LEN: usize = 254;
+const MAX_LABEL_LEN: usize = 63;
+
+/// Characters allowed in an RFC 5322 `atext` besides ASCII alphanumerics.
+const ATEXT_SPECIALS: &str = "!#$%&'*+/=?^_`{|}~-";
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum EmailError {
+    #[error("Email must contain exactly one '@'")]
+    MissingAt,
+    #[error("Email is too long")]
+    TooLong,
+    #[error("Invalid local part")]
+    InvalidLocalPart,
+    #[error("Invalid domain")]
+    InvalidDomain,
+    #[error("Disposable email domains are not allowed")]
+    DisposableDomain,
+}
+
+/// Email syntax rules and normalisation applied at registration and login.
+///
+/// Accepted addresses follow the RFC 5322 `dot-atom` form (quoted local parts and
+/// address literals are rejected). Internationalised domains are converted to their
+/// ASCII (punycode) form, which is lowercase by construction.
+#[derive(Debug, Clone, Default)]
+pub struct EmailPolicy {
+    /// Lowercase the local part as well as the domain. Lookups and uniqueness ignore
+    /// case either way: this only changes the address stored, shown and mailed to.
+    pub fold_local_part: bool,
+    /// Domains (and their subdomains) refused at registration.
+    pub blocked_domains: HashSet<String>,
+}
+
+impl EmailPolicy {
+    /// Loads a disposable-domain blocklist: one domain per line, `#` starts a comment.
+    ///
+    /// # Errors
+    ///
+    /// Returns an I/O error if the file cannot be read.
+    pub fn load_blocklist(path: &Path) -> std::io::Result<HashSet<String>> {
+        let contents = std::fs::read_to_string(path)?;
+        Ok(contents
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or_default().trim())
+            .filter(|line| !line.is_empty())
+            .filter_map(|domain| idna::domain_to_ascii(domain).ok())
+            .collect())
+    }
+
+    /// Validates `raw` and returns its normalised form.
+    ///
+    /// Does not consult the blocklist; see [`EmailPolicy::check_allowed`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`EmailError`] describing the first syntax problem found.
+    pub fn normalize(&self, raw: &str) -> Result<String, EmailError> {
+        let raw = raw.trim();
+        let (local, domain) = match raw.rsplit_once('@') {
+            Some((local, domain)) if !local.contains('@') => (local, domain),
+            _ => return Err(EmailError::MissingAt),
+        };
+
+        let local: String = local.nfc().collect();
+        if !is_valid_local_part(&local) {
+            return Err(EmailError::InvalidLocalPart);
+        }
+        let local = if self.fold_local_part {
+            local.to_lowercase()
+        } else {
+            local
+        };
+
+        let domain = normalize_domain(domain)?;
+
+        let email = format!("{local}@{domain}");
+        if email.len() > MAX_EMAIL_LEN {
+            return Err(EmailError::TooLong);
+        }
+        Ok(email)
+    }
+
+    /// Rejects normalised addresses whose domain (or a parent domain) is blocklisted.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`EmailError::DisposableDomain`] on a blocklist match.
+    pub fn check_allowed(&self, normalized: &str) -> Result<(), EmailError> {
+        let Some((_, mut domain)) = normalized.rsplit_once('@') else {
+            return Err(EmailError::MissingAt);
+        };
+        loop {
+            if self.blocked_domains.contains(domain) {
+                return Err(EmailError::DisposableDomain);
+            }
+            match domain.split_once('.') {
+                Some((_, parent)) if parent.contains('.') => domain = parent,
+                _ => return Ok(()),
+            }
+        }
+    }
+}
+
+/// Stable, non-reversible key for an attempted email: hex SHA-256 of the lowercased
+/// normalised address. Lets admins look up failed logins against unknown emails
+/// without storing the addresses themselves.
+pub fn email_hash(normalized: &str) -> String {
+    hex::encode(Sha256::digest(normalized.to_lowercase().as_bytes()))
+}
+
+fn is_valid_local_part(local: &str) -> bool {
+    if local.is_empty() || local.len() > MAX_LOCAL_PART_LEN {
+        return false;
+    }
+    // dot-atom: atoms separated by single dots, no leading/trailing dot
+    local.split('.').all(|atom| {
+        !atom.is_empty()
+            && atom.chars().all(|c| {
+                c.is_ascii_alphanumeric()
+                    || ATEXT_SPECIALS.contains(c)
+                    // RFC 6531: UTF-8 letters and digits are allowed in the local part
+                    || (!c.is_ascii() && c.is_alphanumeric())
+            })
+    })
+}
+
+fn normalize_domain(domain: &str) -> Result<String, EmailError> {
+    let ascii = idna::domain_to_ascii(domain).map_err(|_| EmailError::InvalidDomain)?;
+
+    let labels: Vec<&str> = ascii.split('.').collect();
+    if labels.len() < 2 {
+        return Err(EmailError::InvalidDomain);
+    }
+    let labels_ok = labels.iter().all(|label| {
+        !label.is_empty()
+            && label.len() <= MAX_LABEL_LEN
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    });
+    // A purely numeric TLD means an IP address or a typo, never a mail domain
+    let tld_ok = labels
+        .last()
+        .is_some_and(|tld| !tld.chars().all(|c| c.is_ascii_digit()));
+    if !labels_ok || !tld_ok {
+        return Err(EmailError::InvalidDomain);
+    }
+
+    Ok(ascii)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> EmailPolicy {
+        EmailPolicy::default()
+    }
+
+    #[test]
+    fn normalize_lowercases_domain_only_by_default() {
+        assert_eq!(
+            policy().normalize("Foo.Bar@Example.COM").unwrap(),
+            "Foo.Bar@example.com"
+        );
+    }
+
+    #[test]
+    fn normalize_folds_local_part_when_configured() {
+        let policy = EmailPolicy {
+            fold_local_part: true,
+            ..EmailPolicy::default()
+        };
+        assert_eq!(
+            policy.normalize("Foo@Example.com").unwrap(),
+            "foo@example.com"
+        );
+    }
+
+    #[test]
+    fn normalize_accepts_plus_addressing_and_specials() {
+        assert!(policy().normalize("user+tag@example.com").is_ok());
+        assert!(policy().normalize("o'brien@example.co.uk").is_ok());
+    }
+
+    #[test]
+    fn normalize_converts_idn_domain_to_punycode() {
+        assert_eq!(
+            policy().normalize("user@Bücher.example").unwrap(),
+            "user@xn--bcher-kva.example"
+        );
+    }
+
+    #[test]
+    fn normalize_rejects_missing_or_multiple_at() {
+        assert_eq!(
+            policy().normalize("invalid-email"),
+            Err(EmailError::MissingAt)
+        );
+        assert_eq!(
+            policy().normalize("a@b@example.com"),
+            Err(EmailError::MissingAt)
+        );
+    }
+
+    #[test]
+    fn normalize_rejects_bad_local_parts() {
+        for email in [
+            "@example.com",
+            ".user@example.com",
+            "user.@example.com",
+            "us..er@example.com",
+            "us er@example.com",
+            "\"quoted\"@example.com",
+        ] {
+            assert_eq!(
+                policy().normalize(email),
+                Err(EmailError::InvalidLocalPart),
+                "{email}"
+            );
+        }
+    }
+
+    #[test]
+    fn normalize_rejects_bad_domains() {
+        for email in [
+            "user@localhost",
+            "user@-example.com",
+            "user@example..com",
+            "user@exa_mple.com",
+            "user@127.0.0.1",
+            "user@[127.0.0.1]",
+        ] {
+            assert_eq!(
+                policy().normalize(email),
+                Err(EmailError::InvalidDomain),
+                "{email}"
+            );
+        }
+    }
+
+    #[test]
+    fn normalize_rejects_overlong_local_part() {
+        let email = format!("{}@example.com", "a".repeat(65));
+        assert_eq!(
+            policy().normalize(&email),
+            Err(EmailError::InvalidLocalPart)
+        );
+    }
+
+    #[test]
+    fn check_allowed_rejects_blocked_domain_and_subdomains() {
+        let policy = EmailPolicy {
+            blocked_domains: HashSet::from(["mailinator.com".to_string()]),
+            ..EmailPolicy::default()
+        };
+        assert_eq!(
+            policy.check_allowed("a@mailinator.com"),
+            Err(EmailError::DisposableDomain)
+        );
+        assert_eq!(
+            policy.check_allowed("a@eu.mailinator.com"),
+            Err(EmailError::DisposableDomain)
+        );
+        assert!(policy.check_allowed("a@example.com").is_ok());
+    }
+
+    #[test]
+    fn email_hash_is_case_insensitive_hex_sha256() {
+        let hash = email_hash("Foo@example.com");
+        assert_eq!(hash, email_hash("foo@example.com"));
+        assert_eq!(hash.len(), 64);
+        assert!(hash.chars().all(|c| c.is_ascii_hexdigit()));
+    }
+
+    #[test]
+    fn load_blocklist_skips_comments_and_blank_lines() {
+        let path = std::env::temp_dir().join(format!("blocklist_{}.txt", uuid::Uuid::new_v4()));
+        std::fs::write(
+            &path,
+            "# disposable\nMailinator.com\n\ntrashmail.de # inline\n",
+        )
+        .unwrap();
+
+        let domains = EmailPolicy::load_blocklist(&path).expect("readable file");
+        assert_eq!(
+            domains,
+            HashSet::from(["mailinator.com".to_string(), "trashmail.de".to_string()])
+        );
+
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -1,7 +1,8 @@
-use axum::extract::FromRequestParts;
+use axum::extract::{FromRef, FromRequestParts};
 use axum::http::{header, request::Parts};
 
 use crate::auth::jwt::{Claims, JwtManager};
+use crate::db::store::Repositories;
 use crate::error::AppError;
 
 /// Extracteur d'authentification pour les routes protégées.
@@ -32,38 +33,103 @@ impl From<Claims> for AuthClaims {
     }
 }
 
-/// Implémentation de l'extracteur pour un router ayant `JwtManager` comme state.
-impl FromRequestParts<JwtManager> for AuthClaims {
+/// Valide `Authorization: Bearer <JWT>` et retourne les claims du token.
+fn bearer_claims(parts: &Parts, jwt_manager: &JwtManager) -> Result<Claims, AppError> {
+    const BEARER: &str = "Bearer ";
+
+    // Récupère le header Authorization
+    let auth_header = parts
+        .headers
+        .get(header::AUTHORIZATION)
+        .ok_or(AppError::InvalidTokenFormat)?;
+
+    let auth_str = auth_header
+        .to_str()
+        .map_err(|_| AppError::InvalidTokenFormat)?;
+
+    // Doit être de type Bearer
+    if !auth_str.starts_with(BEARER) {
+        return Err(AppError::InvalidTokenFormat);
+    }
+
+    let token = &auth_str[BEARER.len()..];
+
+    // Vérifie et décode le token
+    jwt_manager
+        .verify_token(token)
+        .map_err(|_| AppError::unauthorized("Invalid token"))
+}
+
+/// Implémentation de l'extracteur pour tout state exposant un `JwtManager`.
+/// Un token émis pour un mot de passe expiré est refusé.
+impl<S> FromRequestParts<S> for AuthClaims
+where
+    JwtManager: FromRef<S>,
+    S: Send + Sync,
+{
     type Rejection = AppError;
 
-    async fn from_request_parts(
-        parts: &mut Parts,
-        jwt_manager: &JwtManager,
-    ) -> Result<Self, Self::Rejection> {
-        const BEARER: &str = "Bearer ";
-
-        // Récupère le header Authorization
-        let auth_header = parts
-            .headers
-            .get(header::AUTHORIZATION)
-            .ok_or(AppError::InvalidTokenFormat)?;
-
-        let auth_str = auth_header
-            .to_str()
-            .map_err(|_| AppError::InvalidTokenFormat)?;
-
-        // Doit être de type Bearer
-        if !auth_str.starts_with(BEARER) {
-            return Err(AppError::InvalidTokenFormat);
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let claims = bearer_claims(parts, &JwtManager::from_ref(state))?;
+        if claims.password_change_required {
+            return Err(AppError::PasswordChangeRequired);
         }
+        Ok(AuthClaims::from(claims))
+    }
+}
 
-        let token = &auth_str[BEARER.len()..];
+/// Extracteur des routes encore accessibles avec un mot de passe expiré
+/// (changement de mot de passe, déconnexion) : accepte aussi les tokens restreints.
+#[derive(Debug, Clone)]
+pub struct PasswordChangeClaims {
+    pub sub: uuid::Uuid,
+}
 
-        // Vérifie et décode le token
-        let claims = jwt_manager
-            .verify_token(token)
-            .map_err(|_| AppError::unauthorized("Invalid token"))?;
+impl<S> FromRequestParts<S> for PasswordChangeClaims
+where
+    JwtManager: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
 
-        Ok(AuthClaims::from(claims))
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let claims = bearer_claims(parts, &JwtManager::from_ref(state))?;
+        Ok(PasswordChangeClaims { sub: claims.sub })
+    }
+}
+
+/// Extracteur pour les routes d'administration.
+/// Valide le JWT comme `AuthClaims`, puis vérifie en base que l'utilisateur
+/// est toujours actif et administrateur (le rôle n'est pas porté par le token).
+#[derive(Debug, Clone)]
+pub struct AdminClaims {
+    pub sub: uuid::Uuid,
+}
+
+impl<S> FromRequestParts<S> for AdminClaims
+where
+    JwtManager: FromRef<S>,
+    Repositories: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let claims = AuthClaims::from_request_parts(parts, state).await?;
+
+        let users = Repositories::from_ref(state).users;
+        let user = crate::blocking::run(move || {
+            users
+                .find_by_id(claims.sub)
+                .map_err(AppError::from)?
+                .ok_or_else(|| AppError::unauthorized("Invalid token"))
+        })
+        .await?;
+
+        if !user.is_active || !user.is_admin {
+            return Err(AppError::forbidden("Administrator access required"));
+        }
+
+        Ok(AdminClaims { sub: claims.sub })
     }
 }
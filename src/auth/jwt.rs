@@ -1,4 +1,5 @@
 use chrono::{Duration, Utc};
+use jsonwebtoken::errors::ErrorKind;
 use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation, decode, encode};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -16,12 +17,17 @@ pub struct Claims {
     pub sub: Uuid,
     pub exp: i64,
     pub iat: i64,
+    /// Token issued for an expired password: only good for changing it
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub password_change_required: bool,
 }
 
 #[derive(Clone)]
 pub struct JwtManager {
     encoding_key: EncodingKey,
     decoding_key: DecodingKey,
+    /// Secret replaced by the last rotation, still accepted until its tokens expire
+    previous_decoding_key: Option<DecodingKey>,
     expiration_hours: i64,
 }
 
@@ -31,10 +37,18 @@ impl JwtManager {
         Self {
             encoding_key: EncodingKey::from_secret(secret.as_ref()),
             decoding_key: DecodingKey::from_secret(secret.as_ref()),
+            previous_decoding_key: None,
             expiration_hours,
         }
     }
 
+    /// Also accepts tokens signed with `secret`, the one in use before a rotation.
+    #[must_use]
+    pub fn with_previous_secret(mut self, secret: &str) -> Self {
+        self.previous_decoding_key = Some(DecodingKey::from_secret(secret.as_ref()));
+        self
+    }
+
     /// Generates an access token using the configured expiration duration.
     ///
     /// # Errors
@@ -44,6 +58,15 @@ impl JwtManager {
         self.generate_token(user_id, self.expiration_hours)
     }
 
+    /// Generates an access token that only allows changing an expired password.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`JwtError::GenerationFailed`] if token encoding fails.
+    pub fn generate_password_change_token(&self, user_id: Uuid) -> Result<String, JwtError> {
+        self.encode_claims(user_id, self.expiration_hours, true)
+    }
+
     /// Returns the configured token lifetime in hours.
     pub fn expiration_hours(&self) -> i64 {
         self.expiration_hours
@@ -55,6 +78,15 @@ impl JwtManager {
     ///
     /// Returns [`JwtError::GenerationFailed`] if token encoding fails.
     pub fn generate_token(&self, user_id: Uuid, expires_in_hours: i64) -> Result<String, JwtError> {
+        self.encode_claims(user_id, expires_in_hours, false)
+    }
+
+    fn encode_claims(
+        &self,
+        user_id: Uuid,
+        expires_in_hours: i64,
+        password_change_required: bool,
+    ) -> Result<String, JwtError> {
         let now = Utc::now();
         let exp = (now + Duration::hours(expires_in_hours)).timestamp();
 
@@ -62,6 +94,7 @@ impl JwtManager {
             sub: user_id,
             exp,
             iat: now.timestamp(),
+            password_change_required,
         };
 
         encode(&Header::default(), &claims, &self.encoding_key).map_err(JwtError::GenerationFailed)
@@ -73,7 +106,17 @@ impl JwtManager {
     ///
     /// Returns [`JwtError::VerificationFailed`] if the token is invalid, expired, or tampered with.
     pub fn verify_token(&self, token: &str) -> Result<Claims, JwtError> {
-        decode(token, &self.decoding_key, &Validation::default())
+        let validation = Validation::default();
+        let result = match (
+            decode(token, &self.decoding_key, &validation),
+            &self.previous_decoding_key,
+        ) {
+            (Err(e), Some(previous)) if *e.kind() == ErrorKind::InvalidSignature => {
+                decode(token, previous, &validation)
+            }
+            (result, _) => result,
+        };
+        result
             .map(|data| data.claims)
             .map_err(JwtError::VerificationFailed)
     }
@@ -135,6 +178,37 @@ mod tests {
         );
     }
 
+    #[test]
+    fn password_change_token_is_flagged() {
+        let jwt = make_jwt_manager();
+        let user_id = Uuid::new_v4();
+
+        let restricted = jwt.generate_password_change_token(user_id).unwrap();
+        assert!(
+            jwt.verify_token(&restricted)
+                .unwrap()
+                .password_change_required
+        );
+
+        let regular = jwt.generate_access_token(user_id).unwrap();
+        assert!(!jwt.verify_token(&regular).unwrap().password_change_required);
+    }
+
+    #[test]
+    fn previous_secret_is_accepted_after_rotation() {
+        let old = JwtManager::new("old_secret_key", 1);
+        let rotated = JwtManager::new("new_secret_key", 1).with_previous_secret("old_secret_key");
+        let user_id = Uuid::new_v4();
+
+        let token = old.generate_access_token(user_id).unwrap();
+        assert_eq!(rotated.verify_token(&token).unwrap().sub, user_id);
+        assert!(make_jwt_manager().verify_token(&token).is_err());
+
+        // Les nouveaux jetons sont signés avec le nouveau secret
+        let fresh = rotated.generate_access_token(user_id).unwrap();
+        assert!(old.verify_token(&fresh).is_err());
+    }
+
     #[test]
     fn verify_token_fails_with_invalid_input() {
         let jwt = make_jwt_manager();
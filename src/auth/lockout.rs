@@ -0,0 +1,256 @@
+use chrono::{DateTime, Duration, Utc};
+
+use crate::db::models::user::LockoutState;
+
+/// Why a login is currently refused for an account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lock {
+    /// Refused until the given time (exponential delay or lockout tier)
+    Until(DateTime<Utc>),
+    /// Refused until an administrator or the emailed unlock link lifts it
+    Permanent,
+}
+
+/// A lock newly triggered by a failed login, worth notifying the owner about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockEvent {
+    /// Lockout tier `level` (1-based) reached
+    Tier {
+        level: i32,
+        until: DateTime<Utc>,
+    },
+    Permanent,
+}
+
+/// Per-account lockout rules.
+///
+/// Each failed password check delays the next attempt by `backoff_base`, doubled
+/// per consecutive failure and capped at `backoff_max`. After `threshold` failures
+/// (forgotten once older than `failure_window`) the account is locked for the next
+/// duration in `tiers`; once every tier has been used it is locked permanently if
+/// `permanent_after_tiers`, otherwise it stays on the last tier. A successful login
+/// resets everything.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockoutPolicy {
+    pub threshold: u32,
+    pub failure_window: Duration,
+    pub backoff_base: Duration,
+    pub backoff_max: Duration,
+    pub tiers: Vec<Duration>,
+    pub permanent_after_tiers: bool,
+}
+
+impl Default for LockoutPolicy {
+    fn default() -> Self {
+        Self {
+            threshold: 5,
+            failure_window: Duration::minutes(15),
+            backoff_base: Duration::seconds(1),
+            backoff_max: Duration::seconds(30),
+            tiers: vec![
+                Duration::minutes(15),
+                Duration::hours(1),
+                Duration::hours(24),
+            ],
+            permanent_after_tiers: true,
+        }
+    }
+}
+
+impl LockoutPolicy {
+    /// Returns the lock currently preventing a login, if any.
+    pub fn current_lock(state: &LockoutState, now: DateTime<Utc>) -> Option<Lock> {
+        if state.locked_permanently {
+            return Some(Lock::Permanent);
+        }
+        state
+            .locked_until
+            .filter(|until| *until > now)
+            .map(Lock::Until)
+    }
+
+    /// Computes the state after a failed password check, and the lock it
+    /// triggers if the threshold was reached.
+    pub fn register_failure(
+        &self,
+        state: LockoutState,
+        now: DateTime<Utc>,
+    ) -> (LockoutState, Option<LockEvent>) {
+        let recent = state
+            .last_failed_login_at
+            .is_some_and(|at| now - at <= self.failure_window);
+        let failures = if recent {
+            state.failed_login_count.saturating_add(1)
+        } else {
+            1
+        };
+
+        if u32::try_from(failures).unwrap_or(u32::MAX) < self.threshold {
+            let next = LockoutState {
+                failed_login_count: failures,
+                locked_until: Some(now + self.backoff(failures)),
+                last_failed_login_at: Some(now),
+                ..state
+            };
+            return (next, None);
+        }
+
+        let level = state.lockout_level.saturating_add(1);
+        let tier = usize::try_from(level - 1)
+            .ok()
+            .and_then(|index| self.tiers.get(index))
+            .or_else(|| {
+                // Tous les paliers épuisés : on reste sur le dernier sauf verrouillage définitif
+                (!self.permanent_after_tiers)
+                    .then(|| self.tiers.last())
+                    .flatten()
+            });
+        let (locked_until, locked_permanently, event) = match tier {
+            Some(duration) => {
+                let until = now + *duration;
+                (Some(until), false, LockEvent::Tier { level, until })
+            }
+            None => (None, true, LockEvent::Permanent),
+        };
+
+        let next = LockoutState {
+            failed_login_count: 0,
+            lockout_level: level,
+            locked_until,
+            locked_permanently,
+            last_failed_login_at: Some(now),
+        };
+        (next, Some(event))
+    }
+
+    /// Delay imposed after the `failures`-th consecutive failure.
+    fn backoff(&self, failures: i32) -> Duration {
+        let exponent = u32::try_from(failures.saturating_sub(1))
+            .unwrap_or(0)
+            .min(30);
+        self.backoff_base
+            .checked_mul(1 << exponent)
+            .map_or(self.backoff_max, |delay| delay.min(self.backoff_max))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fail_times(
+        policy: &LockoutPolicy,
+        mut state: LockoutState,
+        times: u32,
+        now: DateTime<Utc>,
+    ) -> (LockoutState, Option<LockEvent>) {
+        let mut event = None;
+        for _ in 0..times {
+            (state, event) = policy.register_failure(state, now);
+        }
+        (state, event)
+    }
+
+    #[test]
+    fn failures_below_threshold_apply_exponential_backoff() {
+        let policy = LockoutPolicy::default();
+        let now = Utc::now();
+
+        let (state, event) = fail_times(&policy, LockoutState::default(), 1, now);
+        assert!(event.is_none());
+        assert_eq!(state.locked_until, Some(now + Duration::seconds(1)));
+
+        let (state, _) = fail_times(&policy, state, 2, now);
+        assert_eq!(state.failed_login_count, 3);
+        assert_eq!(state.locked_until, Some(now + Duration::seconds(4)));
+        assert_eq!(
+            LockoutPolicy::current_lock(&state, now),
+            Some(Lock::Until(now + Duration::seconds(4)))
+        );
+        assert_eq!(
+            LockoutPolicy::current_lock(&state, now + Duration::seconds(5)),
+            None
+        );
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        let policy = LockoutPolicy {
+            threshold: 100,
+            ..LockoutPolicy::default()
+        };
+        let now = Utc::now();
+        let (state, _) = fail_times(&policy, LockoutState::default(), 40, now);
+        assert_eq!(state.locked_until, Some(now + Duration::seconds(30)));
+    }
+
+    #[test]
+    fn threshold_triggers_progressive_tiers_then_permanent_lock() {
+        let policy = LockoutPolicy::default();
+        let now = Utc::now();
+
+        let (state, event) = fail_times(&policy, LockoutState::default(), 5, now);
+        assert_eq!(
+            event,
+            Some(LockEvent::Tier {
+                level: 1,
+                until: now + Duration::minutes(15),
+            })
+        );
+        assert_eq!(state.failed_login_count, 0);
+
+        let (state, event) = fail_times(&policy, state, 5, now);
+        assert_eq!(
+            event,
+            Some(LockEvent::Tier {
+                level: 2,
+                until: now + Duration::hours(1),
+            })
+        );
+
+        let (state, event) = fail_times(&policy, state, 5, now);
+        assert!(matches!(event, Some(LockEvent::Tier { level: 3, .. })));
+
+        let (state, event) = fail_times(&policy, state, 5, now);
+        assert_eq!(event, Some(LockEvent::Permanent));
+        assert_eq!(
+            LockoutPolicy::current_lock(&state, now),
+            Some(Lock::Permanent)
+        );
+        assert_eq!(
+            LockoutPolicy::current_lock(&state, now + Duration::days(365)),
+            Some(Lock::Permanent)
+        );
+    }
+
+    #[test]
+    fn last_tier_repeats_when_permanent_lock_is_disabled() {
+        let policy = LockoutPolicy {
+            permanent_after_tiers: false,
+            tiers: vec![Duration::minutes(15)],
+            ..LockoutPolicy::default()
+        };
+        let now = Utc::now();
+        let (state, _) = fail_times(&policy, LockoutState::default(), 5, now);
+        let (state, event) = fail_times(&policy, state, 5, now);
+        assert_eq!(
+            event,
+            Some(LockEvent::Tier {
+                level: 2,
+                until: now + Duration::minutes(15),
+            })
+        );
+        assert!(!state.locked_permanently);
+    }
+
+    #[test]
+    fn old_failures_are_forgotten() {
+        let policy = LockoutPolicy::default();
+        let earlier = Utc::now() - Duration::hours(1);
+        let (state, _) = fail_times(&policy, LockoutState::default(), 4, earlier);
+
+        let (state, event) = policy.register_failure(state, Utc::now());
+        assert!(event.is_none());
+        assert_eq!(state.failed_login_count, 1);
+    }
+}
@@ -1,4 +1,14 @@
+pub mod audit;
+pub mod breach;
+pub mod client_info;
+pub mod cookie;
+pub mod device;
+pub mod email;
 pub mod extractors;
 pub mod jwt;
+pub mod lockout;
 pub mod password;
+pub mod rate_limit;
+pub mod risk;
 pub mod services;
+pub mod username;
@@ -1,46 +1,323 @@
-use bcrypt::{DEFAULT_COST, hash, verify};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{LazyLock, OnceLock};
+
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{self, PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::{Algorithm, Argon2, Params, Version};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+/// Argon2id parameters set at startup by [`PasswordManager::configure`].
+static PARAMS: OnceLock<Params> = OnceLock::new();
+
+/// Pepper keys set at startup by [`PasswordManager::configure`].
+static PEPPERS: OnceLock<Peppers> = OnceLock::new();
+
+/// Hash checked when there is no real one, so unknown accounts cost as much as known ones.
+static DUMMY_HASH: LazyLock<Option<HashedPassword>> =
+    LazyLock::new(|| PasswordManager::hash("dummy password, never matches").ok());
 
 #[derive(Debug, thiserror::Error)]
 pub enum PasswordError {
     #[error("Password hashing failed: {0}")]
-    HashingFailed(bcrypt::BcryptError),
+    HashingFailed(String),
     #[error("Password verification failed: {0}")]
-    VerificationFailed(bcrypt::BcryptError),
+    VerificationFailed(String),
+    #[error("Invalid Argon2 parameters: {0}")]
+    InvalidParams(String),
+    #[error("Unsupported password hash format")]
+    UnsupportedHash,
+    #[error("Unknown password pepper version {0}")]
+    UnknownPepper(i32),
+    #[error("Password peppers already configured with different keys")]
+    PeppersAlreadyConfigured,
+}
+
+/// A password hash and the version of the pepper mixed into it, stored together.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HashedPassword {
+    pub hash: String,
+    /// `None` when the password was hashed without a pepper
+    pub pepper_version: Option<i32>,
+}
+
+/// Server-side secret keys, by version, used to HMAC passwords before hashing.
+///
+/// A database dump alone is then useless for offline guessing. New hashes use
+/// the current key; older versions are kept only to verify existing hashes until
+/// they are re-peppered at the next login.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Peppers {
+    current: Option<i32>,
+    keys: HashMap<i32, Vec<u8>>,
+}
+
+impl fmt::Debug for Peppers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut versions: Vec<_> = self.keys.keys().collect();
+        versions.sort();
+        f.debug_struct("Peppers")
+            .field("current", &self.current)
+            .field("versions", &versions)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Peppers {
+    /// Uses `key` as pepper `version` for new hashes.
+    pub fn new(version: i32, key: impl Into<Vec<u8>>) -> Self {
+        Self {
+            current: Some(version),
+            keys: HashMap::from([(version, key.into())]),
+        }
+    }
+
+    /// Keeps a retired pepper to verify hashes made with it.
+    #[must_use]
+    pub fn with_previous(mut self, version: i32, key: impl Into<Vec<u8>>) -> Self {
+        self.keys.entry(version).or_insert_with(|| key.into());
+        self
+    }
+
+    /// Version of the pepper applied to new hashes, if any.
+    pub fn current_version(&self) -> Option<i32> {
+        self.current
+    }
+
+    fn current(&self) -> Option<(i32, &[u8])> {
+        let version = self.current?;
+        self.keys.get(&version).map(|key| (version, key.as_slice()))
+    }
+
+    /// Password bytes fed to the hash function for a given pepper version.
+    fn apply<'a>(
+        &self,
+        password: &'a str,
+        version: Option<i32>,
+    ) -> Result<Cow<'a, [u8]>, PasswordError> {
+        let Some(version) = version else {
+            return Ok(Cow::Borrowed(password.as_bytes()));
+        };
+        let key = self
+            .keys
+            .get(&version)
+            .ok_or(PasswordError::UnknownPepper(version))?;
+        let mut mac = Hmac::<Sha256>::new_from_slice(key)
+            .map_err(|e| PasswordError::HashingFailed(e.to_string()))?;
+        mac.update(password.as_bytes());
+        Ok(Cow::Owned(mac.finalize().into_bytes().to_vec()))
+    }
+}
+
+/// Argon2id cost parameters. Defaults follow the OWASP recommendation
+/// (19 MiB, 2 iterations, 1 lane).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Argon2Config {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
 }
 
-/// Stateless helper for bcrypt password hashing and verification.
+impl Default for Argon2Config {
+    fn default() -> Self {
+        Self {
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+impl Argon2Config {
+    fn params(self) -> Result<Params, PasswordError> {
+        Params::new(self.memory_kib, self.iterations, self.parallelism, None)
+            .map_err(|e| PasswordError::InvalidParams(e.to_string()))
+    }
+}
+
+/// Stateless helper for password hashing and verification.
+///
+/// New hashes are Argon2id PHC strings (`$argon2id$v=19$m=…,t=…,p=…$salt$hash`),
+/// computed over an HMAC of the password when a pepper is configured.
+/// Legacy bcrypt hashes (`$2b$…`) are still verified, and [`needs_rehash`](Self::needs_rehash)
+/// tells when a stored hash should be replaced after a successful login.
+///
+/// Computations are synchronous and CPU-bound: handlers call them through
+/// [`blocking::run_hashing`](crate::blocking::run_hashing), which bounds how many run
+/// at the same time.
 pub struct PasswordManager;
 
 impl PasswordManager {
-    /// Hashes `password` with bcrypt at the default cost factor.
+    /// Sets the Argon2id parameters and peppers used for new hashes. Call once at
+    /// startup; later parameters are ignored. Without it, [`Argon2Config::default`] is
+    /// used and passwords are not peppered.
     ///
     /// # Errors
     ///
-    /// Returns [`PasswordError::HashingFailed`] if bcrypt fails.
-    pub fn hash(password: &str) -> Result<String, PasswordError> {
-        hash(password, DEFAULT_COST).map_err(PasswordError::HashingFailed)
+    /// Returns [`PasswordError::InvalidParams`] if the parameters are out of range, and
+    /// [`PasswordError::PeppersAlreadyConfigured`] if other peppers are already in use
+    /// (hashing on without the configured pepper is not an option).
+    pub fn configure(config: Argon2Config, peppers: Peppers) -> Result<(), PasswordError> {
+        Self::configure_cells(&PARAMS, &PEPPERS, config, peppers)
     }
 
-    /// Returns `true` if `password` matches the bcrypt `hash`.
+    fn configure_cells(
+        params_cell: &OnceLock<Params>,
+        peppers_cell: &OnceLock<Peppers>,
+        config: Argon2Config,
+        peppers: Peppers,
+    ) -> Result<(), PasswordError> {
+        let params = config.params()?;
+        // Chaque cellule est renseignée indépendamment : des paramètres déjà fixés
+        // n'empêchent pas d'installer les peppers
+        if let Err(peppers) = peppers_cell.set(peppers)
+            && peppers_cell.get() != Some(&peppers)
+        {
+            return Err(PasswordError::PeppersAlreadyConfigured);
+        }
+        if params_cell.set(params).is_err() {
+            tracing::warn!("Argon2 parameters already configured, ignoring");
+        }
+        Ok(())
+    }
+
+    fn params() -> Params {
+        PARAMS.get().cloned().unwrap_or_default()
+    }
+
+    fn peppers() -> &'static Peppers {
+        static NONE: LazyLock<Peppers> = LazyLock::new(Peppers::default);
+        PEPPERS.get().unwrap_or(&NONE)
+    }
+
+    /// Hashes `password` with Argon2id, a random salt and the current pepper.
     ///
     /// # Errors
     ///
-    /// Returns [`PasswordError::VerificationFailed`] if bcrypt fails.
-    pub fn verify(password: &str, hash: &str) -> Result<bool, PasswordError> {
-        verify(password, hash).map_err(PasswordError::VerificationFailed)
+    /// Returns [`PasswordError::HashingFailed`] if hashing fails.
+    pub fn hash(password: &str) -> Result<HashedPassword, PasswordError> {
+        Self::hash_with(password, Self::params(), Self::peppers())
+    }
+
+    fn hash_with(
+        password: &str,
+        params: Params,
+        peppers: &Peppers,
+    ) -> Result<HashedPassword, PasswordError> {
+        let pepper_version = peppers.current().map(|(version, _)| version);
+        let input = peppers.apply(password, pepper_version)?;
+        let salt = SaltString::generate(&mut OsRng);
+        let hash = Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password(&input, &salt)
+            .map_err(|e| PasswordError::HashingFailed(e.to_string()))?;
+        Ok(HashedPassword {
+            hash: hash.to_string(),
+            pepper_version,
+        })
+    }
+
+    /// Returns `true` if `password` matches `hash` (Argon2 PHC string or bcrypt),
+    /// peppered with `pepper_version`.
+    ///
+    /// # Errors
+    ///
+    /// - [`PasswordError::UnsupportedHash`] if `hash` is in neither format.
+    /// - [`PasswordError::UnknownPepper`] if the pepper version is not configured.
+    /// - [`PasswordError::VerificationFailed`] if `hash` is malformed.
+    pub fn verify(
+        password: &str,
+        hash: &str,
+        pepper_version: Option<i32>,
+    ) -> Result<bool, PasswordError> {
+        Self::verify_with(password, hash, pepper_version, Self::peppers())
+    }
+
+    fn verify_with(
+        password: &str,
+        hash: &str,
+        pepper_version: Option<i32>,
+        peppers: &Peppers,
+    ) -> Result<bool, PasswordError> {
+        let input = peppers.apply(password, pepper_version)?;
+        if hash.starts_with("$argon2") {
+            let parsed = PasswordHash::new(hash)
+                .map_err(|e| PasswordError::VerificationFailed(e.to_string()))?;
+            // Les paramètres sont lus dans la chaîne PHC, pas dans la configuration
+            return match Argon2::default().verify_password(&input, &parsed) {
+                Ok(()) => Ok(true),
+                Err(password_hash::Error::Password) => Ok(false),
+                Err(e) => Err(PasswordError::VerificationFailed(e.to_string())),
+            };
+        }
+        if is_bcrypt(hash) {
+            return bcrypt::verify(&input, hash)
+                .map_err(|e| PasswordError::VerificationFailed(e.to_string()));
+        }
+        Err(PasswordError::UnsupportedHash)
+    }
+
+    /// Returns `true` if `hash` is not Argon2id with the current parameters and
+    /// pepper, and should be replaced the next time the plain password is known.
+    pub fn needs_rehash(hash: &str, pepper_version: Option<i32>) -> bool {
+        Self::needs_rehash_with(hash, pepper_version, &Self::params(), Self::peppers())
+    }
+
+    fn needs_rehash_with(
+        hash: &str,
+        pepper_version: Option<i32>,
+        current: &Params,
+        peppers: &Peppers,
+    ) -> bool {
+        if pepper_version != peppers.current().map(|(version, _)| version) {
+            return true;
+        }
+        let Ok(parsed) = PasswordHash::new(hash) else {
+            return true;
+        };
+        if parsed.algorithm != Algorithm::Argon2id.ident()
+            || parsed.version != Some(Version::V0x13.into())
+        {
+            return true;
+        }
+        Params::try_from(&parsed).map_or(true, |params| {
+            params.m_cost() != current.m_cost()
+                || params.t_cost() != current.t_cost()
+                || params.p_cost() != current.p_cost()
+        })
+    }
+
+    /// Runs a verification against a fixed hash and discards the result, to spend
+    /// the same time as [`verify`](Self::verify) when there is no account to check.
+    pub fn dummy_verify(password: &str) {
+        if let Some(dummy) = DUMMY_HASH.as_ref() {
+            let _ = Self::verify(password, &dummy.hash, dummy.pepper_version);
+        }
     }
 }
 
+fn is_bcrypt(hash: &str) -> bool {
+    ["$2a$", "$2b$", "$2x$", "$2y$"]
+        .iter()
+        .any(|prefix| hash.starts_with(prefix))
+}
+
 #[cfg(test)]
 mod tests {
-    use super::PasswordManager;
+    use super::*;
+
+    fn verify(password: &str, hashed: &HashedPassword) -> bool {
+        PasswordManager::verify(password, &hashed.hash, hashed.pepper_version)
+            .expect("Verification failed")
+    }
 
     #[test]
     fn verify_returns_true_when_password_matches() {
         let password = "secure_password_@123P";
         let hashed = PasswordManager::hash(password).expect("Hashing failed");
 
-        assert!(PasswordManager::verify(password, &hashed).expect("Verification failed"));
+        assert!(verify(password, &hashed));
     }
 
     #[test]
@@ -48,9 +325,7 @@ mod tests {
         let password = "secure_password_@123P";
         let hashed = PasswordManager::hash(password).expect("Hashing failed");
 
-        assert!(
-            !PasswordManager::verify("wrong_password_@123", &hashed).expect("Verification failed")
-        );
+        assert!(!verify("wrong_password_@123", &hashed));
     }
 
     #[test]
@@ -69,8 +344,8 @@ mod tests {
         let hash1 = PasswordManager::hash(password1).unwrap();
         let hash2 = PasswordManager::hash(password2).unwrap();
 
-        assert!(!PasswordManager::verify(password1, &hash2).unwrap());
-        assert!(!PasswordManager::verify(password2, &hash1).unwrap());
+        assert!(!verify(password1, &hash2));
+        assert!(!verify(password2, &hash1));
     }
 
     #[test]
@@ -79,9 +354,161 @@ mod tests {
         let hash = PasswordManager::hash(password).unwrap();
 
         let wrong_case = "mypassword";
-        let result = PasswordManager::verify(wrong_case, &hash);
+        let result = PasswordManager::verify(wrong_case, &hash.hash, hash.pepper_version);
 
         assert!(result.is_ok());
         assert!(!result.unwrap()); // Should be false, not error
     }
+
+    #[test]
+    fn hash_is_argon2id_phc_string() {
+        let hashed = PasswordManager::hash("phc_password").unwrap();
+
+        assert!(hashed.hash.starts_with("$argon2id$v=19$m=19456,t=2,p=1$"));
+        assert!(!PasswordManager::needs_rehash(
+            &hashed.hash,
+            hashed.pepper_version
+        ));
+    }
+
+    #[test]
+    fn passwords_longer_than_72_bytes_are_not_truncated() {
+        let long = "a".repeat(80);
+        let hashed = PasswordManager::hash(&long).unwrap();
+
+        assert!(!verify(&"a".repeat(72), &hashed));
+    }
+
+    #[test]
+    fn legacy_bcrypt_hashes_verify_and_need_rehash() {
+        let legacy = bcrypt::hash("legacy_password", 4).unwrap();
+
+        assert!(PasswordManager::verify("legacy_password", &legacy, None).unwrap());
+        assert!(!PasswordManager::verify("other_password", &legacy, None).unwrap());
+        assert!(PasswordManager::needs_rehash(&legacy, None));
+    }
+
+    #[test]
+    fn outdated_argon2_parameters_need_rehash() {
+        let weak = Params::new(8 * 1024, 1, 1, None).unwrap();
+        let hashed = PasswordManager::hash_with("old_params", weak, &Peppers::default()).unwrap();
+
+        assert!(verify("old_params", &hashed));
+        assert!(PasswordManager::needs_rehash(&hashed.hash, None));
+    }
+
+    #[test]
+    fn unknown_hash_format_is_rejected() {
+        assert!(matches!(
+            PasswordManager::verify("password", "plaintext", None),
+            Err(PasswordError::UnsupportedHash)
+        ));
+    }
+
+    #[test]
+    fn argon2_config_rejects_out_of_range_params() {
+        let config = Argon2Config {
+            memory_kib: 1,
+            ..Argon2Config::default()
+        };
+        assert!(matches!(
+            config.params(),
+            Err(PasswordError::InvalidParams(_))
+        ));
+    }
+
+    #[test]
+    fn peppered_hash_needs_the_pepper_to_verify() {
+        let peppers = Peppers::new(1, "first pepper, at least 32 bytes long");
+        let hashed =
+            PasswordManager::hash_with("peppered_password", Params::default(), &peppers).unwrap();
+        assert_eq!(hashed.pepper_version, Some(1));
+
+        assert!(
+            PasswordManager::verify_with("peppered_password", &hashed.hash, Some(1), &peppers)
+                .unwrap()
+        );
+        // Sans le pepper, le hash seul ne permet pas de vérifier le mot de passe
+        assert!(
+            !PasswordManager::verify_with(
+                "peppered_password",
+                &hashed.hash,
+                None,
+                &Peppers::default()
+            )
+            .unwrap_or(false)
+        );
+        assert!(matches!(
+            PasswordManager::verify_with(
+                "peppered_password",
+                &hashed.hash,
+                Some(1),
+                &Peppers::default()
+            ),
+            Err(PasswordError::UnknownPepper(1))
+        ));
+    }
+
+    #[test]
+    fn rotated_pepper_still_verifies_and_requests_rehash() {
+        let old = Peppers::new(1, "first pepper, at least 32 bytes long");
+        let hashed = PasswordManager::hash_with("rotate_me", Params::default(), &old).unwrap();
+
+        let rotated = Peppers::new(2, "second pepper, at least 32 bytes long")
+            .with_previous(1, "first pepper, at least 32 bytes long");
+        assert!(
+            PasswordManager::verify_with("rotate_me", &hashed.hash, Some(1), &rotated).unwrap()
+        );
+        assert!(PasswordManager::needs_rehash_with(
+            &hashed.hash,
+            Some(1),
+            &Params::default(),
+            &rotated
+        ));
+        assert!(!PasswordManager::needs_rehash_with(
+            &hashed.hash,
+            Some(1),
+            &Params::default(),
+            &old
+        ));
+    }
+
+    #[test]
+    fn configure_installs_peppers_even_when_params_are_already_set() {
+        let params = OnceLock::new();
+        let peppers = OnceLock::new();
+        params.set(Params::default()).unwrap();
+        let pepper = Peppers::new(1, "first pepper, at least 32 bytes long");
+
+        PasswordManager::configure_cells(
+            &params,
+            &peppers,
+            Argon2Config::default(),
+            pepper.clone(),
+        )
+        .unwrap();
+        assert_eq!(peppers.get(), Some(&pepper));
+
+        // Mêmes peppers : sans effet ; d'autres clés : erreur plutôt qu'un hachage sans pepper
+        PasswordManager::configure_cells(&params, &peppers, Argon2Config::default(), pepper)
+            .unwrap();
+        assert!(matches!(
+            PasswordManager::configure_cells(
+                &params,
+                &peppers,
+                Argon2Config::default(),
+                Peppers::new(2, "second pepper, at least 32 bytes long"),
+            ),
+            Err(PasswordError::PeppersAlreadyConfigured)
+        ));
+    }
+
+    #[test]
+    fn peppers_debug_does_not_print_keys() {
+        let peppers = Peppers::new(2, "super secret key").with_previous(1, "older secret key");
+        let debug = format!("{peppers:?}");
+
+        assert!(!debug.contains("secret"));
+        assert!(debug.contains("current: Some(2)"));
+    }
 }
@@ -0,0 +1,443 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::db::DbPool;
+use crate::db::error::RepositoryError;
+use crate::db::models::rate_limit_bucket::RateLimitBucket;
+use crate::db::repositories::rate_limit_repository::RateLimitRepository;
+use crate::error::AppError;
+
+/// Storage for fixed-window failure counters.
+///
+/// `since` is the start of the oldest window still considered live: buckets whose
+/// window started at or before it are treated as expired.
+pub trait RateLimitStore: Send + Sync {
+    /// Returns the live bucket for `key`, if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`RepositoryError`] if the backing store is unavailable.
+    fn get(
+        &self,
+        key: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Option<RateLimitBucket>, RepositoryError>;
+
+    /// Adds one hit to `key`, opening a new window at `now` if the current one expired.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`RepositoryError`] if the backing store is unavailable.
+    fn increment(
+        &self,
+        key: &str,
+        now: DateTime<Utc>,
+        since: DateTime<Utc>,
+    ) -> Result<RateLimitBucket, RepositoryError>;
+
+    /// Forgets `key`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`RepositoryError`] if the backing store is unavailable.
+    fn reset(&self, key: &str) -> Result<(), RepositoryError>;
+
+    /// Deletes the buckets whose window started at or before `before`; returns how
+    /// many were deleted.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`RepositoryError`] if the backing store is unavailable.
+    fn delete_expired(&self, before: DateTime<Utc>) -> Result<usize, RepositoryError>;
+}
+
+/// Counters shared across Lambda instances through the `rate_limit_buckets` table.
+pub struct PostgresRateLimitStore {
+    repository: RateLimitRepository,
+}
+
+impl PostgresRateLimitStore {
+    pub fn new(pool: DbPool) -> Self {
+        Self {
+            repository: RateLimitRepository::new(pool),
+        }
+    }
+}
+
+impl RateLimitStore for PostgresRateLimitStore {
+    fn get(
+        &self,
+        key: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Option<RateLimitBucket>, RepositoryError> {
+        self.repository.find_active(key, since)
+    }
+
+    fn increment(
+        &self,
+        key: &str,
+        now: DateTime<Utc>,
+        since: DateTime<Utc>,
+    ) -> Result<RateLimitBucket, RepositoryError> {
+        self.repository.increment(key, now, since)
+    }
+
+    fn reset(&self, key: &str) -> Result<(), RepositoryError> {
+        self.repository.delete(key)
+    }
+
+    fn delete_expired(&self, before: DateTime<Utc>) -> Result<usize, RepositoryError> {
+        self.repository.delete_expired(before)
+    }
+}
+
+/// Process-local counters, for local development and tests.
+#[derive(Default)]
+pub struct InMemoryRateLimitStore {
+    buckets: Mutex<HashMap<String, RateLimitBucket>>,
+}
+
+impl InMemoryRateLimitStore {
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, RateLimitBucket>> {
+        // Un compteur n'a pas d'invariant à protéger : on ignore l'empoisonnement
+        self.buckets
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+impl RateLimitStore for InMemoryRateLimitStore {
+    fn get(
+        &self,
+        key: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Option<RateLimitBucket>, RepositoryError> {
+        Ok(self
+            .lock()
+            .get(key)
+            .filter(|bucket| bucket.window_start > since)
+            .cloned())
+    }
+
+    fn increment(
+        &self,
+        key: &str,
+        now: DateTime<Utc>,
+        since: DateTime<Utc>,
+    ) -> Result<RateLimitBucket, RepositoryError> {
+        let mut buckets = self.lock();
+        let bucket = buckets
+            .entry(key.to_string())
+            .or_insert_with(|| RateLimitBucket {
+                key: key.to_string(),
+                window_start: now,
+                hits: 0,
+            });
+        if bucket.window_start <= since {
+            bucket.window_start = now;
+            bucket.hits = 0;
+        }
+        bucket.hits += 1;
+        Ok(bucket.clone())
+    }
+
+    fn reset(&self, key: &str) -> Result<(), RepositoryError> {
+        self.lock().remove(key);
+        Ok(())
+    }
+
+    fn delete_expired(&self, before: DateTime<Utc>) -> Result<usize, RepositoryError> {
+        let mut buckets = self.lock();
+        let count = buckets.len();
+        buckets.retain(|_, bucket| bucket.window_start > before);
+        Ok(count - buckets.len())
+    }
+}
+
+/// At most `max_failures` failed logins per `window`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitRule {
+    pub max_failures: u32,
+    pub window: Duration,
+}
+
+impl RateLimitRule {
+    /// Parses `"<max>/<window seconds>"`, e.g. `"5/900"`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a message describing the expected format.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let invalid = || format!("expected \"<max>/<seconds>\", got {spec:?}");
+        let (max, secs) = spec.trim().split_once('/').ok_or_else(invalid)?;
+        let max_failures = max.trim().parse::<u32>().map_err(|_| invalid())?;
+        let secs = secs.trim().parse::<i64>().map_err(|_| invalid())?;
+        if max_failures == 0 || secs <= 0 {
+            return Err(invalid());
+        }
+        // Une fenêtre qui remonte avant la plus ancienne date représentable ferait
+        // paniquer le calcul de son début
+        let window = Duration::try_seconds(secs)
+            .filter(|window| Utc::now().checked_sub_signed(*window).is_some())
+            .ok_or_else(|| format!("window out of range, got {spec:?}"))?;
+        Ok(Self {
+            max_failures,
+            window,
+        })
+    }
+}
+
+/// Login throttling rules, one per key kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitConfig {
+    /// Failures from one IP, across all emails (credential stuffing)
+    pub ip: RateLimitRule,
+    /// Failures against one email, from any IP (distributed guessing)
+    pub email: RateLimitRule,
+    /// Failures for one email from one IP
+    pub ip_email: RateLimitRule,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        let fifteen_minutes = Duration::minutes(15);
+        Self {
+            ip: RateLimitRule {
+                max_failures: 50,
+                window: fifteen_minutes,
+            },
+            email: RateLimitRule {
+                max_failures: 20,
+                window: fifteen_minutes,
+            },
+            ip_email: RateLimitRule {
+                max_failures: 5,
+                window: fifteen_minutes,
+            },
+        }
+    }
+}
+
+/// Throttles login attempts by IP, by email and by IP+email.
+///
+/// Only failures are counted. Keys use the email hash so the store never holds
+/// plain addresses, including ones that match no account.
+#[derive(Clone)]
+pub struct RateLimiter {
+    store: Arc<dyn RateLimitStore>,
+    config: RateLimitConfig,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(
+            Arc::new(InMemoryRateLimitStore::default()),
+            RateLimitConfig::default(),
+        )
+    }
+}
+
+impl RateLimiter {
+    pub fn new(store: Arc<dyn RateLimitStore>, config: RateLimitConfig) -> Self {
+        Self { store, config }
+    }
+
+    fn keys(&self, ip: Option<IpAddr>, email_hash: &str) -> Vec<(String, RateLimitRule)> {
+        let mut keys = vec![(format!("email:{email_hash}"), self.config.email)];
+        if let Some(ip) = ip {
+            keys.push((format!("ip:{ip}"), self.config.ip));
+            keys.push((format!("ip_email:{ip}:{email_hash}"), self.config.ip_email));
+        }
+        keys
+    }
+
+    /// Rejects the attempt if any key has used up its failures for the window.
+    ///
+    /// # Errors
+    ///
+    /// - [`AppError::RateLimited`] with the number of seconds until the longest
+    ///   exhausted window resets.
+    /// - [`AppError::DatabaseError`] if the store is unavailable.
+    pub fn check(&self, ip: Option<IpAddr>, email_hash: &str) -> Result<(), AppError> {
+        let now = Utc::now();
+        let mut retry_after: Option<Duration> = None;
+
+        for (key, rule) in self.keys(ip, email_hash) {
+            let Some(bucket) = self.store.get(&key, now - rule.window)? else {
+                continue;
+            };
+            if u32::try_from(bucket.hits).unwrap_or(0) >= rule.max_failures {
+                let remaining = bucket.window_start + rule.window - now;
+                retry_after = Some(retry_after.map_or(remaining, |r| r.max(remaining)));
+            }
+        }
+
+        match retry_after {
+            Some(remaining) => Err(AppError::RateLimited {
+                // Arrondi au supérieur, au moins une seconde
+                retry_after_secs: u64::try_from((remaining.num_milliseconds() + 999) / 1000)
+                    .unwrap_or(0)
+                    .max(1),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Counts a failed attempt against every key. Best effort.
+    pub fn record_failure(&self, ip: Option<IpAddr>, email_hash: &str) {
+        let now = Utc::now();
+        for (key, rule) in self.keys(ip, email_hash) {
+            let _ = self
+                .store
+                .increment(&key, now, now - rule.window)
+                .inspect_err(|e| tracing::warn!("Failed to record rate limit hit: {e}"));
+        }
+    }
+
+    /// Deletes the counters whose window has closed under every rule.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`RepositoryError`] if the store is unavailable.
+    pub fn delete_expired(&self) -> Result<usize, RepositoryError> {
+        let longest = [self.config.ip, self.config.email, self.config.ip_email]
+            .iter()
+            .map(|rule| rule.window)
+            .max()
+            .unwrap_or_default();
+        self.store.delete_expired(Utc::now() - longest)
+    }
+
+    /// Clears the IP+email counter after a successful login. The IP and email
+    /// counters are kept so a success cannot be used to reset a wider attack.
+    pub fn record_success(&self, ip: Option<IpAddr>, email_hash: &str) {
+        if let Some(ip) = ip {
+            let _ = self
+                .store
+                .reset(&format!("ip_email:{ip}:{email_hash}"))
+                .inspect_err(|e| tracing::warn!("Failed to reset rate limit: {e}"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(max_failures: u32) -> RateLimitRule {
+        RateLimitRule {
+            max_failures,
+            window: Duration::minutes(15),
+        }
+    }
+
+    fn limiter(per_ip: u32, per_email: u32, per_ip_email: u32) -> RateLimiter {
+        RateLimiter::new(
+            Arc::new(InMemoryRateLimitStore::default()),
+            RateLimitConfig {
+                ip: rule(per_ip),
+                email: rule(per_email),
+                ip_email: rule(per_ip_email),
+            },
+        )
+    }
+
+    fn addr(s: &str) -> Option<IpAddr> {
+        s.parse().ok()
+    }
+
+    #[test]
+    fn parse_rule_accepts_max_and_seconds() {
+        assert_eq!(
+            RateLimitRule::parse("5/900").unwrap(),
+            RateLimitRule {
+                max_failures: 5,
+                window: Duration::seconds(900),
+            }
+        );
+        assert!(RateLimitRule::parse("5").is_err());
+        assert!(RateLimitRule::parse("0/900").is_err());
+        assert!(RateLimitRule::parse("5/-1").is_err());
+        assert!(RateLimitRule::parse("5/99999999999999999").is_err());
+        assert!(RateLimitRule::parse("5/9000000000000000").is_err());
+    }
+
+    #[test]
+    fn ip_limit_applies_across_emails() {
+        let limiter = limiter(3, 100, 100);
+        for n in 0..3 {
+            let email = format!("hash{n}");
+            assert!(limiter.check(addr("198.51.100.1"), &email).is_ok());
+            limiter.record_failure(addr("198.51.100.1"), &email);
+        }
+
+        let result = limiter.check(addr("198.51.100.1"), "another");
+        assert!(matches!(
+            result,
+            Err(AppError::RateLimited { retry_after_secs }) if retry_after_secs > 0 && retry_after_secs <= 900
+        ));
+        assert!(limiter.check(addr("198.51.100.2"), "another").is_ok());
+    }
+
+    #[test]
+    fn email_limit_applies_across_ips_and_without_ip() {
+        let limiter = limiter(100, 2, 100);
+        limiter.record_failure(addr("198.51.100.1"), "victim");
+        limiter.record_failure(None, "victim");
+
+        assert!(limiter.check(addr("203.0.113.5"), "victim").is_err());
+        assert!(limiter.check(None, "victim").is_err());
+        assert!(limiter.check(None, "someone_else").is_ok());
+    }
+
+    #[test]
+    fn success_resets_ip_email_counter_only() {
+        let limiter = limiter(100, 100, 2);
+        limiter.record_failure(addr("198.51.100.1"), "user");
+        limiter.record_failure(addr("198.51.100.1"), "user");
+        assert!(limiter.check(addr("198.51.100.1"), "user").is_err());
+
+        limiter.record_success(addr("198.51.100.1"), "user");
+        assert!(limiter.check(addr("198.51.100.1"), "user").is_ok());
+    }
+
+    #[test]
+    fn in_memory_store_expires_windows() {
+        let store = InMemoryRateLimitStore::default();
+        let start = Utc::now() - Duration::minutes(20);
+        store
+            .increment("k", start, start - Duration::minutes(15))
+            .unwrap();
+
+        let now = Utc::now();
+        let since = now - Duration::minutes(15);
+        assert!(store.get("k", since).unwrap().is_none());
+        assert_eq!(store.increment("k", now, since).unwrap().hits, 1);
+    }
+
+    #[test]
+    fn delete_expired_keeps_windows_open_under_the_longest_rule() {
+        let store = Arc::new(InMemoryRateLimitStore::default());
+        let limiter = RateLimiter::new(
+            store.clone(),
+            RateLimitConfig {
+                ip: RateLimitRule {
+                    max_failures: 50,
+                    window: Duration::hours(1),
+                },
+                ..RateLimitConfig::default()
+            },
+        );
+        let now = Utc::now();
+        let old = now - Duration::hours(2);
+        let recent = now - Duration::minutes(30);
+        store.increment("old", old, old).unwrap();
+        store.increment("recent", recent, recent).unwrap();
+
+        assert_eq!(limiter.delete_expired().unwrap(), 1);
+        assert!(store.get("recent", old).unwrap().is_some());
+    }
+}
@@ -0,0 +1,256 @@
+//! Détection des connexions suspectes.
+//!
+//! Chaque connexion réussie est comparée aux appareils déjà connus du compte par une
+//! liste de [`RiskRule`]. Une règle ne voit que le [`LoginContext`] : de nouvelles
+//! règles (voyage impossible via une base géographique locale…) s'ajoutent sans toucher au
+//! service.
+
+use std::net::IpAddr;
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::auth::client_info::ClientInfo;
+use crate::auth::device::parse_user_agent;
+use crate::db::models::known_device::{KnownDevice, NewKnownDevice};
+
+/// Coarse identity of the device a login comes from: browser and OS families plus
+/// the network prefix, so that browser updates and DHCP renewals are not "new".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceFingerprint {
+    pub browser: Option<String>,
+    pub os: Option<String>,
+    pub ip_prefix: Option<String>,
+    /// SHA-256 of the fields above, hex encoded
+    pub hash: String,
+}
+
+impl DeviceFingerprint {
+    pub fn from_client(client: &ClientInfo) -> Self {
+        use sha2::{Digest, Sha256};
+
+        let info = parse_user_agent(client.user_agent.as_deref());
+        let ip_prefix = client.ip.map(ip_prefix);
+        let material = format!(
+            "{}|{}|{:?}|{}",
+            info.browser.as_deref().unwrap_or("-"),
+            info.os.as_deref().unwrap_or("-"),
+            info.device_type,
+            ip_prefix.as_deref().unwrap_or("-"),
+        );
+        Self {
+            browser: info.browser,
+            os: info.os,
+            ip_prefix,
+            hash: hex::encode(Sha256::digest(material.as_bytes())),
+        }
+    }
+
+    pub fn to_new_device(&self, user_id: Uuid) -> NewKnownDevice {
+        NewKnownDevice {
+            user_id,
+            fingerprint: self.hash.clone(),
+            browser: self.browser.clone(),
+            os: self.os.clone(),
+            ip_prefix: self.ip_prefix.clone(),
+        }
+    }
+
+    /// Human readable summary for notification emails.
+    pub fn describe(&self) -> String {
+        let browser = self.browser.as_deref().unwrap_or("Unknown browser");
+        let os = self.os.as_deref().unwrap_or("unknown system");
+        match &self.ip_prefix {
+            Some(prefix) => format!("{browser} on {os}, network {prefix}"),
+            None => format!("{browser} on {os}"),
+        }
+    }
+}
+
+/// `/24` for IPv4, `/48` for IPv6 (a typical customer allocation).
+fn ip_prefix(ip: IpAddr) -> String {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, c, _] = v4.octets();
+            format!("{a}.{b}.{c}.0/24")
+        }
+        IpAddr::V6(v6) => {
+            let s = v6.segments();
+            format!("{:x}:{:x}:{:x}::/48", s[0], s[1], s[2])
+        }
+    }
+}
+
+/// What a rule knows about a successful login.
+pub struct LoginContext<'a> {
+    pub user_id: Uuid,
+    pub client: &'a ClientInfo,
+    pub device: &'a DeviceFingerprint,
+    /// Devices seen before this login, most recent first
+    pub known_devices: &'a [KnownDevice],
+    pub now: DateTime<Utc>,
+}
+
+/// Why a rule considers a login suspicious.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RiskSignal {
+    /// Stable rule identifier, recorded in the audit log
+    pub rule: &'static str,
+    pub detail: String,
+}
+
+/// A suspicious-login detection rule.
+pub trait RiskRule: Send + Sync {
+    /// Returns a signal when the login looks suspicious.
+    fn evaluate(&self, login: &LoginContext<'_>) -> Option<RiskSignal>;
+}
+
+/// Flags the first login from a device the account has never used. The very first
+/// login of an account sets the baseline and is not flagged.
+pub struct NewDeviceRule;
+
+impl RiskRule for NewDeviceRule {
+    fn evaluate(&self, login: &LoginContext<'_>) -> Option<RiskSignal> {
+        if login.known_devices.is_empty()
+            || login
+                .known_devices
+                .iter()
+                .any(|known| known.fingerprint == login.device.hash)
+        {
+            return None;
+        }
+        Some(RiskSignal {
+            rule: "new_device",
+            detail: login.device.describe(),
+        })
+    }
+}
+
+/// Runs every configured rule against a login.
+#[derive(Clone)]
+pub struct RiskEngine {
+    rules: Vec<Arc<dyn RiskRule>>,
+}
+
+impl Default for RiskEngine {
+    fn default() -> Self {
+        Self::new(vec![Arc::new(NewDeviceRule)])
+    }
+}
+
+impl RiskEngine {
+    pub fn new(rules: Vec<Arc<dyn RiskRule>>) -> Self {
+        Self { rules }
+    }
+
+    /// Engine that never flags anything.
+    pub fn disabled() -> Self {
+        Self::new(Vec::new())
+    }
+
+    pub fn with_rule(mut self, rule: Arc<dyn RiskRule>) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    pub fn assess(&self, login: &LoginContext<'_>) -> Vec<RiskSignal> {
+        self.rules
+            .iter()
+            .filter_map(|rule| rule.evaluate(login))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIREFOX: &str = "Mozilla/5.0 (X11; Linux x86_64; rv:121.0) Gecko/20100101 Firefox/121.0";
+    const FIREFOX_NEXT: &str =
+        "Mozilla/5.0 (X11; Linux x86_64; rv:122.0) Gecko/20100101 Firefox/122.0";
+    const SAFARI_IPHONE: &str = "Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.0 Mobile/15E148 Safari/604.1";
+
+    fn client(ua: &str, ip: &str) -> ClientInfo {
+        ClientInfo {
+            ip: Some(ip.parse().unwrap()),
+            user_agent: Some(ua.to_string()),
+            request_id: None,
+        }
+    }
+
+    fn known(device: &DeviceFingerprint) -> KnownDevice {
+        KnownDevice {
+            id: Uuid::new_v4(),
+            user_id: Uuid::nil(),
+            fingerprint: device.hash.clone(),
+            browser: device.browser.clone(),
+            os: device.os.clone(),
+            ip_prefix: device.ip_prefix.clone(),
+            first_seen_at: Utc::now(),
+            last_seen_at: Utc::now(),
+        }
+    }
+
+    fn assess(engine: &RiskEngine, client: &ClientInfo, known: &[KnownDevice]) -> Vec<RiskSignal> {
+        let device = DeviceFingerprint::from_client(client);
+        engine.assess(&LoginContext {
+            user_id: Uuid::nil(),
+            client,
+            device: &device,
+            known_devices: known,
+            now: Utc::now(),
+        })
+    }
+
+    #[test]
+    fn fingerprint_ignores_browser_version_and_host_part_of_ip() {
+        let a = DeviceFingerprint::from_client(&client(FIREFOX, "203.0.113.7"));
+        let b = DeviceFingerprint::from_client(&client(FIREFOX_NEXT, "203.0.113.99"));
+        assert_eq!(a, b);
+        assert_eq!(a.ip_prefix.as_deref(), Some("203.0.113.0/24"));
+
+        let other_network = DeviceFingerprint::from_client(&client(FIREFOX, "198.51.100.7"));
+        assert_ne!(a.hash, other_network.hash);
+
+        let v6 = DeviceFingerprint::from_client(&client(FIREFOX, "2001:db8:1:2::42"));
+        assert_eq!(v6.ip_prefix.as_deref(), Some("2001:db8:1::/48"));
+    }
+
+    #[test]
+    fn new_device_rule_skips_first_login_and_known_devices() {
+        let engine = RiskEngine::default();
+        let laptop = client(FIREFOX, "203.0.113.7");
+        assert!(assess(&engine, &laptop, &[]).is_empty());
+
+        let known_laptop = known(&DeviceFingerprint::from_client(&laptop));
+        assert!(assess(&engine, &laptop, std::slice::from_ref(&known_laptop)).is_empty());
+
+        let signals = assess(
+            &engine,
+            &client(SAFARI_IPHONE, "198.51.100.7"),
+            &[known_laptop],
+        );
+        assert_eq!(signals.len(), 1);
+        assert_eq!(signals[0].rule, "new_device");
+        assert_eq!(signals[0].detail, "Safari on iOS, network 198.51.100.0/24");
+    }
+
+    #[test]
+    fn custom_rules_are_pluggable() {
+        struct NoUserAgent;
+        impl RiskRule for NoUserAgent {
+            fn evaluate(&self, login: &LoginContext<'_>) -> Option<RiskSignal> {
+                login.client.user_agent.is_none().then(|| RiskSignal {
+                    rule: "no_user_agent",
+                    detail: String::new(),
+                })
+            }
+        }
+
+        let engine = RiskEngine::disabled().with_rule(Arc::new(NoUserAgent));
+        assert!(assess(&engine, &client(FIREFOX, "203.0.113.7"), &[]).is_empty());
+        let signals = assess(&engine, &ClientInfo::default(), &[]);
+        assert_eq!(signals[0].rule, "no_user_agent");
+    }
+}
@@ -2,29 +2,162 @@
 
 use crate::error::AppError;
 use auth_manager_api::{
-    LoginRequest, LoginResponse, RefreshTokenRequest, RefreshTokenResponse, RegisterRequest,
-    UserResponse,
+    AccountExport, AuditEventType, LoginFailureReason, LoginHistoryPage, LoginRequest,
+    LoginResponse, PasswordPolicy, PasswordViolation, RefreshTokenRequest, RefreshTokenResponse,
+    RegisterRequest, UserResponse,
 };
 
+use crate::auth::audit::Audit;
+use crate::auth::breach::BreachChecker;
+use crate::auth::client_info::ClientInfo;
+use crate::auth::email::{EmailPolicy, email_hash};
+use crate::auth::lockout::{Lock, LockEvent, LockoutPolicy};
+use crate::auth::rate_limit::RateLimiter;
+use crate::auth::risk::{DeviceFingerprint, LoginContext, RiskEngine, RiskSignal};
+use crate::auth::username::UsernamePolicy;
+use crate::db::error::RepositoryError;
+use crate::db::models::login_attempt::NewLoginAttempt;
 use crate::db::models::refresh_token::NewRefreshToken;
-use crate::db::models::user::NewUser;
-
-use crate::db::repositories::login_attempt_repository::LoginAttemptRepository;
-use crate::db::repositories::refresh_token_repository::RefreshTokenRepository;
-use crate::db::repositories::user_repository::UserRepository;
-
-use chrono::Utc;
-
-const MAX_FAILED_ATTEMPTS: i64 = 5;
-const LOCKOUT_WINDOW_MINUTES: i64 = 15;
+use crate::db::models::user::{
+    EMAIL_UNIQUE_KEYS, LockoutState, NewUser, USERNAME_UNIQUE_KEYS, UpdateUser, User,
+};
+use crate::db::models::user_token::{NewUserToken, TokenPurpose};
+use crate::mailer::{EmailMessage, LogMailer, Mailer};
+
+use crate::db::repositories::login_attempt_repository::HistoryCursor;
+use crate::db::store::Repositories;
+
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+
+const ACCOUNT_DELETION_GRACE_DAYS: i64 = 14;
+const PURGE_BATCH_SIZE: i64 = 100;
+const LOGIN_HISTORY_DEFAULT_LIMIT: i64 = 20;
+const LOGIN_HISTORY_MAX_LIMIT: i64 = 100;
+const UNLOCK_TOKEN_VALIDITY_HOURS: i64 = 24;
+const DELETION_TOKEN_VALIDITY_HOURS: i64 = 1;
+const REFRESH_TOKEN_VALIDITY_DAYS: i64 = 7;
+/// Long enough to outlive every session opened by the reported login
+const SESSION_REVOKE_TOKEN_VALIDITY_DAYS: i64 = REFRESH_TOKEN_VALIDITY_DAYS;
+const DEFAULT_PASSWORD_HISTORY_SIZE: usize = 5;
+
+/// Outcome of a registration request.
+#[derive(Debug)]
+pub enum Registration {
+    Created(UserResponse),
+    /// Enumeration-safe mode: the request was accepted and the outcome (new account
+    /// or existing email) is only communicated to the mailbox owner
+    Accepted,
+}
 
 pub struct AuthService {
     jwt_manager: super::jwt::JwtManager,
+    repos: Repositories,
+    /// Hide whether an email is registered from login and registration responses
+    enumeration_safe: bool,
+    email_policy: EmailPolicy,
+    rate_limiter: RateLimiter,
+    lockout_policy: LockoutPolicy,
+    password_policy: PasswordPolicy,
+    /// Number of previous passwords that cannot be reused
+    password_history_size: usize,
+    /// Passwords older than this must be changed at next login
+    password_max_age: Option<chrono::Duration>,
+    /// Rejects new passwords found in a breach corpus, when configured
+    breach_checker: Option<BreachChecker>,
+    /// Flags suspicious logins (new device…) and triggers the owner notification
+    risk_engine: RiskEngine,
+    mailer: Arc<dyn Mailer>,
+    /// Frontend base URL used to build links in emails
+    public_url: String,
 }
 
 impl AuthService {
-    pub fn new(jwt_manager: super::jwt::JwtManager) -> Self {
-        Self { jwt_manager }
+    pub fn new(jwt_manager: super::jwt::JwtManager, repos: Repositories) -> Self {
+        Self {
+            jwt_manager,
+            repos,
+            enumeration_safe: false,
+            email_policy: EmailPolicy::default(),
+            rate_limiter: RateLimiter::default(),
+            lockout_policy: LockoutPolicy::default(),
+            password_policy: PasswordPolicy::default(),
+            password_history_size: DEFAULT_PASSWORD_HISTORY_SIZE,
+            password_max_age: None,
+            breach_checker: None,
+            risk_engine: RiskEngine::default(),
+            mailer: Arc::new(LogMailer),
+            public_url: "http://localhost:8080".to_string(),
+        }
+    }
+
+    /// Replaces the default email validation and normalisation rules.
+    pub fn with_email_policy(mut self, email_policy: EmailPolicy) -> Self {
+        self.email_policy = email_policy;
+        self
+    }
+
+    /// Replaces the default in-memory login rate limiter.
+    pub fn with_rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        self.rate_limiter = rate_limiter;
+        self
+    }
+
+    /// Replaces the default account lockout rules.
+    pub fn with_lockout_policy(mut self, lockout_policy: LockoutPolicy) -> Self {
+        self.lockout_policy = lockout_policy;
+        self
+    }
+
+    /// Replaces the default rules for new passwords.
+    pub fn with_password_policy(mut self, password_policy: PasswordPolicy) -> Self {
+        self.password_policy = password_policy;
+        self
+    }
+
+    /// Keeps the `size` previous passwords of each user and refuses their reuse (the
+    /// current password is always refused).
+    pub fn with_password_history(mut self, size: usize) -> Self {
+        self.password_history_size = size;
+        self
+    }
+
+    /// Requires users whose password is older than `max_age` to change it at next login.
+    pub fn with_password_max_age(mut self, max_age: Option<chrono::Duration>) -> Self {
+        self.password_max_age = max_age;
+        self
+    }
+
+    /// Rejects new passwords that appear in the checker's breach corpus.
+    pub fn with_breach_checker(mut self, breach_checker: BreachChecker) -> Self {
+        self.breach_checker = Some(breach_checker);
+        self
+    }
+
+    /// Replaces the suspicious-login rules (new device detection by default).
+    pub fn with_risk_engine(mut self, risk_engine: RiskEngine) -> Self {
+        self.risk_engine = risk_engine;
+        self
+    }
+
+    /// Makes login and registration responses identical whether or not the email is
+    /// registered: login failures become [`AppError::InvalidCredentials`] and
+    /// registration always answers [`Registration::Accepted`].
+    pub fn with_enumeration_protection(mut self, enabled: bool) -> Self {
+        self.enumeration_safe = enabled;
+        self
+    }
+
+    /// Sends notification emails through `mailer`, with links pointing at `public_url`.
+    pub fn with_mailer(mut self, mailer: Arc<dyn Mailer>, public_url: impl Into<String>) -> Self {
+        self.mailer = mailer;
+        self.public_url = public_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Rules enforced on new passwords, published so clients can validate as the user types.
+    pub fn password_policy(&self) -> &PasswordPolicy {
+        &self.password_policy
     }
 
     /// Returns the current authenticated user's profile.
@@ -32,8 +165,8 @@ impl AuthService {
     /// # Errors
     ///
     /// Returns [`AppError::NotFound`] if the user no longer exists, or a database error.
-    pub fn get_current_user(user_id: uuid::Uuid) -> Result<UserResponse, AppError> {
-        Self::get_user_by_id(user_id)
+    pub fn get_current_user(&self, user_id: uuid::Uuid) -> Result<UserResponse, AppError> {
+        self.get_user_by_id(user_id)
     }
 
     /// Revokes all refresh tokens for the given user (logout).
@@ -41,8 +174,15 @@ impl AuthService {
     /// # Errors
     ///
     /// Returns a database error if token deletion fails.
-    pub fn logout(user_id: uuid::Uuid) -> Result<(), AppError> {
-        RefreshTokenRepository::delete_by_user(user_id).map_err(AppError::from)?;
+    pub fn logout(&self, user_id: uuid::Uuid, client: &ClientInfo) -> Result<(), AppError> {
+        self.repos
+            .refresh_tokens
+            .delete_by_user(user_id)
+            .map_err(AppError::from)?;
+        Audit::new(AuditEventType::Logout)
+            .user(user_id)
+            .client(client)
+            .record(self.repos.audit_events.as_ref());
         Ok(())
     }
 
@@ -51,182 +191,808 @@ impl AuthService {
     /// # Errors
     ///
     /// Returns [`AppError::NotFound`] if no user with that ID exists, or a database error.
-    pub fn get_user_by_id(user_id: uuid::Uuid) -> Result<UserResponse, AppError> {
-        UserRepository::find_by_id(user_id)
+    pub fn get_user_by_id(&self, user_id: uuid::Uuid) -> Result<UserResponse, AppError> {
+        self.repos
+            .users
+            .find_by_id(user_id)
             .map_err(AppError::from)?
             .map(UserResponse::from)
             .ok_or_else(|| AppError::not_found("User not found"))
     }
 
-    /// Permanently deletes a user account.
+    /// Schedules the account for deletion after a grace period.
+    ///
+    /// All sessions are revoked; logging in again before the deadline cancels the
+    /// deletion. Returns the date at which the account will be purged.
+    ///
+    /// # Errors
+    ///
+    /// - [`AppError::NotFound`] if the user does not exist.
+    /// - [`AppError::PasswordNotSet`] if the account has no password to confirm with:
+    ///   see [`Self::send_account_deletion_confirmation`].
+    /// - [`AppError::InvalidPassword`] if `password` does not match the stored hash.
+    /// - [`AppError::DatabaseError`] on persistence failures.
+    pub fn request_account_deletion(
+        &self,
+        user_id: uuid::Uuid,
+        password: &str,
+        client: &ClientInfo,
+    ) -> Result<DateTime<Utc>, AppError> {
+        let user = self
+            .repos
+            .users
+            .find_by_id(user_id)?
+            .ok_or_else(|| AppError::not_found("User not found"))?;
+
+        let password_hash = user
+            .password_hash
+            .as_ref()
+            .ok_or(AppError::PasswordNotSet)?;
+
+        if !super::password::PasswordManager::verify(
+            password,
+            password_hash,
+            user.password_pepper_version,
+        )
+        .map_err(AppError::from)?
+        {
+            return Err(AppError::InvalidPassword);
+        }
+
+        self.schedule_account_deletion(user_id, client, "password")
+    }
+
+    /// Emails a single-use link confirming the deletion of an account that has no
+    /// password to confirm with (accounts created through an identity provider).
+    ///
+    /// # Errors
+    ///
+    /// - [`AppError::NotFound`] if the user does not exist.
+    /// - [`AppError::InvalidInput`] if the account has a password: it must be used instead.
+    /// - [`AppError::DatabaseError`] if the token cannot be stored.
+    pub fn send_account_deletion_confirmation(&self, user_id: uuid::Uuid) -> Result<(), AppError> {
+        let user = self
+            .repos
+            .users
+            .find_by_id(user_id)?
+            .ok_or_else(|| AppError::not_found("User not found"))?;
+        if user.password_hash.is_some() {
+            return Err(AppError::invalid_input(
+                "Confirm the deletion with the account password",
+            ));
+        }
+
+        let token = generate_token();
+        self.repos.user_tokens.create(&NewUserToken {
+            user_id,
+            purpose: TokenPurpose::AccountDeletion.as_str().to_string(),
+            token_hash: hash_token(&token),
+            expires_at: Utc::now() + chrono::Duration::hours(DELETION_TOKEN_VALIDITY_HOURS),
+        })?;
+
+        self.send_email(
+            &user,
+            "Confirm the deletion of your account",
+            &format!(
+                "Hello {},\n\nYou asked to delete your account. Confirm here:\n{}/confirm-deletion?token={token}\n\nThe account will then be deleted after {ACCOUNT_DELETION_GRACE_DAYS} days; logging in before that cancels the deletion.\n\nThis link expires in {DELETION_TOKEN_VALIDITY_HOURS} hour(s). If this was not you, you can ignore this email.\n",
+                user.username, self.public_url
+            ),
+        );
+        Ok(())
+    }
+
+    /// Schedules the deletion of the account using the token emailed by
+    /// [`Self::send_account_deletion_confirmation`]. Returns the date at which the
+    /// account will be purged.
+    ///
+    /// # Errors
+    ///
+    /// - [`AppError::InvalidInput`] if the token is unknown, expired or already used.
+    /// - [`AppError::DatabaseError`] on persistence failures.
+    pub fn confirm_account_deletion_with_token(
+        &self,
+        token: &str,
+        client: &ClientInfo,
+    ) -> Result<DateTime<Utc>, AppError> {
+        let consumed = self
+            .repos
+            .user_tokens
+            .consume(
+                &hash_token(token),
+                TokenPurpose::AccountDeletion,
+                Utc::now(),
+            )?
+            .ok_or_else(|| AppError::invalid_input("Invalid or expired deletion token"))?;
+
+        self.schedule_account_deletion(consumed.user_id, client, "email")
+    }
+
+    /// Schedules the deletion once confirmed, and revokes every session.
+    fn schedule_account_deletion(
+        &self,
+        user_id: uuid::Uuid,
+        client: &ClientInfo,
+        method: &str,
+    ) -> Result<DateTime<Utc>, AppError> {
+        let scheduled_at = self
+            .repos
+            .users
+            .schedule_deletion(
+                user_id,
+                Utc::now() + chrono::Duration::days(ACCOUNT_DELETION_GRACE_DAYS),
+            )?
+            .deletion_scheduled_at
+            .ok_or_else(|| AppError::database("Deletion date was not stored"))?;
+        self.repos.refresh_tokens.delete_by_user(user_id)?;
+
+        tracing::info!(%user_id, %scheduled_at, "Account deletion scheduled");
+        Audit::new(AuditEventType::AccountDeletionRequested)
+            .user(user_id)
+            .client(client)
+            .metadata(serde_json::json!({ "scheduled_at": scheduled_at, "method": method }))
+            .record(self.repos.audit_events.as_ref());
+        Ok(scheduled_at)
+    }
+
+    /// Permanently deletes accounts whose grace period has elapsed.
+    ///
+    /// Login history is kept in anonymised form. Returns the number of purged accounts.
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if the pending deletions cannot be listed.
+    pub fn purge_due_deletions(&self) -> Result<usize, AppError> {
+        let due = self
+            .repos
+            .users
+            .find_due_for_deletion(Utc::now(), PURGE_BATCH_SIZE)?;
+
+        let mut purged = 0;
+        for user in due {
+            match self.repos.users.purge(user.id) {
+                Ok(pseudonym) => {
+                    purged += 1;
+                    // Sous le pseudonyme qui remplace désormais le compte dans le journal
+                    Audit::new(AuditEventType::AccountPurged)
+                        .target(pseudonym)
+                        .record(self.repos.audit_events.as_ref());
+                }
+                Err(e) => tracing::error!(user_id = %user.id, "Failed to purge account: {e}"),
+            }
+        }
+        Ok(purged)
+    }
+
+    /// Builds the GDPR data export for a user: profile, linked identities,
+    /// sessions and login history.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::NotFound`] if the user does not exist, or a database error.
+    pub fn export_account(&self, user_id: uuid::Uuid) -> Result<AccountExport, AppError> {
+        let user = self
+            .repos
+            .users
+            .find_by_id(user_id)?
+            .ok_or_else(|| AppError::not_found("User not found"))?;
+
+        let identities = self.repos.identities.find_by_user(user_id)?;
+        let sessions = self.repos.refresh_tokens.find_by_user(user_id)?;
+        let login_history = self.repos.login_attempts.find_all_by_user(user_id)?;
+
+        Ok(AccountExport {
+            exported_at: Utc::now(),
+            user: user.into(),
+            identities: identities.into_iter().map(Into::into).collect(),
+            sessions: sessions.into_iter().map(Into::into).collect(),
+            login_history: login_history.into_iter().map(Into::into).collect(),
+        })
+    }
+
+    /// Lifts a lockout using the single-use token from the lock notification email.
+    ///
+    /// # Errors
+    ///
+    /// - [`AppError::InvalidInput`] if the token is unknown, expired or already used.
+    /// - [`AppError::DatabaseError`] on persistence failures.
+    pub fn unlock_account_with_token(
+        &self,
+        token: &str,
+        client: &ClientInfo,
+    ) -> Result<(), AppError> {
+        let consumed = self
+            .repos
+            .user_tokens
+            .consume(&hash_token(token), TokenPurpose::AccountUnlock, Utc::now())?
+            .ok_or_else(|| AppError::invalid_input("Invalid or expired unlock token"))?;
+
+        self.repos.users.reset_lockout(consumed.user_id)?;
+        tracing::info!(user_id = %consumed.user_id, "Account unlocked by email link");
+        Audit::new(AuditEventType::AccountUnlocked)
+            .user(consumed.user_id)
+            .client(client)
+            .metadata(serde_json::json!({ "method": "email" }))
+            .record(self.repos.audit_events.as_ref());
+        Ok(())
+    }
+
+    /// Revokes every session of the account using the "this wasn't me" token from a
+    /// suspicious-login email.
+    ///
+    /// # Errors
+    ///
+    /// - [`AppError::InvalidInput`] if the token is unknown, expired or already used.
+    /// - [`AppError::DatabaseError`] on persistence failures.
+    pub fn revoke_sessions_with_token(
+        &self,
+        token: &str,
+        client: &ClientInfo,
+    ) -> Result<(), AppError> {
+        let consumed = self
+            .repos
+            .user_tokens
+            .consume(&hash_token(token), TokenPurpose::SessionRevoke, Utc::now())?
+            .ok_or_else(|| AppError::invalid_input("Invalid or expired token"))?;
+
+        self.repos.refresh_tokens.delete_by_user(consumed.user_id)?;
+        tracing::warn!(user_id = %consumed.user_id, "Sessions revoked: login reported as not made by the owner");
+        Audit::new(AuditEventType::SessionsRevoked)
+            .user(consumed.user_id)
+            .client(client)
+            .metadata(serde_json::json!({ "method": "not_me" }))
+            .record(self.repos.audit_events.as_ref());
+        Ok(())
+    }
+
+    /// Lifts any lockout on the account (administrator action).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::NotFound`] if the user does not exist, or a database error.
+    pub fn unlock_account(&self, user_id: uuid::Uuid) -> Result<(), AppError> {
+        self.repos.users.reset_lockout(user_id)?;
+        Ok(())
+    }
+
+    /// Finds a user by email, normalised with the service's [`EmailPolicy`].
+    ///
+    /// # Errors
+    ///
+    /// - [`AppError::InvalidEmail`] if the email format is invalid.
+    /// - [`AppError::NotFound`] if no user has that email.
+    /// - [`AppError::DatabaseError`] on persistence failures.
+    pub fn find_user_by_email(&self, email: &str) -> Result<UserResponse, AppError> {
+        let email = self.email_policy.normalize(email)?;
+        self.repos
+            .users
+            .find_by_email(&email)?
+            .map(UserResponse::from)
+            .ok_or_else(|| AppError::not_found("User not found"))
+    }
+
+    /// Creates an administrator account, with the same email, username and password
+    /// rules as a registration. The email is considered verified.
+    ///
+    /// # Errors
+    ///
+    /// - Every error of [`AuthService::register`].
+    /// - [`AppError::InternalServerError`] if the service is enumeration-safe, since
+    ///   registration then hides whether the account was created.
+    pub fn create_admin(&self, request: &RegisterRequest) -> Result<UserResponse, AppError> {
+        if self.enumeration_safe {
+            return Err(AppError::internal(
+                "Admin accounts cannot be created by an enumeration-safe service",
+            ));
+        }
+        let Registration::Created(user) = self.register(request, &ClientInfo::default())? else {
+            return Err(AppError::internal(
+                "Registration did not create the account",
+            ));
+        };
+
+        let admin = self.repos.users.update(
+            user.id,
+            &UpdateUser {
+                is_admin: Some(true),
+                email_verified: Some(true),
+                ..UpdateUser::default()
+            },
+        )?;
+        tracing::info!(user_id = %admin.id, "Administrator account created");
+        Audit::new(AuditEventType::AdminCreated)
+            .target(admin.id)
+            .record(self.repos.audit_events.as_ref());
+        Ok(admin.into())
+    }
+
+    /// Sets a password chosen by an administrator, without the current one.
+    ///
+    /// The password rules and history apply as for [`AuthService::change_password`],
+    /// and every session of the user is revoked.
     ///
     /// # Errors
     ///
-    /// Returns a database error if deletion fails.
-    pub fn delete_user(user_id: uuid::Uuid) -> Result<(), AppError> {
-        UserRepository::delete(user_id)?;
+    /// - [`AppError::WeakPassword`] if `new_password` breaks the [`PasswordPolicy`],
+    ///   appears in a data breach, or matches the current or a recent password.
+    /// - [`AppError::NotFound`] if the user does not exist.
+    /// - [`AppError::DatabaseError`] on persistence failures.
+    pub fn reset_password(&self, user_id: uuid::Uuid, new_password: &str) -> Result<(), AppError> {
+        let user = self
+            .repos
+            .users
+            .find_by_id(user_id)?
+            .ok_or_else(|| AppError::not_found("User not found"))?;
+
+        self.check_new_password(new_password, &[&user.email, &user.username])?;
+        if self.is_recent_password(&user, new_password)? {
+            return Err(AppError::WeakPassword(vec![
+                PasswordViolation::RecentlyUsed,
+            ]));
+        }
+
+        let new_password =
+            super::password::PasswordManager::hash(new_password).map_err(AppError::from)?;
+        self.repos.users.change_password(
+            user_id,
+            &new_password.hash,
+            new_password.pepper_version,
+            i64::try_from(self.password_history_size).unwrap_or(i64::MAX),
+        )?;
+        tracing::info!(%user_id, "Password reset by an administrator");
+        Audit::new(AuditEventType::PasswordReset)
+            .target(user_id)
+            .record(self.repos.audit_events.as_ref());
         Ok(())
     }
 
+    /// Deletes sessions (refresh tokens) that expired more than `retention` ago.
+    /// Returns how many were deleted.
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if the deletion fails.
+    pub fn purge_expired_sessions(&self, retention: chrono::Duration) -> Result<usize, AppError> {
+        Ok(self
+            .repos
+            .refresh_tokens
+            .delete_expired(Utc::now() - retention)?)
+    }
+
+    /// Deletes login attempts older than `retention`. Returns how many were deleted.
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if the deletion fails.
+    pub fn prune_login_attempts(&self, retention: chrono::Duration) -> Result<usize, AppError> {
+        Ok(self
+            .repos
+            .login_attempts
+            .delete_before(Utc::now() - retention)?)
+    }
+
+    /// Deletes the rate-limit counters whose window has closed. Returns how many were
+    /// deleted.
+    ///
+    /// # Errors
+    ///
+    /// Returns a database error if the deletion fails.
+    pub fn prune_rate_limits(&self) -> Result<usize, AppError> {
+        Ok(self.rate_limiter.delete_expired()?)
+    }
+
+    /// Returns one page of the user's login attempts, newest first.
+    ///
+    /// `cursor` is the `next_cursor` of the previous page; `limit` defaults to 20
+    /// and is capped at 100.
+    ///
+    /// # Errors
+    ///
+    /// - [`AppError::InvalidInput`] if the cursor is malformed.
+    /// - [`AppError::DatabaseError`] on persistence failures.
+    pub fn login_history(
+        &self,
+        user_id: uuid::Uuid,
+        cursor: Option<&str>,
+        limit: Option<i64>,
+    ) -> Result<LoginHistoryPage, AppError> {
+        let cursor = cursor.map(decode_history_cursor).transpose()?;
+        let limit = clamp_history_limit(limit);
+        let rows = self
+            .repos
+            .login_attempts
+            .find_page_by_user(user_id, cursor, limit + 1)?;
+        Ok(build_history_page(rows, limit))
+    }
+
+    /// Admin lookup of login attempts made against an email, including attempts
+    /// on emails that match no account.
+    ///
+    /// Accepts either the hex hash directly or a plain email, which is normalised
+    /// with the service's email policy before hashing. An email the policy rejects is
+    /// hashed as typed (trimmed) and simply matches nothing, since logins with it are
+    /// refused before being recorded.
+    ///
+    /// Returns the page and the hash searched, which the caller audits in place of the
+    /// email.
+    ///
+    /// # Errors
+    ///
+    /// - [`AppError::InvalidInput`] if neither or both of `email_hash` and `email`
+    ///   are given, or if the cursor is malformed.
+    /// - [`AppError::DatabaseError`] on persistence failures.
+    pub fn login_attempts_by_email(
+        &self,
+        email_hash_param: Option<&str>,
+        email: Option<&str>,
+        cursor: Option<&str>,
+        limit: Option<i64>,
+    ) -> Result<(LoginHistoryPage, String), AppError> {
+        let hash = match (email_hash_param, email) {
+            (Some(hash), None) => {
+                if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                    return Err(AppError::invalid_input("email_hash must be a hex SHA-256"));
+                }
+                hash.to_ascii_lowercase()
+            }
+            (None, Some(email)) => match self.email_policy.normalize(email) {
+                Ok(normalized) => email_hash(&normalized),
+                Err(_) => email_hash(email.trim()),
+            },
+            _ => {
+                return Err(AppError::invalid_input(
+                    "Exactly one of email_hash or email is required",
+                ));
+            }
+        };
+
+        let cursor = cursor.map(decode_history_cursor).transpose()?;
+        let limit = clamp_history_limit(limit);
+        let rows = self
+            .repos
+            .login_attempts
+            .find_page_by_email_hash(&hash, cursor, limit + 1)?;
+        Ok((build_history_page(rows, limit), hash))
+    }
+
     /// Changes the user's password after verifying the current one.
     ///
+    /// Every session of the user is revoked; the caller gets a fresh one, whose refresh
+    /// token **hash** is returned for the `HttpOnly` cookie.
+    ///
     /// # Errors
     ///
-    /// - [`AppError::WeakPassword`] if `new_password` does not meet strength requirements.
+    /// - [`AppError::WeakPassword`] if `new_password` breaks the [`PasswordPolicy`],
+    ///   appears in a data breach, or matches the current or a recent password.
     /// - [`AppError::NotFound`] if the user does not exist.
+    /// - [`AppError::PasswordNotSet`] if the account has no password to confirm with.
     /// - [`AppError::InvalidPassword`] if `old_password` does not match the stored hash.
     /// - [`AppError::DatabaseError`] on persistence failures.
     pub fn change_password(
+        &self,
         user_id: uuid::Uuid,
         old_password: &str,
         new_password: &str,
-    ) -> Result<(), AppError> {
-        if !Self::is_strong_password(new_password) {
-            return Err(AppError::WeakPassword(
-                "Password must be at least 8 characters with uppercase, lowercase and numbers"
-                    .to_string(),
-            ));
-        }
-
-        let user = UserRepository::find_by_id(user_id)?;
+        client: &ClientInfo,
+    ) -> Result<String, AppError> {
+        let user = self.repos.users.find_by_id(user_id)?;
         let user = user.ok_or_else(|| AppError::not_found("User not found"))?;
 
+        self.check_new_password(new_password, &[&user.email, &user.username])?;
+
         let password_hash = user
             .password_hash
             .as_ref()
-            .ok_or_else(|| AppError::database("Password not set for user"))?;
-
-        if !super::password::PasswordManager::verify(old_password, password_hash)
-            .map_err(AppError::from)?
+            .ok_or(AppError::PasswordNotSet)?;
+
+        if !super::password::PasswordManager::verify(
+            old_password,
+            password_hash,
+            user.password_pepper_version,
+        )
+        .map_err(AppError::from)?
         {
             return Err(AppError::InvalidPassword);
         }
+        if self.is_recent_password(&user, new_password)? {
+            return Err(AppError::WeakPassword(vec![
+                PasswordViolation::RecentlyUsed,
+            ]));
+        }
 
-        let new_password_hash =
+        let new_password =
             super::password::PasswordManager::hash(new_password).map_err(AppError::from)?;
 
-        UserRepository::update_password(user_id, &new_password_hash)?;
-        Ok(())
+        self.repos.users.change_password(
+            user_id,
+            &new_password.hash,
+            new_password.pepper_version,
+            i64::try_from(self.password_history_size).unwrap_or(i64::MAX),
+        )?;
+        Audit::new(AuditEventType::PasswordChanged)
+            .user(user_id)
+            .client(client)
+            .record(self.repos.audit_events.as_ref());
+        let (_, refresh_token_hash) = self.issue_refresh_token(user_id, false)?;
+        Ok(refresh_token_hash)
+    }
+
+    /// Whether `password` is the user's current password or one of the
+    /// `password_history_size` previous ones.
+    fn is_recent_password(&self, user: &User, password: &str) -> Result<bool, AppError> {
+        let limit = i64::try_from(self.password_history_size).unwrap_or(i64::MAX);
+        let previous = if limit > 0 {
+            self.repos.password_history.find_recent(user.id, limit)?
+        } else {
+            Vec::new()
+        };
+        let candidates = user
+            .password_hash
+            .iter()
+            .map(|hash| (hash.as_str(), user.password_pepper_version))
+            .chain(
+                previous
+                    .iter()
+                    .map(|entry| (entry.password_hash.as_str(), entry.password_pepper_version)),
+            );
+
+        for (hash, pepper_version) in candidates {
+            // Un ancien hash illisible (pepper retiré…) ne bloque pas le changement
+            match super::password::PasswordManager::verify(password, hash, pepper_version) {
+                Ok(true) => return Ok(true),
+                Ok(false) => {}
+                Err(e) => {
+                    tracing::warn!(user_id = %user.id, "Skipping password history entry: {e}");
+                }
+            }
+        }
+        Ok(false)
     }
 
     /// Registers a new user account.
     ///
+    /// The email is normalised by the service's [`EmailPolicy`] and the username by
+    /// [`UsernamePolicy`]; both are unique regardless of case.
+    ///
+    /// In enumeration-safe mode an already registered email is not an error: the
+    /// password is still hashed so timing matches, the owner is emailed, and the
+    /// caller gets the same [`Registration::Accepted`] as for a new account.
+    ///
     /// # Errors
     ///
     /// - [`AppError::InvalidEmail`] if the email format is invalid.
-    /// - [`AppError::WeakPassword`] if the password does not meet strength requirements.
-    /// - [`AppError::UserAlreadyExists`] if the email is already registered.
+    /// - [`AppError::DisposableEmail`] if the email domain is blocklisted.
+    /// - [`AppError::InvalidUsername`] if the username violates the username policy.
+    /// - [`AppError::WeakPassword`] if the password breaks the [`PasswordPolicy`] or
+    ///   appears in a data breach.
+    /// - [`AppError::UserAlreadyExists`] if the email is already registered (unless
+    ///   enumeration-safe).
+    /// - [`AppError::UsernameTaken`] if the username (or a lookalike) is already registered
+    ///   (unless enumeration-safe).
     /// - [`AppError::DatabaseError`] on persistence failures.
-    pub fn register(register_request: RegisterRequest) -> Result<UserResponse, AppError> {
-        if !Self::is_valid_email(&register_request.email) {
-            return Err(AppError::InvalidEmail);
-        }
-
-        if !Self::is_strong_password(&register_request.password) {
-            return Err(AppError::WeakPassword(
-                "Password must be at least 8 characters with uppercase, lowercase and numbers"
-                    .to_string(),
-            ));
-        }
-
-        let user = UserRepository::find_by_email(&register_request.email)?;
-        if user.is_some() {
-            return Err(AppError::UserAlreadyExists);
+    pub fn register(
+        &self,
+        register_request: &RegisterRequest,
+        client: &ClientInfo,
+    ) -> Result<Registration, AppError> {
+        let email = self.email_policy.normalize(&register_request.email)?;
+        self.email_policy.check_allowed(&email)?;
+
+        let username = UsernamePolicy::default().normalize(&register_request.username)?;
+
+        self.check_new_password(
+            &register_request.password,
+            &[&email, &register_request.username],
+        )?;
+
+        // Username vérifié avant l'email : en mode anti-énumération, un username pris
+        // doit donner la même réponse que l'email soit inscrit ou non
+        let username_taken = self
+            .repos
+            .users
+            .find_by_username_canonical(&username.canonical)?
+            .is_some();
+        let existing = self.repos.users.find_by_email(&email)?;
+
+        if !self.enumeration_safe {
+            if existing.is_some() {
+                return Err(AppError::UserAlreadyExists);
+            }
+            if username_taken {
+                return Err(AppError::UsernameTaken);
+            }
+        } else if existing.is_some() || username_taken {
+            // Même coût qu'une vraie inscription
+            let _ = super::password::PasswordManager::hash(&register_request.password);
+            match &existing {
+                Some(existing) => self.send_existing_account_notice(existing),
+                None => self.send_username_taken_notice(&email, &username.display),
+            }
+            return Ok(Registration::Accepted);
         }
 
-        let password_hash = super::password::PasswordManager::hash(&register_request.password)
+        let password = super::password::PasswordManager::hash(&register_request.password)
             .map_err(AppError::from)?;
 
         let new_user = NewUser {
-            email: register_request.email,
-            username: register_request.username,
-            password_hash: Some(password_hash),
+            email,
+            username: username.display,
+            password_hash: Some(password.hash),
+            username_canonical: username.canonical,
+            password_pepper_version: password.pepper_version,
         };
 
-        UserRepository::create(&new_user)
-            .map(std::convert::Into::into)
-            .map_err(AppError::from)
+        // Les index uniques restent l'arbitre final en cas d'inscriptions concurrentes
+        let user = match self.repos.users.create(&new_user) {
+            Ok(user) => user,
+            Err(RepositoryError::UniqueViolation { constraint })
+                if USERNAME_UNIQUE_KEYS.contains(&constraint.as_str()) =>
+            {
+                if !self.enumeration_safe {
+                    return Err(AppError::UsernameTaken);
+                }
+                self.send_username_taken_notice(&new_user.email, &new_user.username);
+                return Ok(Registration::Accepted);
+            }
+            Err(RepositoryError::UniqueViolation { constraint })
+                if EMAIL_UNIQUE_KEYS.contains(&constraint.as_str()) =>
+            {
+                if !self.enumeration_safe {
+                    return Err(AppError::UserAlreadyExists);
+                }
+                if let Ok(Some(existing)) = self.repos.users.find_by_email(&new_user.email) {
+                    self.send_existing_account_notice(&existing);
+                }
+                return Ok(Registration::Accepted);
+            }
+            Err(e) => return Err(AppError::from(e)),
+        };
+        Audit::new(AuditEventType::UserRegistered)
+            .user(user.id)
+            .client(client)
+            .record(self.repos.audit_events.as_ref());
+
+        if !self.enumeration_safe {
+            return Ok(Registration::Created(user.into()));
+        }
+        self.send_email(
+            &user,
+            "Welcome",
+            &format!(
+                "Hello {},\n\nYour account has been created. You can now log in:\n{}/login\n",
+                user.username, self.public_url
+            ),
+        );
+        Ok(Registration::Accepted)
     }
 
     /// Authenticates a user and returns an access token + refresh token hash.
     ///
-    /// The second element of the returned tuple is the **bcrypt hash** of the refresh token,
+    /// The second element of the returned tuple is the **SHA-256 hash** of the refresh token,
     /// intended to be stored in an `HttpOnly` cookie — never returned in the response body.
     ///
     /// # Errors
     ///
     /// - [`AppError::InvalidEmail`] if the email format is invalid.
+    /// - [`AppError::InvalidCredentials`] in enumeration-safe mode, in place of
+    ///   `NotFound`, `TooManyAttempts` and `InvalidPassword`.
     /// - [`AppError::NotFound`] if no user with that email exists.
-    /// - [`AppError::TooManyAttempts`] if the account is temporarily locked.
+    /// - [`AppError::RateLimited`] if too many logins failed recently for this IP or email.
+    /// - [`AppError::TooManyAttempts`] if the account is locked, or if this failure locks it.
     /// - [`AppError::InvalidPassword`] if the password does not match.
     /// - [`AppError::DatabaseError`] on persistence failures.
     pub fn login(
         &self,
         login_request: &LoginRequest,
-        user_agent: Option<String>,
+        client: &ClientInfo,
     ) -> Result<(LoginResponse, String), AppError> {
-        if !Self::is_valid_email(&login_request.email) {
-            return Err(AppError::InvalidEmail);
+        let email = self.email_policy.normalize(&login_request.email)?;
+        let hashed_email = email_hash(&email);
+
+        if let Err(e) = self.rate_limiter.check(client.ip, &hashed_email) {
+            if matches!(e, AppError::RateLimited { .. }) {
+                self.record_attempt(
+                    client,
+                    NewLoginAttempt {
+                        email_hash: Some(hashed_email),
+                        failure_reason: Some(LoginFailureReason::RateLimited.as_str().into()),
+                        ..NewLoginAttempt::default()
+                    },
+                );
+            }
+            return Err(e);
         }
 
-        let user = match UserRepository::find_by_email(&login_request.email) {
+        let user = match self.repos.users.find_by_email(&email) {
             Ok(Some(u)) => u,
             Ok(None) => {
-                let _ = LoginAttemptRepository::create(None, false, user_agent)
-                    .inspect_err(|e| tracing::warn!("Failed to log login attempt: {e}"));
-                return Err(AppError::not_found("User"));
+                self.rate_limiter.record_failure(client.ip, &hashed_email);
+                self.record_attempt(
+                    client,
+                    NewLoginAttempt {
+                        email_hash: Some(hashed_email),
+                        failure_reason: Some(LoginFailureReason::UnknownEmail.as_str().into()),
+                        ..NewLoginAttempt::default()
+                    },
+                );
+                return Err(
+                    self.credentials_error(&login_request.password, || AppError::not_found("User"))
+                );
             }
             Err(e) => return Err(AppError::from(e)),
         };
 
-        let failed_count =
-            LoginAttemptRepository::count_failed_attempts(user.id, LOCKOUT_WINDOW_MINUTES)
-                .map_err(AppError::from)?;
-        if failed_count >= MAX_FAILED_ATTEMPTS {
-            return Err(AppError::TooManyAttempts(format!(
-                "Account temporarily locked after {MAX_FAILED_ATTEMPTS} failed attempts. Try again in {LOCKOUT_WINDOW_MINUTES} minutes."
-            )));
+        if let Some(lock) = LockoutPolicy::current_lock(&user.lockout_state(), Utc::now()) {
+            self.record_failure(client, user.id, LoginFailureReason::AccountLocked);
+            return Err(self.credentials_error(&login_request.password, || lock_error(lock)));
         }
 
-        let password_hash = user
-            .password_hash
-            .as_ref()
-            .ok_or_else(|| AppError::database("Password not set for user"))?;
+        let Some(password_hash) = user.password_hash.as_ref() else {
+            self.record_failure(client, user.id, LoginFailureReason::PasswordNotSet);
+            return Err(self.credentials_error(&login_request.password, || {
+                AppError::database("Password not set for user")
+            }));
+        };
 
-        if !super::password::PasswordManager::verify(&login_request.password, password_hash)
-            .map_err(AppError::from)?
+        if !super::password::PasswordManager::verify(
+            &login_request.password,
+            password_hash,
+            user.password_pepper_version,
+        )
+        .map_err(AppError::from)?
         {
-            let _ = LoginAttemptRepository::create(Some(user.id), false, user_agent)
-                .inspect_err(|e| tracing::warn!("Failed to log failed login attempt: {e}"));
-            return Err(AppError::InvalidPassword);
+            self.rate_limiter.record_failure(client.ip, &hashed_email);
+            self.record_failure(client, user.id, LoginFailureReason::InvalidPassword);
+            let error = self.register_password_failure(&user, client);
+            return Err(if self.enumeration_safe {
+                AppError::InvalidCredentials
+            } else {
+                error
+            });
         }
 
-        let access_token = self
-            .jwt_manager
-            .generate_access_token(user.id)
-            .map_err(AppError::from)?;
+        self.rate_limiter.record_success(client.ip, &hashed_email);
+        self.upgrade_password_hash(&user, &login_request.password);
+        if user.lockout_state() != LockoutState::default() {
+            self.repos.users.reset_lockout(user.id)?;
+        }
 
-        let refresh_token = uuid::Uuid::new_v4().to_string();
-        let refresh_token_hash =
-            super::password::PasswordManager::hash(&refresh_token).map_err(AppError::from)?;
+        let (access_token, password_change_required) = self.access_token_for(&user)?;
+        let risk = self.assess_login(&user, client);
+        let (refresh_token, refresh_token_hash) =
+            self.issue_refresh_token(user.id, !risk.is_empty())?;
+        self.repos.users.update_last_login(user.id)?;
+        if !risk.is_empty() {
+            self.notify_suspicious_login(&user, client, &risk);
+        }
 
-        let new_refresh_token = NewRefreshToken {
-            user_id: user.id,
-            token_hash: refresh_token_hash.clone(),
-            expires_at: Utc::now() + chrono::Duration::days(7),
-        };
+        // Se reconnecter annule une suppression de compte en attente
+        if user.deletion_scheduled_at.is_some() {
+            self.repos.users.cancel_deletion(user.id)?;
+            tracing::info!(user_id = %user.id, "Pending account deletion cancelled by login");
+            Audit::new(AuditEventType::AccountDeletionCancelled)
+                .user(user.id)
+                .client(client)
+                .record(self.repos.audit_events.as_ref());
+        }
 
-        let _created = RefreshTokenRepository::create(&new_refresh_token)?;
-        UserRepository::update_last_login(user.id)?;
+        self.record_attempt(
+            client,
+            NewLoginAttempt {
+                user_id: Some(user.id),
+                success: true,
+                ..NewLoginAttempt::default()
+            },
+        );
 
-        let _ = LoginAttemptRepository::create(Some(user.id), true, user_agent)
-            .inspect_err(|e| tracing::warn!("Failed to log successful login attempt: {e}"));
+        let mut user_response = UserResponse::from(user);
+        user_response.deletion_scheduled_at = None;
 
         let resp = LoginResponse {
             access_token,
             refresh_token,
-            user: user.into(),
+            user: user_response,
             expires_in: self.jwt_manager.expiration_hours() * 3600,
+            password_change_required,
         };
 
         Ok((resp, refresh_token_hash))
@@ -234,7 +1000,7 @@ impl AuthService {
 
     /// Rotates a refresh token: invalidates the old one and issues a new pair.
     ///
-    /// The second element of the returned tuple is the **bcrypt hash** of the new refresh token,
+    /// The second element of the returned tuple is the **SHA-256 hash** of the new refresh token,
     /// intended to be stored in an `HttpOnly` cookie.
     ///
     /// # Errors
@@ -250,7 +1016,10 @@ impl AuthService {
             return Err(AppError::InvalidRefreshToken);
         }
 
-        let old_token = RefreshTokenRepository::find_by_hash(&refresh_token_request.refresh_token)
+        let old_token = self
+            .repos
+            .refresh_tokens
+            .find_by_hash(&refresh_token_request.refresh_token)
             .map_err(AppError::from)?
             .ok_or(AppError::InvalidRefreshToken)?;
 
@@ -258,90 +1027,473 @@ impl AuthService {
             return Err(AppError::RefreshTokenExpired);
         }
 
-        let access_token = self
-            .jwt_manager
-            .generate_access_token(old_token.user_id)
-            .map_err(AppError::from)?;
+        let user = self
+            .repos
+            .users
+            .find_by_id(old_token.user_id)?
+            .ok_or(AppError::InvalidRefreshToken)?;
+        let (access_token, password_change_required) = self.access_token_for(&user)?;
 
-        RefreshTokenRepository::delete(old_token.id)
+        self.repos
+            .refresh_tokens
+            .delete(old_token.id)
             .inspect_err(|e| {
                 tracing::error!("Failed to delete old refresh token {}: {e}", old_token.id);
             })
             .ok();
 
-        let new_refresh_token_str = uuid::Uuid::new_v4().to_string();
-        let new_refresh_token_hash = super::password::PasswordManager::hash(&new_refresh_token_str)
-            .map_err(AppError::from)?;
-
-        let new_refresh_token = NewRefreshToken {
-            user_id: old_token.user_id,
-            token_hash: new_refresh_token_hash.clone(),
-            expires_at: Utc::now() + chrono::Duration::days(7),
-        };
-
-        RefreshTokenRepository::create(&new_refresh_token)?;
+        // Une session signalée le reste après rotation
+        let (_, new_refresh_token_hash) =
+            self.issue_refresh_token(old_token.user_id, old_token.risk_flagged)?;
 
         Ok((
             RefreshTokenResponse {
                 access_token,
                 expires_in: self.jwt_manager.expiration_hours() * 3600,
+                password_change_required,
             },
             new_refresh_token_hash,
         ))
     }
 
-    // === Helpers de validation ===
-
-    fn is_valid_email(email: &str) -> bool {
-        email.contains('@') && email.contains('.') && email.len() > 5
+    /// Access token for a logged-in user, restricted to changing the password when it
+    /// has expired. The flag tells whether it is restricted.
+    fn access_token_for(&self, user: &User) -> Result<(String, bool), AppError> {
+        let expired = self.password_expired(user, Utc::now());
+        let token = if expired {
+            self.jwt_manager.generate_password_change_token(user.id)
+        } else {
+            self.jwt_manager.generate_access_token(user.id)
+        };
+        Ok((token.map_err(AppError::from)?, expired))
     }
 
-    fn is_strong_password(password: &str) -> bool {
-        if password.len() < 8 {
-            return false;
-        }
-        let (mut upper, mut lower, mut digit) = (false, false, false);
-        for c in password.chars() {
-            upper |= c.is_uppercase();
-            lower |= c.is_lowercase();
-            digit |= c.is_ascii_digit();
-            if upper && lower && digit {
-                return true;
+    fn password_expired(&self, user: &User, now: DateTime<Utc>) -> bool {
+        match (self.password_max_age, user.password_changed_at) {
+            (Some(max_age), Some(changed_at)) => {
+                user.password_hash.is_some() && changed_at + max_age <= now
             }
+            _ => false,
         }
-        upper && lower && digit
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::auth::password::PasswordManager;
-    use crate::db::connection::init_test_pool;
-    use crate::db::models::user::NewUser;
-    use crate::db::repositories::user_repository::UserRepository;
+    /// Opens a session: stores a new refresh token and returns it with its hash.
+    fn issue_refresh_token(
+        &self,
+        user_id: uuid::Uuid,
+        risk_flagged: bool,
+    ) -> Result<(String, String), AppError> {
+        let refresh_token = uuid::Uuid::new_v4().to_string();
+        let refresh_token_hash = hash_token(&refresh_token);
 
-    fn create_test_register_request() -> RegisterRequest {
-        init_test_pool();
+        self.repos.refresh_tokens.create(&NewRefreshToken {
+            user_id,
+            token_hash: refresh_token_hash.clone(),
+            expires_at: Utc::now() + chrono::Duration::days(REFRESH_TOKEN_VALIDITY_DAYS),
+            risk_flagged,
+        })?;
+        Ok((refresh_token, refresh_token_hash))
+    }
 
-        let unique = uuid::Uuid::new_v4();
-        RegisterRequest {
-            email: format!("test+{unique}@example.com"),
-            username: format!("testuser_{unique}"),
-            password: "TestPassword123!".to_string(),
+    // === Helpers de validation ===
+
+    /// Error for a login refused before the password could be checked. In
+    /// enumeration-safe mode, a dummy hash verification keeps the response time in
+    /// line with a real password check and the detailed error is hidden.
+    fn credentials_error(&self, password: &str, detailed: impl FnOnce() -> AppError) -> AppError {
+        if !self.enumeration_safe {
+            return detailed();
         }
+        super::password::PasswordManager::dummy_verify(password);
+        AppError::InvalidCredentials
     }
 
-    #[test]
+    fn record_failure(&self, client: &ClientInfo, user_id: uuid::Uuid, reason: LoginFailureReason) {
+        self.record_attempt(
+            client,
+            NewLoginAttempt {
+                user_id: Some(user_id),
+                failure_reason: Some(reason.as_str().into()),
+                ..NewLoginAttempt::default()
+            },
+        );
+    }
+
+    /// Replaces a legacy, outdated or formerly peppered hash after a successful login, while
+    /// the plain password is known. Best effort: the old hash keeps working.
+    fn upgrade_password_hash(&self, user: &User, password: &str) {
+        let Some(current_hash) = user.password_hash.as_deref() else {
+            return;
+        };
+        if !super::password::PasswordManager::needs_rehash(
+            current_hash,
+            user.password_pepper_version,
+        ) {
+            return;
+        }
+
+        let user_id = user.id;
+        let upgraded = super::password::PasswordManager::hash(password)
+            .map_err(AppError::from)
+            .and_then(|hash| {
+                self.repos
+                    .users
+                    .update_password(user_id, &hash.hash, hash.pepper_version)
+                    .map_err(AppError::from)
+            });
+        match upgraded {
+            Ok(()) => tracing::info!(%user_id, "Password hash upgraded"),
+            Err(e) => tracing::warn!(%user_id, "Failed to upgrade password hash: {e}"),
+        }
+    }
+
+    /// Logs a login attempt enriched with the client metadata, in the login history
+    /// and the audit log. Best effort: a logging failure must never block authentication.
+    fn record_attempt(&self, client: &ClientInfo, attempt: NewLoginAttempt) {
+        let audit = if attempt.success {
+            Audit::new(AuditEventType::LoginSucceeded)
+        } else {
+            Audit::new(AuditEventType::LoginFailed).metadata(serde_json::json!({
+                "reason": attempt.failure_reason,
+                "email_hash": attempt.email_hash,
+            }))
+        };
+        match attempt.user_id {
+            Some(user_id) if attempt.success => audit.user(user_id),
+            Some(user_id) => audit.target(user_id),
+            None => audit,
+        }
+        .client(client)
+        .record(self.repos.audit_events.as_ref());
+
+        let attempt = NewLoginAttempt {
+            user_agent: client.user_agent.clone(),
+            ip_address: client.ip.map(|ip| ip.to_string()),
+            request_id: client.request_id.clone(),
+            ..attempt
+        };
+        let _ = self
+            .repos
+            .login_attempts
+            .create(&attempt)
+            .inspect_err(|e| tracing::warn!("Failed to log login attempt: {e}"));
+    }
+
+    /// Updates the lockout state after a wrong password and notifies the owner
+    /// when a lock is triggered. Returns the error to report for this attempt.
+    fn register_password_failure(&self, user: &User, client: &ClientInfo) -> AppError {
+        let now = Utc::now();
+        let mut event = None;
+        let updated = self.repos.users.update_lockout(user.id, &mut |state| {
+            let (next, triggered) = self.lockout_policy.register_failure(state, now);
+            event = triggered;
+            next
+        });
+        if let Err(e) = updated {
+            tracing::warn!(user_id = %user.id, "Failed to update lockout state: {e}");
+            return AppError::InvalidPassword;
+        }
+
+        match event {
+            Some(LockEvent::Tier { level, until }) => {
+                tracing::warn!(user_id = %user.id, level, %until, "Account locked");
+                Audit::new(AuditEventType::AccountLocked)
+                    .target(user.id)
+                    .client(client)
+                    .metadata(serde_json::json!({ "level": level, "until": until }))
+                    .record(self.repos.audit_events.as_ref());
+                self.send_lock_notification(user, Some(until));
+                AppError::TooManyAttempts {
+                    unlock_at: Some(until),
+                }
+            }
+            Some(LockEvent::Permanent) => {
+                tracing::warn!(user_id = %user.id, "Account locked permanently");
+                Audit::new(AuditEventType::AccountLocked)
+                    .target(user.id)
+                    .client(client)
+                    .metadata(serde_json::json!({ "permanent": true }))
+                    .record(self.repos.audit_events.as_ref());
+                self.send_lock_notification(user, None);
+                AppError::TooManyAttempts { unlock_at: None }
+            }
+            None => AppError::InvalidPassword,
+        }
+    }
+
+    /// Emails the owner of a newly locked account with a single-use unlock link.
+    /// Best effort: a mail failure must not change the login response.
+    fn send_lock_notification(&self, user: &User, until: Option<DateTime<Utc>>) {
+        let token = generate_token();
+        let new_token = NewUserToken {
+            user_id: user.id,
+            purpose: TokenPurpose::AccountUnlock.as_str().to_string(),
+            token_hash: hash_token(&token),
+            expires_at: Utc::now() + chrono::Duration::hours(UNLOCK_TOKEN_VALIDITY_HOURS),
+        };
+        if let Err(e) = self.repos.user_tokens.create(&new_token) {
+            tracing::warn!(user_id = %user.id, "Failed to store unlock token: {e}");
+            return;
+        }
+
+        let status = match until {
+            Some(until) => format!(
+                "Your account has been locked until {} after several failed login attempts.",
+                until.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+            ),
+            None => {
+                "Your account has been locked after repeated failed login attempts.".to_string()
+            }
+        };
+        self.send_email(
+            user,
+            "Your account has been locked",
+            &format!(
+                "Hello {},\n\n{status}\n\nIf this was you, you can unlock it now:\n{}/unlock?token={token}\n\nThis link expires in {UNLOCK_TOKEN_VALIDITY_HOURS} hours. If this was not you, consider changing your password.\n",
+                user.username, self.public_url
+            ),
+        );
+    }
+
+    /// Runs the risk rules against a successful login and remembers the device.
+    /// Best effort: if known devices cannot be read, the login is not flagged.
+    fn assess_login(&self, user: &User, client: &ClientInfo) -> Vec<RiskSignal> {
+        let device = DeviceFingerprint::from_client(client);
+        let known_devices = self
+            .repos
+            .known_devices
+            .find_by_user(user.id)
+            .inspect_err(
+                |e| tracing::warn!(user_id = %user.id, "Failed to load known devices: {e}"),
+            )
+            .unwrap_or_default();
+
+        let signals = self.risk_engine.assess(&LoginContext {
+            user_id: user.id,
+            client,
+            device: &device,
+            known_devices: &known_devices,
+            now: Utc::now(),
+        });
+
+        let _ = self
+            .repos
+            .known_devices
+            .touch(&device.to_new_device(user.id))
+            .inspect_err(|e| tracing::warn!(user_id = %user.id, "Failed to record device: {e}"));
+        signals
+    }
+
+    /// Emails the owner about a flagged login with a "this wasn't me" link that
+    /// revokes every session. Best effort, like the other notifications.
+    fn notify_suspicious_login(&self, user: &User, client: &ClientInfo, signals: &[RiskSignal]) {
+        tracing::warn!(user_id = %user.id, rules = ?signals.iter().map(|s| s.rule).collect::<Vec<_>>(), "Suspicious login");
+        Audit::new(AuditEventType::SuspiciousLogin)
+            .user(user.id)
+            .client(client)
+            .metadata(serde_json::json!({
+                "signals": signals
+                    .iter()
+                    .map(|s| serde_json::json!({ "rule": s.rule, "detail": s.detail }))
+                    .collect::<Vec<_>>(),
+            }))
+            .record(self.repos.audit_events.as_ref());
+
+        let token = generate_token();
+        let new_token = NewUserToken {
+            user_id: user.id,
+            purpose: TokenPurpose::SessionRevoke.as_str().to_string(),
+            token_hash: hash_token(&token),
+            expires_at: Utc::now() + chrono::Duration::days(SESSION_REVOKE_TOKEN_VALIDITY_DAYS),
+        };
+        if let Err(e) = self.repos.user_tokens.create(&new_token) {
+            tracing::warn!(user_id = %user.id, "Failed to store session revoke token: {e}");
+            return;
+        }
+
+        let details = signals
+            .iter()
+            .map(|signal| format!("- {}", signal.detail))
+            .collect::<Vec<_>>()
+            .join("\n");
+        self.send_email(
+            user,
+            "New sign-in to your account",
+            &format!(
+                "Hello {},\n\nYour account was just signed in to from a device we have not seen before:\n{details}\n\nIf this was you, you can ignore this email.\n\nIf this was not you, sign out everywhere now:\n{}/not-me?token={token}\n\nThen change your password.\n",
+                user.username, self.public_url
+            ),
+        );
+    }
+
+    /// Tells the owner of an existing account that someone tried to register
+    /// with their email (enumeration-safe registration).
+    fn send_existing_account_notice(&self, user: &User) {
+        tracing::info!(user_id = %user.id, "Registration attempted with an existing email");
+        self.send_email(
+            user,
+            "Registration attempt with your email",
+            &format!(
+                "Hello {},\n\nSomeone tried to create an account with this email address, which already has one.\n\nIf this was you, log in here instead:\n{}/login\n\nIf this was not you, you can ignore this email.\n",
+                user.username, self.public_url
+            ),
+        );
+    }
+
+    /// Tells whoever registered with a new email that the username is taken
+    /// (enumeration-safe registration): no account exists for that address yet.
+    fn send_username_taken_notice(&self, email: &str, username: &str) {
+        tracing::info!("Registration attempted with a taken username");
+        let message = EmailMessage {
+            to: email.to_string(),
+            subject: "Your registration could not be completed".to_string(),
+            body: format!(
+                "Hello,\n\nSomeone tried to create an account with this email address and the username {username}, which is already taken.\n\nIf this was you, register again with another username:\n{}/register\n\nIf this was not you, you can ignore this email.\n",
+                self.public_url
+            ),
+        };
+        let _ = self
+            .mailer
+            .send(&message)
+            .inspect_err(|e| tracing::warn!("{e}"));
+    }
+
+    /// Sends an email to `user`. Best effort: a mail failure is only logged.
+    fn send_email(&self, user: &User, subject: &str, body: &str) {
+        let message = EmailMessage {
+            to: user.email.clone(),
+            subject: subject.to_string(),
+            body: body.to_string(),
+        };
+        let _ = self
+            .mailer
+            .send(&message)
+            .inspect_err(|e| tracing::warn!(user_id = %user.id, "{e}"));
+    }
+
+    /// Rules applied to every new password: the [`PasswordPolicy`], then the breach
+    /// corpus. `user_inputs` are the account's email and username.
+    fn check_new_password(&self, password: &str, user_inputs: &[&str]) -> Result<(), AppError> {
+        let violations = self.password_policy.check(password, user_inputs);
+        if !violations.is_empty() {
+            return Err(AppError::WeakPassword(violations));
+        }
+        if let Some(checker) = &self.breach_checker {
+            checker.check(password)?;
+        }
+        Ok(())
+    }
+}
+
+fn lock_error(lock: Lock) -> AppError {
+    AppError::TooManyAttempts {
+        unlock_at: match lock {
+            Lock::Until(until) => Some(until),
+            Lock::Permanent => None,
+        },
+    }
+}
+
+/// 244 random bits, hex encoded; long enough to be used as a bearer secret.
+fn generate_token() -> String {
+    format!(
+        "{}{}",
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    )
+}
+
+/// Emailed tokens are stored as SHA-256 so a database leak does not expose them.
+fn hash_token(token: &str) -> String {
+    use sha2::{Digest, Sha256};
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+fn clamp_history_limit(limit: Option<i64>) -> i64 {
+    limit
+        .unwrap_or(LOGIN_HISTORY_DEFAULT_LIMIT)
+        .clamp(1, LOGIN_HISTORY_MAX_LIMIT)
+}
+
+/// Cursors are `<attempted_at as unix micros>_<id>`; opaque to clients.
+pub(crate) fn encode_history_cursor(attempted_at: DateTime<Utc>, id: uuid::Uuid) -> String {
+    format!("{}_{}", attempted_at.timestamp_micros(), id.simple())
+}
+
+pub(crate) fn decode_history_cursor(cursor: &str) -> Result<HistoryCursor, AppError> {
+    let invalid = || AppError::invalid_input("Invalid cursor");
+    let (micros, id) = cursor.split_once('_').ok_or_else(invalid)?;
+    let micros = micros.parse::<i64>().map_err(|_| invalid())?;
+    let attempted_at = DateTime::<Utc>::from_timestamp_micros(micros).ok_or_else(invalid)?;
+    let id = uuid::Uuid::parse_str(id).map_err(|_| invalid())?;
+    Ok((attempted_at, id))
+}
+
+/// `rows` was fetched with `limit + 1` so the presence of a next page is known
+/// without a separate count.
+fn build_history_page(
+    mut rows: Vec<crate::db::models::login_attempt::LoginAttempt>,
+    limit: i64,
+) -> LoginHistoryPage {
+    let limit = usize::try_from(limit).unwrap_or(usize::MAX);
+    let next_cursor = if rows.len() > limit {
+        rows.truncate(limit);
+        rows.last()
+            .map(|last| encode_history_cursor(last.attempted_at, last.id))
+    } else {
+        None
+    };
+
+    LoginHistoryPage {
+        items: rows.into_iter().map(Into::into).collect(),
+        next_cursor,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::password::PasswordManager;
+    use crate::db::models::user::NewUser;
+    use crate::db::store::test_repositories;
+    use crate::mailer::MemoryMailer;
+
+    fn test_service() -> AuthService {
+        AuthService::new(
+            crate::auth::jwt::JwtManager::new("secret_key", 1),
+            test_repositories(),
+        )
+    }
+
+    fn register_user(register_request: &RegisterRequest) -> UserResponse {
+        match test_service()
+            .register(register_request, &ClientInfo::default())
+            .expect("Registration should succeed")
+        {
+            Registration::Created(user) => user,
+            Registration::Accepted => panic!("Registration should create the user directly"),
+        }
+    }
+
+    fn create_test_register_request() -> RegisterRequest {
+        let unique = uuid::Uuid::new_v4();
+        RegisterRequest {
+            email: format!("test+{unique}@example.com"),
+            username: format!("testuser_{}", &unique.simple().to_string()[..16]),
+            password: "TestPassword123!".to_string(),
+        }
+    }
+
+    #[test]
     fn register_succeeds_with_valid_data() {
         let register_request = create_test_register_request();
         let email = register_request.email.clone();
-        let user = AuthService::register(register_request).expect("Registration should succeed");
+        let user = register_user(&register_request);
 
-        let result = UserRepository::find_by_email(&email);
+        let result = test_repositories().users.find_by_email(&email);
         assert!(result.is_ok(), "Should find the newly registered user");
 
-        let _ = UserRepository::delete(user.id);
+        let _ = test_repositories().users.delete(user.id);
     }
 
     #[test]
@@ -352,7 +1504,7 @@ mod tests {
             password: "TestPassword123!".to_string(),
         };
 
-        let result: Result<UserResponse, AppError> = AuthService::register(register_request);
+        let result = test_service().register(&register_request, &ClientInfo::default());
         assert!(result.is_err());
     }
 
@@ -364,23 +1516,169 @@ mod tests {
             password: "weak".to_string(),
         };
 
-        let result = AuthService::register(register_request);
+        let result = test_service().register(&register_request, &ClientInfo::default());
         assert!(result.is_err());
     }
 
+    #[test]
+    fn weak_password_reports_each_failed_rule() {
+        let mut register_request = create_test_register_request();
+        register_request.password = format!("{}Aa1!", register_request.username);
+
+        let Err(AppError::WeakPassword(violations)) =
+            test_service().register(&register_request, &ClientInfo::default())
+        else {
+            panic!("expected WeakPassword");
+        };
+        assert!(violations.contains(&PasswordViolation::ContainsUserInfo));
+
+        // Le nom d'utilisateur rend aussi le mot de passe facile à deviner
+        assert!(
+            violations
+                .iter()
+                .any(|v| matches!(v, PasswordViolation::TooWeak { .. }))
+        );
+
+        let lenient = test_service().with_password_policy(PasswordPolicy {
+            forbid_user_info: false,
+            min_strength_score: 0,
+            ..PasswordPolicy::default()
+        });
+        let Registration::Created(user) = lenient
+            .register(&register_request, &ClientInfo::default())
+            .unwrap()
+        else {
+            panic!("Registration should create the user directly");
+        };
+
+        let _ = test_repositories().users.delete(user.id);
+    }
+
+    #[test]
+    fn fold_local_part_only_changes_the_stored_address() {
+        let login = |service: &AuthService, email: &str| {
+            service.login(
+                &LoginRequest {
+                    email: email.to_string(),
+                    password: "TestPassword123!".to_string(),
+                },
+                &ClientInfo::default(),
+            )
+        };
+
+        for fold_local_part in [false, true] {
+            let service = test_service().with_email_policy(EmailPolicy {
+                fold_local_part,
+                ..EmailPolicy::default()
+            });
+            let mut register_request = create_test_register_request();
+            register_request.email = register_request.email.replace("test+", "Mixed.Case+");
+            let Registration::Created(user) = service
+                .register(&register_request, &ClientInfo::default())
+                .unwrap()
+            else {
+                panic!("Registration should create the user directly");
+            };
+
+            let expected = if fold_local_part {
+                register_request.email.to_lowercase()
+            } else {
+                register_request.email.clone()
+            };
+            assert_eq!(user.email, expected);
+            // Connexion et unicité ignorent la casse dans les deux cas
+            assert!(login(&service, &register_request.email.to_uppercase()).is_ok());
+            assert!(matches!(
+                service.register(
+                    &RegisterRequest {
+                        email: register_request.email.to_lowercase(),
+                        username: format!("{}_2", register_request.username),
+                        ..register_request.clone()
+                    },
+                    &ClientInfo::default()
+                ),
+                Err(AppError::UserAlreadyExists)
+            ));
+
+            let _ = test_repositories().users.delete(user.id);
+        }
+    }
+
     #[test]
     fn register_fails_when_email_already_exists() {
         let register_request = create_test_register_request();
 
         // Première inscription
-        let result1 = AuthService::register(register_request.clone())
-            .expect("First registration should succeed");
+        let result1 = register_user(&register_request);
 
         // Deuxième inscription avec le même email
-        let result2 = AuthService::register(register_request);
+        let result2 = test_service().register(&register_request, &ClientInfo::default());
         assert!(result2.is_err());
 
-        let _ = UserRepository::delete(result1.id);
+        let _ = test_repositories().users.delete(result1.id);
+    }
+
+    #[test]
+    fn register_fails_when_username_is_invalid() {
+        let mut register_request = create_test_register_request();
+        register_request.username = "x".repeat(200);
+
+        let result = test_service().register(&register_request, &ClientInfo::default());
+        assert!(matches!(result, Err(AppError::InvalidUsername(_))));
+    }
+
+    #[test]
+    fn register_fails_when_username_differs_only_by_case() {
+        let register_request = create_test_register_request();
+        let first = register_user(&register_request);
+
+        let mut second_request = create_test_register_request();
+        second_request.username = register_request.username.to_uppercase();
+        let result = test_service().register(&second_request, &ClientInfo::default());
+        assert!(matches!(result, Err(AppError::UsernameTaken)));
+
+        let _ = test_repositories().users.delete(first.id);
+    }
+
+    #[test]
+    fn register_fails_when_email_differs_only_by_case() {
+        let register_request = create_test_register_request();
+        let first = register_user(&register_request);
+
+        let mut second_request = create_test_register_request();
+        second_request.email = register_request.email.to_uppercase();
+        let result = test_service().register(&second_request, &ClientInfo::default());
+        assert!(matches!(result, Err(AppError::UserAlreadyExists)));
+
+        let _ = test_repositories().users.delete(first.id);
+    }
+
+    #[test]
+    fn register_stores_normalized_email() {
+        let mut register_request = create_test_register_request();
+        let local = register_request
+            .email
+            .split('@')
+            .next()
+            .unwrap()
+            .to_string();
+        register_request.email = format!("  {local}@EXAMPLE.com ");
+
+        let user = register_user(&register_request);
+        assert_eq!(user.email, format!("{local}@example.com"));
+
+        let _ = test_repositories().users.delete(user.id);
+    }
+
+    #[test]
+    fn register_fails_when_email_domain_is_blocklisted() {
+        let service = test_service().with_email_policy(EmailPolicy {
+            blocked_domains: std::collections::HashSet::from(["example.com".to_string()]),
+            ..EmailPolicy::default()
+        });
+
+        let result = service.register(&create_test_register_request(), &ClientInfo::default());
+        assert!(matches!(result, Err(AppError::DisposableEmail)));
     }
 
     #[test]
@@ -390,10 +1688,10 @@ mod tests {
         let email = register_request.email.clone();
         let password = register_request.password.clone();
 
-        AuthService::register(register_request).expect("Registration should succeed");
+        register_user(&register_request);
 
         let jwt_manager = crate::auth::jwt::JwtManager::new("secret_key", 1);
-        let auth_service = AuthService::new(jwt_manager);
+        let auth_service = AuthService::new(jwt_manager, test_repositories());
 
         let login_request = LoginRequest {
             email: email.clone(),
@@ -401,90 +1699,1155 @@ mod tests {
         };
 
         let (login_response, _refresh_hash) = auth_service
-            .login(&login_request, None)
+            .login(&login_request, &ClientInfo::default())
             .expect("Login should succeed");
 
         assert_eq!(login_response.user.email, email);
 
-        let _ = UserRepository::delete(login_response.user.id);
+        let _ = test_repositories().users.delete(login_response.user.id);
+    }
+
+    #[test]
+    fn login_succeeds_when_email_domain_case_differs() {
+        let register_request = create_test_register_request();
+        let email = register_request
+            .email
+            .to_uppercase()
+            .replace("EXAMPLE.COM", "Example.Com");
+        let password = register_request.password.clone();
+        let service = test_service();
+        let user = register_user(&register_request);
+
+        let login_request = LoginRequest { email, password };
+        let result = service.login(&login_request, &ClientInfo::default());
+        assert!(result.is_ok(), "Login should match case-insensitively");
+
+        let _ = test_repositories().users.delete(user.id);
     }
 
     #[test]
     fn login_fails_with_wrong_password() {
         let register_request = create_test_register_request();
         let email = register_request.email.clone();
-        let user = AuthService::register(register_request).expect("Registration should succeed");
+        let user = register_user(&register_request);
 
         let jwt_manager = crate::auth::jwt::JwtManager::new("default_secret", 1);
-        let auth_service = AuthService::new(jwt_manager);
+        let auth_service = AuthService::new(jwt_manager, test_repositories());
 
         let login_request = LoginRequest {
             email,
             password: "WrongPassword123!".to_string(),
         };
 
-        let result = auth_service.login(&login_request, None);
+        let result = auth_service.login(&login_request, &ClientInfo::default());
         assert!(result.is_err());
 
-        let _ = UserRepository::delete(user.id);
+        let _ = test_repositories().users.delete(user.id);
     }
 
     #[test]
     fn login_fails_when_user_not_found() {
-        init_test_pool();
         let jwt_manager = crate::auth::jwt::JwtManager::new("secret_key", 1);
-        let auth_service = AuthService::new(jwt_manager);
+        let auth_service = AuthService::new(jwt_manager, test_repositories());
 
         let login_request = LoginRequest {
             email: "nonexistent@example.com".to_string(),
             password: "TestPassword123!".to_string(),
         };
 
-        let result = auth_service.login(&login_request, None);
+        let result = auth_service.login(&login_request, &ClientInfo::default());
         assert!(result.is_err());
     }
 
     #[test]
     fn change_password_succeeds_with_correct_old_password() {
-        init_test_pool();
         // Create user with known password
         let old_hash = PasswordManager::hash("OldPass123!").expect("hash");
         let new_user = NewUser {
             email: format!("change_pw_{}@example.com", uuid::Uuid::new_v4()),
             username: "change_pw_user".to_string(),
-            password_hash: Some(old_hash),
+            password_hash: Some(old_hash.hash),
+            password_pepper_version: old_hash.pepper_version,
+            username_canonical: "change_pw_user".to_string(),
         };
-        let user = UserRepository::create(&new_user).expect("create user");
+        let user = test_repositories()
+            .users
+            .create(&new_user)
+            .expect("create user");
 
         // Change password via service
-        let result = AuthService::change_password(user.id, "OldPass123!", "NewPass456!");
+        let result = test_service().change_password(
+            user.id,
+            "OldPass123!",
+            "NewPass456!",
+            &ClientInfo::default(),
+        );
         assert!(result.is_ok(), "Change password should succeed");
 
         // Verify new password
-        let updated = UserRepository::find_by_id(user.id)
+        let updated = test_repositories()
+            .users
+            .find_by_id(user.id)
             .expect("find")
             .expect("exists");
         let hash = updated.password_hash.as_ref().expect("hash");
-        let ok = PasswordManager::verify("NewPass456!", hash).expect("verify");
+        let ok = PasswordManager::verify("NewPass456!", hash, updated.password_pepper_version)
+            .expect("verify");
         assert!(ok);
 
-        let _ = UserRepository::delete(user.id);
+        let _ = test_repositories().users.delete(user.id);
     }
 
     #[test]
     fn change_password_fails_when_old_password_is_wrong() {
-        init_test_pool();
         let old_hash = PasswordManager::hash("OldPass123!").expect("hash");
         let new_user = NewUser {
             email: format!("change_pw_wrong_{}@example.com", uuid::Uuid::new_v4()),
             username: "change_pw_wrong_user".to_string(),
-            password_hash: Some(old_hash),
+            password_hash: Some(old_hash.hash),
+            password_pepper_version: old_hash.pepper_version,
+            username_canonical: "change_pw_wrong_user".to_string(),
         };
-        let user = UserRepository::create(&new_user).expect("create user");
-
-        let result = AuthService::change_password(user.id, "WrongOld!", "NewPass456!");
+        let user = test_repositories()
+            .users
+            .create(&new_user)
+            .expect("create user");
+
+        let result = test_service().change_password(
+            user.id,
+            "WrongOld!",
+            "NewPass456!",
+            &ClientInfo::default(),
+        );
         assert!(result.is_err(), "Should fail with invalid old password");
 
-        let _ = UserRepository::delete(user.id);
+        let _ = test_repositories().users.delete(user.id);
+    }
+
+    fn create_user_with_password(password: &str) -> crate::db::models::user::User {
+        let username = format!("svc_{}", &uuid::Uuid::new_v4().simple().to_string()[..16]);
+        let new_user = NewUser {
+            email: format!("{username}@example.com"),
+            username_canonical: username.clone(),
+            username,
+            password_hash: Some(PasswordManager::hash(password).expect("hash").hash),
+            password_pepper_version: None,
+        };
+        test_repositories()
+            .users
+            .create(&new_user)
+            .expect("create user")
+    }
+
+    #[test]
+    fn change_password_refuses_current_and_recent_passwords() {
+        let service = test_service().with_password_history(2);
+        let user = create_user_with_password("First-Pass-111");
+
+        let reused = |old: &str, new: &str| {
+            matches!(
+                service.change_password(user.id, old, new, &ClientInfo::default()),
+                Err(AppError::WeakPassword(violations))
+                    if violations == [PasswordViolation::RecentlyUsed]
+            )
+        };
+
+        assert!(reused("First-Pass-111", "First-Pass-111"));
+        service
+            .change_password(
+                user.id,
+                "First-Pass-111",
+                "Second-Pass-222",
+                &ClientInfo::default(),
+            )
+            .expect("First change should succeed");
+        assert!(reused("Second-Pass-222", "First-Pass-111"));
+        service
+            .change_password(
+                user.id,
+                "Second-Pass-222",
+                "Third-Pass-333",
+                &ClientInfo::default(),
+            )
+            .expect("Second change should succeed");
+        service
+            .change_password(
+                user.id,
+                "Third-Pass-333",
+                "Fourth-Pass-444",
+                &ClientInfo::default(),
+            )
+            .expect("Third change should succeed");
+
+        // Seuls les deux mots de passe précédents sont conservés
+        assert_eq!(
+            test_repositories()
+                .password_history
+                .find_recent(user.id, 10)
+                .unwrap()
+                .len(),
+            2
+        );
+        assert!(
+            service
+                .change_password(
+                    user.id,
+                    "Fourth-Pass-444",
+                    "First-Pass-111",
+                    &ClientInfo::default()
+                )
+                .is_ok()
+        );
+
+        let _ = test_repositories().users.delete(user.id);
+    }
+
+    #[test]
+    fn account_changes_are_written_to_the_audit_log() {
+        use crate::db::repositories::audit_event_repository::AuditEventFilter;
+        let client = ClientInfo {
+            ip: Some("203.0.113.7".parse().unwrap()),
+            user_agent: Some("AuditTest/1.0".to_string()),
+            request_id: Some("req-audit".to_string()),
+        };
+        let service = test_service();
+        let Registration::Created(user) = service
+            .register(&create_test_register_request(), &client)
+            .expect("register")
+        else {
+            panic!("Expected a created account");
+        };
+        service
+            .change_password(user.id, "TestPassword123!", "Another-Pass-456", &client)
+            .expect("change");
+
+        let events = test_repositories()
+            .audit_events
+            .find_page(
+                &AuditEventFilter {
+                    target_id: Some(user.id),
+                    ..AuditEventFilter::default()
+                },
+                None,
+                10,
+            )
+            .expect("Query should succeed");
+        let types: Vec<&str> = events.iter().map(|e| e.event_type.as_str()).collect();
+        assert_eq!(types, ["password_changed", "user_registered"]);
+        assert_eq!(events[0].actor_id, Some(user.id));
+        assert_eq!(events[0].ip_address.as_deref(), Some("203.0.113.7"));
+        assert_eq!(events[0].request_id.as_deref(), Some("req-audit"));
+
+        let _ = test_repositories().users.delete(user.id);
+    }
+
+    #[test]
+    fn change_password_revokes_other_sessions() {
+        let service = test_service();
+        let user = create_user_with_password("OldPass123!");
+        let login_request = LoginRequest {
+            email: user.email.clone(),
+            password: "OldPass123!".to_string(),
+        };
+        let (_, first_session) = service
+            .login(&login_request, &ClientInfo::default())
+            .expect("Login should succeed");
+        service
+            .login(&login_request, &ClientInfo::default())
+            .expect("Login should succeed");
+
+        let new_session = service
+            .change_password(
+                user.id,
+                "OldPass123!",
+                "NewPass456!",
+                &ClientInfo::default(),
+            )
+            .expect("Change should succeed");
+
+        let sessions = test_repositories()
+            .refresh_tokens
+            .find_by_user(user.id)
+            .unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert!(
+            test_repositories()
+                .refresh_tokens
+                .find_by_hash(&first_session)
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            test_repositories()
+                .refresh_tokens
+                .find_by_hash(&new_session)
+                .unwrap()
+                .is_some()
+        );
+        let changed = test_repositories()
+            .users
+            .find_by_id(user.id)
+            .unwrap()
+            .unwrap();
+        assert!(changed.password_changed_at > user.password_changed_at);
+
+        let _ = test_repositories().users.delete(user.id);
+    }
+
+    #[test]
+    fn reset_password_applies_policy_and_revokes_sessions() {
+        let service = test_service();
+        let user = create_user_with_password("OldPass123!");
+        let login = |password: &str| {
+            service.login(
+                &LoginRequest {
+                    email: user.email.clone(),
+                    password: password.to_string(),
+                },
+                &ClientInfo::default(),
+            )
+        };
+        let (_, session) = login("OldPass123!").expect("Login should succeed");
+
+        assert!(matches!(
+            service.reset_password(user.id, "short"),
+            Err(AppError::WeakPassword(_))
+        ));
+        assert!(matches!(
+            service.reset_password(user.id, "OldPass123!"),
+            Err(AppError::WeakPassword(_))
+        ));
+        service
+            .reset_password(user.id, "Reset-Pass-789")
+            .expect("Reset should succeed");
+
+        assert!(
+            test_repositories()
+                .refresh_tokens
+                .find_by_hash(&session)
+                .unwrap()
+                .is_none()
+        );
+        assert!(login("Reset-Pass-789").is_ok());
+        let _ = test_repositories().users.delete(user.id);
+    }
+
+    #[test]
+    fn create_admin_registers_a_verified_administrator() {
+        let request = create_test_register_request();
+        let admin = test_service()
+            .create_admin(&request)
+            .expect("Admin creation should succeed");
+        assert!(admin.is_admin);
+        assert!(admin.email_verified);
+        assert_eq!(
+            test_service()
+                .find_user_by_email(&request.email.to_uppercase())
+                .unwrap()
+                .id,
+            admin.id
+        );
+
+        // Mêmes règles qu'une inscription
+        assert!(matches!(
+            test_service().create_admin(&request),
+            Err(AppError::UserAlreadyExists)
+        ));
+        assert!(
+            test_service()
+                .with_enumeration_protection(true)
+                .create_admin(&create_test_register_request())
+                .is_err()
+        );
+        let _ = test_repositories().users.delete(admin.id);
+    }
+
+    #[test]
+    fn expired_password_gets_a_restricted_token_until_changed() {
+        let jwt = crate::auth::jwt::JwtManager::new("secret_key", 1);
+        let expiring = AuthService::new(jwt.clone(), test_repositories())
+            .with_password_max_age(Some(chrono::Duration::zero()));
+        let user = create_user_with_password("OldPass123!");
+        let login = |service: &AuthService, password: &str| {
+            service
+                .login(
+                    &LoginRequest {
+                        email: user.email.clone(),
+                        password: password.to_string(),
+                    },
+                    &ClientInfo::default(),
+                )
+                .expect("Login should succeed")
+        };
+
+        let (response, refresh_hash) = login(&expiring, "OldPass123!");
+        assert!(response.password_change_required);
+        assert!(
+            jwt.verify_token(&response.access_token)
+                .unwrap()
+                .password_change_required
+        );
+        let (refreshed, _) = expiring
+            .refresh_token(&RefreshTokenRequest {
+                refresh_token: refresh_hash,
+            })
+            .expect("Refresh should succeed");
+        assert!(refreshed.password_change_required);
+
+        expiring
+            .change_password(
+                user.id,
+                "OldPass123!",
+                "NewPass456!",
+                &ClientInfo::default(),
+            )
+            .expect("Change should succeed");
+        let thirty_days = AuthService::new(jwt, test_repositories())
+            .with_password_max_age(Some(chrono::Duration::days(30)));
+        let (response, _) = login(&thirty_days, "NewPass456!");
+        assert!(!response.password_change_required);
+
+        let _ = test_repositories().users.delete(user.id);
+    }
+
+    #[test]
+    fn request_account_deletion_schedules_deletion_and_revokes_sessions() {
+        let user = create_user_with_password("DeleteMe123!");
+        let token = NewRefreshToken {
+            user_id: user.id,
+            token_hash: format!("delete_me_{}", uuid::Uuid::new_v4()),
+            expires_at: Utc::now() + chrono::Duration::days(7),
+            risk_flagged: false,
+        };
+        test_repositories()
+            .refresh_tokens
+            .create(&token)
+            .expect("create token");
+
+        let scheduled_at = test_service()
+            .request_account_deletion(user.id, "DeleteMe123!", &ClientInfo::default())
+            .expect("Deletion request should succeed");
+
+        assert!(scheduled_at > Utc::now() + chrono::Duration::days(13));
+        let stored = test_repositories()
+            .users
+            .find_by_id(user.id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.deletion_scheduled_at, Some(scheduled_at));
+        assert!(
+            test_repositories()
+                .refresh_tokens
+                .find_by_user(user.id)
+                .unwrap()
+                .is_empty()
+        );
+
+        let _ = test_repositories().users.delete(user.id);
+    }
+
+    #[test]
+    fn request_account_deletion_fails_with_wrong_password() {
+        let user = create_user_with_password("DeleteMe123!");
+
+        let result = test_service().request_account_deletion(
+            user.id,
+            "WrongPass123!",
+            &ClientInfo::default(),
+        );
+        assert!(matches!(result, Err(AppError::InvalidPassword)));
+
+        let stored = test_repositories()
+            .users
+            .find_by_id(user.id)
+            .unwrap()
+            .unwrap();
+        assert!(stored.deletion_scheduled_at.is_none());
+
+        let _ = test_repositories().users.delete(user.id);
+    }
+
+    #[test]
+    fn request_account_deletion_without_password_asks_for_one() {
+        let username = format!("svc_{}", &uuid::Uuid::new_v4().simple().to_string()[..16]);
+        let user = test_repositories()
+            .users
+            .create(&NewUser {
+                email: format!("{username}@example.com"),
+                username_canonical: username.clone(),
+                username,
+                password_hash: None,
+                password_pepper_version: None,
+            })
+            .expect("create user");
+
+        let result = test_service().request_account_deletion(user.id, "", &ClientInfo::default());
+        let error = result.expect_err("Deletion needs a password to confirm with");
+        assert!(matches!(error, AppError::PasswordNotSet));
+        assert_eq!(error.status_code(), axum::http::StatusCode::FORBIDDEN);
+
+        let stored = test_repositories()
+            .users
+            .find_by_id(user.id)
+            .unwrap()
+            .unwrap();
+        assert!(stored.deletion_scheduled_at.is_none());
+
+        let _ = test_repositories().users.delete(user.id);
+    }
+
+    #[test]
+    fn account_without_password_confirms_deletion_by_email() {
+        let username = format!("svc_{}", &uuid::Uuid::new_v4().simple().to_string()[..16]);
+        let user = test_repositories()
+            .users
+            .create(&NewUser {
+                email: format!("{username}@example.com"),
+                username_canonical: username.clone(),
+                username,
+                password_hash: None,
+                password_pepper_version: None,
+            })
+            .expect("create user");
+        let mailer = Arc::new(MemoryMailer::default());
+        let service = test_service().with_mailer(
+            Arc::clone(&mailer) as Arc<dyn Mailer>,
+            "https://app.example.com",
+        );
+
+        service
+            .send_account_deletion_confirmation(user.id)
+            .expect("Confirmation email should be sent");
+        let sent = mailer.sent();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].to, user.email);
+        let token = sent[0]
+            .body
+            .split("https://app.example.com/confirm-deletion?token=")
+            .nth(1)
+            .and_then(|rest| rest.split_whitespace().next())
+            .expect("confirmation link in email");
+
+        let scheduled_at = service
+            .confirm_account_deletion_with_token(token, &ClientInfo::default())
+            .expect("Confirmation should schedule the deletion");
+        assert!(scheduled_at > Utc::now() + chrono::Duration::days(13));
+        let stored = test_repositories()
+            .users
+            .find_by_id(user.id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.deletion_scheduled_at, Some(scheduled_at));
+        assert!(
+            service
+                .confirm_account_deletion_with_token(token, &ClientInfo::default())
+                .is_err(),
+            "Deletion tokens are single-use"
+        );
+
+        let _ = test_repositories().users.delete(user.id);
+    }
+
+    #[test]
+    fn account_with_password_cannot_confirm_deletion_by_email() {
+        let user = create_user_with_password("DeleteMe123!");
+
+        let result = test_service().send_account_deletion_confirmation(user.id);
+        assert!(matches!(result, Err(AppError::InvalidInput(_))));
+
+        let _ = test_repositories().users.delete(user.id);
+    }
+
+    #[test]
+    fn login_cancels_pending_account_deletion() {
+        let user = create_user_with_password("ComeBack123!");
+        test_service()
+            .request_account_deletion(user.id, "ComeBack123!", &ClientInfo::default())
+            .expect("schedule");
+
+        let login_request = LoginRequest {
+            email: user.email.clone(),
+            password: "ComeBack123!".to_string(),
+        };
+        let (response, _) = test_service()
+            .login(&login_request, &ClientInfo::default())
+            .expect("Login should succeed");
+
+        assert!(response.user.deletion_scheduled_at.is_none());
+        let stored = test_repositories()
+            .users
+            .find_by_id(user.id)
+            .unwrap()
+            .unwrap();
+        assert!(stored.deletion_scheduled_at.is_none());
+
+        let _ = test_repositories().users.delete(user.id);
+    }
+
+    #[test]
+    fn purge_due_deletions_removes_expired_accounts() {
+        let user = create_user_with_password("PurgeMe123!");
+        test_repositories()
+            .users
+            .schedule_deletion(user.id, Utc::now() - chrono::Duration::seconds(1))
+            .expect("schedule");
+
+        let purged = test_service()
+            .purge_due_deletions()
+            .expect("Purge should succeed");
+
+        assert!(purged >= 1);
+        assert!(
+            test_repositories()
+                .users
+                .find_by_id(user.id)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn export_account_includes_sessions_and_login_history() {
+        let user = create_user_with_password("ExportMe123!");
+        let login_request = LoginRequest {
+            email: user.email.clone(),
+            password: "ExportMe123!".to_string(),
+        };
+        test_service()
+            .login(
+                &login_request,
+                &ClientInfo {
+                    user_agent: Some("ExportAgent/1.0".to_string()),
+                    ..ClientInfo::default()
+                },
+            )
+            .expect("Login should succeed");
+
+        let export = test_service()
+            .export_account(user.id)
+            .expect("Export should succeed");
+
+        assert_eq!(export.user.id, user.id);
+        assert_eq!(export.sessions.len(), 1);
+        assert_eq!(export.login_history.len(), 1);
+        assert_eq!(
+            export.login_history[0].user_agent.as_deref(),
+            Some("ExportAgent/1.0")
+        );
+        let json = serde_json::to_string(&export).expect("serializable");
+        assert!(
+            !json.contains("password"),
+            "Export must not leak credentials"
+        );
+
+        let _ = test_repositories().users.delete(user.id);
+    }
+
+    #[test]
+    fn login_history_paginates_with_cursor() {
+        let user = create_user_with_password("History123!");
+        for _ in 0..3 {
+            test_repositories()
+                .login_attempts
+                .create(&NewLoginAttempt {
+                    user_id: Some(user.id),
+                    user_agent: Some("curl/8.4.0".to_string()),
+                    ..NewLoginAttempt::default()
+                })
+                .expect("log attempt");
+        }
+
+        let first = test_service()
+            .login_history(user.id, None, Some(2))
+            .expect("first page");
+        assert_eq!(first.items.len(), 2);
+        let cursor = first.next_cursor.expect("more pages");
+
+        let second = test_service()
+            .login_history(user.id, Some(&cursor), Some(2))
+            .expect("second page");
+        assert_eq!(second.items.len(), 1);
+        assert!(second.next_cursor.is_none());
+        assert!(first.items.iter().all(|a| a.id != second.items[0].id));
+        assert_eq!(
+            second.items[0].device.device_type,
+            auth_manager_api::DeviceType::Bot
+        );
+
+        let _ = test_repositories().users.delete(user.id);
+    }
+
+    #[test]
+    fn login_history_rejects_malformed_cursor() {
+        let result = test_service().login_history(uuid::Uuid::new_v4(), Some("garbage"), None);
+        assert!(matches!(result, Err(AppError::InvalidInput(_))));
+    }
+
+    #[test]
+    fn history_cursor_round_trips() {
+        let at = DateTime::<Utc>::from_timestamp_micros(1_700_000_000_123_456).unwrap();
+        let id = uuid::Uuid::new_v4();
+        let decoded = decode_history_cursor(&encode_history_cursor(at, id)).expect("valid");
+        assert_eq!(decoded, (at, id));
+    }
+
+    #[test]
+    fn unknown_email_attempts_are_queryable_by_email() {
+        let email = format!("ghost_{}@example.com", uuid::Uuid::new_v4().simple());
+        let login_request = LoginRequest {
+            email: email.clone(),
+            password: "Whatever123!".to_string(),
+        };
+        let service = test_service();
+        assert!(
+            service
+                .login(&login_request, &ClientInfo::default())
+                .is_err()
+        );
+
+        let (page, searched) = service
+            .login_attempts_by_email(None, Some(&email.to_uppercase()), None, None)
+            .expect("lookup by email");
+        assert_eq!(page.items.len(), 1);
+        assert!(!page.items[0].success);
+
+        let hash = email_hash(&email);
+        assert_eq!(searched, hash);
+        let (by_hash, _) = service
+            .login_attempts_by_email(Some(&hash), None, None, None)
+            .expect("lookup by hash");
+        assert_eq!(by_hash.items.len(), 1);
+
+        let (invalid, searched) = service
+            .login_attempts_by_email(None, Some(" Not An Email "), None, None)
+            .expect("an invalid email matches nothing");
+        assert!(invalid.items.is_empty());
+        assert_eq!(searched, email_hash("not an email"));
+
+        assert!(matches!(
+            service.login_attempts_by_email(None, None, None, None),
+            Err(AppError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn failed_login_records_client_metadata_and_reason() {
+        let user = create_user_with_password("Metadata123!");
+        let client = ClientInfo {
+            ip: Some("203.0.113.42".parse().unwrap()),
+            user_agent: Some("MetaAgent/1.0".to_string()),
+            request_id: Some("req-meta".to_string()),
+        };
+        let login_request = LoginRequest {
+            email: user.email.clone(),
+            password: "WrongPass123!".to_string(),
+        };
+        assert!(test_service().login(&login_request, &client).is_err());
+
+        let attempts = test_repositories()
+            .login_attempts
+            .find_all_by_user(user.id)
+            .expect("query");
+        assert_eq!(attempts.len(), 1);
+        assert_eq!(attempts[0].ip_address.as_deref(), Some("203.0.113.42"));
+        assert_eq!(attempts[0].request_id.as_deref(), Some("req-meta"));
+        assert_eq!(
+            attempts[0].failure_reason.as_deref(),
+            Some(LoginFailureReason::InvalidPassword.as_str())
+        );
+
+        let _ = test_repositories().users.delete(user.id);
+    }
+
+    #[test]
+    fn login_is_rate_limited_for_unknown_emails() {
+        use crate::auth::rate_limit::{
+            InMemoryRateLimitStore, RateLimitConfig, RateLimitRule, RateLimiter,
+        };
+        let strict = RateLimitRule {
+            max_failures: 2,
+            window: chrono::Duration::minutes(15),
+        };
+        let service = test_service().with_rate_limiter(RateLimiter::new(
+            std::sync::Arc::new(InMemoryRateLimitStore::default()),
+            RateLimitConfig {
+                ip_email: strict,
+                ..RateLimitConfig::default()
+            },
+        ));
+        let client = ClientInfo {
+            ip: Some("198.51.100.99".parse().unwrap()),
+            ..ClientInfo::default()
+        };
+        let login_request = LoginRequest {
+            email: format!("nobody_{}@example.com", uuid::Uuid::new_v4().simple()),
+            password: "Guess123!".to_string(),
+        };
+
+        for _ in 0..2 {
+            assert!(matches!(
+                service.login(&login_request, &client),
+                Err(AppError::NotFound(_))
+            ));
+        }
+        assert!(matches!(
+            service.login(&login_request, &client),
+            Err(AppError::RateLimited { .. })
+        ));
+    }
+
+    fn lockout_service(mailer: Arc<MemoryMailer>) -> AuthService {
+        test_service()
+            .with_lockout_policy(LockoutPolicy {
+                threshold: 2,
+                backoff_base: chrono::Duration::zero(),
+                tiers: vec![chrono::Duration::minutes(15)],
+                ..LockoutPolicy::default()
+            })
+            .with_mailer(mailer, "https://app.example.com/")
+    }
+
+    #[test]
+    fn repeated_failures_lock_account_and_email_unlock_link() {
+        let user = create_user_with_password("Correct123!");
+        let mailer = Arc::new(MemoryMailer::default());
+        let service = lockout_service(Arc::clone(&mailer));
+        let wrong = LoginRequest {
+            email: user.email.clone(),
+            password: "Wrong123!".to_string(),
+        };
+
+        assert!(matches!(
+            service.login(&wrong, &ClientInfo::default()),
+            Err(AppError::InvalidPassword)
+        ));
+        assert!(matches!(
+            service.login(&wrong, &ClientInfo::default()),
+            Err(AppError::TooManyAttempts { unlock_at: Some(_) })
+        ));
+
+        // Même le bon mot de passe est refusé tant que le compte est verrouillé
+        let right = LoginRequest {
+            email: user.email.clone(),
+            password: "Correct123!".to_string(),
+        };
+        assert!(matches!(
+            service.login(&right, &ClientInfo::default()),
+            Err(AppError::TooManyAttempts { .. })
+        ));
+
+        let sent = mailer.sent();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].to, user.email);
+        let token = sent[0]
+            .body
+            .split("https://app.example.com/unlock?token=")
+            .nth(1)
+            .and_then(|rest| rest.split_whitespace().next())
+            .expect("unlock link in email");
+
+        test_service()
+            .unlock_account_with_token(token, &ClientInfo::default())
+            .expect("unlock should succeed");
+        assert!(service.login(&right, &ClientInfo::default()).is_ok());
+        assert!(
+            test_service()
+                .unlock_account_with_token(token, &ClientInfo::default())
+                .is_err(),
+            "Unlock tokens are single-use"
+        );
+
+        let _ = test_repositories().users.delete(user.id);
+    }
+
+    #[test]
+    fn login_from_new_device_flags_session_and_emails_revoke_link() {
+        let user = create_user_with_password("Correct123!");
+        let mailer = Arc::new(MemoryMailer::default());
+        let service = test_service().with_mailer(
+            Arc::clone(&mailer) as Arc<dyn Mailer>,
+            "https://app.example.com",
+        );
+        let request = LoginRequest {
+            email: user.email.clone(),
+            password: "Correct123!".to_string(),
+        };
+        let laptop = ClientInfo {
+            ip: Some("203.0.113.7".parse().unwrap()),
+            user_agent: Some("Mozilla/5.0 (X11; Linux x86_64; rv:121.0) Firefox/121.0".into()),
+            request_id: None,
+        };
+        let phone = ClientInfo {
+            ip: Some("198.51.100.20".parse().unwrap()),
+            user_agent: Some(
+                "Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X) Mobile Safari/604.1".into(),
+            ),
+            request_id: None,
+        };
+
+        // Premier appareil : référence, puis reconnu
+        service.login(&request, &laptop).expect("first login");
+        service.login(&request, &laptop).expect("known device");
+        assert!(mailer.sent().is_empty());
+        assert!(
+            test_repositories()
+                .refresh_tokens
+                .find_by_user(user.id)
+                .unwrap()
+                .iter()
+                .all(|session| !session.risk_flagged)
+        );
+
+        service.login(&request, &phone).expect("new device");
+        let sessions = test_repositories()
+            .refresh_tokens
+            .find_by_user(user.id)
+            .unwrap();
+        assert_eq!(sessions.iter().filter(|s| s.risk_flagged).count(), 1);
+
+        let sent = mailer.sent();
+        assert_eq!(sent.len(), 1);
+        assert!(sent[0].body.contains("Safari on iOS"));
+        let token = sent[0]
+            .body
+            .split("https://app.example.com/not-me?token=")
+            .nth(1)
+            .and_then(|rest| rest.split_whitespace().next())
+            .expect("revoke link in email");
+
+        test_service()
+            .revoke_sessions_with_token(token, &ClientInfo::default())
+            .expect("revoke should succeed");
+        assert!(
+            test_repositories()
+                .refresh_tokens
+                .find_by_user(user.id)
+                .unwrap()
+                .is_empty()
+        );
+        assert!(
+            test_service()
+                .revoke_sessions_with_token(token, &ClientInfo::default())
+                .is_err(),
+            "Revoke tokens are single-use"
+        );
+
+        let _ = test_repositories().users.delete(user.id);
+    }
+
+    #[test]
+    fn admin_unlock_clears_permanent_lock() {
+        let user = create_user_with_password("Correct123!");
+        test_repositories()
+            .users
+            .update_lockout(user.id, &mut |state| LockoutState {
+                locked_permanently: true,
+                lockout_level: 4,
+                ..state
+            })
+            .expect("lock user");
+
+        let service = lockout_service(Arc::new(MemoryMailer::default()));
+        let right = LoginRequest {
+            email: user.email.clone(),
+            password: "Correct123!".to_string(),
+        };
+        assert!(matches!(
+            service.login(&right, &ClientInfo::default()),
+            Err(AppError::TooManyAttempts { unlock_at: None })
+        ));
+
+        test_service()
+            .unlock_account(user.id)
+            .expect("admin unlock");
+        assert!(service.login(&right, &ClientInfo::default()).is_ok());
+
+        let _ = test_repositories().users.delete(user.id);
+    }
+
+    fn enumeration_safe_service(mailer: Arc<MemoryMailer>) -> AuthService {
+        test_service()
+            .with_enumeration_protection(true)
+            .with_mailer(mailer, "https://app.example.com")
+    }
+
+    #[test]
+    fn enumeration_safe_login_hides_whether_email_exists() {
+        let user = create_user_with_password("Correct123!");
+        let service = enumeration_safe_service(Arc::new(MemoryMailer::default()));
+
+        let unknown = service.login(
+            &LoginRequest {
+                email: format!("nobody_{}@example.com", uuid::Uuid::new_v4().simple()),
+                password: "Guess123!".to_string(),
+            },
+            &ClientInfo::default(),
+        );
+        let wrong_password = service.login(
+            &LoginRequest {
+                email: user.email.clone(),
+                password: "Guess123!".to_string(),
+            },
+            &ClientInfo::default(),
+        );
+
+        assert!(matches!(unknown, Err(AppError::InvalidCredentials)));
+        assert!(matches!(wrong_password, Err(AppError::InvalidCredentials)));
+
+        let _ = test_repositories().users.delete(user.id);
+    }
+
+    #[test]
+    fn enumeration_safe_register_answers_identically_and_emails_owner() {
+        let mailer = Arc::new(MemoryMailer::default());
+        let service = enumeration_safe_service(Arc::clone(&mailer));
+        let register_request = create_test_register_request();
+
+        let first = service
+            .register(&register_request, &ClientInfo::default())
+            .expect("first");
+        let second = service
+            .register(
+                &RegisterRequest {
+                    username: format!("other_{}", &uuid::Uuid::new_v4().simple().to_string()[..16]),
+                    ..register_request.clone()
+                },
+                &ClientInfo::default(),
+            )
+            .expect("second");
+        assert!(matches!(first, Registration::Accepted));
+        assert!(matches!(second, Registration::Accepted));
+
+        let sent = mailer.sent();
+        assert_eq!(sent.len(), 2);
+        assert!(sent.iter().all(|m| m.to == register_request.email));
+        assert_eq!(sent[1].subject, "Registration attempt with your email");
+
+        let user = test_repositories()
+            .users
+            .find_by_email(&register_request.email)
+            .unwrap()
+            .expect("account created once");
+        let _ = test_repositories().users.delete(user.id);
+    }
+
+    #[test]
+    fn enumeration_safe_register_hides_whether_email_exists_when_username_is_taken() {
+        let mailer = Arc::new(MemoryMailer::default());
+        let service = enumeration_safe_service(Arc::clone(&mailer));
+        let owner = register_user(&create_test_register_request());
+        let taken = register_user(&create_test_register_request());
+
+        let existing_email = service
+            .register(
+                &RegisterRequest {
+                    email: owner.email.clone(),
+                    username: taken.username.clone(),
+                    password: "TestPassword123!".to_string(),
+                },
+                &ClientInfo::default(),
+            )
+            .map(|registration| format!("{registration:?}"))
+            .map_err(|e| e.to_string());
+        let new_email = format!("new+{}@example.com", uuid::Uuid::new_v4().simple());
+        let unknown_email = service
+            .register(
+                &RegisterRequest {
+                    email: new_email.clone(),
+                    username: taken.username.clone(),
+                    password: "TestPassword123!".to_string(),
+                },
+                &ClientInfo::default(),
+            )
+            .map(|registration| format!("{registration:?}"))
+            .map_err(|e| e.to_string());
+
+        assert_eq!(existing_email, unknown_email);
+        assert_eq!(existing_email, Ok("Accepted".to_string()));
+        let sent = mailer.sent();
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent[0].to, owner.email);
+        assert_eq!(sent[1].to, new_email);
+        assert!(
+            test_repositories()
+                .users
+                .find_by_email(&new_email)
+                .unwrap()
+                .is_none()
+        );
+
+        let _ = test_repositories().users.delete(owner.id);
+        let _ = test_repositories().users.delete(taken.id);
+    }
+
+    #[test]
+    fn login_upgrades_legacy_bcrypt_hash_to_argon2id() {
+        let user = create_user_with_password("Legacy123!");
+        let legacy = bcrypt::hash("Legacy123!", 4).expect("bcrypt hash");
+        test_repositories()
+            .users
+            .update_password(user.id, &legacy, None)
+            .expect("store legacy hash");
+
+        let login_request = LoginRequest {
+            email: user.email.clone(),
+            password: "Legacy123!".to_string(),
+        };
+        test_service()
+            .login(&login_request, &ClientInfo::default())
+            .expect("Legacy hash should still verify");
+
+        let stored = test_repositories()
+            .users
+            .find_by_id(user.id)
+            .unwrap()
+            .expect("user");
+        let hash = stored.password_hash.expect("hash");
+        assert!(hash.starts_with("$argon2id$"));
+        assert!(
+            PasswordManager::verify("Legacy123!", &hash, stored.password_pepper_version).unwrap()
+        );
+
+        let _ = test_repositories().users.delete(user.id);
+    }
+
+    #[test]
+    fn breached_passwords_are_rejected_at_registration_and_change() {
+        use crate::auth::breach::InMemoryRangeSource;
+
+        let service = test_service().with_breach_checker(BreachChecker::new(Arc::new(
+            InMemoryRangeSource::with_passwords(&["Breached-Horse-42"]),
+        )));
+
+        let mut register_request = create_test_register_request();
+        register_request.password = "Breached-Horse-42".to_string();
+        assert!(matches!(
+            service.register(&register_request, &ClientInfo::default()),
+            Err(AppError::WeakPassword(violations))
+                if matches!(violations.as_slice(), [PasswordViolation::Breached { .. }])
+        ));
+
+        let user = create_user_with_password("OldPass123!");
+        assert!(matches!(
+            service.change_password(
+                user.id,
+                "OldPass123!",
+                "Breached-Horse-42",
+                &ClientInfo::default()
+            ),
+            Err(AppError::WeakPassword(_))
+        ));
+        assert!(
+            service
+                .change_password(
+                    user.id,
+                    "OldPass123!",
+                    "Unbreached456!",
+                    &ClientInfo::default()
+                )
+                .is_ok()
+        );
+
+        let _ = test_repositories().users.delete(user.id);
     }
 }
@@ -0,0 +1,223 @@
+use unicode_normalization::UnicodeNormalization;
+use unicode_security::{GeneralSecurityProfile, MixedScript, skeleton};
+
+/// Usernames that could be mistaken for the service itself or for staff accounts.
+/// Compared on the canonical (case-folded, confusable-skeleton) form.
+const RESERVED_USERNAMES: &[&str] = &[
+    "abuse",
+    "admin",
+    "administrator",
+    "api",
+    "auth",
+    "help",
+    "login",
+    "logout",
+    "me",
+    "moderator",
+    "noreply",
+    "null",
+    "postmaster",
+    "register",
+    "root",
+    "security",
+    "settings",
+    "staff",
+    "support",
+    "system",
+    "undefined",
+    "webmaster",
+];
+
+/// Separators allowed between alphanumeric characters.
+const SEPARATORS: &[char] = &['_', '-', '.'];
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum UsernameError {
+    #[error("Username must be at least {0} characters")]
+    TooShort(usize),
+    #[error("Username must be at most {0} characters")]
+    TooLong(usize),
+    #[error("Username contains a forbidden character: {0:?}")]
+    InvalidCharacter(char),
+    #[error("Username must start and end with a letter or digit")]
+    InvalidBoundary,
+    #[error("Username must not mix characters from different scripts")]
+    MixedScript,
+    #[error("Username is reserved")]
+    Reserved,
+}
+
+/// A username after policy checks.
+///
+/// `display` is the NFKC-normalised form shown to users; `canonical` is the
+/// lowercase confusable skeleton used for uniqueness, so `Alice`, `alice` and
+/// `аlice` (Cyrillic `а`) all map to the same account name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NormalizedUsername {
+    pub display: String,
+    pub canonical: String,
+}
+
+/// Username rules applied at registration.
+#[derive(Debug, Clone)]
+pub struct UsernamePolicy {
+    pub min_length: usize,
+    pub max_length: usize,
+    pub reserved: Vec<String>,
+}
+
+impl Default for UsernamePolicy {
+    fn default() -> Self {
+        Self {
+            min_length: 3,
+            max_length: 32,
+            reserved: RESERVED_USERNAMES.iter().map(|s| canonicalize(s)).collect(),
+        }
+    }
+}
+
+impl UsernamePolicy {
+    /// Normalises `raw` and checks it against the policy.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first [`UsernameError`] rule the username violates.
+    pub fn normalize(&self, raw: &str) -> Result<NormalizedUsername, UsernameError> {
+        let display: String = raw.trim().nfkc().collect();
+
+        let length = display.chars().count();
+        if length < self.min_length {
+            return Err(UsernameError::TooShort(self.min_length));
+        }
+        if length > self.max_length {
+            return Err(UsernameError::TooLong(self.max_length));
+        }
+
+        if let Some(c) = display.chars().find(|&c| !is_allowed_char(c)) {
+            return Err(UsernameError::InvalidCharacter(c));
+        }
+
+        let starts_ok = display.chars().next().is_some_and(char::is_alphanumeric);
+        let ends_ok = display.chars().last().is_some_and(char::is_alphanumeric);
+        if !starts_ok || !ends_ok {
+            return Err(UsernameError::InvalidBoundary);
+        }
+
+        if !display.as_str().is_single_script() {
+            return Err(UsernameError::MixedScript);
+        }
+
+        let canonical = canonicalize(&display);
+        if self.reserved.contains(&canonical) {
+            return Err(UsernameError::Reserved);
+        }
+
+        Ok(NormalizedUsername { display, canonical })
+    }
+}
+
+/// Letters and digits permitted by the UTS #39 identifier profile, plus separators.
+fn is_allowed_char(c: char) -> bool {
+    SEPARATORS.contains(&c) || (c.is_alphanumeric() && c.identifier_allowed())
+}
+
+/// Canonical form of a username stored without the policy checks (accounts created
+/// before it), as [`UsernamePolicy::normalize`] computes it for a new one.
+pub fn canonical_form(username: &str) -> String {
+    canonicalize(&username.trim().nfkc().collect::<String>())
+}
+
+/// Case-folds `username` and reduces it to its UTS #39 confusable skeleton.
+fn canonicalize(username: &str) -> String {
+    let lowered = username.to_lowercase();
+    skeleton(&lowered).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> UsernamePolicy {
+        UsernamePolicy::default()
+    }
+
+    #[test]
+    fn normalize_accepts_simple_username() {
+        let name = policy().normalize("Alice_42").expect("valid username");
+        assert_eq!(name.display, "Alice_42");
+        assert_eq!(name.canonical, canonicalize("alice_42"));
+    }
+
+    #[test]
+    fn normalize_rejects_empty_and_short_usernames() {
+        assert_eq!(policy().normalize(""), Err(UsernameError::TooShort(3)));
+        assert_eq!(policy().normalize("  ab "), Err(UsernameError::TooShort(3)));
+    }
+
+    #[test]
+    fn normalize_rejects_too_long_username() {
+        let long = "a".repeat(200);
+        assert_eq!(policy().normalize(&long), Err(UsernameError::TooLong(32)));
+    }
+
+    #[test]
+    fn normalize_rejects_forbidden_characters() {
+        assert_eq!(
+            policy().normalize("bob smith"),
+            Err(UsernameError::InvalidCharacter(' '))
+        );
+        assert_eq!(
+            policy().normalize("bob@home"),
+            Err(UsernameError::InvalidCharacter('@'))
+        );
+    }
+
+    #[test]
+    fn normalize_rejects_leading_or_trailing_separator() {
+        assert_eq!(
+            policy().normalize("_bob"),
+            Err(UsernameError::InvalidBoundary)
+        );
+        assert_eq!(
+            policy().normalize("bob."),
+            Err(UsernameError::InvalidBoundary)
+        );
+    }
+
+    #[test]
+    fn normalize_applies_nfkc() {
+        // Fullwidth letters fold to their ASCII counterparts
+        let name = policy().normalize("ｂｏｂ").expect("valid username");
+        assert_eq!(name.display, "bob");
+    }
+
+    #[test]
+    fn normalize_rejects_mixed_script_confusables() {
+        // Latin letters with a Cyrillic "а"
+        assert_eq!(
+            policy().normalize("pаypal"),
+            Err(UsernameError::MixedScript)
+        );
+    }
+
+    #[test]
+    fn normalize_rejects_reserved_names_case_insensitively() {
+        assert_eq!(policy().normalize("Admin"), Err(UsernameError::Reserved));
+        assert_eq!(policy().normalize("ROOT"), Err(UsernameError::Reserved));
+    }
+
+    #[test]
+    fn canonical_form_is_case_insensitive() {
+        let a = policy().normalize("GuildMaster").unwrap();
+        let b = policy().normalize("guildmaster").unwrap();
+        assert_eq!(a.canonical, b.canonical);
+    }
+
+    #[test]
+    fn canonical_form_collapses_single_script_confusables() {
+        // All-Cyrillic lookalike of "cop" maps to the same skeleton
+        let latin = policy().normalize("cop").unwrap();
+        let cyrillic = policy().normalize("сор").unwrap();
+        assert_eq!(latin.canonical, cyrillic.canonical);
+    }
+}
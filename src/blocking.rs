@@ -0,0 +1,283 @@
+//! Exécution du code bloquant hors des workers Tokio.
+//!
+//! Les repositories (diesel/r2d2) et le hachage des mots de passe sont synchrones :
+//! les handlers les passent à [`run`], ou à [`run_hashing`] quand ils hachent ou
+//! vérifient un mot de passe, qui les exécutent sur le pool bloquant de Tokio avec une
+//! concurrence bornée.
+
+use std::sync::{Arc, LazyLock, OnceLock};
+
+use tokio::sync::Semaphore;
+
+use crate::db::connection::POOL_MAX_SIZE;
+use crate::error::AppError;
+
+/// One permit per pooled connection: extra requests wait here, asynchronously,
+/// instead of occupying a blocking thread while waiting for a connection.
+static PERMITS: LazyLock<Limiter> = LazyLock::new(|| Limiter::new(POOL_MAX_SIZE as usize));
+
+/// Concurrent password hashes, set by [`limit_hashing`]: each one takes a CPU and
+/// `memory_kib` of Argon2 memory, so a burst of logins must queue.
+static HASHING_SLOTS: OnceLock<Arc<Semaphore>> = OnceLock::new();
+
+/// Runs blocking service code (database access, password hashing) on the blocking
+/// thread pool and awaits its result.
+///
+/// # Errors
+///
+/// Returns the closure's error, or [`AppError::InternalServerError`] if the task
+/// panicked.
+pub async fn run<T, F>(f: F) -> Result<T, AppError>
+where
+    F: FnOnce() -> Result<T, AppError> + Send + 'static,
+    T: Send + 'static,
+{
+    PERMITS.run(f).await
+}
+
+/// [`run`] for service code that hashes or verifies passwords (login, registration,
+/// password change, account deletion). The hashing slot is awaited before the
+/// connection permit, so queued hashes hold neither a blocking thread nor a permit.
+///
+/// # Errors
+///
+/// Same as [`run`].
+pub async fn run_hashing<T, F>(f: F) -> Result<T, AppError>
+where
+    F: FnOnce() -> Result<T, AppError> + Send + 'static,
+    T: Send + 'static,
+{
+    let slots = HASHING_SLOTS.get_or_init(|| {
+        Arc::new(Semaphore::new(
+            std::thread::available_parallelism().map_or(1, Into::into),
+        ))
+    });
+    PERMITS.run_with_slot(slots, f).await
+}
+
+/// Caps the number of password hashes computed at the same time. Call once at startup;
+/// later calls are ignored. Defaults to the number of CPUs.
+pub fn limit_hashing(max_concurrent: usize) {
+    if HASHING_SLOTS
+        .set(Arc::new(Semaphore::new(max_concurrent.max(1))))
+        .is_err()
+    {
+        tracing::warn!("Password hashing concurrency already configured, ignoring");
+    }
+}
+
+/// Waits for the running tasks to finish, then refuses new ones. Called at shutdown so
+/// that the connection pool is only closed once nothing uses it.
+pub async fn drain() {
+    PERMITS.drain().await;
+}
+
+/// Bounded blocking executor behind [`run`] and [`drain`].
+struct Limiter {
+    permits: Arc<Semaphore>,
+    size: u32,
+}
+
+impl Limiter {
+    fn new(size: usize) -> Self {
+        Self {
+            permits: Arc::new(Semaphore::new(size)),
+            size: u32::try_from(size).unwrap_or(u32::MAX),
+        }
+    }
+
+    async fn run<T, F>(&self, f: F) -> Result<T, AppError>
+    where
+        F: FnOnce() -> Result<T, AppError> + Send + 'static,
+        T: Send + 'static,
+    {
+        let permit = Arc::clone(&self.permits)
+            .acquire_owned()
+            .await
+            .map_err(|_| AppError::internal("Blocking executor closed"))?;
+        // Le permis suit la tâche bloquante, pas l'appelant : si la requête est abandonnée
+        // (client parti, timeout), il n'est rendu qu'une fois la connexion libérée
+        tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+            f()
+        })
+        .await
+        .map_err(|e| AppError::internal(format!("Blocking task failed: {e}")))?
+    }
+
+    /// [`run`](Self::run) once one of `slots` is free, kept until `f` returns.
+    async fn run_with_slot<T, F>(&self, slots: &Arc<Semaphore>, f: F) -> Result<T, AppError>
+    where
+        F: FnOnce() -> Result<T, AppError> + Send + 'static,
+        T: Send + 'static,
+    {
+        let slot = Arc::clone(slots)
+            .acquire_owned()
+            .await
+            .map_err(|_| AppError::internal("Hashing slots closed"))?;
+        self.run(move || {
+            let _slot = slot;
+            f()
+        })
+        .await
+    }
+
+    async fn drain(&self) {
+        if let Ok(permits) = self.permits.acquire_many(self.size).await {
+            permits.forget();
+        }
+        self.permits.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn returns_the_closure_result() {
+        assert_eq!(run(|| Ok(42)).await.unwrap(), 42);
+        assert!(matches!(
+            run(|| Err::<(), _>(AppError::InvalidPassword)).await,
+            Err(AppError::InvalidPassword)
+        ));
+    }
+
+    #[tokio::test]
+    async fn panics_become_internal_errors() {
+        let result: Result<(), AppError> = run(|| panic!("boom")).await;
+        assert!(matches!(result, Err(AppError::InternalServerError(_))));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn concurrency_is_bounded_by_the_pool_size() {
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+
+        let tasks: Vec<_> = (0..POOL_MAX_SIZE * 3)
+            .map(|_| {
+                let running = Arc::clone(&running);
+                let peak = Arc::clone(&peak);
+                tokio::spawn(run(move || {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    std::thread::sleep(std::time::Duration::from_millis(10));
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    Ok(())
+                }))
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap().unwrap();
+        }
+
+        assert!(peak.load(Ordering::SeqCst) <= POOL_MAX_SIZE as usize);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn cancelled_callers_keep_their_permit_until_the_work_ends() {
+        let limiter = Arc::new(Limiter::new(2));
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let release = Arc::new(AtomicBool::new(false));
+        let task = |limiter: &Arc<Limiter>| {
+            let (limiter, running, peak, release) = (
+                Arc::clone(limiter),
+                Arc::clone(&running),
+                Arc::clone(&peak),
+                Arc::clone(&release),
+            );
+            tokio::spawn(async move {
+                limiter
+                    .run(move || {
+                        let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                        peak.fetch_max(now, Ordering::SeqCst);
+                        while !release.load(Ordering::SeqCst) {
+                            std::thread::sleep(Duration::from_millis(5));
+                        }
+                        running.fetch_sub(1, Ordering::SeqCst);
+                        Ok(())
+                    })
+                    .await
+            })
+        };
+
+        let callers = [task(&limiter), task(&limiter)];
+        while running.load(Ordering::SeqCst) < 2 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        // Les appelants abandonnent, les tâches bloquantes continuent
+        for caller in &callers {
+            caller.abort();
+        }
+        let late = task(&limiter);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(running.load(Ordering::SeqCst), 2);
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), limiter.drain())
+                .await
+                .is_err(),
+            "drain() must wait for the abandoned work"
+        );
+
+        release.store(true, Ordering::SeqCst);
+        late.await.unwrap().unwrap();
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+        limiter.drain().await;
+        assert!(matches!(
+            limiter.run(|| Ok(())).await,
+            Err(AppError::InternalServerError(_))
+        ));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn queued_hashes_hold_no_connection_permit() {
+        let limiter = Arc::new(Limiter::new(4));
+        let slots = Arc::new(Semaphore::new(1));
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let release = Arc::new(AtomicBool::new(false));
+
+        let hashes: Vec<_> = (0..3)
+            .map(|_| {
+                let (limiter, slots, running, peak, release) = (
+                    Arc::clone(&limiter),
+                    Arc::clone(&slots),
+                    Arc::clone(&running),
+                    Arc::clone(&peak),
+                    Arc::clone(&release),
+                );
+                tokio::spawn(async move {
+                    limiter
+                        .run_with_slot(&slots, move || {
+                            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                            peak.fetch_max(now, Ordering::SeqCst);
+                            while !release.load(Ordering::SeqCst) {
+                                std::thread::sleep(Duration::from_millis(5));
+                            }
+                            running.fetch_sub(1, Ordering::SeqCst);
+                            Ok(())
+                        })
+                        .await
+                })
+            })
+            .collect();
+        while running.load(Ordering::SeqCst) < 1 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        // Un seul hachage tourne, les deux autres attendent sans permis de connexion
+        assert_eq!(limiter.permits.available_permits(), 3);
+        assert_eq!(limiter.run(|| Ok(7)).await.unwrap(), 7);
+
+        release.store(true, Ordering::SeqCst);
+        for hash in hashes {
+            hash.await.unwrap().unwrap();
+        }
+        assert_eq!(peak.load(Ordering::SeqCst), 1);
+        assert_eq!(slots.available_permits(), 1);
+    }
+}
@@ -0,0 +1,339 @@
+//! Ligne de commande : le serveur (par défaut), les migrations et les tâches
+//! d'administration, avec une sortie lisible ou JSON (`--json`).
+//!
+//! Les commandes d'administration réutilisent `Config`, les repositories et
+//! `AuthService` : elles fonctionnent contre une base locale, sans Lambda.
+
+use std::io::{BufRead, IsTerminal, Write};
+
+use anyhow::{Context, Result};
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use auth_manager_api::{AuditEventType, RegisterRequest, UserResponse};
+use clap::{Parser, Subcommand};
+use uuid::Uuid;
+
+use crate::auth::audit::Audit;
+use crate::auth::services::AuthService;
+use crate::config::Config;
+use crate::db::store::Repositories;
+use crate::maintenance::MaintenanceReport;
+
+#[derive(Debug, Parser)]
+#[command(name = "auth-manager", version, about = "Authentication service")]
+pub struct Cli {
+    /// Print command results as JSON
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    /// Print the effective configuration, secrets masked, and exit (same as `config`)
+    #[arg(long)]
+    pub print_config: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Run the HTTP server, or the Lambda handler on AWS (default)
+    Serve,
+    /// Apply pending database migrations
+    Migrate,
+    /// Print the effective configuration, secrets masked
+    Config,
+    /// Run the maintenance job once (expired sessions, old login attempts…)
+    Maintenance,
+    /// Generate a new JWT signing secret; move the current one to `JWT_SECRET_PREVIOUS`
+    RotateJwtSecret,
+    #[command(flatten)]
+    Admin(AdminCommand),
+}
+
+/// Commands working on accounts and sessions, through [`AuthService`].
+#[derive(Debug, Subcommand)]
+pub enum AdminCommand {
+    /// Create an administrator account (password read from stdin)
+    CreateAdmin {
+        #[arg(long)]
+        email: String,
+        #[arg(long)]
+        username: String,
+    },
+    /// Set a user's password (read from stdin) and revoke their sessions
+    ResetPassword {
+        /// Email or id of the user
+        user: String,
+    },
+    /// Lift a lockout, including a permanent one
+    Unlock {
+        /// Email or id of the user
+        user: String,
+    },
+    /// Delete expired sessions (refresh tokens)
+    PurgeExpiredTokens,
+    /// Delete the accounts whose deletion grace period has elapsed
+    PurgeDeletedAccounts,
+}
+
+impl Cli {
+    /// Whether the command runs the service, as opposed to a one-shot task whose
+    /// result goes to stdout.
+    pub fn serves(&self) -> bool {
+        !self.print_config && matches!(self.command, None | Some(Command::Serve))
+    }
+}
+
+/// Result of a command, printed as text or JSON.
+#[derive(Debug)]
+pub struct Report {
+    text: String,
+    value: serde_json::Value,
+}
+
+impl Report {
+    fn new(text: impl Into<String>, value: serde_json::Value) -> Self {
+        Self {
+            text: text.into(),
+            value,
+        }
+    }
+
+    pub fn print(&self, json: bool) {
+        if json {
+            println!("{}", self.value);
+        } else {
+            println!("{}", self.text);
+        }
+    }
+}
+
+/// `auth-manager config`
+pub fn print_config(config: &Config) -> Report {
+    let value = config.masked();
+    let text = value
+        .as_object()
+        .into_iter()
+        .flatten()
+        .map(|(key, value)| match value {
+            serde_json::Value::String(s) => format!("{key} = {s}"),
+            other => format!("{key} = {other}"),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    Report::new(text, value)
+}
+
+/// `auth-manager maintenance`
+pub fn maintenance_report(report: &MaintenanceReport) -> Report {
+    Report::new(
+        format!(
+            "{} expired session(s), {} login attempt(s), {} rate limit counter(s) and \
+             {} account(s) deleted, {} failed step(s)",
+            report.expired_sessions,
+            report.login_attempts,
+            report.rate_limit_buckets,
+            report.purged_accounts,
+            report.failed_steps
+        ),
+        serde_json::json!(report),
+    )
+}
+
+/// `auth-manager rotate-jwt-secret`: the new secret signs new tokens, the current one
+/// keeps verifying those already issued until they expire.
+///
+/// Only the new secret is printed: the current one must not end up in terminal
+/// scrollback or CI logs, the operator moves it to `JWT_SECRET_PREVIOUS` themselves.
+pub fn rotate_jwt_secret(hours: i64) -> Report {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    let secret = hex::encode(bytes);
+
+    Report::new(
+        format!(
+            "Move the current JWT_SECRET value to JWT_SECRET_PREVIOUS, then set this one and redeploy:\n\
+             JWT_SECRET={secret}\n\
+             Remove JWT_SECRET_PREVIOUS after {hours} hour(s), once the tokens it signed have expired."
+        ),
+        serde_json::json!({
+            "jwt_secret": secret,
+            "keep_previous_hours": hours,
+        }),
+    )
+}
+
+/// Runs an account or session command.
+///
+/// # Errors
+///
+/// Returns the service error (unknown user, weak password…) or a stdin read error.
+pub fn run_admin(
+    command: AdminCommand,
+    service: &AuthService,
+    repositories: &Repositories,
+) -> Result<Report> {
+    match command {
+        AdminCommand::CreateAdmin { email, username } => {
+            let password = read_password()?;
+            let admin = service.create_admin(&RegisterRequest {
+                email,
+                username,
+                password,
+            })?;
+            Ok(user_report(
+                format!("Administrator {} created ({})", admin.email, admin.id),
+                &admin,
+            ))
+        }
+        AdminCommand::ResetPassword { user } => {
+            let user = find_user(service, &user)?;
+            let password = read_password()?;
+            service.reset_password(user.id, &password)?;
+            Ok(user_report(
+                format!("Password of {} reset, sessions revoked", user.email),
+                &user,
+            ))
+        }
+        AdminCommand::Unlock { user } => {
+            let user = find_user(service, &user)?;
+            service.unlock_account(user.id)?;
+            tracing::info!(user_id = %user.id, "Account unlocked from the command line");
+            Audit::new(AuditEventType::AccountUnlocked)
+                .target(user.id)
+                .metadata(serde_json::json!({ "method": "cli" }))
+                .record(repositories.audit_events.as_ref());
+            Ok(user_report(format!("{} unlocked", user.email), &user))
+        }
+        AdminCommand::PurgeExpiredTokens => {
+            let purged = service.purge_expired_sessions(chrono::Duration::zero())?;
+            Ok(Report::new(
+                format!("{purged} expired session(s) deleted"),
+                serde_json::json!({ "purged": purged }),
+            ))
+        }
+        AdminCommand::PurgeDeletedAccounts => {
+            let purged = service.purge_due_deletions()?;
+            Ok(Report::new(
+                format!("{purged} account(s) deleted"),
+                serde_json::json!({ "purged": purged }),
+            ))
+        }
+    }
+}
+
+fn user_report(text: String, user: &UserResponse) -> Report {
+    Report::new(text, serde_json::json!({ "user": user }))
+}
+
+/// Finds a user by id or, failing that, by email.
+fn find_user(service: &AuthService, user: &str) -> Result<UserResponse> {
+    let found = match Uuid::parse_str(user) {
+        Ok(id) => service.get_user_by_id(id),
+        Err(_) => service.find_user_by_email(user),
+    };
+    found.with_context(|| format!("User {user:?}"))
+}
+
+/// Reads a password from the first line of stdin (not from the arguments, which end up
+/// in the shell history and the process list).
+fn read_password() -> Result<String> {
+    let stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        eprint!("Password: ");
+        std::io::stderr().flush()?;
+    }
+    let mut line = String::new();
+    stdin
+        .lock()
+        .read_line(&mut line)
+        .context("Failed to read the password from stdin")?;
+    let password = line.trim_end_matches(['\r', '\n']);
+    if password.is_empty() {
+        anyhow::bail!("No password given on stdin");
+    }
+    Ok(password.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::store::test_repositories;
+
+    #[test]
+    fn parses_admin_commands_and_defaults_to_serve() {
+        let cli = Cli::try_parse_from(["auth-manager"]).unwrap();
+        assert!(cli.serves());
+
+        let cli =
+            Cli::try_parse_from(["auth-manager", "unlock", "a@example.com", "--json"]).unwrap();
+        assert!(cli.json);
+        assert!(!cli.serves());
+        assert!(matches!(
+            cli.command,
+            Some(Command::Admin(AdminCommand::Unlock { user })) if user == "a@example.com"
+        ));
+
+        let cli = Cli::try_parse_from(["auth-manager", "--print-config"]).unwrap();
+        assert!(cli.print_config);
+        assert!(!cli.serves());
+
+        assert!(Cli::try_parse_from(["auth-manager", "create-admin", "--email", "a@b.c"]).is_err());
+        assert!(Cli::try_parse_from(["auth-manager", "frobnicate"]).is_err());
+    }
+
+    #[test]
+    fn admin_commands_work_on_memory_storage() {
+        let repositories = test_repositories();
+        let service = AuthService::new(
+            crate::auth::jwt::JwtManager::new("secret_key", 1),
+            repositories.clone(),
+        );
+        let admin = service
+            .create_admin(&RegisterRequest {
+                email: "ops@example.com".to_string(),
+                username: "operator".to_string(),
+                password: "Sturdy-Lantern-42".to_string(),
+            })
+            .unwrap();
+
+        let report = run_admin(
+            AdminCommand::Unlock {
+                user: "OPS@example.com".to_string(),
+            },
+            &service,
+            &repositories,
+        )
+        .unwrap();
+        assert_eq!(report.value["user"]["id"], admin.id.to_string());
+
+        let report = run_admin(
+            AdminCommand::Unlock {
+                user: Uuid::new_v4().to_string(),
+            },
+            &service,
+            &repositories,
+        );
+        assert!(report.is_err());
+
+        let report = run_admin(AdminCommand::PurgeExpiredTokens, &service, &repositories).unwrap();
+        assert_eq!(report.value, serde_json::json!({ "purged": 0 }));
+
+        repositories
+            .users
+            .schedule_deletion(admin.id, chrono::Utc::now() - chrono::Duration::seconds(1))
+            .unwrap();
+        let report =
+            run_admin(AdminCommand::PurgeDeletedAccounts, &service, &repositories).unwrap();
+        assert_eq!(report.value, serde_json::json!({ "purged": 1 }));
+    }
+
+    #[test]
+    fn rotate_jwt_secret_never_prints_the_current_secret() {
+        let report = rotate_jwt_secret(24);
+
+        let secret = report.value["jwt_secret"].as_str().unwrap();
+        assert_eq!(secret.len(), 64);
+        assert_eq!(report.value.as_object().unwrap().len(), 2);
+        assert!(!report.text.contains("JWT_SECRET_PREVIOUS="));
+    }
+}
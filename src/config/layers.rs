@@ -0,0 +1,365 @@
+//! Sources de la configuration, de la plus faible à la plus forte priorité : valeurs par
+//! défaut (dans `Config`), fichier TOML, `.env` (en local) puis variables d'environnement.
+//!
+//! Toutes les couches sont indexées par le nom de la variable d'environnement : la clé
+//! `per_ip` de la table `[rate_limit]` du fichier TOML est `RATE_LIMIT_PER_IP`.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashSet};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+/// File read when `CONFIG_FILE` is not set, if it exists.
+pub const DEFAULT_CONFIG_FILE: &str = "auth-manager.toml";
+
+/// Settings of the TOML file and `.env`, looked up under the process environment.
+#[derive(Debug, Default)]
+pub struct Layers {
+    /// Values of the TOML file, overridden by those of `.env`
+    files: BTreeMap<String, FileValue>,
+    /// TOML file in use
+    config_file: Option<PathBuf>,
+    /// Keys looked up so far, to report the file settings nothing reads (typos)
+    read: RefCell<HashSet<String>>,
+}
+
+#[derive(Debug)]
+struct FileValue {
+    value: String,
+    from_toml: bool,
+}
+
+/// Source of a value, from the lowest priority to the highest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Layer {
+    Toml,
+    Dotenv,
+    Env,
+}
+
+impl Layer {
+    fn describe(self) -> &'static str {
+        match self {
+            Self::Toml => "the configuration file",
+            Self::Dotenv => ".env",
+            Self::Env => "the environment",
+        }
+    }
+}
+
+impl Layers {
+    /// Reads `CONFIG_FILE` (or `auth-manager.toml` if present) and, in local mode, `.env`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `CONFIG_FILE` cannot be read, or a file is malformed.
+    pub fn load(local: bool) -> Result<Self> {
+        let mut layers = Self::default();
+
+        let config_file = match env::var("CONFIG_FILE") {
+            Ok(path) if !path.is_empty() => Some(PathBuf::from(path)),
+            _ => Some(PathBuf::from(DEFAULT_CONFIG_FILE)).filter(|path| path.is_file()),
+        };
+        if let Some(path) = config_file {
+            tracing::info!("📦 Loading configuration file {}", path.display());
+            let text = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            layers
+                .add_toml(&text)
+                .with_context(|| format!("Invalid configuration file {}", path.display()))?;
+            layers.config_file = Some(path);
+        }
+
+        // Sur Lambda (Dev ou Production), les variables sont injectées par AWS
+        if !local {
+            tracing::info!("📦 Lambda mode: using injected environment variables");
+            return Ok(layers);
+        }
+        let dotenv = Path::new(".env");
+        if dotenv.is_file() {
+            tracing::info!("📦 Local mode: loading .env file");
+            let text = fs::read_to_string(dotenv).context("Failed to read .env")?;
+            layers.add_dotenv(&text).context("Invalid .env file")?;
+        } else {
+            tracing::debug!("   .env file not found, using environment variables");
+        }
+        Ok(layers)
+    }
+
+    /// TOML file in use, if any.
+    pub fn config_file(&self) -> Option<&Path> {
+        self.config_file.as_deref()
+    }
+
+    /// Value of `key`, from the environment first. Empty values count as unset.
+    pub fn var(&self, key: &str) -> Option<String> {
+        self.lookup(key).map(|(value, _)| value)
+    }
+
+    fn lookup(&self, key: &str) -> Option<(String, Layer)> {
+        self.read.borrow_mut().insert(key.to_string());
+        let from_env = env::var(key)
+            .ok()
+            .filter(|value| !value.is_empty())
+            .map(|value| (value, Layer::Env));
+        from_env.or_else(|| {
+            self.files
+                .get(key)
+                .filter(|file| !file.value.is_empty())
+                .map(|file| {
+                    let layer = if file.from_toml {
+                        Layer::Toml
+                    } else {
+                        Layer::Dotenv
+                    };
+                    (file.value.clone(), layer)
+                })
+        })
+    }
+
+    /// Value of a secret: `key` itself, or the content of the file named by `<key>_FILE`
+    /// (Docker and Kubernetes secrets), without its trailing newline. When both are set
+    /// in different layers, the higher-priority one wins.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if both are set in the same layer, or the file cannot be read.
+    pub fn secret(&self, key: &str) -> Result<Option<String>> {
+        let file_key = format!("{key}_FILE");
+        let path = match (self.lookup(key), self.lookup(&file_key)) {
+            (Some((_, layer)), Some((_, file_layer))) if layer == file_layer => {
+                anyhow::bail!("{key} and {file_key} are both set in {}", layer.describe())
+            }
+            (Some((value, layer)), file) if file.as_ref().is_none_or(|(_, f)| *f < layer) => {
+                return Ok(Some(value));
+            }
+            (_, Some((path, _))) => path,
+            (_, None) => return Ok(None),
+        };
+        let secret = fs::read_to_string(&path)
+            .with_context(|| format!("{file_key}: failed to read {path}"))?;
+        let secret = secret.trim_end_matches(['\r', '\n']).to_string();
+        Ok(Some(secret).filter(|s| !s.is_empty()))
+    }
+
+    /// Marks the keys of a group as known, so that a getter stopping at the first invalid
+    /// value of the group does not turn the following ones into unknown settings.
+    pub fn declare(&self, keys: &[&str]) {
+        self.read
+            .borrow_mut()
+            .extend(keys.iter().map(|key| (*key).to_string()));
+    }
+
+    /// Settings of the TOML file that were never looked up.
+    pub fn unknown_keys(&self) -> Vec<String> {
+        let read = self.read.borrow();
+        self.files
+            .iter()
+            .filter(|(key, file)| file.from_toml && !read.contains(*key))
+            .map(|(key, _)| key.clone())
+            .collect()
+    }
+
+    fn add_toml(&mut self, text: &str) -> Result<()> {
+        let table: toml::Table = text.parse()?;
+        let mut values = Vec::new();
+        flatten_toml("", &table, &mut values)?;
+        for (key, value) in values {
+            self.files.insert(
+                key,
+                FileValue {
+                    value,
+                    from_toml: true,
+                },
+            );
+        }
+        Ok(())
+    }
+
+    /// `KEY=VALUE` lines, with optional `export` and quotes; `#` starts a comment line.
+    fn add_dotenv(&mut self, text: &str) -> Result<()> {
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let (key, value) = line
+                .split_once('=')
+                .map(|(key, value)| (key.trim(), value.trim()))
+                .filter(|(key, _)| is_variable_name(key))
+                .ok_or_else(|| anyhow::anyhow!("line {}: expected KEY=VALUE", number + 1))?;
+            self.files.insert(
+                key.to_string(),
+                FileValue {
+                    value: unquote(value).to_string(),
+                    from_toml: false,
+                },
+            );
+        }
+        Ok(())
+    }
+}
+
+/// `[lockout] tiers_secs = [900, 3600]` → `LOCKOUT_TIERS_SECS=900,3600`
+fn flatten_toml(prefix: &str, table: &toml::Table, out: &mut Vec<(String, String)>) -> Result<()> {
+    for (key, value) in table {
+        let name = key.to_ascii_uppercase().replace('-', "_");
+        let name = if prefix.is_empty() {
+            name
+        } else {
+            format!("{prefix}_{name}")
+        };
+        match value {
+            toml::Value::Table(table) => flatten_toml(&name, table, out)?,
+            toml::Value::Array(items) => {
+                let items = items
+                    .iter()
+                    .map(toml_scalar)
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(|| anyhow::anyhow!("{name}: expected a list of values"))?;
+                out.push((name, items.join(",")));
+            }
+            other => {
+                let value = toml_scalar(other)
+                    .ok_or_else(|| anyhow::anyhow!("{name}: unsupported value {other:?}"))?;
+                out.push((name, value));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn toml_scalar(value: &toml::Value) -> Option<String> {
+    match value {
+        toml::Value::String(s) => Some(s.clone()),
+        toml::Value::Integer(n) => Some(n.to_string()),
+        toml::Value::Float(n) => Some(n.to_string()),
+        toml::Value::Boolean(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+fn is_variable_name(key: &str) -> bool {
+    !key.is_empty()
+        && !key.starts_with(|c: char| c.is_ascii_digit())
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn unquote(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if let Some(inner) = value
+            .strip_prefix(quote)
+            .and_then(|rest| rest.strip_suffix(quote))
+        {
+            return inner;
+        }
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toml_tables_and_lists_map_to_variable_names() {
+        let mut layers = Layers::default();
+        layers
+            .add_toml(
+                r#"
+                jwt_expiration_hours = 2
+                [rate_limit]
+                per-ip = "10/60"
+                [lockout]
+                tiers_secs = [900, 3600]
+                "#,
+            )
+            .unwrap();
+
+        assert_eq!(layers.var("LAYERS_TEST_UNSET"), None);
+        assert_eq!(layers.var("JWT_EXPIRATION_HOURS").as_deref(), Some("2"));
+        assert_eq!(layers.var("RATE_LIMIT_PER_IP").as_deref(), Some("10/60"));
+        assert_eq!(layers.unknown_keys(), ["LOCKOUT_TIERS_SECS"]);
+        assert!(layers.add_toml("when = 1979-05-27").is_err());
+    }
+
+    #[test]
+    fn environment_overrides_dotenv_which_overrides_toml() {
+        let mut layers = Layers::default();
+        layers
+            .add_toml(
+                "layers_test_a = \"toml\"\nlayers_test_b = \"toml\"\nlayers_test_c = \"toml\"",
+            )
+            .unwrap();
+        layers
+            .add_dotenv("# comment\nexport LAYERS_TEST_B=\"dotenv\"\nLAYERS_TEST_C='dotenv'\n")
+            .unwrap();
+        unsafe {
+            env::set_var("LAYERS_TEST_C", "env");
+        }
+
+        assert_eq!(layers.var("LAYERS_TEST_A").as_deref(), Some("toml"));
+        assert_eq!(layers.var("LAYERS_TEST_B").as_deref(), Some("dotenv"));
+        assert_eq!(layers.var("LAYERS_TEST_C").as_deref(), Some("env"));
+        unsafe {
+            env::remove_var("LAYERS_TEST_C");
+        }
+        assert!(layers.add_dotenv("NOT A VARIABLE").is_err());
+    }
+
+    #[test]
+    fn secrets_can_be_read_from_files() {
+        let path = env::temp_dir().join(format!("secret-{}", uuid::Uuid::new_v4()));
+        fs::write(&path, "from-a-file\n").unwrap();
+        let mut layers = Layers::default();
+        layers
+            .add_dotenv(&format!("LAYERS_TEST_SECRET_FILE={}", path.display()))
+            .unwrap();
+
+        assert_eq!(
+            layers.secret("LAYERS_TEST_SECRET").unwrap().as_deref(),
+            Some("from-a-file")
+        );
+        layers.add_dotenv("LAYERS_TEST_SECRET=inline").unwrap();
+        assert!(layers.secret("LAYERS_TEST_SECRET").is_err());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn secret_from_a_higher_layer_overrides_a_secret_file() {
+        let path = env::temp_dir().join(format!("secret-{}", uuid::Uuid::new_v4()));
+        fs::write(&path, "from-a-file\n").unwrap();
+        let mut layers = Layers::default();
+        layers
+            .add_toml(&format!(
+                "layers_test_override_file = {:?}\nlayers_test_inline = \"toml\"",
+                path.display().to_string()
+            ))
+            .unwrap();
+        unsafe {
+            env::set_var("LAYERS_TEST_OVERRIDE", "from-env");
+        }
+        // La valeur de l'environnement l'emporte sur le fichier nommé par le TOML
+        assert_eq!(
+            layers.secret("LAYERS_TEST_OVERRIDE").unwrap().as_deref(),
+            Some("from-env")
+        );
+        unsafe {
+            env::remove_var("LAYERS_TEST_OVERRIDE");
+        }
+
+        // ... et un `_FILE` de `.env` sur une valeur du TOML
+        layers
+            .add_dotenv(&format!("LAYERS_TEST_INLINE_FILE={}", path.display()))
+            .unwrap();
+        assert_eq!(
+            layers.secret("LAYERS_TEST_INLINE").unwrap().as_deref(),
+            Some("from-a-file")
+        );
+        fs::remove_file(path).unwrap();
+    }
+}
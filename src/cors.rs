@@ -0,0 +1,193 @@
+//! Origines autorisées par CORS : origines exactes (`https://app.example.com`) ou motifs
+//! de sous-domaines (`https://*.example.com`), configurés par `CORS_ALLOWED_ORIGINS`.
+//!
+//! Les requêtes sont faites avec credentials (cookie du refresh token) : l'origine
+//! acceptée est renvoyée telle quelle, jamais `*`.
+
+use axum::http::{HeaderValue, Method, header, request::Parts};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+/// Allowed origins of cross-origin requests.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AllowedOrigins {
+    /// Origins matched as-is (lowercase)
+    exact: Vec<String>,
+    /// `https://*.example.com` patterns
+    wildcards: Vec<WildcardOrigin>,
+}
+
+/// `<scheme>://*.<suffix>`: any subdomain of `suffix`, not `suffix` itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct WildcardOrigin {
+    scheme: String,
+    /// Domain and optional port, e.g. `example.com` or `example.com:8080`
+    suffix: String,
+}
+
+impl AllowedOrigins {
+    /// Parses a comma-separated list of origins and `scheme://*.domain` patterns.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first entry that is not an origin (path, missing scheme, `*`...).
+    pub fn parse(list: &str) -> Result<Self, String> {
+        let mut origins = Self::default();
+        for entry in list.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let origin = entry.to_ascii_lowercase();
+            let (scheme, authority) = origin
+                .split_once("://")
+                .filter(|(scheme, authority)| {
+                    matches!(*scheme, "http" | "https") && is_authority(authority)
+                })
+                .ok_or_else(|| entry.to_string())?;
+            match authority.strip_prefix("*.") {
+                Some(suffix) if is_authority(suffix) && suffix.contains('.') => {
+                    origins.wildcards.push(WildcardOrigin {
+                        scheme: scheme.to_string(),
+                        suffix: suffix.to_string(),
+                    });
+                }
+                None if !authority.contains('*') && HeaderValue::from_str(&origin).is_ok() => {
+                    origins.exact.push(origin);
+                }
+                _ => return Err(entry.to_string()),
+            }
+        }
+        Ok(origins)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.exact.is_empty() && self.wildcards.is_empty()
+    }
+
+    /// First exact origin, the default frontend URL of email links.
+    pub fn first_exact(&self) -> Option<&str> {
+        self.exact.first().map(String::as_str)
+    }
+
+    /// Whether `origin` (an `Origin` header value) may call the API.
+    pub fn allows(&self, origin: &str) -> bool {
+        let origin = origin.to_ascii_lowercase();
+        if self.exact.contains(&origin) {
+            return true;
+        }
+        let Some((scheme, authority)) = origin.split_once("://") else {
+            return false;
+        };
+        self.wildcards.iter().any(|pattern| {
+            pattern.scheme == scheme
+                && authority
+                    .strip_suffix(pattern.suffix.as_str())
+                    .and_then(|subdomain| subdomain.strip_suffix('.'))
+                    .is_some_and(|subdomain| {
+                        !subdomain.is_empty()
+                            && subdomain
+                                .split('.')
+                                .all(|label| !label.is_empty() && is_label(label))
+                    })
+        })
+    }
+
+    /// Configured entries, as written in `CORS_ALLOWED_ORIGINS`.
+    pub fn to_list(&self) -> Vec<String> {
+        self.exact
+            .iter()
+            .cloned()
+            .chain(
+                self.wildcards
+                    .iter()
+                    .map(|pattern| format!("{}://*.{}", pattern.scheme, pattern.suffix)),
+            )
+            .collect()
+    }
+
+    /// Couche CORS de l'API.
+    pub fn layer(self) -> CorsLayer {
+        CorsLayer::new()
+            .allow_origin(AllowOrigin::predicate(
+                move |origin: &HeaderValue, _: &Parts| {
+                    origin.to_str().is_ok_and(|origin| self.allows(origin))
+                },
+            ))
+            .allow_methods([
+                Method::GET,
+                Method::POST,
+                Method::PUT,
+                Method::DELETE,
+                Method::OPTIONS,
+                Method::PATCH,
+            ])
+            .allow_headers([
+                header::CONTENT_TYPE,
+                header::AUTHORIZATION,
+                header::ACCEPT,
+                header::ORIGIN,
+                header::ACCESS_CONTROL_REQUEST_METHOD,
+                header::ACCESS_CONTROL_REQUEST_HEADERS,
+            ])
+            .expose_headers([
+                header::CONTENT_TYPE,
+                header::AUTHORIZATION,
+                header::HeaderName::from_static("x-next-cursor"),
+            ])
+            .allow_credentials(true)
+            .max_age(std::time::Duration::from_secs(3600))
+    }
+}
+
+/// `host` or `host:port`, without path, query or credentials
+fn is_authority(authority: &str) -> bool {
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) => (host, Some(port)),
+        None => (authority, None),
+    };
+    !host.is_empty()
+        && host
+            .split('.')
+            .enumerate()
+            .all(|(i, label)| (i == 0 && label == "*") || (!label.is_empty() && is_label(label)))
+        && port.is_none_or(|port| port.parse::<u16>().is_ok())
+}
+
+fn is_label(label: &str) -> bool {
+    label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wildcard_matches_subdomains_only() {
+        let origins =
+            AllowedOrigins::parse("https://app.example.com, https://*.example.org").unwrap();
+
+        assert!(origins.allows("https://app.example.com"));
+        assert!(origins.allows("https://a.example.org"));
+        assert!(origins.allows("https://A.b.Example.org"));
+        assert!(!origins.allows("https://example.org"));
+        assert!(!origins.allows("https://evilexample.org"));
+        assert!(!origins.allows("https://a.example.org.evil.com"));
+        assert!(!origins.allows("http://a.example.org"));
+        assert!(!origins.allows("https://a.example.org:8443"));
+        assert_eq!(origins.first_exact(), Some("https://app.example.com"));
+    }
+
+    #[test]
+    fn rejects_entries_that_are_not_origins() {
+        for entry in [
+            "*",
+            "https://*",
+            "https://*.com",
+            "example.com",
+            "ftp://example.com",
+            "https://example.com/",
+            "https://user@example.com",
+            "https://a.*.example.com",
+            "http://localhost:http",
+        ] {
+            assert_eq!(AllowedOrigins::parse(entry), Err(entry.to_string()));
+        }
+        assert!(AllowedOrigins::parse("http://localhost:8080").is_ok());
+    }
+}
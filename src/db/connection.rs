@@ -1,67 +1,48 @@
 use super::error::RepositoryError;
 use super::{DbConnection, DbPool};
-use anyhow::{Context, Result, anyhow};
+use anyhow::{Context, Result};
 use diesel::PgConnection;
 use diesel::r2d2::ConnectionManager;
-use std::sync::OnceLock;
 
-static POOL: OnceLock<DbPool> = OnceLock::new();
+/// Maximum number of pooled connections.
+pub const POOL_MAX_SIZE: u32 = 15;
 
-/// Initialize the `PostgreSQL` connection pool with the given database URL.
-/// This should be called once at application startup.
-pub fn init_pool_with_url(database_url: &str) -> Result<()> {
+/// Builds the `PostgreSQL` connection pool for `database_url`.
+/// Called once at startup; the pool is then handed to the repositories.
+pub fn build_pool(database_url: &str) -> Result<DbPool> {
     let manager = ConnectionManager::<PgConnection>::new(database_url);
-    let pool = diesel::r2d2::Pool::builder()
-        .max_size(15)
+    diesel::r2d2::Pool::builder()
+        .max_size(POOL_MAX_SIZE)
         .build(manager)
-        .context("Failed to build r2d2 pool")?;
-
-    POOL.set(pool)
-        .map_err(|_| anyhow!("Pool already initialized"))?;
-
-    Ok(())
-}
-
-/// Initialize the `PostgreSQL` connection pool using `DATABASE_URL` env var.
-/// This should be called once at application startup.
-#[expect(
-    dead_code,
-    reason = "Kept as a convenience wrapper; used in local tooling, not the Lambda binary"
-)]
-pub fn init_pool() -> Result<()> {
-    let url = std::env::var("DATABASE_URL").context("DATABASE_URL env var not set")?;
-    init_pool_with_url(&url)
-}
-
-/// Get a reference to the initialized pool.
-///
-/// # Panics
-///
-/// Panics if [`init_pool_with_url`] hasn't been called before this function.
-pub fn get_pool() -> &'static DbPool {
-    POOL.get()
-        .expect("DB pool not initialized. Call init_pool() first.")
+        .context("Failed to build r2d2 pool")
 }
 
 /// Get a connection from the pool.
 /// Returns `RepositoryError` for use in repository layer.
-pub fn get_connection() -> Result<DbConnection, RepositoryError> {
-    get_pool()
-        .get()
+pub fn get_connection(pool: &DbPool) -> Result<DbConnection, RepositoryError> {
+    pool.get()
         .map_err(|e| RepositoryError::PoolError(e.to_string()))
 }
 
+/// Pool shared by the Postgres repository and migration tests, built from
+/// `TEST_DATABASE_URL` (or `DATABASE_URL`). Other tests use
+/// [`test_repositories`](crate::db::store::test_repositories), which needs neither.
+///
+/// # Panics
+///
+/// Panics if neither variable is set or the pool cannot be built.
 #[cfg(test)]
-pub fn init_test_pool() {
-    use std::sync::Once;
-    static INIT: Once = Once::new();
+pub fn test_pool() -> DbPool {
+    use std::sync::OnceLock;
+    static POOL: OnceLock<DbPool> = OnceLock::new();
 
-    INIT.call_once(|| {
+    POOL.get_or_init(|| {
         let url = std::env::var("TEST_DATABASE_URL")
             .or_else(|_| std::env::var("DATABASE_URL"))
             .expect("TEST_DATABASE_URL (or DATABASE_URL) must be set for tests");
-        let _ = init_pool_with_url(&url);
-    });
+        build_pool(&url).expect("Failed to build test pool")
+    })
+    .clone()
 }
 
 #[cfg(test)]
@@ -69,12 +50,8 @@ mod tests {
     use super::*;
 
     #[test]
-    fn pool_initializes_and_returns_connection() {
-        init_test_pool();
-        let conn = get_connection();
-        assert!(
-            conn.is_ok(),
-            "Should get a connection from the initialized pool"
-        );
+    fn pool_returns_connection() {
+        let conn = get_connection(&test_pool());
+        assert!(conn.is_ok(), "Should get a connection from the test pool");
     }
 }
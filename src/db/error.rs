@@ -5,8 +5,10 @@ pub enum RepositoryError {
     PoolError(String),
     #[error("Not found: {0}")]
     NotFound(String),
-    #[error("Unique constraint violation: {0}")]
-    UniqueViolation(String),
+    /// `constraint`: the Postgres name of the violated constraint or unique index, which
+    /// every backend reports (`users_email_lower_key`)
+    #[error("Unique constraint violation: {constraint}")]
+    UniqueViolation { constraint: String },
     #[error("Foreign key constraint violation: {0}")]
     ForeignKeyViolation(String),
     #[error("Database error: {0}")]
@@ -22,7 +24,11 @@ impl From<diesel::result::Error> for RepositoryError {
             Error::DatabaseError(kind, info) => {
                 let message = info.message().to_string();
                 match kind {
-                    DatabaseErrorKind::UniqueViolation => RepositoryError::UniqueViolation(message),
+                    DatabaseErrorKind::UniqueViolation => RepositoryError::UniqueViolation {
+                        constraint: info
+                            .constraint_name()
+                            .map_or_else(|| sqlite_constraint(&message), str::to_string),
+                    },
                     DatabaseErrorKind::ForeignKeyViolation => {
                         RepositoryError::ForeignKeyViolation(message)
                     }
@@ -34,6 +40,33 @@ impl From<diesel::result::Error> for RepositoryError {
     }
 }
 
+/// `SQLite` does not name the constraint: `UNIQUE constraint failed: users.email_lower`
+/// lists the columns, named here as Postgres names a column constraint
+/// (`users_email_lower_key`); an index on expressions is reported by name.
+fn sqlite_constraint(message: &str) -> String {
+    let Some(target) = message.strip_prefix("UNIQUE constraint failed: ") else {
+        return message.to_string();
+    };
+    if let Some(index) = target
+        .strip_prefix("index '")
+        .and_then(|index| index.strip_suffix('\''))
+    {
+        return index.to_string();
+    }
+    let mut table = "";
+    let mut columns = Vec::new();
+    for column in target.split(", ") {
+        match column.split_once('.') {
+            Some((column_table, name)) => {
+                table = column_table;
+                columns.push(name);
+            }
+            None => return message.to_string(),
+        }
+    }
+    format!("{table}_{}_key", columns.join("_"))
+}
+
 impl From<diesel::r2d2::PoolError> for RepositoryError {
     fn from(err: diesel::r2d2::PoolError) -> Self {
         RepositoryError::PoolError(err.to_string())
@@ -0,0 +1,689 @@
+//! In-memory storage for tests and local demos (`STORAGE=memory`).
+//!
+//! Mirrors the Postgres schema: same unique constraints, same foreign keys (cascade or
+//! set null) and same expiry rules. Everything is lost when the process exits.
+
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::db::error::RepositoryError;
+use crate::db::models::audit_event::{AuditEvent, NewAuditEvent};
+use crate::db::models::known_device::{KnownDevice, NewKnownDevice};
+use crate::db::models::login_attempt::{LoginAttempt, NewLoginAttempt};
+use crate::db::models::password_history::PasswordHistoryEntry;
+use crate::db::models::refresh_token::{NewRefreshToken, RefreshToken};
+use crate::db::models::user::{LockoutState, NewUser, UpdateUser, User};
+use crate::db::models::user_identity::{NewUserIdentity, UserIdentity};
+use crate::db::models::user_token::{NewUserToken, TokenPurpose, UserToken};
+use crate::db::repositories::audit_event_repository::{AuditCursor, AuditEventFilter};
+use crate::db::repositories::login_attempt_repository::HistoryCursor;
+use crate::db::store::{
+    AuditEventStore, KnownDeviceStore, LoginAttemptStore, PasswordHistoryStore, RefreshTokenStore,
+    UserIdentityStore, UserStore, UserTokenStore,
+};
+
+#[derive(Default)]
+struct Tables {
+    users: Vec<User>,
+    refresh_tokens: Vec<RefreshToken>,
+    login_attempts: Vec<LoginAttempt>,
+    identities: Vec<UserIdentity>,
+    password_history: Vec<PasswordHistoryEntry>,
+    user_tokens: Vec<UserToken>,
+    known_devices: Vec<KnownDevice>,
+    audit_events: Vec<AuditEvent>,
+}
+
+impl Tables {
+    fn user(&self, id: Uuid) -> Option<&User> {
+        self.users.iter().find(|u| u.id == id)
+    }
+
+    /// Row of `users` as an `UPDATE` sees it: missing rows are `NotFound`, and
+    /// `updated_at` is bumped like the Postgres trigger does.
+    fn user_for_update(&mut self, id: Uuid) -> Result<&mut User, RepositoryError> {
+        let user = self
+            .users
+            .iter_mut()
+            .find(|u| u.id == id)
+            .ok_or_else(record_not_found)?;
+        user.updated_at = Utc::now();
+        Ok(user)
+    }
+
+    /// Foreign key towards `users(id)`.
+    fn check_user_exists(&self, id: Uuid, constraint: &str) -> Result<(), RepositoryError> {
+        if self.user(id).is_none() {
+            return Err(RepositoryError::ForeignKeyViolation(format!(
+                "insert violates foreign key constraint \"{constraint}\""
+            )));
+        }
+        Ok(())
+    }
+
+    /// `DELETE FROM users`, with the `ON DELETE` actions of the schema.
+    fn delete_user(&mut self, id: Uuid) {
+        self.users.retain(|u| u.id != id);
+        self.refresh_tokens.retain(|t| t.user_id != id);
+        self.identities.retain(|i| i.user_id != id);
+        self.password_history.retain(|p| p.user_id != id);
+        self.user_tokens.retain(|t| t.user_id != id);
+        self.known_devices.retain(|d| d.user_id != id);
+        for attempt in &mut self.login_attempts {
+            if attempt.user_id == Some(id) {
+                attempt.user_id = None;
+            }
+        }
+    }
+}
+
+/// Every table of the schema behind one lock, shared by all the stores built from it.
+#[derive(Clone, Default)]
+pub struct MemoryStore {
+    tables: Arc<Mutex<Tables>>,
+}
+
+impl MemoryStore {
+    fn lock(&self) -> MutexGuard<'_, Tables> {
+        // Chaque écriture est complète avant de rendre le verrou : on ignore l'empoisonnement
+        self.tables
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+fn record_not_found() -> RepositoryError {
+    RepositoryError::NotFound("Record not found".to_string())
+}
+
+fn unique_violation(constraint: &str) -> RepositoryError {
+    RepositoryError::UniqueViolation {
+        constraint: constraint.to_string(),
+    }
+}
+
+fn limit_to_usize(limit: i64) -> usize {
+    usize::try_from(limit).unwrap_or(0)
+}
+
+/// Keyset page: rows strictly after `cursor`, newest first, by `(timestamp, id)`.
+fn keyset_page<T: Clone>(
+    rows: impl Iterator<Item = T>,
+    key: impl Fn(&T) -> (DateTime<Utc>, Uuid),
+    cursor: Option<(DateTime<Utc>, Uuid)>,
+    limit: i64,
+) -> Vec<T> {
+    let mut page: Vec<T> = rows
+        .filter(|row| cursor.is_none_or(|cursor| key(row) < cursor))
+        .collect();
+    page.sort_by_key(|row| std::cmp::Reverse(key(row)));
+    page.truncate(limit_to_usize(limit));
+    page
+}
+
+impl UserStore for MemoryStore {
+    fn find_by_email(&self, email: &str) -> Result<Option<User>, RepositoryError> {
+        let email = email.to_lowercase();
+        Ok(self
+            .lock()
+            .users
+            .iter()
+            .find(|u| u.email.to_lowercase() == email)
+            .cloned())
+    }
+
+    fn find_by_username_canonical(&self, canonical: &str) -> Result<Option<User>, RepositoryError> {
+        Ok(self
+            .lock()
+            .users
+            .iter()
+            .find(|u| u.username_canonical == canonical)
+            .cloned())
+    }
+
+    fn find_by_id(&self, id: Uuid) -> Result<Option<User>, RepositoryError> {
+        Ok(self.lock().user(id).cloned())
+    }
+
+    fn create(&self, new_user: &NewUser) -> Result<User, RepositoryError> {
+        let mut tables = self.lock();
+        let email = new_user.email.to_lowercase();
+        if tables.users.iter().any(|u| u.email.to_lowercase() == email) {
+            return Err(unique_violation("users_email_lower_key"));
+        }
+        if tables.users.iter().any(|u| u.username == new_user.username) {
+            return Err(unique_violation("users_username_key"));
+        }
+        if tables
+            .users
+            .iter()
+            .any(|u| u.username_canonical == new_user.username_canonical)
+        {
+            return Err(unique_violation("users_username_canonical_key"));
+        }
+
+        let now = Utc::now();
+        let user = User {
+            id: Uuid::new_v4(),
+            email: new_user.email.clone(),
+            username: new_user.username.clone(),
+            password_hash: new_user.password_hash.clone(),
+            email_verified: false,
+            is_active: true,
+            is_admin: false,
+            created_at: now,
+            updated_at: now,
+            last_login_at: None,
+            username_canonical: new_user.username_canonical.clone(),
+            deletion_scheduled_at: None,
+            failed_login_count: 0,
+            lockout_level: 0,
+            locked_until: None,
+            locked_permanently: false,
+            last_failed_login_at: None,
+            password_pepper_version: new_user.password_pepper_version,
+            password_changed_at: Some(now),
+        };
+        tables.users.push(user.clone());
+        Ok(user)
+    }
+
+    fn update_password(
+        &self,
+        id: Uuid,
+        new_password_hash: &str,
+        pepper_version: Option<i32>,
+    ) -> Result<(), RepositoryError> {
+        // Comme un UPDATE sans ligne correspondante : pas d'erreur
+        if let Ok(user) = self.lock().user_for_update(id) {
+            user.password_hash = Some(new_password_hash.to_string());
+            user.password_pepper_version = pepper_version;
+        }
+        Ok(())
+    }
+
+    fn change_password(
+        &self,
+        id: Uuid,
+        new_password_hash: &str,
+        pepper_version: Option<i32>,
+        history_size: i64,
+    ) -> Result<(), RepositoryError> {
+        let mut tables = self.lock();
+        let now = Utc::now();
+        let user = tables.user_for_update(id)?;
+        let previous = user.password_hash.replace(new_password_hash.to_string());
+        let previous_pepper = std::mem::replace(&mut user.password_pepper_version, pepper_version);
+        user.password_changed_at = Some(now);
+
+        if let Some(password_hash) = previous.filter(|_| history_size > 0) {
+            tables.password_history.push(PasswordHistoryEntry {
+                id: Uuid::new_v4(),
+                user_id: id,
+                password_hash,
+                password_pepper_version: previous_pepper,
+                created_at: now,
+            });
+        }
+        let kept: Vec<Uuid> = keyset_page(
+            tables.password_history.iter().filter(|p| p.user_id == id),
+            |p| (p.created_at, p.id),
+            None,
+            history_size,
+        )
+        .into_iter()
+        .map(|p| p.id)
+        .collect();
+        tables
+            .password_history
+            .retain(|p| p.user_id != id || kept.contains(&p.id));
+        tables.refresh_tokens.retain(|t| t.user_id != id);
+        Ok(())
+    }
+
+    fn update(&self, id: Uuid, changes: &UpdateUser) -> Result<User, RepositoryError> {
+        let mut tables = self.lock();
+        let user = tables.user_for_update(id)?;
+        if let Some(email_verified) = changes.email_verified {
+            user.email_verified = email_verified;
+        }
+        if let Some(is_active) = changes.is_active {
+            user.is_active = is_active;
+        }
+        if let Some(is_admin) = changes.is_admin {
+            user.is_admin = is_admin;
+        }
+        if let Some(last_login_at) = changes.last_login_at {
+            user.last_login_at = last_login_at;
+        }
+        if let Some(deletion_scheduled_at) = changes.deletion_scheduled_at {
+            user.deletion_scheduled_at = deletion_scheduled_at;
+        }
+        Ok(user.clone())
+    }
+
+    fn delete(&self, id: Uuid) -> Result<(), RepositoryError> {
+        self.lock().delete_user(id);
+        Ok(())
+    }
+
+    fn find_due_for_deletion(
+        &self,
+        now: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<User>, RepositoryError> {
+        let mut due: Vec<User> = self
+            .lock()
+            .users
+            .iter()
+            .filter(|u| u.deletion_scheduled_at.is_some_and(|at| at <= now))
+            .cloned()
+            .collect();
+        due.sort_by_key(|u| u.deletion_scheduled_at);
+        due.truncate(limit_to_usize(limit));
+        Ok(due)
+    }
+
+    fn update_lockout(
+        &self,
+        id: Uuid,
+        f: &mut dyn FnMut(LockoutState) -> LockoutState,
+    ) -> Result<LockoutState, RepositoryError> {
+        let mut tables = self.lock();
+        let user = tables.user_for_update(id)?;
+        let next = f(user.lockout_state());
+        user.failed_login_count = next.failed_login_count;
+        user.lockout_level = next.lockout_level;
+        user.locked_until = next.locked_until;
+        user.locked_permanently = next.locked_permanently;
+        user.last_failed_login_at = next.last_failed_login_at;
+        Ok(next)
+    }
+
+    fn reset_lockout(&self, id: Uuid) -> Result<(), RepositoryError> {
+        self.update_lockout(id, &mut |_| LockoutState::default())
+            .map(|_| ())
+            .map_err(|_| RepositoryError::NotFound("User not found".to_string()))
+    }
+
+    fn purge(&self, id: Uuid) -> Result<Uuid, RepositoryError> {
+        let pseudonym = Uuid::new_v4();
+        let mut tables = self.lock();
+        for attempt in &mut tables.login_attempts {
+            if attempt.user_id == Some(id) {
+                attempt.user_agent = None;
+                attempt.ip_address = None;
+                attempt.email_hash = None;
+            }
+        }
+        for event in &mut tables.audit_events {
+            if event.actor_id != Some(id) && event.target_id != Some(id) {
+                continue;
+            }
+            event.ip_address = None;
+            event.user_agent = None;
+            if let Some(email_hash) = event.metadata.get_mut("email_hash") {
+                *email_hash = serde_json::Value::Null;
+            }
+            for user_id in [&mut event.actor_id, &mut event.target_id] {
+                if *user_id == Some(id) {
+                    *user_id = Some(pseudonym);
+                }
+            }
+        }
+        tables.delete_user(id);
+        Ok(pseudonym)
+    }
+}
+
+impl RefreshTokenStore for MemoryStore {
+    fn create(&self, new_refresh_token: &NewRefreshToken) -> Result<RefreshToken, RepositoryError> {
+        let mut tables = self.lock();
+        tables.check_user_exists(new_refresh_token.user_id, "refresh_tokens_user_id_fkey")?;
+        if tables
+            .refresh_tokens
+            .iter()
+            .any(|t| t.token_hash == new_refresh_token.token_hash)
+        {
+            return Err(unique_violation("refresh_tokens_token_hash_key"));
+        }
+
+        let now = Utc::now();
+        let token = RefreshToken {
+            id: Uuid::new_v4(),
+            user_id: new_refresh_token.user_id,
+            token_hash: new_refresh_token.token_hash.clone(),
+            expires_at: new_refresh_token.expires_at,
+            created_at: now,
+            updated_at: now,
+            risk_flagged: new_refresh_token.risk_flagged,
+        };
+        tables.refresh_tokens.push(token.clone());
+        Ok(token)
+    }
+
+    fn find_by_hash(&self, hash: &str) -> Result<Option<RefreshToken>, RepositoryError> {
+        let now = Utc::now();
+        Ok(self
+            .lock()
+            .refresh_tokens
+            .iter()
+            .find(|t| t.token_hash == hash && t.expires_at > now)
+            .cloned())
+    }
+
+    fn find_by_user(&self, user_id: Uuid) -> Result<Vec<RefreshToken>, RepositoryError> {
+        let mut tokens: Vec<RefreshToken> = self
+            .lock()
+            .refresh_tokens
+            .iter()
+            .filter(|t| t.user_id == user_id)
+            .cloned()
+            .collect();
+        tokens.sort_by_key(|t| std::cmp::Reverse(t.created_at));
+        Ok(tokens)
+    }
+
+    fn delete(&self, id: Uuid) -> Result<(), RepositoryError> {
+        self.lock().refresh_tokens.retain(|t| t.id != id);
+        Ok(())
+    }
+
+    fn delete_by_user(&self, user_id: Uuid) -> Result<(), RepositoryError> {
+        self.lock().refresh_tokens.retain(|t| t.user_id != user_id);
+        Ok(())
+    }
+
+    fn delete_expired(&self, before: DateTime<Utc>) -> Result<usize, RepositoryError> {
+        let tokens = &mut self.lock().refresh_tokens;
+        let count = tokens.len();
+        tokens.retain(|t| t.expires_at >= before);
+        Ok(count - tokens.len())
+    }
+}
+
+impl LoginAttemptStore for MemoryStore {
+    fn create(&self, new_attempt: &NewLoginAttempt) -> Result<LoginAttempt, RepositoryError> {
+        let mut tables = self.lock();
+        if let Some(user_id) = new_attempt.user_id {
+            tables.check_user_exists(user_id, "login_attempts_user_id_fkey")?;
+        }
+
+        let attempt = LoginAttempt {
+            id: Uuid::new_v4(),
+            user_id: new_attempt.user_id,
+            success: new_attempt.success,
+            attempted_at: Utc::now(),
+            user_agent: new_attempt.user_agent.clone(),
+            ip_address: new_attempt.ip_address.clone(),
+            email_hash: new_attempt.email_hash.clone(),
+            failure_reason: new_attempt.failure_reason.clone(),
+            request_id: new_attempt.request_id.clone(),
+        };
+        tables.login_attempts.push(attempt.clone());
+        Ok(attempt)
+    }
+
+    fn find_all_by_user(&self, user_id: Uuid) -> Result<Vec<LoginAttempt>, RepositoryError> {
+        let mut attempts: Vec<LoginAttempt> = self
+            .lock()
+            .login_attempts
+            .iter()
+            .filter(|a| a.user_id == Some(user_id))
+            .cloned()
+            .collect();
+        attempts.sort_by_key(|a| std::cmp::Reverse(a.attempted_at));
+        Ok(attempts)
+    }
+
+    fn find_by_id(&self, id: Uuid) -> Result<Option<LoginAttempt>, RepositoryError> {
+        Ok(self
+            .lock()
+            .login_attempts
+            .iter()
+            .find(|a| a.id == id)
+            .cloned())
+    }
+
+    fn find_page_by_user(
+        &self,
+        user_id: Uuid,
+        cursor: Option<HistoryCursor>,
+        limit: i64,
+    ) -> Result<Vec<LoginAttempt>, RepositoryError> {
+        let tables = self.lock();
+        Ok(keyset_page(
+            tables
+                .login_attempts
+                .iter()
+                .filter(|a| a.user_id == Some(user_id))
+                .cloned(),
+            |a| (a.attempted_at, a.id),
+            cursor,
+            limit,
+        ))
+    }
+
+    fn find_page_by_email_hash(
+        &self,
+        email_hash: &str,
+        cursor: Option<HistoryCursor>,
+        limit: i64,
+    ) -> Result<Vec<LoginAttempt>, RepositoryError> {
+        let tables = self.lock();
+        Ok(keyset_page(
+            tables
+                .login_attempts
+                .iter()
+                .filter(|a| a.email_hash.as_deref() == Some(email_hash))
+                .cloned(),
+            |a| (a.attempted_at, a.id),
+            cursor,
+            limit,
+        ))
+    }
+
+    fn delete_before(&self, before: DateTime<Utc>) -> Result<usize, RepositoryError> {
+        let attempts = &mut self.lock().login_attempts;
+        let count = attempts.len();
+        attempts.retain(|a| a.attempted_at >= before);
+        Ok(count - attempts.len())
+    }
+}
+
+impl UserIdentityStore for MemoryStore {
+    fn create(&self, new_identity: &NewUserIdentity) -> Result<UserIdentity, RepositoryError> {
+        let mut tables = self.lock();
+        tables.check_user_exists(new_identity.user_id, "user_identities_user_id_fkey")?;
+        if tables.identities.iter().any(|i| {
+            i.provider == new_identity.provider
+                && i.provider_user_id == new_identity.provider_user_id
+        }) {
+            return Err(unique_violation(
+                "user_identities_provider_provider_user_id_key",
+            ));
+        }
+
+        let identity = UserIdentity {
+            id: Uuid::new_v4(),
+            user_id: new_identity.user_id,
+            provider: new_identity.provider.clone(),
+            provider_user_id: new_identity.provider_user_id.clone(),
+            email: new_identity.email.clone(),
+            created_at: Some(Utc::now()),
+        };
+        tables.identities.push(identity.clone());
+        Ok(identity)
+    }
+
+    fn find_by_user(&self, user_id: Uuid) -> Result<Vec<UserIdentity>, RepositoryError> {
+        let mut identities: Vec<UserIdentity> = self
+            .lock()
+            .identities
+            .iter()
+            .filter(|i| i.user_id == user_id)
+            .cloned()
+            .collect();
+        identities.sort_by_key(|i| i.created_at);
+        Ok(identities)
+    }
+}
+
+impl PasswordHistoryStore for MemoryStore {
+    fn find_recent(
+        &self,
+        user_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<PasswordHistoryEntry>, RepositoryError> {
+        let tables = self.lock();
+        Ok(keyset_page(
+            tables
+                .password_history
+                .iter()
+                .filter(|p| p.user_id == user_id)
+                .cloned(),
+            |p| (p.created_at, p.id),
+            None,
+            limit,
+        ))
+    }
+}
+
+impl UserTokenStore for MemoryStore {
+    fn create(&self, new_token: &NewUserToken) -> Result<UserToken, RepositoryError> {
+        let mut tables = self.lock();
+        tables.check_user_exists(new_token.user_id, "user_tokens_user_id_fkey")?;
+        if tables
+            .user_tokens
+            .iter()
+            .any(|t| t.token_hash == new_token.token_hash)
+        {
+            return Err(unique_violation("user_tokens_token_hash_key"));
+        }
+
+        let token = UserToken {
+            id: Uuid::new_v4(),
+            user_id: new_token.user_id,
+            purpose: new_token.purpose.clone(),
+            token_hash: new_token.token_hash.clone(),
+            expires_at: new_token.expires_at,
+            used_at: None,
+            created_at: Utc::now(),
+        };
+        tables.user_tokens.push(token.clone());
+        Ok(token)
+    }
+
+    fn consume(
+        &self,
+        token_hash: &str,
+        purpose: TokenPurpose,
+        now: DateTime<Utc>,
+    ) -> Result<Option<UserToken>, RepositoryError> {
+        let mut tables = self.lock();
+        let token = tables.user_tokens.iter_mut().find(|t| {
+            t.token_hash == token_hash
+                && t.purpose == purpose.as_str()
+                && t.used_at.is_none()
+                && t.expires_at > now
+        });
+        Ok(token.map(|token| {
+            token.used_at = Some(now);
+            token.clone()
+        }))
+    }
+}
+
+impl KnownDeviceStore for MemoryStore {
+    fn find_by_user(&self, user_id: Uuid) -> Result<Vec<KnownDevice>, RepositoryError> {
+        let mut devices: Vec<KnownDevice> = self
+            .lock()
+            .known_devices
+            .iter()
+            .filter(|d| d.user_id == user_id)
+            .cloned()
+            .collect();
+        devices.sort_by_key(|d| std::cmp::Reverse(d.last_seen_at));
+        Ok(devices)
+    }
+
+    fn touch(&self, device: &NewKnownDevice) -> Result<KnownDevice, RepositoryError> {
+        let mut tables = self.lock();
+        tables.check_user_exists(device.user_id, "known_devices_user_id_fkey")?;
+
+        let now = Utc::now();
+        if let Some(known) = tables
+            .known_devices
+            .iter_mut()
+            .find(|d| d.user_id == device.user_id && d.fingerprint == device.fingerprint)
+        {
+            known.last_seen_at = now;
+            return Ok(known.clone());
+        }
+
+        let known = KnownDevice {
+            id: Uuid::new_v4(),
+            user_id: device.user_id,
+            fingerprint: device.fingerprint.clone(),
+            browser: device.browser.clone(),
+            os: device.os.clone(),
+            ip_prefix: device.ip_prefix.clone(),
+            first_seen_at: now,
+            last_seen_at: now,
+        };
+        tables.known_devices.push(known.clone());
+        Ok(known)
+    }
+}
+
+impl AuditEventStore for MemoryStore {
+    fn create(&self, new_event: &NewAuditEvent) -> Result<AuditEvent, RepositoryError> {
+        let event = AuditEvent {
+            id: Uuid::new_v4(),
+            occurred_at: Utc::now(),
+            event_type: new_event.event_type.clone(),
+            actor_id: new_event.actor_id,
+            target_id: new_event.target_id,
+            ip_address: new_event.ip_address.clone(),
+            user_agent: new_event.user_agent.clone(),
+            request_id: new_event.request_id.clone(),
+            metadata: new_event.metadata.clone(),
+        };
+        self.lock().audit_events.push(event.clone());
+        Ok(event)
+    }
+
+    fn find_page(
+        &self,
+        filter: &AuditEventFilter,
+        cursor: Option<AuditCursor>,
+        limit: i64,
+    ) -> Result<Vec<AuditEvent>, RepositoryError> {
+        let tables = self.lock();
+        let matches = |e: &&AuditEvent| {
+            filter
+                .event_type
+                .as_ref()
+                .is_none_or(|t| *t == e.event_type)
+                && filter.actor_id.is_none_or(|id| e.actor_id == Some(id))
+                && filter.target_id.is_none_or(|id| e.target_id == Some(id))
+                && filter.from.is_none_or(|from| e.occurred_at >= from)
+                && filter.to.is_none_or(|to| e.occurred_at < to)
+        };
+        Ok(keyset_page(
+            tables.audit_events.iter().filter(matches).cloned(),
+            |e| (e.occurred_at, e.id),
+            cursor,
+            limit,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::store::{Repositories, store_conformance_tests};
+
+    store_conformance_tests!(Repositories::memory());
+}
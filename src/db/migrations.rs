@@ -0,0 +1,358 @@
+//! Migrations embedded in the binary.
+//!
+//! `auth-manager migrate` (or `MIGRATE_ON_STARTUP=true`) applies the pending ones; the
+//! server refuses to start while any is left, rather than failing on the first query.
+//!
+//! Some data cannot be computed in SQL: the run also fills it in, in the same
+//! transaction (the UTS #39 skeleton of usernames stored before the username policy).
+
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+use diesel::backend::Backend;
+use diesel::prelude::*;
+use diesel::sql_types::BigInt;
+use diesel_migrations::{EmbeddedMigrations, MigrationHarness, embed_migrations};
+use uuid::Uuid;
+
+use super::DbPool;
+use super::schema::users;
+use crate::auth::username::canonical_form;
+
+/// `migrations/`, applied to `PostgreSQL`.
+pub const POSTGRES_MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
+
+/// `migrations-sqlite/`, applied to `SQLite`.
+#[cfg(feature = "sqlite")]
+pub const SQLITE_MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations-sqlite");
+
+/// Advisory lock taken while migrating, so that concurrent Lambda cold starts run the
+/// migrations once ("authmigr" in ASCII).
+const MIGRATION_LOCK_KEY: i64 = 0x6175_7468_6d69_6772;
+
+/// Migration adding `users.username_canonical`, initialised with `lower(username)`.
+const USERNAME_POLICY_VERSION: &str = "00000000000002";
+
+/// Applies the pending `PostgreSQL` migrations and returns their versions.
+///
+/// Everything runs in one transaction holding [`MIGRATION_LOCK_KEY`]: a concurrent
+/// caller waits, then finds nothing left to apply. The transaction-level lock also
+/// works through a transaction-mode pooler (Neon, `PgBouncer`).
+///
+/// # Errors
+///
+/// Returns an error if no connection is available, a migration fails, or existing
+/// accounts collide once canonicalised (every migration of the run is then rolled back).
+pub fn migrate_postgres(pool: &DbPool) -> Result<Vec<String>> {
+    let mut conn = pool.get().context("Failed to get a database connection")?;
+    conn.transaction(|conn| {
+        diesel::sql_query("SELECT pg_advisory_xact_lock($1)")
+            .bind::<BigInt, _>(MIGRATION_LOCK_KEY)
+            .execute(conn)?;
+        let applied = run_pending(conn, POSTGRES_MIGRATIONS)?;
+        if applied
+            .iter()
+            .any(|version| version == USERNAME_POLICY_VERSION)
+        {
+            backfill_username_canonical(conn)?;
+        }
+        Ok(applied)
+    })
+}
+
+/// Replaces the `lower(username)` left by the username policy migration with the
+/// canonical form the application computes, so that existing accounts are found and
+/// protected against lookalikes like new ones.
+///
+/// # Errors
+///
+/// Returns an error listing the usernames that share a canonical form (to rename
+/// before migrating again), or on a database failure.
+fn backfill_username_canonical(conn: &mut PgConnection) -> Result<()> {
+    let rows = users::table
+        .select((users::id, users::username, users::username_canonical))
+        .load::<(Uuid, String, String)>(conn)?;
+    let updates = canonical_updates(rows).map_err(|collisions| {
+        anyhow::anyhow!(
+            "Usernames that differ only by lookalike characters, rename them first: {}",
+            collisions
+                .iter()
+                .map(|names| names.join(", "))
+                .collect::<Vec<_>>()
+                .join("; ")
+        )
+    })?;
+    if updates.is_empty() {
+        return Ok(());
+    }
+
+    // L'index unique est vérifié ligne à ligne : une mise à jour intermédiaire pourrait
+    // heurter l'ancienne valeur d'une autre ligne. Il est recréé une fois tout écrit.
+    diesel::sql_query("DROP INDEX users_username_canonical_key").execute(conn)?;
+    for (id, canonical) in &updates {
+        diesel::update(users::table.find(id))
+            .set(users::username_canonical.eq(canonical))
+            .execute(conn)?;
+    }
+    diesel::sql_query(
+        "CREATE UNIQUE INDEX users_username_canonical_key ON users (username_canonical)",
+    )
+    .execute(conn)?;
+    tracing::info!(count = updates.len(), "Canonical usernames backfilled");
+    Ok(())
+}
+
+/// Rows whose stored canonical form is outdated, with the new one; or the groups of
+/// usernames sharing a canonical form.
+fn canonical_updates(
+    rows: Vec<(Uuid, String, String)>,
+) -> Result<Vec<(Uuid, String)>, Vec<Vec<String>>> {
+    let mut by_canonical: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut updates = Vec::new();
+    for (id, username, stored) in rows {
+        let canonical = canonical_form(&username);
+        if canonical != stored {
+            updates.push((id, canonical.clone()));
+        }
+        by_canonical.entry(canonical).or_default().push(username);
+    }
+    let collisions: Vec<_> = by_canonical
+        .into_values()
+        .filter(|names| names.len() > 1)
+        .collect();
+    if collisions.is_empty() {
+        Ok(updates)
+    } else {
+        Err(collisions)
+    }
+}
+
+/// Fails if the `PostgreSQL` schema is missing migrations embedded in this binary.
+///
+/// # Errors
+///
+/// Returns an error listing the pending migrations, or if they cannot be read.
+pub fn check_postgres(pool: &DbPool) -> Result<()> {
+    let mut conn = pool.get().context("Failed to get a database connection")?;
+    ensure_up_to_date(&mut conn, POSTGRES_MIGRATIONS)
+}
+
+/// Applies the pending `SQLite` migrations and returns their versions.
+///
+/// The run holds the database write lock (`BEGIN IMMEDIATE`), the `SQLite` counterpart
+/// of the advisory lock.
+///
+/// # Errors
+///
+/// Returns an error if no connection is available or a migration fails.
+#[cfg(feature = "sqlite")]
+pub fn migrate_sqlite(pool: &super::sqlite::SqlitePool) -> Result<Vec<String>> {
+    let mut conn = pool.get().context("Failed to get a SQLite connection")?;
+    conn.immediate_transaction(|conn| run_pending(conn, SQLITE_MIGRATIONS))
+}
+
+/// Fails if the `SQLite` schema is missing migrations embedded in this binary.
+///
+/// # Errors
+///
+/// Returns an error listing the pending migrations, or if they cannot be read.
+#[cfg(feature = "sqlite")]
+pub fn check_sqlite(pool: &super::sqlite::SqlitePool) -> Result<()> {
+    let mut conn = pool.get().context("Failed to get a SQLite connection")?;
+    ensure_up_to_date(&mut conn, SQLITE_MIGRATIONS)
+}
+
+fn run_pending<DB: Backend>(
+    conn: &mut impl MigrationHarness<DB>,
+    migrations: EmbeddedMigrations,
+) -> Result<Vec<String>> {
+    let applied = conn
+        .run_pending_migrations(migrations)
+        .map_err(|e| anyhow::anyhow!("Migration failed: {e}"))?;
+    Ok(applied.iter().map(ToString::to_string).collect())
+}
+
+fn pending<DB: Backend>(
+    conn: &mut impl MigrationHarness<DB>,
+    migrations: EmbeddedMigrations,
+) -> Result<Vec<String>> {
+    let pending = conn
+        .pending_migrations(migrations)
+        .map_err(|e| anyhow::anyhow!("Failed to read applied migrations: {e}"))?;
+    Ok(pending.iter().map(|m| m.name().to_string()).collect())
+}
+
+fn ensure_up_to_date<DB: Backend>(
+    conn: &mut impl MigrationHarness<DB>,
+    migrations: EmbeddedMigrations,
+) -> Result<()> {
+    let pending = pending(conn, migrations)?;
+    if !pending.is_empty() {
+        anyhow::bail!(
+            "Database schema is behind, pending migrations: {} \
+             (run `auth-manager migrate` or set MIGRATE_ON_STARTUP=true)",
+            pending.join(", ")
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::connection::{build_pool, test_pool};
+
+    /// Database of its own on the test server, migrated up to the username policy
+    /// (excluded), dropped at the end of the test.
+    struct ScratchDatabase {
+        name: String,
+        pool: Option<DbPool>,
+    }
+
+    impl ScratchDatabase {
+        fn before_username_policy() -> Self {
+            let name = format!("auth_scratch_{}", Uuid::new_v4().simple());
+            let mut admin = test_pool().get().unwrap();
+            diesel::sql_query(format!("CREATE DATABASE {name}"))
+                .execute(&mut admin)
+                .unwrap();
+            let url = std::env::var("TEST_DATABASE_URL")
+                .or_else(|_| std::env::var("DATABASE_URL"))
+                .unwrap();
+            let (server, _) = url.rsplit_once('/').unwrap();
+            let pool = build_pool(&format!("{server}/{name}")).unwrap();
+            let mut conn = pool.get().unwrap();
+            while conn
+                .pending_migrations(POSTGRES_MIGRATIONS)
+                .unwrap()
+                .first()
+                .is_some_and(|next| next.name().version().to_string() != USERNAME_POLICY_VERSION)
+            {
+                conn.run_next_migration(POSTGRES_MIGRATIONS).unwrap();
+            }
+            Self {
+                name,
+                pool: Some(pool),
+            }
+        }
+
+        fn pool(&self) -> &DbPool {
+            self.pool.as_ref().unwrap()
+        }
+
+        fn insert_users(&self, users: &[(&str, &str)]) {
+            let mut conn = self.pool().get().unwrap();
+            for (email, username) in users {
+                diesel::insert_into(users::table)
+                    .values((users::email.eq(email), users::username.eq(username)))
+                    .execute(&mut conn)
+                    .unwrap();
+            }
+        }
+    }
+
+    impl Drop for ScratchDatabase {
+        fn drop(&mut self) {
+            drop(self.pool.take());
+            if let Ok(mut admin) = test_pool().get() {
+                let _ = diesel::sql_query(format!(
+                    "DROP DATABASE IF EXISTS {} WITH (FORCE)",
+                    self.name
+                ))
+                .execute(&mut admin);
+            }
+        }
+    }
+
+    #[test]
+    fn username_policy_migration_backfills_canonical_forms() {
+        let db = ScratchDatabase::before_username_policy();
+        // Pleine chasse (NFKC) et cyrillique : ni l'un ni l'autre n'est couvert par lower()
+        db.insert_users(&[
+            ("alice@example.com", "Alice"),
+            ("john@example.com", "ＪＯＨＮ"),
+            ("cop@example.com", "СОР"),
+        ]);
+
+        migrate_postgres(db.pool()).unwrap();
+
+        let mut conn = db.pool().get().unwrap();
+        let stored: BTreeMap<String, String> = users::table
+            .select((users::username, users::username_canonical))
+            .load(&mut conn)
+            .unwrap()
+            .into_iter()
+            .collect();
+        assert_eq!(stored["Alice"], "alice");
+        assert_eq!(stored["ＪＯＨＮ"], canonical_form("john"));
+        assert_eq!(stored["СОР"], canonical_form("cop"));
+        check_postgres(db.pool()).unwrap();
+    }
+
+    #[test]
+    fn username_policy_migration_reports_duplicates() {
+        let db = ScratchDatabase::before_username_policy();
+        db.insert_users(&[
+            ("bob@example.com", "Bob"),
+            ("bob2@example.com", "bob"),
+            ("Carol@example.com", "carol"),
+            ("carol@EXAMPLE.com", "carol2"),
+        ]);
+
+        let err = format!("{:#}", migrate_postgres(db.pool()).unwrap_err());
+        assert!(err.contains("username Bob, bob"), "{err}");
+        assert!(
+            err.contains("email Carol@example.com, carol@EXAMPLE.com"),
+            "{err}"
+        );
+        assert!(check_postgres(db.pool()).is_err(), "the run is rolled back");
+    }
+
+    #[test]
+    fn username_policy_migration_reports_lookalike_usernames() {
+        let db = ScratchDatabase::before_username_policy();
+        // "alice" avec un « а » cyrillique : distinct pour lower(), même squelette
+        db.insert_users(&[("a@example.com", "alice"), ("b@example.com", "аlice")]);
+
+        let err = format!("{:#}", migrate_postgres(db.pool()).unwrap_err());
+        assert!(err.contains("alice, аlice"), "{err}");
+        assert!(check_postgres(db.pool()).is_err(), "the run is rolled back");
+    }
+
+    #[test]
+    fn test_database_is_up_to_date() {
+        check_postgres(&test_pool()).expect("test database should be fully migrated");
+    }
+
+    #[test]
+    fn concurrent_migrations_apply_nothing_twice() {
+        let handles: Vec<_> = (0..4)
+            .map(|_| std::thread::spawn(|| migrate_postgres(&test_pool())))
+            .collect();
+        for handle in handles {
+            let applied = handle.join().unwrap().expect("migration run");
+            assert!(applied.is_empty(), "already migrated: {applied:?}");
+        }
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn sqlite_schema_is_behind_until_migrated() {
+        let pool = crate::db::sqlite::build_pool(&format!(
+            "file:migrations-test-{}?mode=memory&cache=shared",
+            uuid::Uuid::new_v4()
+        ))
+        .unwrap();
+
+        let err = check_sqlite(&pool).unwrap_err().to_string();
+        assert!(err.contains("create_tables"), "{err}");
+
+        let applied = migrate_sqlite(&pool).unwrap();
+        assert_eq!(
+            applied,
+            ["00000000000001", "00000000000002", "00000000000003"]
+        );
+        check_sqlite(&pool).unwrap();
+        assert!(migrate_sqlite(&pool).unwrap().is_empty());
+    }
+}
@@ -1,8 +1,13 @@
 pub mod connection;
 pub mod error;
+pub mod memory;
+pub mod migrations;
 pub mod models;
 pub mod repositories;
 pub mod schema;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod store;
 
 use diesel::PgConnection;
 use diesel::r2d2::{self, ConnectionManager};
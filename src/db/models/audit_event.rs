@@ -0,0 +1,48 @@
+use crate::db::schema::audit_events;
+use auth_manager_api::AuditEventEntry;
+use chrono::{DateTime, Utc};
+use diesel::{Insertable, Queryable, Selectable};
+use uuid::Uuid;
+
+#[derive(Insertable, Debug, Clone, Default)]
+#[diesel(table_name = audit_events)]
+pub struct NewAuditEvent {
+    /// `AuditEventType::as_str`
+    pub event_type: String,
+    pub actor_id: Option<Uuid>,
+    pub target_id: Option<Uuid>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub request_id: Option<String>,
+    pub metadata: serde_json::Value,
+}
+
+#[derive(Queryable, Selectable, Debug, Clone)]
+#[diesel(table_name = audit_events)]
+pub struct AuditEvent {
+    pub id: Uuid,
+    pub occurred_at: DateTime<Utc>,
+    pub event_type: String,
+    pub actor_id: Option<Uuid>,
+    pub target_id: Option<Uuid>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub request_id: Option<String>,
+    pub metadata: serde_json::Value,
+}
+
+impl From<AuditEvent> for AuditEventEntry {
+    fn from(event: AuditEvent) -> Self {
+        AuditEventEntry {
+            id: event.id,
+            occurred_at: event.occurred_at,
+            event_type: event.event_type,
+            actor_id: event.actor_id,
+            target_id: event.target_id,
+            ip_address: event.ip_address,
+            user_agent: event.user_agent,
+            request_id: event.request_id,
+            metadata: event.metadata,
+        }
+    }
+}
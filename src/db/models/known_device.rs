@@ -0,0 +1,29 @@
+use crate::db::schema::known_devices;
+use chrono::{DateTime, Utc};
+use diesel::{Insertable, Queryable, Selectable};
+use uuid::Uuid;
+
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = known_devices)]
+pub struct NewKnownDevice {
+    pub user_id: Uuid,
+    /// See `auth::risk::DeviceFingerprint`
+    pub fingerprint: String,
+    pub browser: Option<String>,
+    pub os: Option<String>,
+    pub ip_prefix: Option<String>,
+}
+
+/// A device (browser family and network) the user already logged in from.
+#[derive(Queryable, Selectable, Debug, Clone)]
+#[diesel(table_name = known_devices)]
+pub struct KnownDevice {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub fingerprint: String,
+    pub browser: Option<String>,
+    pub os: Option<String>,
+    pub ip_prefix: Option<String>,
+    pub first_seen_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+}
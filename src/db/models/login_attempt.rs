@@ -1,14 +1,21 @@
 use crate::db::schema::login_attempts;
+use auth_manager_api::{LoginAttemptExport, LoginFailureReason, LoginHistoryEntry};
 use chrono::{DateTime, Utc};
 use diesel::{Insertable, Queryable, Selectable};
 use uuid::Uuid;
 
-#[derive(Insertable, Debug, Clone)]
+#[derive(Insertable, Debug, Clone, Default)]
 #[diesel(table_name = login_attempts)]
-pub struct NewLoginAttempt<'a> {
-    pub user_id: &'a Option<Uuid>,
+pub struct NewLoginAttempt {
+    pub user_id: Option<Uuid>,
     pub success: bool,
-    pub user_agent: &'a Option<String>,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    /// Set when the attempt is not tied to an account (see `email::email_hash`)
+    pub email_hash: Option<String>,
+    /// `LoginFailureReason::as_str`, `None` on success
+    pub failure_reason: Option<String>,
+    pub request_id: Option<String>,
 }
 
 // All fields are required for Diesel Queryable deserialization (schema alignment).
@@ -21,4 +28,40 @@ pub struct LoginAttempt {
     pub success: bool,
     pub attempted_at: DateTime<Utc>,
     pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub email_hash: Option<String>,
+    pub failure_reason: Option<String>,
+    pub request_id: Option<String>,
+}
+
+impl From<LoginAttempt> for LoginAttemptExport {
+    fn from(attempt: LoginAttempt) -> Self {
+        LoginAttemptExport {
+            attempted_at: attempt.attempted_at,
+            success: attempt.success,
+            user_agent: attempt.user_agent,
+            ip_address: attempt.ip_address,
+            failure_reason: attempt
+                .failure_reason
+                .as_deref()
+                .and_then(LoginFailureReason::from_str_opt),
+        }
+    }
+}
+
+impl From<LoginAttempt> for LoginHistoryEntry {
+    fn from(attempt: LoginAttempt) -> Self {
+        LoginHistoryEntry {
+            id: attempt.id,
+            attempted_at: attempt.attempted_at,
+            success: attempt.success,
+            device: crate::auth::device::parse_user_agent(attempt.user_agent.as_deref()),
+            user_agent: attempt.user_agent,
+            ip_address: attempt.ip_address,
+            failure_reason: attempt
+                .failure_reason
+                .as_deref()
+                .and_then(LoginFailureReason::from_str_opt),
+        }
+    }
 }
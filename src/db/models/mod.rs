@@ -1,4 +1,9 @@
+pub mod audit_event;
+pub mod known_device;
 pub mod login_attempt;
+pub mod password_history;
+pub mod rate_limit_bucket;
 pub mod refresh_token;
 pub mod user;
-//pub mod user_identity;
+pub mod user_identity;
+pub mod user_token;
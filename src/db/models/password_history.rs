@@ -0,0 +1,15 @@
+use crate::db::schema::password_history;
+use chrono::{DateTime, Utc};
+use diesel::{Queryable, Selectable};
+use uuid::Uuid;
+
+/// A password the user replaced, kept to refuse its reuse.
+#[derive(Queryable, Selectable, Debug, Clone)]
+#[diesel(table_name = password_history)]
+pub struct PasswordHistoryEntry {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub password_hash: String,
+    pub password_pepper_version: Option<i32>,
+    pub created_at: DateTime<Utc>,
+}
@@ -0,0 +1,12 @@
+use crate::db::schema::rate_limit_buckets;
+use chrono::{DateTime, Utc};
+use diesel::{Queryable, QueryableByName, Selectable};
+
+/// Fixed-window counter for one rate-limit key.
+#[derive(Queryable, QueryableByName, Selectable, Debug, Clone, PartialEq, Eq)]
+#[diesel(table_name = rate_limit_buckets)]
+pub struct RateLimitBucket {
+    pub key: String,
+    pub window_start: DateTime<Utc>,
+    pub hits: i32,
+}
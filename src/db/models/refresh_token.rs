@@ -1,4 +1,5 @@
 use crate::db::schema::refresh_tokens;
+use auth_manager_api::SessionExport;
 use chrono::{DateTime, Utc};
 use diesel::{Insertable, Queryable, Selectable};
 use uuid::Uuid;
@@ -9,6 +10,8 @@ pub struct NewRefreshToken {
     pub user_id: Uuid,
     pub token_hash: String,
     pub expires_at: DateTime<Utc>,
+    /// Opened by a login that a risk rule flagged
+    pub risk_flagged: bool,
 }
 
 #[derive(Queryable, Selectable, Debug, Clone)]
@@ -16,11 +19,20 @@ pub struct NewRefreshToken {
 pub struct RefreshToken {
     pub id: Uuid,
     pub user_id: Uuid,
-    #[allow(dead_code)]
     pub token_hash: String,
     pub expires_at: DateTime<Utc>,
-    #[expect(dead_code, reason = "Required for Diesel Queryable deserialization")]
     pub created_at: DateTime<Utc>,
-    #[expect(dead_code, reason = "Required for Diesel Queryable deserialization")]
     pub updated_at: DateTime<Utc>,
+    pub risk_flagged: bool,
+}
+
+impl From<RefreshToken> for SessionExport {
+    fn from(token: RefreshToken) -> Self {
+        SessionExport {
+            id: token.id,
+            created_at: token.created_at,
+            expires_at: token.expires_at,
+            risk_flagged: token.risk_flagged,
+        }
+    }
 }
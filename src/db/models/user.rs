@@ -1,20 +1,30 @@
 use crate::db::schema::users;
-use auth_manager_api::UserResponse;
+use auth_manager_api::{UserExport, UserResponse};
 use chrono::{DateTime, Utc};
 use diesel::{AsChangeset, Insertable, Queryable, Selectable};
 use uuid::Uuid;
 
+/// Unique keys of `users` that reject an already used username, as reported by
+/// [`RepositoryError::UniqueViolation`](crate::db::error::RepositoryError::UniqueViolation).
+pub const USERNAME_UNIQUE_KEYS: &[&str] = &["users_username_key", "users_username_canonical_key"];
+
+/// Unique keys of `users` that reject an already used email.
+pub const EMAIL_UNIQUE_KEYS: &[&str] = &["users_email_key", "users_email_lower_key"];
+
 #[derive(Insertable, Debug, Clone)]
 #[diesel(table_name = users)]
 pub struct NewUser {
     pub email: String,
     pub username: String,
     pub password_hash: Option<String>,
+    pub username_canonical: String,
+    pub password_pepper_version: Option<i32>,
 }
 
 #[derive(Queryable, Selectable, Debug, Clone)]
 #[diesel(table_name = users)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
+#[allow(clippy::struct_excessive_bools)]
 pub struct User {
     pub id: Uuid,
     pub email: String,
@@ -26,6 +36,40 @@ pub struct User {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub last_login_at: Option<DateTime<Utc>>,
+    pub username_canonical: String,
+    pub deletion_scheduled_at: Option<DateTime<Utc>>,
+    pub failed_login_count: i32,
+    pub lockout_level: i32,
+    pub locked_until: Option<DateTime<Utc>>,
+    pub locked_permanently: bool,
+    pub last_failed_login_at: Option<DateTime<Utc>>,
+    pub password_pepper_version: Option<i32>,
+    /// Last password change (defaults to the creation date); `None` without a password
+    pub password_changed_at: Option<DateTime<Utc>>,
+}
+
+impl User {
+    pub fn lockout_state(&self) -> LockoutState {
+        LockoutState {
+            failed_login_count: self.failed_login_count,
+            lockout_level: self.lockout_level,
+            locked_until: self.locked_until,
+            locked_permanently: self.locked_permanently,
+            last_failed_login_at: self.last_failed_login_at,
+        }
+    }
+}
+
+/// Login lockout columns of `users`, read and written as a unit.
+#[derive(Queryable, Selectable, AsChangeset, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[diesel(table_name = users)]
+#[diesel(treat_none_as_null = true)]
+pub struct LockoutState {
+    pub failed_login_count: i32,
+    pub lockout_level: i32,
+    pub locked_until: Option<DateTime<Utc>>,
+    pub locked_permanently: bool,
+    pub last_failed_login_at: Option<DateTime<Utc>>,
 }
 
 impl From<User> for UserResponse {
@@ -38,6 +82,25 @@ impl From<User> for UserResponse {
             is_active: user.is_active,
             is_admin: user.is_admin,
             created_at: user.created_at,
+            deletion_scheduled_at: user.deletion_scheduled_at,
+            password_changed_at: user.password_changed_at,
+        }
+    }
+}
+
+impl From<User> for UserExport {
+    fn from(user: User) -> Self {
+        UserExport {
+            id: user.id,
+            email: user.email,
+            username: user.username,
+            email_verified: user.email_verified,
+            is_active: user.is_active,
+            is_admin: user.is_admin,
+            created_at: user.created_at,
+            updated_at: user.updated_at,
+            last_login_at: user.last_login_at,
+            deletion_scheduled_at: user.deletion_scheduled_at,
         }
     }
 }
@@ -50,4 +113,6 @@ pub struct UpdateUser {
     pub is_admin: Option<bool>,
     #[allow(clippy::option_option)]
     pub last_login_at: Option<Option<DateTime<Utc>>>,
+    #[allow(clippy::option_option)]
+    pub deletion_scheduled_at: Option<Option<DateTime<Utc>>>,
 }
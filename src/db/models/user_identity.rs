@@ -1,7 +1,8 @@
-use diesel::{Insertable, Queryable, Selectable};
+use crate::db::schema::user_identities;
+use auth_manager_api::IdentityExport;
 use chrono::{DateTime, Utc};
+use diesel::{Insertable, Queryable, Selectable};
 use uuid::Uuid;
-use crate::db::schema::user_identities;
 
 #[derive(Insertable, Debug, Clone)]
 #[diesel(table_name = user_identities)]
@@ -21,4 +22,15 @@ pub struct UserIdentity {
     pub provider_user_id: String,
     pub email: Option<String>,
     pub created_at: Option<DateTime<Utc>>,
-}
\ No newline at end of file
+}
+
+impl From<UserIdentity> for IdentityExport {
+    fn from(identity: UserIdentity) -> Self {
+        IdentityExport {
+            provider: identity.provider,
+            provider_user_id: identity.provider_user_id,
+            email: identity.email,
+            created_at: identity.created_at,
+        }
+    }
+}
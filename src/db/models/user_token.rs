@@ -0,0 +1,46 @@
+use crate::db::schema::user_tokens;
+use chrono::{DateTime, Utc};
+use diesel::{Insertable, Queryable, Selectable};
+use uuid::Uuid;
+
+/// What a single-use emailed token grants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenPurpose {
+    /// Lifts a login lockout
+    AccountUnlock,
+    /// Revokes every session after a login the owner did not make
+    SessionRevoke,
+    /// Confirms the deletion of an account that has no password
+    AccountDeletion,
+}
+
+impl TokenPurpose {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::AccountUnlock => "account_unlock",
+            Self::SessionRevoke => "session_revoke",
+            Self::AccountDeletion => "account_deletion",
+        }
+    }
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = user_tokens)]
+pub struct NewUserToken {
+    pub user_id: Uuid,
+    pub purpose: String,
+    pub token_hash: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Queryable, Selectable, Debug, Clone)]
+#[diesel(table_name = user_tokens)]
+pub struct UserToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub purpose: String,
+    pub token_hash: String,
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
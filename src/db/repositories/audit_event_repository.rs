@@ -0,0 +1,147 @@
+use crate::db::DbPool;
+use crate::db::connection::get_connection;
+use crate::db::error::RepositoryError;
+use crate::db::models::audit_event::{AuditEvent, NewAuditEvent};
+use crate::db::schema::audit_events;
+use crate::db::store::AuditEventStore;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use uuid::Uuid;
+
+/// Keyset pagination position: the `(occurred_at, id)` of the last row already returned.
+pub type AuditCursor = (DateTime<Utc>, Uuid);
+
+/// Optional filters of an audit log query, combined with AND.
+#[derive(Debug, Clone, Default)]
+pub struct AuditEventFilter {
+    pub event_type: Option<String>,
+    pub actor_id: Option<Uuid>,
+    pub target_id: Option<Uuid>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone)]
+pub struct AuditEventRepository {
+    pool: DbPool,
+}
+
+impl AuditEventRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl AuditEventStore for AuditEventRepository {
+    /// Appends an event. The table rejects updates and deletes.
+    fn create(&self, new_event: &NewAuditEvent) -> Result<AuditEvent, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        diesel::insert_into(audit_events::table)
+            .values(new_event)
+            .get_result::<AuditEvent>(&mut conn)
+            .map_err(Into::into)
+    }
+
+    /// Page of matching events, newest first, strictly after `cursor`.
+    fn find_page(
+        &self,
+        filter: &AuditEventFilter,
+        cursor: Option<AuditCursor>,
+        limit: i64,
+    ) -> Result<Vec<AuditEvent>, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        let mut query = audit_events::table.into_boxed();
+        if let Some(event_type) = &filter.event_type {
+            query = query.filter(audit_events::event_type.eq(event_type.clone()));
+        }
+        if let Some(actor_id) = filter.actor_id {
+            query = query.filter(audit_events::actor_id.eq(actor_id));
+        }
+        if let Some(target_id) = filter.target_id {
+            query = query.filter(audit_events::target_id.eq(target_id));
+        }
+        if let Some(from) = filter.from {
+            query = query.filter(audit_events::occurred_at.ge(from));
+        }
+        if let Some(to) = filter.to {
+            query = query.filter(audit_events::occurred_at.lt(to));
+        }
+        if let Some((occurred_at, id)) = cursor {
+            query = query.filter(
+                audit_events::occurred_at
+                    .lt(occurred_at)
+                    .or(audit_events::occurred_at
+                        .eq(occurred_at)
+                        .and(audit_events::id.lt(id))),
+            );
+        }
+
+        query
+            .order_by((audit_events::occurred_at.desc(), audit_events::id.desc()))
+            .limit(limit)
+            .load::<AuditEvent>(&mut conn)
+            .map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::store::postgres_test_repositories;
+
+    fn event_for(target_id: Uuid, event_type: &str) -> NewAuditEvent {
+        NewAuditEvent {
+            event_type: event_type.to_string(),
+            target_id: Some(target_id),
+            metadata: serde_json::json!({ "n": 1 }),
+            ..NewAuditEvent::default()
+        }
+    }
+
+    #[test]
+    fn find_page_filters_and_paginates_newest_first() {
+        let target_id = Uuid::new_v4();
+        for event_type in ["logout", "password_changed", "logout"] {
+            postgres_test_repositories()
+                .audit_events
+                .create(&event_for(target_id, event_type))
+                .expect("Should append event");
+        }
+
+        let filter = AuditEventFilter {
+            target_id: Some(target_id),
+            event_type: Some("logout".to_string()),
+            ..AuditEventFilter::default()
+        };
+        let first_page = postgres_test_repositories()
+            .audit_events
+            .find_page(&filter, None, 1)
+            .expect("Query should succeed");
+        assert_eq!(first_page.len(), 1);
+        assert_eq!(first_page[0].metadata["n"], 1);
+
+        let last = &first_page[0];
+        let second_page = postgres_test_repositories()
+            .audit_events
+            .find_page(&filter, Some((last.occurred_at, last.id)), 10)
+            .expect("Query should succeed");
+        assert_eq!(second_page.len(), 1);
+        assert_ne!(second_page[0].id, last.id);
+        assert!(second_page[0].occurred_at <= last.occurred_at);
+    }
+
+    #[test]
+    fn events_cannot_be_deleted() {
+        let event = postgres_test_repositories()
+            .audit_events
+            .create(&event_for(Uuid::new_v4(), "logout"))
+            .expect("Should append event");
+
+        let mut conn = get_connection(&crate::db::connection::test_pool()).unwrap();
+        let deleted = diesel::delete(audit_events::table.filter(audit_events::id.eq(event.id)))
+            .execute(&mut conn);
+        assert!(deleted.is_err());
+    }
+}
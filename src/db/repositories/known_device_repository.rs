@@ -0,0 +1,100 @@
+use crate::db::DbPool;
+use crate::db::connection::get_connection;
+use crate::db::error::RepositoryError;
+use crate::db::models::known_device::{KnownDevice, NewKnownDevice};
+use crate::db::schema::known_devices;
+use crate::db::store::KnownDeviceStore;
+use chrono::Utc;
+use diesel::prelude::*;
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct KnownDeviceRepository {
+    pool: DbPool,
+}
+
+impl KnownDeviceRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl KnownDeviceStore for KnownDeviceRepository {
+    /// Returns the user's known devices, most recently seen first.
+    fn find_by_user(&self, user_id: Uuid) -> Result<Vec<KnownDevice>, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        known_devices::table
+            .filter(known_devices::user_id.eq(user_id))
+            .order_by(known_devices::last_seen_at.desc())
+            .load::<KnownDevice>(&mut conn)
+            .map_err(Into::into)
+    }
+
+    /// Records a login from the device: inserted on first sight, `last_seen_at`
+    /// bumped afterwards.
+    fn touch(&self, device: &NewKnownDevice) -> Result<KnownDevice, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        diesel::insert_into(known_devices::table)
+            .values(device)
+            .on_conflict((known_devices::user_id, known_devices::fingerprint))
+            .do_update()
+            .set(known_devices::last_seen_at.eq(Utc::now()))
+            .get_result::<KnownDevice>(&mut conn)
+            .map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::password::PasswordManager;
+    use crate::db::models::user::NewUser;
+    use crate::db::store::postgres_test_repositories;
+
+    #[test]
+    fn touch_inserts_once_then_bumps_last_seen() {
+        let username = format!("device_{}", &Uuid::new_v4().simple().to_string()[..16]);
+        let user = postgres_test_repositories()
+            .users
+            .create(&NewUser {
+                email: format!("{username}@example.com"),
+                username_canonical: username.clone(),
+                username,
+                password_hash: Some(PasswordManager::hash("Device123!").expect("hash").hash),
+                password_pepper_version: None,
+            })
+            .expect("create user");
+        let device = NewKnownDevice {
+            user_id: user.id,
+            fingerprint: "a".repeat(64),
+            browser: Some("Firefox".to_string()),
+            os: Some("Linux".to_string()),
+            ip_prefix: Some("203.0.113.0/24".to_string()),
+        };
+
+        let first = postgres_test_repositories()
+            .known_devices
+            .touch(&device)
+            .expect("insert");
+        let second = postgres_test_repositories()
+            .known_devices
+            .touch(&device)
+            .expect("update");
+
+        assert_eq!(first.id, second.id);
+        assert_eq!(second.first_seen_at, first.first_seen_at);
+        assert!(second.last_seen_at >= first.last_seen_at);
+        assert_eq!(
+            postgres_test_repositories()
+                .known_devices
+                .find_by_user(user.id)
+                .unwrap()
+                .len(),
+            1
+        );
+
+        let _ = postgres_test_repositories().users.delete(user.id);
+    }
+}
@@ -1,27 +1,59 @@
+use crate::db::DbPool;
 use crate::db::connection::get_connection;
 use crate::db::error::RepositoryError;
 use crate::db::models::login_attempt::{LoginAttempt, NewLoginAttempt};
 use crate::db::schema::login_attempts;
+use crate::db::store::LoginAttemptStore;
+use chrono::{DateTime, Utc};
 use diesel::prelude::*;
 use uuid::Uuid;
-pub struct LoginAttemptRepository;
+
+/// Keyset pagination position: the `(attempted_at, id)` of the last row already returned.
+pub type HistoryCursor = (DateTime<Utc>, Uuid);
+
+#[derive(Clone)]
+pub struct LoginAttemptRepository {
+    pool: DbPool,
+}
 
 impl LoginAttemptRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    fn load_page(
+        &self,
+        mut query: login_attempts::BoxedQuery<'static, diesel::pg::Pg>,
+        cursor: Option<HistoryCursor>,
+        limit: i64,
+    ) -> Result<Vec<LoginAttempt>, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        if let Some((attempted_at, id)) = cursor {
+            query = query.filter(
+                login_attempts::attempted_at
+                    .lt(attempted_at)
+                    .or(login_attempts::attempted_at
+                        .eq(attempted_at)
+                        .and(login_attempts::id.lt(id))),
+            );
+        }
+
+        query
+            .order_by((
+                login_attempts::attempted_at.desc(),
+                login_attempts::id.desc(),
+            ))
+            .limit(limit)
+            .load::<LoginAttempt>(&mut conn)
+            .map_err(Into::into)
+    }
+}
+
+impl LoginAttemptStore for LoginAttemptRepository {
     /// Créer une tentative de login
-    // user_agent must be owned: NewLoginAttempt borrows &Option<String> from it
-    #[allow(clippy::needless_pass_by_value)]
-    pub fn create(
-        user_id: Option<Uuid>,
-        success: bool,
-        user_agent: Option<String>,
-    ) -> Result<LoginAttempt, RepositoryError> {
-        let mut conn = get_connection()?;
-
-        let new_attempt = NewLoginAttempt {
-            user_id: &user_id,
-            success,
-            user_agent: &user_agent,
-        };
+    fn create(&self, new_attempt: &NewLoginAttempt) -> Result<LoginAttempt, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
 
         diesel::insert_into(login_attempts::table)
             .values(new_attempt)
@@ -29,32 +61,147 @@ impl LoginAttemptRepository {
             .map_err(Into::into)
     }
 
-    /// Compter les tentatives échouées pour un user dans les X dernières minutes
-    pub fn count_failed_attempts(user_id: Uuid, minutes: i64) -> Result<i64, RepositoryError> {
-        let mut conn = get_connection()?;
+    /// Récupérer toutes les tentatives d'un user (export de données)
+    fn find_all_by_user(&self, user_id: Uuid) -> Result<Vec<LoginAttempt>, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
 
         login_attempts::table
             .filter(login_attempts::user_id.eq(user_id))
-            .filter(login_attempts::success.eq(false))
-            .filter(
-                login_attempts::attempted_at
-                    .gt(chrono::Utc::now() - chrono::Duration::minutes(minutes)),
-            )
-            .count()
-            .get_result::<i64>(&mut conn)
+            .order_by(login_attempts::attempted_at.desc())
+            .load::<LoginAttempt>(&mut conn)
             .map_err(Into::into)
     }
 
-    /// Récupérer les dernières tentatives d'un user
-    #[expect(dead_code, reason = "Planned for login history endpoint")]
-    pub fn find_by_user(user_id: Uuid, limit: i64) -> Result<Vec<LoginAttempt>, RepositoryError> {
-        let mut conn = get_connection()?;
+    /// Récupérer une tentative par ID
+    fn find_by_id(&self, id: Uuid) -> Result<Option<LoginAttempt>, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
 
         login_attempts::table
+            .filter(login_attempts::id.eq(id))
+            .first::<LoginAttempt>(&mut conn)
+            .optional()
+            .map_err(Into::into)
+    }
+
+    /// Page of a user's attempts, newest first, strictly after `cursor`.
+    fn find_page_by_user(
+        &self,
+        user_id: Uuid,
+        cursor: Option<HistoryCursor>,
+        limit: i64,
+    ) -> Result<Vec<LoginAttempt>, RepositoryError> {
+        let query = login_attempts::table
             .filter(login_attempts::user_id.eq(user_id))
-            .order_by(login_attempts::attempted_at.desc())
-            .limit(limit)
-            .load::<LoginAttempt>(&mut conn)
+            .into_boxed();
+        self.load_page(query, cursor, limit)
+    }
+
+    /// Page of attempts against a given email hash, newest first, strictly after `cursor`.
+    fn find_page_by_email_hash(
+        &self,
+        email_hash: &str,
+        cursor: Option<HistoryCursor>,
+        limit: i64,
+    ) -> Result<Vec<LoginAttempt>, RepositoryError> {
+        let query = login_attempts::table
+            .filter(login_attempts::email_hash.eq(email_hash.to_string()))
+            .into_boxed();
+        self.load_page(query, cursor, limit)
+    }
+
+    /// Supprimer les tentatives antérieures à `before` (rétention)
+    fn delete_before(&self, before: DateTime<Utc>) -> Result<usize, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        diesel::delete(login_attempts::table.filter(login_attempts::attempted_at.lt(before)))
+            .execute(&mut conn)
             .map_err(Into::into)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::store::postgres_test_repositories;
+
+    fn unknown_email_attempt(email_hash: &str) -> NewLoginAttempt {
+        NewLoginAttempt {
+            email_hash: Some(email_hash.to_string()),
+            ..NewLoginAttempt::default()
+        }
+    }
+
+    #[test]
+    fn find_page_by_email_hash_paginates_newest_first() {
+        let email_hash = format!("{:0>64}", Uuid::new_v4().simple());
+        let created: Vec<LoginAttempt> = (0..3)
+            .map(|_| {
+                postgres_test_repositories()
+                    .login_attempts
+                    .create(&unknown_email_attempt(&email_hash))
+                    .expect("Should log attempt")
+            })
+            .collect();
+
+        let first_page = postgres_test_repositories()
+            .login_attempts
+            .find_page_by_email_hash(&email_hash, None, 2)
+            .expect("Query should succeed");
+        assert_eq!(first_page.len(), 2);
+        assert!(first_page[0].attempted_at >= first_page[1].attempted_at);
+
+        let last = &first_page[1];
+        let second_page = postgres_test_repositories()
+            .login_attempts
+            .find_page_by_email_hash(&email_hash, Some((last.attempted_at, last.id)), 2)
+            .expect("Query should succeed");
+        assert_eq!(second_page.len(), 1);
+
+        let mut seen: Vec<Uuid> = first_page
+            .iter()
+            .chain(&second_page)
+            .map(|a| a.id)
+            .collect();
+        let mut expected: Vec<Uuid> = created.iter().map(|a| a.id).collect();
+        seen.sort();
+        expected.sort();
+        assert_eq!(seen, expected, "Pages must not overlap nor skip rows");
+    }
+
+    #[test]
+    fn delete_before_prunes_old_attempts_only() {
+        let email_hash = format!("{:0>64}", Uuid::new_v4().simple());
+        let repos = postgres_test_repositories();
+        let old = repos
+            .login_attempts
+            .create(&unknown_email_attempt(&email_hash))
+            .unwrap();
+        let fresh = repos
+            .login_attempts
+            .create(&unknown_email_attempt(&email_hash))
+            .unwrap();
+        let mut conn = get_connection(&crate::db::connection::test_pool()).unwrap();
+        diesel::update(login_attempts::table.filter(login_attempts::id.eq(old.id)))
+            .set(login_attempts::attempted_at.eq(Utc::now() - chrono::Duration::days(400)))
+            .execute(&mut conn)
+            .unwrap();
+
+        // La base est partagée : on ne compte que les lignes de ce test
+        let deleted = repos
+            .login_attempts
+            .delete_before(Utc::now() - chrono::Duration::days(365))
+            .unwrap();
+        assert!(deleted >= 1);
+        assert!(repos.login_attempts.find_by_id(old.id).unwrap().is_none());
+        assert!(repos.login_attempts.find_by_id(fresh.id).unwrap().is_some());
+    }
+
+    #[test]
+    fn find_page_by_user_returns_empty_for_unknown_user() {
+        let page = postgres_test_repositories()
+            .login_attempts
+            .find_page_by_user(Uuid::new_v4(), None, 10)
+            .expect("Query should succeed");
+        assert!(page.is_empty());
+    }
+}
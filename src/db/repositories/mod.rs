@@ -1,3 +1,9 @@
+pub mod audit_event_repository;
+pub mod known_device_repository;
 pub mod login_attempt_repository;
+pub mod password_history_repository;
+pub mod rate_limit_repository;
 pub mod refresh_token_repository;
+pub mod user_identity_repository;
 pub mod user_repository;
+pub mod user_token_repository;
@@ -0,0 +1,37 @@
+use crate::db::DbPool;
+use crate::db::connection::get_connection;
+use crate::db::error::RepositoryError;
+use crate::db::models::password_history::PasswordHistoryEntry;
+use crate::db::schema::password_history;
+use crate::db::store::PasswordHistoryStore;
+use diesel::prelude::*;
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct PasswordHistoryRepository {
+    pool: DbPool,
+}
+
+impl PasswordHistoryRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl PasswordHistoryStore for PasswordHistoryRepository {
+    /// Returns the user's `limit` most recent previous passwords, newest first.
+    fn find_recent(
+        &self,
+        user_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<PasswordHistoryEntry>, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        password_history::table
+            .filter(password_history::user_id.eq(user_id))
+            .order_by(password_history::created_at.desc())
+            .limit(limit)
+            .load::<PasswordHistoryEntry>(&mut conn)
+            .map_err(Into::into)
+    }
+}
@@ -0,0 +1,165 @@
+use crate::db::DbPool;
+use crate::db::connection::get_connection;
+use crate::db::error::RepositoryError;
+use crate::db::models::rate_limit_bucket::RateLimitBucket;
+use crate::db::schema::rate_limit_buckets;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel::sql_types::{Timestamptz, Varchar};
+
+#[derive(Clone)]
+pub struct RateLimitRepository {
+    pool: DbPool,
+}
+
+impl RateLimitRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Compteur courant pour `key`, ignoré si sa fenêtre a commencé avant `since`
+    pub fn find_active(
+        &self,
+        key: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Option<RateLimitBucket>, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        rate_limit_buckets::table
+            .filter(rate_limit_buckets::key.eq(key))
+            .filter(rate_limit_buckets::window_start.gt(since))
+            .first::<RateLimitBucket>(&mut conn)
+            .optional()
+            .map_err(Into::into)
+    }
+
+    /// Incrémente atomiquement le compteur de `key`.
+    ///
+    /// Une fenêtre commencée avant `since` est expirée : elle repart de `now` à 1.
+    pub fn increment(
+        &self,
+        key: &str,
+        now: DateTime<Utc>,
+        since: DateTime<Utc>,
+    ) -> Result<RateLimitBucket, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        // Upsert en une requête pour rester correct entre instances concurrentes
+        diesel::sql_query(
+            "INSERT INTO rate_limit_buckets (key, window_start, hits) VALUES ($1, $2, 1) \
+             ON CONFLICT (key) DO UPDATE SET \
+                 hits = CASE WHEN rate_limit_buckets.window_start <= $3 \
+                     THEN 1 ELSE rate_limit_buckets.hits + 1 END, \
+                 window_start = CASE WHEN rate_limit_buckets.window_start <= $3 \
+                     THEN EXCLUDED.window_start ELSE rate_limit_buckets.window_start END \
+             RETURNING key, window_start, hits",
+        )
+        .bind::<Varchar, _>(key)
+        .bind::<Timestamptz, _>(now)
+        .bind::<Timestamptz, _>(since)
+        .get_result::<RateLimitBucket>(&mut conn)
+        .map_err(Into::into)
+    }
+
+    /// Supprime le compteur de `key`
+    pub fn delete(&self, key: &str) -> Result<(), RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        diesel::delete(rate_limit_buckets::table.filter(rate_limit_buckets::key.eq(key)))
+            .execute(&mut conn)?;
+        Ok(())
+    }
+
+    /// Supprime les compteurs dont la fenêtre a commencé avant `before`
+    pub fn delete_expired(&self, before: DateTime<Utc>) -> Result<usize, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        diesel::delete(
+            rate_limit_buckets::table.filter(rate_limit_buckets::window_start.le(before)),
+        )
+        .execute(&mut conn)
+        .map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::connection::test_pool;
+    use chrono::Duration;
+
+    fn repo() -> RateLimitRepository {
+        RateLimitRepository::new(test_pool())
+    }
+
+    fn test_key(prefix: &str) -> String {
+        format!("{prefix}:{}", uuid::Uuid::new_v4())
+    }
+
+    #[test]
+    fn increment_counts_within_window() {
+        let key = test_key("test_window");
+        let now = Utc::now();
+        let since = now - Duration::minutes(15);
+
+        assert_eq!(repo().increment(&key, now, since).unwrap().hits, 1);
+        let bucket = repo().increment(&key, now, since).unwrap();
+        assert_eq!(bucket.hits, 2);
+
+        let active = repo()
+            .find_active(&key, since)
+            .unwrap()
+            .expect("bucket should be active");
+        assert_eq!(active.hits, 2);
+
+        repo().delete(&key).unwrap();
+        assert!(repo().find_active(&key, since).unwrap().is_none());
+    }
+
+    #[test]
+    fn increment_restarts_expired_window() {
+        let key = test_key("test_expired");
+        let start = Utc::now() - Duration::minutes(30);
+        repo()
+            .increment(&key, start, start - Duration::minutes(15))
+            .unwrap();
+        repo()
+            .increment(&key, start, start - Duration::minutes(15))
+            .unwrap();
+
+        let now = Utc::now();
+        let since = now - Duration::minutes(15);
+        assert!(repo().find_active(&key, since).unwrap().is_none());
+
+        let bucket = repo().increment(&key, now, since).unwrap();
+        assert_eq!(bucket.hits, 1);
+        assert!(bucket.window_start > since);
+
+        repo().delete(&key).unwrap();
+    }
+
+    #[test]
+    fn delete_expired_removes_old_windows_only() {
+        let old = test_key("test_old");
+        let fresh = test_key("test_fresh");
+        let now = Utc::now();
+        let long_ago = now - Duration::days(2);
+        repo()
+            .increment(&old, long_ago, long_ago - Duration::minutes(15))
+            .unwrap();
+        repo()
+            .increment(&fresh, now, now - Duration::minutes(15))
+            .unwrap();
+
+        let deleted = repo().delete_expired(now - Duration::days(1)).unwrap();
+        assert!(deleted >= 1);
+        assert!(
+            repo()
+                .find_active(&fresh, now - Duration::minutes(15))
+                .unwrap()
+                .is_some()
+        );
+
+        repo().delete(&fresh).unwrap();
+    }
+}
@@ -1,15 +1,26 @@
+use crate::db::DbPool;
 use crate::db::connection::get_connection;
 use crate::db::error::RepositoryError;
 use crate::db::models::refresh_token::{NewRefreshToken, RefreshToken};
 use crate::db::schema::refresh_tokens;
-use chrono::Utc;
+use crate::db::store::RefreshTokenStore;
+use chrono::{DateTime, Utc};
 use diesel::prelude::*;
 use uuid::Uuid;
-pub struct RefreshTokenRepository;
+#[derive(Clone)]
+pub struct RefreshTokenRepository {
+    pool: DbPool,
+}
 
 impl RefreshTokenRepository {
-    pub fn create(new_refresh_token: &NewRefreshToken) -> Result<RefreshToken, RepositoryError> {
-        let mut conn = get_connection()?;
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl RefreshTokenStore for RefreshTokenRepository {
+    fn create(&self, new_refresh_token: &NewRefreshToken) -> Result<RefreshToken, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
 
         diesel::insert_into(refresh_tokens::table)
             .values(new_refresh_token)
@@ -17,9 +28,9 @@ impl RefreshTokenRepository {
             .map_err(Into::into)
     }
 
-    pub fn find_by_hash(hash: &str) -> Result<Option<RefreshToken>, RepositoryError> {
+    fn find_by_hash(&self, hash: &str) -> Result<Option<RefreshToken>, RepositoryError> {
         let hash = hash.to_string();
-        let mut conn = get_connection()?;
+        let mut conn = get_connection(&self.pool)?;
 
         refresh_tokens::table
             .filter(refresh_tokens::token_hash.eq(hash))
@@ -29,8 +40,19 @@ impl RefreshTokenRepository {
             .map_err(Into::into)
     }
 
-    pub fn delete(id: Uuid) -> Result<(), RepositoryError> {
-        let mut conn = get_connection()?;
+    /// Returns every refresh token (session) of a user, newest first.
+    fn find_by_user(&self, user_id: Uuid) -> Result<Vec<RefreshToken>, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        refresh_tokens::table
+            .filter(refresh_tokens::user_id.eq(user_id))
+            .order_by(refresh_tokens::created_at.desc())
+            .load::<RefreshToken>(&mut conn)
+            .map_err(Into::into)
+    }
+
+    fn delete(&self, id: Uuid) -> Result<(), RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
 
         diesel::delete(refresh_tokens::table.filter(refresh_tokens::id.eq(id)))
             .execute(&mut conn)?;
@@ -38,33 +60,44 @@ impl RefreshTokenRepository {
         Ok(())
     }
 
-    pub fn delete_by_user(user_id: Uuid) -> Result<(), RepositoryError> {
-        let mut conn = get_connection()?;
+    fn delete_by_user(&self, user_id: Uuid) -> Result<(), RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
 
         diesel::delete(refresh_tokens::table.filter(refresh_tokens::user_id.eq(user_id)))
             .execute(&mut conn)?;
 
         Ok(())
     }
+
+    fn delete_expired(&self, before: DateTime<Utc>) -> Result<usize, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        diesel::delete(refresh_tokens::table.filter(refresh_tokens::expires_at.lt(before)))
+            .execute(&mut conn)
+            .map_err(Into::into)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::db::connection::init_test_pool;
     use crate::db::models::user::NewUser;
-    use crate::db::repositories::user_repository::UserRepository;
+    use crate::db::store::postgres_test_repositories;
 
     fn create_test_user() -> Uuid {
-        init_test_pool();
-
+        let username = format!("testuser_{}", Uuid::new_v4());
         let new_user = NewUser {
             email: format!("test_{}@example.com", Uuid::new_v4()),
-            username: format!("testuser_{}", Uuid::new_v4()),
+            username_canonical: username.clone(),
+            username,
             password_hash: Some("test_hash".to_string()),
+            password_pepper_version: None,
         };
 
-        let user = UserRepository::create(&new_user).expect("Failed to create test user");
+        let user = postgres_test_repositories()
+            .users
+            .create(&new_user)
+            .expect("Failed to create test user");
         user.id
     }
 
@@ -73,6 +106,7 @@ mod tests {
             user_id,
             token_hash: format!("test_hash_{}", Uuid::new_v4()),
             expires_at: Utc::now() + chrono::Duration::days(7),
+            risk_flagged: false,
         }
     }
 
@@ -86,7 +120,9 @@ mod tests {
         let new_token = create_test_refresh_token(user_id);
 
         // Act
-        let result = RefreshTokenRepository::create(&new_token);
+        let result = postgres_test_repositories()
+            .refresh_tokens
+            .create(&new_token);
 
         // Assert
         let created = result.expect("Should create refresh token");
@@ -94,8 +130,10 @@ mod tests {
         assert_eq!(created.token_hash, new_token.token_hash);
 
         // Cleanup
-        let _ = RefreshTokenRepository::delete(created.id);
-        let _ = UserRepository::delete(user_id);
+        let _ = postgres_test_repositories()
+            .refresh_tokens
+            .delete(created.id);
+        let _ = postgres_test_repositories().users.delete(user_id);
     }
 
     // ============================================
@@ -108,10 +146,15 @@ mod tests {
         let new_token = create_test_refresh_token(user_id);
         let hash = new_token.token_hash.clone();
 
-        let created = RefreshTokenRepository::create(&new_token).expect("Failed to create token");
+        let created = postgres_test_repositories()
+            .refresh_tokens
+            .create(&new_token)
+            .expect("Failed to create token");
 
         // Act
-        let result = RefreshTokenRepository::find_by_hash(&hash);
+        let result = postgres_test_repositories()
+            .refresh_tokens
+            .find_by_hash(&hash);
 
         // Assert
         let found = result
@@ -120,8 +163,10 @@ mod tests {
         assert_eq!(found.id, created.id);
 
         // Cleanup
-        let _ = RefreshTokenRepository::delete(created.id);
-        let _ = UserRepository::delete(user_id);
+        let _ = postgres_test_repositories()
+            .refresh_tokens
+            .delete(created.id);
+        let _ = postgres_test_repositories().users.delete(user_id);
     }
 
     // ============================================
@@ -130,7 +175,9 @@ mod tests {
     #[test]
     fn find_by_hash_returns_none_when_not_found() {
         // Act
-        let result = RefreshTokenRepository::find_by_hash("nonexistent_hash_12345");
+        let result = postgres_test_repositories()
+            .refresh_tokens
+            .find_by_hash("nonexistent_hash_12345");
 
         // Assert
         let found = result.expect("Query should succeed");
@@ -148,21 +195,28 @@ mod tests {
             user_id,
             token_hash: format!("expired_hash_{}", Uuid::new_v4()),
             expires_at: Utc::now() - chrono::Duration::hours(1), // ← Expiré
+            risk_flagged: false,
         };
 
-        let created =
-            RefreshTokenRepository::create(&expired_token).expect("Failed to create token");
+        let created = postgres_test_repositories()
+            .refresh_tokens
+            .create(&expired_token)
+            .expect("Failed to create token");
 
         // Act
-        let result = RefreshTokenRepository::find_by_hash(&expired_token.token_hash);
+        let result = postgres_test_repositories()
+            .refresh_tokens
+            .find_by_hash(&expired_token.token_hash);
 
         // Assert
         let found = result.expect("Query should succeed");
         assert!(found.is_none(), "Expired token should not be found");
 
         // Cleanup
-        let _ = RefreshTokenRepository::delete(created.id);
-        let _ = UserRepository::delete(user_id);
+        let _ = postgres_test_repositories()
+            .refresh_tokens
+            .delete(created.id);
+        let _ = postgres_test_repositories().users.delete(user_id);
     }
 
     // ============================================
@@ -174,27 +228,34 @@ mod tests {
         let user_id = create_test_user(); // ← Créer le user
         let new_token = create_test_refresh_token(user_id);
 
-        let created = RefreshTokenRepository::create(&new_token).expect("Failed to create token");
+        let created = postgres_test_repositories()
+            .refresh_tokens
+            .create(&new_token)
+            .expect("Failed to create token");
         let token_id = created.id;
 
         // Vérifier qu'il existe
-        let before = RefreshTokenRepository::find_by_hash(&new_token.token_hash)
+        let before = postgres_test_repositories()
+            .refresh_tokens
+            .find_by_hash(&new_token.token_hash)
             .expect("Failed to query")
             .expect("Token should exist");
         assert_eq!(before.id, token_id);
 
         // Act
-        let result = RefreshTokenRepository::delete(token_id);
+        let result = postgres_test_repositories().refresh_tokens.delete(token_id);
 
         // Assert
         assert!(result.is_ok(), "Should delete successfully");
 
         // Vérifier qu'il n'existe plus
-        let after =
-            RefreshTokenRepository::find_by_hash(&new_token.token_hash).expect("Failed to query");
+        let after = postgres_test_repositories()
+            .refresh_tokens
+            .find_by_hash(&new_token.token_hash)
+            .expect("Failed to query");
         assert!(after.is_none(), "Token should be deleted");
 
         // Cleanup
-        let _ = UserRepository::delete(user_id);
+        let _ = postgres_test_repositories().users.delete(user_id);
     }
 }
@@ -0,0 +1,114 @@
+use crate::db::DbPool;
+use crate::db::connection::get_connection;
+use crate::db::error::RepositoryError;
+use crate::db::models::user_identity::{NewUserIdentity, UserIdentity};
+use crate::db::schema::user_identities;
+use crate::db::store::UserIdentityStore;
+use diesel::prelude::*;
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct UserIdentityRepository {
+    pool: DbPool,
+}
+
+impl UserIdentityRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl UserIdentityStore for UserIdentityRepository {
+    /// Links an external identity to a user
+    fn create(&self, new_identity: &NewUserIdentity) -> Result<UserIdentity, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        diesel::insert_into(user_identities::table)
+            .values(new_identity)
+            .get_result::<UserIdentity>(&mut conn)
+            .map_err(Into::into)
+    }
+
+    /// Returns all external identities linked to a user
+    fn find_by_user(&self, user_id: Uuid) -> Result<Vec<UserIdentity>, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        user_identities::table
+            .filter(user_identities::user_id.eq(user_id))
+            .order_by(user_identities::created_at.asc())
+            .load::<UserIdentity>(&mut conn)
+            .map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::user::NewUser;
+    use crate::db::store::postgres_test_repositories;
+
+    fn create_test_user() -> Uuid {
+        let username = format!("identity_{}", &Uuid::new_v4().simple().to_string()[..12]);
+        let new_user = NewUser {
+            email: format!("identity_{}@example.com", Uuid::new_v4()),
+            username_canonical: username.clone(),
+            username,
+            password_hash: None,
+            password_pepper_version: None,
+        };
+
+        postgres_test_repositories()
+            .users
+            .create(&new_user)
+            .expect("Failed to create test user")
+            .id
+    }
+
+    #[test]
+    fn find_by_user_returns_linked_identities() {
+        let user_id = create_test_user();
+        let identity = NewUserIdentity {
+            user_id,
+            provider: "github".to_string(),
+            provider_user_id: Uuid::new_v4().to_string(),
+            email: None,
+        };
+        postgres_test_repositories()
+            .identities
+            .create(&identity)
+            .expect("Should create identity");
+
+        let found = postgres_test_repositories()
+            .identities
+            .find_by_user(user_id)
+            .expect("Query should succeed");
+
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].provider, "github");
+
+        let _ = postgres_test_repositories().users.delete(user_id);
+    }
+
+    #[test]
+    fn create_fails_when_provider_identity_already_linked() {
+        let user_id = create_test_user();
+        let identity = NewUserIdentity {
+            user_id,
+            provider: "google".to_string(),
+            provider_user_id: Uuid::new_v4().to_string(),
+            email: None,
+        };
+        postgres_test_repositories()
+            .identities
+            .create(&identity)
+            .expect("Should create identity");
+
+        let result = postgres_test_repositories().identities.create(&identity);
+        assert!(matches!(
+            result,
+            Err(RepositoryError::UniqueViolation { .. })
+        ));
+
+        let _ = postgres_test_repositories().users.delete(user_id);
+    }
+}
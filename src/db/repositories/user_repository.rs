@@ -1,27 +1,54 @@
+use crate::db::DbPool;
 use crate::db::connection::get_connection;
 use crate::db::error::RepositoryError;
-use crate::db::models::user::{NewUser, UpdateUser, User};
-use crate::db::schema::users;
+use crate::db::models::user::{LockoutState, NewUser, UpdateUser, User};
+use crate::db::schema::{audit_events, login_attempts, password_history, refresh_tokens, users};
+use crate::db::store::UserStore;
+use chrono::{DateTime, Utc};
 use diesel::prelude::*;
+use diesel::sql_types::{Bool, Text};
 use uuid::Uuid;
 
-pub struct UserRepository;
+define_sql_function!(fn lower(x: Text) -> Text);
+define_sql_function!(fn set_config(name: Text, value: Text, is_local: Bool) -> Text);
+
+#[derive(Clone)]
+pub struct UserRepository {
+    pool: DbPool,
+}
 
 impl UserRepository {
-    /// Finds a user by email address. Returns `None` if no match.
-    pub fn find_by_email(email: &str) -> Result<Option<User>, RepositoryError> {
-        let mut conn = get_connection()?;
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl UserStore for UserRepository {
+    /// Finds a user by email address, ignoring case. Returns `None` if no match.
+    fn find_by_email(&self, email: &str) -> Result<Option<User>, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
 
         users::table
-            .filter(users::email.eq(email))
+            .filter(lower(users::email).eq(lower(email)))
+            .first::<User>(&mut conn)
+            .optional()
+            .map_err(Into::into)
+    }
+
+    /// Finds a user by the canonical form of their username (see `UsernamePolicy`).
+    fn find_by_username_canonical(&self, canonical: &str) -> Result<Option<User>, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        users::table
+            .filter(users::username_canonical.eq(canonical))
             .first::<User>(&mut conn)
             .optional()
             .map_err(Into::into)
     }
 
     /// Trouver un utilisateur par ID
-    pub fn find_by_id(id: Uuid) -> Result<Option<User>, RepositoryError> {
-        let mut conn = get_connection()?;
+    fn find_by_id(&self, id: Uuid) -> Result<Option<User>, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
 
         users::table
             .filter(users::id.eq(id))
@@ -31,8 +58,8 @@ impl UserRepository {
     }
 
     /// Créer un nouvel utilisateur
-    pub fn create(new_user: &NewUser) -> Result<User, RepositoryError> {
-        let mut conn = get_connection()?;
+    fn create(&self, new_user: &NewUser) -> Result<User, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
 
         diesel::insert_into(users::table)
             .values(new_user)
@@ -40,30 +67,85 @@ impl UserRepository {
             .map_err(Into::into)
     }
 
-    /// Mettre à jour le dernier login
-    pub fn update_last_login(id: Uuid) -> Result<(), RepositoryError> {
-        let changes = UpdateUser {
-            last_login_at: Some(Some(chrono::Utc::now())),
-            ..Default::default()
-        };
-        Self::update(id, &changes)?;
-        Ok(())
-    }
-
-    /// Replaces the stored password hash for the given user.
-    pub fn update_password(id: Uuid, new_password_hash: &str) -> Result<(), RepositoryError> {
-        let mut conn = get_connection()?;
+    /// Replaces the stored password hash, and the version of the pepper it uses, for the given user.
+    fn update_password(
+        &self,
+        id: Uuid,
+        new_password_hash: &str,
+        pepper_version: Option<i32>,
+    ) -> Result<(), RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
 
         diesel::update(users::table.filter(users::id.eq(id)))
-            .set(users::password_hash.eq(new_password_hash))
+            .set((
+                users::password_hash.eq(new_password_hash),
+                users::password_pepper_version.eq(pepper_version),
+            ))
             .execute(&mut conn)?;
 
         Ok(())
     }
 
+    /// Sets a new password chosen by the user, atomically: the current hash moves to
+    /// `password_history` (trimmed to the `history_size` most recent), `password_changed_at`
+    /// is reset and every refresh token of the user is revoked.
+    fn change_password(
+        &self,
+        id: Uuid,
+        new_password_hash: &str,
+        pepper_version: Option<i32>,
+        history_size: i64,
+    ) -> Result<(), RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            let (current_hash, current_pepper) = users::table
+                .filter(users::id.eq(id))
+                .select((users::password_hash, users::password_pepper_version))
+                .for_update()
+                .first::<(Option<String>, Option<i32>)>(conn)?;
+
+            if let Some(current_hash) = current_hash.filter(|_| history_size > 0) {
+                diesel::insert_into(password_history::table)
+                    .values((
+                        password_history::user_id.eq(id),
+                        password_history::password_hash.eq(current_hash),
+                        password_history::password_pepper_version.eq(current_pepper),
+                    ))
+                    .execute(conn)?;
+            }
+            let kept = password_history::table
+                .filter(password_history::user_id.eq(id))
+                .order_by(password_history::created_at.desc())
+                .limit(history_size)
+                .select(password_history::id)
+                .load::<Uuid>(conn)?;
+            diesel::delete(
+                password_history::table
+                    .filter(password_history::user_id.eq(id))
+                    .filter(password_history::id.ne_all(kept)),
+            )
+            .execute(conn)?;
+
+            diesel::update(users::table.filter(users::id.eq(id)))
+                .set((
+                    users::password_hash.eq(new_password_hash),
+                    users::password_pepper_version.eq(pepper_version),
+                    users::password_changed_at.eq(Utc::now()),
+                ))
+                .execute(conn)?;
+
+            diesel::delete(refresh_tokens::table.filter(refresh_tokens::user_id.eq(id)))
+                .execute(conn)?;
+            Ok(())
+        })?;
+
+        Ok(())
+    }
+
     /// Mettre à jour un utilisateur (`email_verified`, `is_active`, `last_login_at`)
-    pub fn update(id: Uuid, changes: &UpdateUser) -> Result<User, RepositoryError> {
-        let mut conn = get_connection()?;
+    fn update(&self, id: Uuid, changes: &UpdateUser) -> Result<User, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
 
         diesel::update(users::table.filter(users::id.eq(id)))
             .set(changes)
@@ -72,24 +154,135 @@ impl UserRepository {
     }
 
     /// Supprimer un utilisateur
-    pub fn delete(id: Uuid) -> Result<(), RepositoryError> {
-        let mut conn = get_connection()?;
+    fn delete(&self, id: Uuid) -> Result<(), RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
 
         diesel::delete(users::table.filter(users::id.eq(id))).execute(&mut conn)?;
 
         Ok(())
     }
+
+    /// Returns up to `limit` users whose scheduled deletion date has passed.
+    fn find_due_for_deletion(
+        &self,
+        now: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<User>, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        users::table
+            .filter(users::deletion_scheduled_at.le(now))
+            .order_by(users::deletion_scheduled_at.asc())
+            .limit(limit)
+            .load::<User>(&mut conn)
+            .map_err(Into::into)
+    }
+
+    /// Applies `f` to the user's lockout state under a row lock, so concurrent
+    /// failed logins cannot lose updates. Returns the state written.
+    fn update_lockout(
+        &self,
+        id: Uuid,
+        f: &mut dyn FnMut(LockoutState) -> LockoutState,
+    ) -> Result<LockoutState, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            let current = users::table
+                .filter(users::id.eq(id))
+                .select(LockoutState::as_select())
+                .for_update()
+                .first::<LockoutState>(conn)?;
+            let next = f(current);
+            diesel::update(users::table.filter(users::id.eq(id)))
+                .set(&next)
+                .execute(conn)?;
+            Ok(next)
+        })
+        .map_err(Into::into)
+    }
+
+    /// Clears any lockout (successful login, admin or email unlock).
+    fn reset_lockout(&self, id: Uuid) -> Result<(), RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        let updated = diesel::update(users::table.filter(users::id.eq(id)))
+            .set(&LockoutState::default())
+            .execute(&mut conn)?;
+        if updated == 0 {
+            return Err(RepositoryError::NotFound("User not found".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Deletes a user while keeping their login history as anonymous records.
+    ///
+    /// The user agent, IP address and email hash are cleared from their login attempts and
+    /// the foreign key (`ON DELETE SET NULL`) detaches the rows from the deleted account.
+    fn purge(&self, id: Uuid) -> Result<Uuid, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+        let pseudonym = Uuid::new_v4();
+
+        conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            diesel::update(login_attempts::table.filter(login_attempts::user_id.eq(id)))
+                .set((
+                    login_attempts::user_agent.eq(None::<String>),
+                    login_attempts::ip_address.eq(None::<String>),
+                    login_attempts::email_hash.eq(None::<String>),
+                ))
+                .execute(conn)?;
+
+            // Le trigger append-only n'accepte ces mises à jour que pour le compte
+            // déclaré ici, jusqu'à la fin de la transaction
+            diesel::select(set_config("auth_manager.purged_user", id.to_string(), true))
+                .execute(conn)?;
+            diesel::update(
+                audit_events::table.filter(
+                    audit_events::actor_id
+                        .eq(id)
+                        .or(audit_events::target_id.eq(id)),
+                ),
+            )
+            .set((
+                audit_events::ip_address.eq(None::<String>),
+                audit_events::user_agent.eq(None::<String>),
+            ))
+            .execute(conn)?;
+            diesel::update(
+                audit_events::table.filter(
+                    audit_events::actor_id
+                        .eq(id)
+                        .or(audit_events::target_id.eq(id))
+                        .and(audit_events::metadata.has_key("email_hash")),
+                ),
+            )
+            .set(
+                audit_events::metadata
+                    .eq(audit_events::metadata.concat(serde_json::json!({ "email_hash": null }))),
+            )
+            .execute(conn)?;
+            diesel::update(audit_events::table.filter(audit_events::actor_id.eq(id)))
+                .set(audit_events::actor_id.eq(pseudonym))
+                .execute(conn)?;
+            diesel::update(audit_events::table.filter(audit_events::target_id.eq(id)))
+                .set(audit_events::target_id.eq(pseudonym))
+                .execute(conn)?;
+
+            diesel::delete(users::table.filter(users::id.eq(id))).execute(conn)?;
+            Ok(())
+        })?;
+
+        Ok(pseudonym)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::auth::password::PasswordManager;
-    use crate::db::connection::init_test_pool;
+    use crate::db::store::postgres_test_repositories;
 
     fn create_test_user(suffix: &str) -> NewUser {
-        init_test_pool();
-
         NewUser {
             email: format!(
                 "test_{}_{:?}@example.com",
@@ -98,6 +291,8 @@ mod tests {
             ),
             username: format!("testuser_{suffix}"),
             password_hash: Some("test_hash".to_string()),
+            password_pepper_version: None,
+            username_canonical: format!("testuser_{suffix}"),
         }
     }
 
@@ -108,64 +303,162 @@ mod tests {
         fn create_user_succeeds_with_valid_data() {
             let new_user = create_test_user("create");
 
-            let created_user = UserRepository::create(&new_user).expect("Should create user");
+            let created_user = postgres_test_repositories()
+                .users
+                .create(&new_user)
+                .expect("Should create user");
 
             assert_eq!(created_user.email, new_user.email);
             assert_eq!(created_user.username, new_user.username);
 
-            let _ = UserRepository::delete(created_user.id);
+            let _ = postgres_test_repositories().users.delete(created_user.id);
         }
 
         #[test]
         fn create_fails_when_email_already_exists() {
-            init_test_pool();
             let email = format!("duplicate_{}@example.com", Uuid::new_v4());
             let user1 = NewUser {
                 email: email.clone(),
                 username: "user1".to_string(),
                 password_hash: Some("hash".to_string()),
+                password_pepper_version: None,
+                username_canonical: "user1".to_string(),
             };
             let user2 = NewUser {
                 email,
                 username: "user2".to_string(),
                 password_hash: Some("hash".to_string()),
+                password_pepper_version: None,
+                username_canonical: "user2".to_string(),
             };
 
-            let created1 = UserRepository::create(&user1).expect("Failed to create first user");
+            let created1 = postgres_test_repositories()
+                .users
+                .create(&user1)
+                .expect("Failed to create first user");
 
-            let result = UserRepository::create(&user2);
+            let result = postgres_test_repositories().users.create(&user2);
 
             assert!(
                 result.is_err(),
                 "Should fail due to unique constraint on email"
             );
 
-            let _ = UserRepository::delete(created1.id);
+            let _ = postgres_test_repositories().users.delete(created1.id);
+        }
+
+        #[test]
+        fn create_fails_when_email_differs_only_by_case() {
+            let user1 = create_test_user("email_case_1");
+            let mut user2 = create_test_user("email_case_2");
+            user2.email = user1.email.to_uppercase();
+
+            let created1 = postgres_test_repositories()
+                .users
+                .create(&user1)
+                .expect("Failed to create first user");
+
+            let result = postgres_test_repositories().users.create(&user2);
+
+            assert!(
+                matches!(result, Err(RepositoryError::UniqueViolation { .. })),
+                "Should fail due to case-insensitive unique index on email"
+            );
+
+            let _ = postgres_test_repositories().users.delete(created1.id);
+        }
+
+        #[test]
+        fn create_fails_when_username_canonical_already_exists() {
+            let user1 = create_test_user("canonical_1");
+            let mut user2 = create_test_user("canonical_2");
+            user2.username_canonical = user1.username_canonical.clone();
+
+            let created1 = postgres_test_repositories()
+                .users
+                .create(&user1)
+                .expect("Failed to create first user");
+
+            let result = postgres_test_repositories().users.create(&user2);
+
+            assert!(
+                matches!(result, Err(RepositoryError::UniqueViolation { .. })),
+                "Should fail due to unique index on username_canonical"
+            );
+
+            let _ = postgres_test_repositories().users.delete(created1.id);
+        }
+    }
+
+    mod find_by_username_canonical {
+        use super::*;
+
+        #[test]
+        fn find_by_username_canonical_returns_user_when_exists() {
+            let new_user = create_test_user("find_canonical");
+            let created = postgres_test_repositories()
+                .users
+                .create(&new_user)
+                .expect("Failed to create user");
+
+            let found = postgres_test_repositories()
+                .users
+                .find_by_username_canonical(&new_user.username_canonical)
+                .expect("Query should succeed")
+                .expect("User should exist");
+
+            assert_eq!(found.id, created.id);
+
+            let _ = postgres_test_repositories().users.delete(created.id);
         }
     }
 
     mod find_by_email {
         use super::*;
 
+        #[test]
+        fn find_by_email_ignores_case() {
+            let new_user = create_test_user("find_email_case");
+            let created = postgres_test_repositories()
+                .users
+                .create(&new_user)
+                .expect("Failed to create user");
+
+            let found = postgres_test_repositories()
+                .users
+                .find_by_email(&new_user.email.to_uppercase())
+                .expect("Query should succeed")
+                .expect("User should exist");
+
+            assert_eq!(found.id, created.id);
+
+            let _ = postgres_test_repositories().users.delete(created.id);
+        }
+
         #[test]
         fn find_by_email_returns_user_when_exists() {
             let new_user = create_test_user("find_email");
-            let created = UserRepository::create(&new_user).expect("Failed to create user");
-
-            let found = UserRepository::find_by_email(&new_user.email)
+            let created = postgres_test_repositories()
+                .users
+                .create(&new_user)
+                .expect("Failed to create user");
+
+            let found = postgres_test_repositories()
+                .users
+                .find_by_email(&new_user.email)
                 .expect("Query should succeed")
                 .expect("User should exist");
 
             assert_eq!(found.id, created.id);
 
-            let _ = UserRepository::delete(created.id);
+            let _ = postgres_test_repositories().users.delete(created.id);
         }
 
         #[test]
         fn find_by_email_returns_none_when_not_found() {
-            init_test_pool();
-
-            let found = UserRepository::find_by_email("nonexistent_email_12345@example.com")
+            let found = postgres_test_repositories()
+                .users
+                .find_by_email("nonexistent_email_12345@example.com")
                 .expect("Query should succeed even if user not found");
 
             assert!(found.is_none(), "User should not exist");
@@ -178,38 +471,198 @@ mod tests {
         #[test]
         fn find_by_id_returns_user_when_exists() {
             let new_user = create_test_user("find_id");
-            let created = UserRepository::create(&new_user).expect("Failed to create user");
-
-            let found = UserRepository::find_by_id(created.id)
+            let created = postgres_test_repositories()
+                .users
+                .create(&new_user)
+                .expect("Failed to create user");
+
+            let found = postgres_test_repositories()
+                .users
+                .find_by_id(created.id)
                 .expect("Query should succeed")
                 .expect("User should exist");
 
             assert_eq!(found.id, created.id);
 
-            let _ = UserRepository::delete(created.id);
+            let _ = postgres_test_repositories().users.delete(created.id);
         }
 
         #[test]
         fn find_by_id_returns_none_when_not_found() {
-            init_test_pool();
-
-            let found = UserRepository::find_by_id(Uuid::new_v4())
+            let found = postgres_test_repositories()
+                .users
+                .find_by_id(Uuid::new_v4())
                 .expect("Query should succeed even if user not found");
 
             assert!(found.is_none(), "User should not exist");
         }
     }
 
+    mod deletion {
+        use super::*;
+        use crate::db::models::login_attempt::NewLoginAttempt;
+
+        #[test]
+        fn schedule_and_cancel_deletion_round_trip() {
+            let new_user = create_test_user("schedule_deletion");
+            let created = postgres_test_repositories()
+                .users
+                .create(&new_user)
+                .expect("Failed to create user");
+            let at = Utc::now() + chrono::Duration::days(14);
+
+            let scheduled = postgres_test_repositories()
+                .users
+                .schedule_deletion(created.id, at)
+                .expect("Should schedule");
+            assert!(scheduled.deletion_scheduled_at.is_some());
+
+            postgres_test_repositories()
+                .users
+                .cancel_deletion(created.id)
+                .expect("Should cancel");
+            let after = postgres_test_repositories()
+                .users
+                .find_by_id(created.id)
+                .expect("Query should succeed")
+                .expect("User should exist");
+            assert!(after.deletion_scheduled_at.is_none());
+
+            let _ = postgres_test_repositories().users.delete(created.id);
+        }
+
+        #[test]
+        fn find_due_for_deletion_only_returns_past_dates() {
+            let due = postgres_test_repositories()
+                .users
+                .create(&create_test_user("due_deletion"))
+                .expect("create");
+            let later = postgres_test_repositories()
+                .users
+                .create(&create_test_user("later_deletion"))
+                .expect("create");
+            postgres_test_repositories()
+                .users
+                .schedule_deletion(due.id, Utc::now() - chrono::Duration::minutes(1))
+                .expect("schedule");
+            postgres_test_repositories()
+                .users
+                .schedule_deletion(later.id, Utc::now() + chrono::Duration::days(1))
+                .expect("schedule");
+
+            let found = postgres_test_repositories()
+                .users
+                .find_due_for_deletion(Utc::now(), 1000)
+                .expect("Query should succeed");
+            let ids: Vec<Uuid> = found.iter().map(|u| u.id).collect();
+            assert!(ids.contains(&due.id));
+            assert!(!ids.contains(&later.id));
+
+            let _ = postgres_test_repositories().users.delete(due.id);
+            let _ = postgres_test_repositories().users.delete(later.id);
+        }
+
+        #[test]
+        fn purge_deletes_user_and_anonymizes_login_history() {
+            let created = postgres_test_repositories()
+                .users
+                .create(&create_test_user("purge"))
+                .expect("create");
+            let attempt = postgres_test_repositories()
+                .login_attempts
+                .create(&NewLoginAttempt {
+                    user_id: Some(created.id),
+                    success: true,
+                    user_agent: Some("UA".to_string()),
+                    ip_address: Some("203.0.113.7".to_string()),
+                    ..NewLoginAttempt::default()
+                })
+                .expect("log attempt");
+
+            postgres_test_repositories()
+                .users
+                .purge(created.id)
+                .expect("Should purge");
+
+            assert!(
+                postgres_test_repositories()
+                    .users
+                    .find_by_id(created.id)
+                    .expect("Query should succeed")
+                    .is_none()
+            );
+            let kept = postgres_test_repositories()
+                .login_attempts
+                .find_by_id(attempt.id)
+                .expect("Query should succeed")
+                .expect("Attempt should be kept");
+            assert!(kept.user_id.is_none());
+            assert!(kept.user_agent.is_none());
+            assert!(kept.ip_address.is_none());
+        }
+
+        #[test]
+        fn audit_log_only_accepts_the_purge_anonymisation() {
+            use crate::db::models::audit_event::NewAuditEvent;
+
+            let repos = postgres_test_repositories();
+            let purged = repos
+                .users
+                .create(&create_test_user("audit_purged"))
+                .expect("create");
+            let other = repos
+                .users
+                .create(&create_test_user("audit_other"))
+                .expect("create");
+            let event = repos
+                .audit_events
+                .create(&NewAuditEvent {
+                    event_type: "purge_test".to_string(),
+                    actor_id: Some(other.id),
+                    ip_address: Some("203.0.113.7".to_string()),
+                    ..NewAuditEvent::default()
+                })
+                .expect("record");
+            let mut conn = get_connection(&crate::db::connection::test_pool()).expect("conn");
+            let clear_ip = |conn: &mut PgConnection| {
+                diesel::update(audit_events::table.find(event.id))
+                    .set(audit_events::ip_address.eq(None::<String>))
+                    .execute(conn)
+            };
+
+            assert!(clear_ip(&mut conn).is_err());
+            // Le compte déclaré par la purge ne couvre pas les événements des autres
+            let declared = conn.transaction::<_, diesel::result::Error, _>(|conn| {
+                diesel::select(set_config(
+                    "auth_manager.purged_user",
+                    purged.id.to_string(),
+                    true,
+                ))
+                .execute(conn)?;
+                clear_ip(conn)
+            });
+            assert!(declared.is_err());
+
+            repos.users.purge(purged.id).expect("Should purge");
+            let _ = repos.users.delete(other.id);
+        }
+    }
+
     mod update {
         use super::*;
 
         #[test]
         fn update_last_login_sets_timestamp_in_db() {
             let new_user = create_test_user("login");
-            let created = UserRepository::create(&new_user).expect("Failed to create user");
+            let created = postgres_test_repositories()
+                .users
+                .create(&new_user)
+                .expect("Failed to create user");
             let user_id = created.id;
 
-            let before = UserRepository::find_by_id(user_id)
+            let before = postgres_test_repositories()
+                .users
+                .find_by_id(user_id)
                 .expect("Query should succeed")
                 .expect("User should exist");
             assert!(
@@ -217,9 +670,14 @@ mod tests {
                 "last_login_at should be None initially"
             );
 
-            UserRepository::update_last_login(user_id).expect("Should update last_login");
+            postgres_test_repositories()
+                .users
+                .update_last_login(user_id)
+                .expect("Should update last_login");
 
-            let after = UserRepository::find_by_id(user_id)
+            let after = postgres_test_repositories()
+                .users
+                .find_by_id(user_id)
                 .expect("Query should succeed")
                 .expect("User should exist");
             assert!(
@@ -227,32 +685,97 @@ mod tests {
                 "last_login_at should be set after update"
             );
 
-            let _ = UserRepository::delete(user_id);
+            let _ = postgres_test_repositories().users.delete(user_id);
         }
 
         #[test]
         fn update_password_stores_new_hash_in_db() {
-            init_test_pool();
             let new_user = NewUser {
                 email: format!("update_pw_{}@example.com", Uuid::new_v4()),
                 username: "update_pw_user".to_string(),
-                password_hash: Some(PasswordManager::hash("OldPass123!").expect("hash")),
+                password_hash: Some(PasswordManager::hash("OldPass123!").expect("hash").hash),
+                password_pepper_version: None,
+                username_canonical: "update_pw_user".to_string(),
             };
 
-            let created = UserRepository::create(&new_user).expect("Failed to create user");
+            let created = postgres_test_repositories()
+                .users
+                .create(&new_user)
+                .expect("Failed to create user");
             let user_id = created.id;
 
             let new_hash = PasswordManager::hash("NewPass456!").expect("hash");
-            UserRepository::update_password(user_id, &new_hash).expect("Should update password");
-
-            let updated = UserRepository::find_by_id(user_id)
+            postgres_test_repositories()
+                .users
+                .update_password(user_id, &new_hash.hash, new_hash.pepper_version)
+                .expect("Should update password");
+
+            let updated = postgres_test_repositories()
+                .users
+                .find_by_id(user_id)
                 .expect("Should find user")
                 .expect("User should exist");
             let hash = updated.password_hash.as_ref().expect("hash present");
-            let ok = PasswordManager::verify("NewPass456!", hash).expect("verify");
+            let ok = PasswordManager::verify("NewPass456!", hash, updated.password_pepper_version)
+                .expect("verify");
             assert!(ok, "New password should verify");
 
-            let _ = UserRepository::delete(user_id);
+            let _ = postgres_test_repositories().users.delete(user_id);
+        }
+    }
+
+    mod lockout {
+        use super::*;
+
+        #[test]
+        fn update_lockout_persists_state_and_reset_clears_it() {
+            let new_user = create_test_user(&Uuid::new_v4().simple().to_string()[..16]);
+            let created = postgres_test_repositories()
+                .users
+                .create(&new_user)
+                .expect("Failed to create user");
+            let until = chrono::Utc::now() + chrono::Duration::minutes(15);
+
+            let written = postgres_test_repositories()
+                .users
+                .update_lockout(created.id, &mut |state| LockoutState {
+                    failed_login_count: state.failed_login_count + 3,
+                    locked_until: Some(until),
+                    ..state
+                })
+                .expect("Update should succeed");
+            assert_eq!(written.failed_login_count, 3);
+
+            let found = postgres_test_repositories()
+                .users
+                .find_by_id(created.id)
+                .expect("Query should succeed")
+                .expect("User should exist");
+            assert_eq!(found.failed_login_count, 3);
+            assert!(found.locked_until.is_some());
+
+            postgres_test_repositories()
+                .users
+                .reset_lockout(created.id)
+                .expect("Reset should succeed");
+            let found = postgres_test_repositories()
+                .users
+                .find_by_id(created.id)
+                .expect("Query should succeed")
+                .expect("User should exist");
+            assert_eq!(found.lockout_state(), LockoutState::default());
+
+            let _ = postgres_test_repositories().users.delete(created.id);
+        }
+
+        #[test]
+        fn reset_lockout_fails_for_unknown_user() {
+            assert!(matches!(
+                postgres_test_repositories()
+                    .users
+                    .reset_lockout(Uuid::new_v4()),
+                Err(RepositoryError::NotFound(_))
+            ));
         }
     }
 }
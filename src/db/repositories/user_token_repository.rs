@@ -0,0 +1,135 @@
+use crate::db::DbPool;
+use crate::db::connection::get_connection;
+use crate::db::error::RepositoryError;
+use crate::db::models::user_token::{NewUserToken, TokenPurpose, UserToken};
+use crate::db::schema::user_tokens;
+use crate::db::store::UserTokenStore;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+
+#[derive(Clone)]
+pub struct UserTokenRepository {
+    pool: DbPool,
+}
+
+impl UserTokenRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl UserTokenStore for UserTokenRepository {
+    /// Enregistre un jeton (seul son hash est stocké)
+    fn create(&self, new_token: &NewUserToken) -> Result<UserToken, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        diesel::insert_into(user_tokens::table)
+            .values(new_token)
+            .get_result::<UserToken>(&mut conn)
+            .map_err(Into::into)
+    }
+
+    /// Consomme atomiquement un jeton valide : non utilisé, non expiré, du bon type.
+    /// Retourne `None` sinon, sans distinguer les cas.
+    fn consume(
+        &self,
+        token_hash: &str,
+        purpose: TokenPurpose,
+        now: DateTime<Utc>,
+    ) -> Result<Option<UserToken>, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        diesel::update(
+            user_tokens::table
+                .filter(user_tokens::token_hash.eq(token_hash))
+                .filter(user_tokens::purpose.eq(purpose.as_str()))
+                .filter(user_tokens::used_at.is_null())
+                .filter(user_tokens::expires_at.gt(now)),
+        )
+        .set(user_tokens::used_at.eq(now))
+        .get_result::<UserToken>(&mut conn)
+        .optional()
+        .map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::password::PasswordManager;
+    use crate::db::models::user::NewUser;
+    use crate::db::store::postgres_test_repositories;
+    use chrono::Duration;
+
+    fn create_user() -> crate::db::models::user::User {
+        let username = format!("tok_{}", &uuid::Uuid::new_v4().simple().to_string()[..16]);
+        postgres_test_repositories()
+            .users
+            .create(&NewUser {
+                email: format!("{username}@example.com"),
+                username_canonical: username.clone(),
+                username,
+                password_hash: Some(PasswordManager::hash("TokenPass123!").expect("hash").hash),
+                password_pepper_version: None,
+            })
+            .expect("create user")
+    }
+
+    fn new_token(user_id: uuid::Uuid, expires_at: DateTime<Utc>) -> NewUserToken {
+        NewUserToken {
+            user_id,
+            purpose: TokenPurpose::AccountUnlock.as_str().to_string(),
+            token_hash: format!("{:0>64}", uuid::Uuid::new_v4().simple()),
+            expires_at,
+        }
+    }
+
+    #[test]
+    fn consume_succeeds_once() {
+        let user = create_user();
+        let token = new_token(user.id, Utc::now() + Duration::hours(1));
+        postgres_test_repositories()
+            .user_tokens
+            .create(&token)
+            .expect("create token");
+
+        let now = Utc::now();
+        let consumed = postgres_test_repositories()
+            .user_tokens
+            .consume(&token.token_hash, TokenPurpose::AccountUnlock, now)
+            .expect("query")
+            .expect("token should be valid");
+        assert_eq!(consumed.user_id, user.id);
+        assert!(consumed.used_at.is_some());
+
+        assert!(
+            postgres_test_repositories()
+                .user_tokens
+                .consume(&token.token_hash, TokenPurpose::AccountUnlock, now)
+                .expect("query")
+                .is_none()
+        );
+
+        let _ = postgres_test_repositories().users.delete(user.id);
+    }
+
+    #[test]
+    fn consume_rejects_expired_token() {
+        let user = create_user();
+        let token = new_token(user.id, Utc::now() - Duration::minutes(1));
+        postgres_test_repositories()
+            .user_tokens
+            .create(&token)
+            .expect("create token");
+
+        assert!(
+            postgres_test_repositories()
+                .user_tokens
+                .consume(&token.token_hash, TokenPurpose::AccountUnlock, Utc::now())
+                .expect("query")
+                .is_none()
+        );
+
+        let _ = postgres_test_repositories().users.delete(user.id);
+    }
+}
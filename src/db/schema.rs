@@ -1,5 +1,39 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    audit_events (id) {
+        id -> Uuid,
+        occurred_at -> Timestamptz,
+        #[max_length = 64]
+        event_type -> Varchar,
+        actor_id -> Nullable<Uuid>,
+        target_id -> Nullable<Uuid>,
+        #[max_length = 45]
+        ip_address -> Nullable<Varchar>,
+        user_agent -> Nullable<Text>,
+        #[max_length = 128]
+        request_id -> Nullable<Varchar>,
+        metadata -> Jsonb,
+    }
+}
+
+diesel::table! {
+    known_devices (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        #[max_length = 64]
+        fingerprint -> Varchar,
+        #[max_length = 64]
+        browser -> Nullable<Varchar>,
+        #[max_length = 64]
+        os -> Nullable<Varchar>,
+        #[max_length = 64]
+        ip_prefix -> Nullable<Varchar>,
+        first_seen_at -> Timestamptz,
+        last_seen_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     login_attempts (id) {
         id -> Uuid,
@@ -7,6 +41,34 @@ diesel::table! {
         success -> Bool,
         attempted_at -> Timestamptz,
         user_agent -> Nullable<Text>,
+        #[max_length = 45]
+        ip_address -> Nullable<Varchar>,
+        #[max_length = 64]
+        email_hash -> Nullable<Varchar>,
+        #[max_length = 32]
+        failure_reason -> Nullable<Varchar>,
+        #[max_length = 128]
+        request_id -> Nullable<Varchar>,
+    }
+}
+
+diesel::table! {
+    password_history (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        #[max_length = 255]
+        password_hash -> Varchar,
+        password_pepper_version -> Nullable<Int4>,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    rate_limit_buckets (key) {
+        #[max_length = 255]
+        key -> Varchar,
+        window_start -> Timestamptz,
+        hits -> Int4,
     }
 }
 
@@ -19,6 +81,7 @@ diesel::table! {
         expires_at -> Timestamptz,
         created_at -> Timestamptz,
         updated_at -> Timestamptz,
+        risk_flagged -> Bool,
     }
 }
 
@@ -36,6 +99,20 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    user_tokens (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        #[max_length = 32]
+        purpose -> Varchar,
+        #[max_length = 64]
+        token_hash -> Varchar,
+        expires_at -> Timestamptz,
+        used_at -> Nullable<Timestamptz>,
+        created_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     users (id) {
         id -> Uuid,
@@ -51,16 +128,34 @@ diesel::table! {
         created_at -> Timestamptz,
         updated_at -> Timestamptz,
         last_login_at -> Nullable<Timestamptz>,
+        #[max_length = 255]
+        username_canonical -> Varchar,
+        deletion_scheduled_at -> Nullable<Timestamptz>,
+        failed_login_count -> Int4,
+        lockout_level -> Int4,
+        locked_until -> Nullable<Timestamptz>,
+        locked_permanently -> Bool,
+        last_failed_login_at -> Nullable<Timestamptz>,
+        password_pepper_version -> Nullable<Int4>,
+        password_changed_at -> Nullable<Timestamptz>,
     }
 }
 
+diesel::joinable!(known_devices -> users (user_id));
 diesel::joinable!(login_attempts -> users (user_id));
+diesel::joinable!(password_history -> users (user_id));
 diesel::joinable!(refresh_tokens -> users (user_id));
 diesel::joinable!(user_identities -> users (user_id));
+diesel::joinable!(user_tokens -> users (user_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    audit_events,
+    known_devices,
     login_attempts,
+    password_history,
+    rate_limit_buckets,
     refresh_tokens,
     user_identities,
+    user_tokens,
     users,
 );
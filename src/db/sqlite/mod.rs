@@ -0,0 +1,113 @@
+//! `SQLite` storage (`STORAGE=sqlite`, `--features sqlite`) for small self-hosted
+//! deployments.
+//!
+//! Same tables and constraints as Postgres, with its own migrations in
+//! `migrations-sqlite/`. UUIDs are stored as hyphenated text and timestamps as UTC
+//! text in a single format, so that they compare correctly as strings.
+
+mod repositories;
+pub mod schema;
+
+use anyhow::{Context, Result};
+use diesel::connection::SimpleConnection;
+use diesel::deserialize::{self, FromSql};
+use diesel::expression::AsExpression;
+use diesel::query_builder::QueryId;
+use diesel::r2d2::{self, ConnectionManager, CustomizeConnection};
+use diesel::serialize::{self, IsNull, Output, ToSql};
+use diesel::sql_types::{SqlType, Text};
+use diesel::sqlite::{Sqlite, SqliteConnection};
+use uuid::Uuid;
+
+use crate::db::error::RepositoryError;
+
+pub use repositories::SqliteStore;
+
+pub type SqlitePool = r2d2::Pool<ConnectionManager<SqliteConnection>>;
+pub type SqlitePooledConnection = r2d2::PooledConnection<ConnectionManager<SqliteConnection>>;
+
+/// Maximum number of pooled connections (`SQLite` serialises writers anyway).
+pub const POOL_MAX_SIZE: u32 = 4;
+
+/// How long a connection waits for the write lock before failing with "database is locked".
+const BUSY_TIMEOUT_MS: u32 = 5_000;
+
+/// SQL type of UUID columns: the hyphenated text form.
+#[derive(SqlType, QueryId)]
+#[diesel(sqlite_type(name = "Text"))]
+pub struct UuidText;
+
+/// A UUID bound to a [`UuidText`] column (values are read back as plain [`Uuid`]).
+#[derive(AsExpression, Debug, Clone, Copy)]
+#[diesel(sql_type = UuidText)]
+pub struct Id(pub Uuid);
+
+impl ToSql<UuidText, Sqlite> for Id {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Sqlite>) -> serialize::Result {
+        out.set_value(self.0.hyphenated().to_string());
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<UuidText, Sqlite> for Uuid {
+    fn from_sql(
+        value: <Sqlite as diesel::backend::Backend>::RawValue<'_>,
+    ) -> deserialize::Result<Self> {
+        let text = <String as FromSql<Text, Sqlite>>::from_sql(value)?;
+        Ok(Uuid::parse_str(&text)?)
+    }
+}
+
+/// Per-connection settings: `SQLite` leaves foreign keys off unless asked.
+#[derive(Debug)]
+struct ConnectionOptions;
+
+impl CustomizeConnection<SqliteConnection, r2d2::Error> for ConnectionOptions {
+    fn on_acquire(&self, conn: &mut SqliteConnection) -> Result<(), r2d2::Error> {
+        conn.batch_execute(&format!(
+            "PRAGMA foreign_keys = ON; \
+             PRAGMA busy_timeout = {BUSY_TIMEOUT_MS}; \
+             PRAGMA journal_mode = WAL; \
+             PRAGMA synchronous = NORMAL;"
+        ))
+        .map_err(r2d2::Error::QueryError)
+    }
+}
+
+/// Builds the `SQLite` connection pool for `database_url` (a file path or `file:` URI).
+pub fn build_pool(database_url: &str) -> Result<SqlitePool> {
+    let manager = ConnectionManager::<SqliteConnection>::new(database_url);
+    r2d2::Pool::builder()
+        .max_size(POOL_MAX_SIZE)
+        .connection_customizer(Box::new(ConnectionOptions))
+        .build(manager)
+        .context("Failed to build SQLite pool")
+}
+
+/// Get a connection from the pool.
+pub fn get_connection(pool: &SqlitePool) -> Result<SqlitePooledConnection, RepositoryError> {
+    pool.get()
+        .map_err(|e| RepositoryError::PoolError(e.to_string()))
+}
+
+/// Fresh in-memory database with `migrations-sqlite/` applied, private to the caller.
+///
+/// # Panics
+///
+/// Panics if the database cannot be created or migrated.
+#[cfg(test)]
+pub fn test_pool() -> SqlitePool {
+    // Cache partagé : toutes les connexions du pool voient la même base en mémoire
+    let url = format!("file:auth-test-{}?mode=memory&cache=shared", Uuid::new_v4());
+    let pool = build_pool(&url).expect("Failed to build SQLite test pool");
+    crate::db::migrations::migrate_sqlite(&pool).expect("Failed to migrate SQLite test database");
+    pool
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::store::{Repositories, store_conformance_tests};
+
+    store_conformance_tests!(Repositories::sqlite(&test_pool()));
+}
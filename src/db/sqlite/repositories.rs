@@ -0,0 +1,933 @@
+//! Every store trait implemented on one `SQLite` pool.
+//!
+//! Row locks (`SELECT ... FOR UPDATE` on Postgres) become `BEGIN IMMEDIATE`
+//! transactions, which take the database write lock up front.
+
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel::sql_types::{Json, Nullable, Text};
+use diesel::sqlite::Sqlite;
+use uuid::Uuid;
+
+use super::schema::{
+    audit_events, audit_purged_users, known_devices, login_attempts, password_history,
+    rate_limit_buckets, refresh_tokens, user_identities, user_tokens, users,
+};
+use super::{Id, SqlitePool, get_connection};
+use crate::auth::rate_limit::RateLimitStore;
+use crate::db::error::RepositoryError;
+use crate::db::models::audit_event::{AuditEvent, NewAuditEvent};
+use crate::db::models::known_device::{KnownDevice, NewKnownDevice};
+use crate::db::models::login_attempt::{LoginAttempt, NewLoginAttempt};
+use crate::db::models::password_history::PasswordHistoryEntry;
+use crate::db::models::rate_limit_bucket::RateLimitBucket;
+use crate::db::models::refresh_token::{NewRefreshToken, RefreshToken};
+use crate::db::models::user::{LockoutState, NewUser, UpdateUser, User};
+use crate::db::models::user_identity::{NewUserIdentity, UserIdentity};
+use crate::db::models::user_token::{NewUserToken, TokenPurpose, UserToken};
+use crate::db::repositories::audit_event_repository::{AuditCursor, AuditEventFilter};
+use crate::db::repositories::login_attempt_repository::HistoryCursor;
+use crate::db::store::{
+    AuditEventStore, KnownDeviceStore, LoginAttemptStore, PasswordHistoryStore, RefreshTokenStore,
+    UserIdentityStore, UserStore, UserTokenStore,
+};
+
+define_sql_function!(fn json_type(json: Json, path: Text) -> Nullable<Text>);
+define_sql_function!(fn json_set(json: Json, path: Text, value: Nullable<Text>) -> Json);
+
+/// Columns of [`User`], in field order (everything but `email_lower`).
+type UserColumns = (
+    users::id,
+    users::email,
+    users::username,
+    users::password_hash,
+    users::email_verified,
+    users::is_active,
+    users::is_admin,
+    users::created_at,
+    users::updated_at,
+    users::last_login_at,
+    users::username_canonical,
+    users::deletion_scheduled_at,
+    users::failed_login_count,
+    users::lockout_level,
+    users::locked_until,
+    users::locked_permanently,
+    users::last_failed_login_at,
+    users::password_pepper_version,
+    users::password_changed_at,
+);
+
+const USER_COLUMNS: UserColumns = (
+    users::id,
+    users::email,
+    users::username,
+    users::password_hash,
+    users::email_verified,
+    users::is_active,
+    users::is_admin,
+    users::created_at,
+    users::updated_at,
+    users::last_login_at,
+    users::username_canonical,
+    users::deletion_scheduled_at,
+    users::failed_login_count,
+    users::lockout_level,
+    users::locked_until,
+    users::locked_permanently,
+    users::last_failed_login_at,
+    users::password_pepper_version,
+    users::password_changed_at,
+);
+
+/// `SQLite` implementation of every store, sharing one pool.
+#[derive(Clone)]
+pub struct SqliteStore {
+    pool: SqlitePool,
+}
+
+impl SqliteStore {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+fn load_attempt_page(
+    conn: &mut SqliteConnection,
+    mut query: login_attempts::BoxedQuery<'static, Sqlite>,
+    cursor: Option<HistoryCursor>,
+    limit: i64,
+) -> Result<Vec<LoginAttempt>, RepositoryError> {
+    if let Some((attempted_at, id)) = cursor {
+        query = query.filter(
+            login_attempts::attempted_at
+                .lt(attempted_at)
+                .or(login_attempts::attempted_at
+                    .eq(attempted_at)
+                    .and(login_attempts::id.lt(Id(id)))),
+        );
+    }
+
+    query
+        .order_by((
+            login_attempts::attempted_at.desc(),
+            login_attempts::id.desc(),
+        ))
+        .limit(limit)
+        .load::<LoginAttempt>(conn)
+        .map_err(Into::into)
+}
+
+impl UserStore for SqliteStore {
+    fn find_by_email(&self, email: &str) -> Result<Option<User>, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        users::table
+            .filter(users::email_lower.eq(email.to_lowercase()))
+            .select(USER_COLUMNS)
+            .first::<User>(&mut conn)
+            .optional()
+            .map_err(Into::into)
+    }
+
+    fn find_by_username_canonical(&self, canonical: &str) -> Result<Option<User>, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        users::table
+            .filter(users::username_canonical.eq(canonical))
+            .select(USER_COLUMNS)
+            .first::<User>(&mut conn)
+            .optional()
+            .map_err(Into::into)
+    }
+
+    fn find_by_id(&self, id: Uuid) -> Result<Option<User>, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        users::table
+            .filter(users::id.eq(Id(id)))
+            .select(USER_COLUMNS)
+            .first::<User>(&mut conn)
+            .optional()
+            .map_err(Into::into)
+    }
+
+    fn create(&self, new_user: &NewUser) -> Result<User, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+        let now = Utc::now();
+
+        diesel::insert_into(users::table)
+            .values((
+                users::id.eq(Id(Uuid::new_v4())),
+                users::email.eq(&new_user.email),
+                users::email_lower.eq(new_user.email.to_lowercase()),
+                users::username.eq(&new_user.username),
+                users::password_hash.eq(&new_user.password_hash),
+                users::username_canonical.eq(&new_user.username_canonical),
+                users::password_pepper_version.eq(new_user.password_pepper_version),
+                users::created_at.eq(now),
+                users::updated_at.eq(now),
+                users::password_changed_at.eq(now),
+            ))
+            .returning(USER_COLUMNS)
+            .get_result::<User>(&mut conn)
+            .map_err(Into::into)
+    }
+
+    fn update_password(
+        &self,
+        id: Uuid,
+        new_password_hash: &str,
+        pepper_version: Option<i32>,
+    ) -> Result<(), RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        diesel::update(users::table.filter(users::id.eq(Id(id))))
+            .set((
+                users::password_hash.eq(new_password_hash),
+                users::password_pepper_version.eq(pepper_version),
+                users::updated_at.eq(Utc::now()),
+            ))
+            .execute(&mut conn)?;
+
+        Ok(())
+    }
+
+    fn change_password(
+        &self,
+        id: Uuid,
+        new_password_hash: &str,
+        pepper_version: Option<i32>,
+        history_size: i64,
+    ) -> Result<(), RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        conn.immediate_transaction::<_, diesel::result::Error, _>(|conn| {
+            let now = Utc::now();
+            let (current_hash, current_pepper) = users::table
+                .filter(users::id.eq(Id(id)))
+                .select((users::password_hash, users::password_pepper_version))
+                .first::<(Option<String>, Option<i32>)>(conn)?;
+
+            if let Some(current_hash) = current_hash.filter(|_| history_size > 0) {
+                diesel::insert_into(password_history::table)
+                    .values((
+                        password_history::id.eq(Id(Uuid::new_v4())),
+                        password_history::user_id.eq(Id(id)),
+                        password_history::password_hash.eq(current_hash),
+                        password_history::password_pepper_version.eq(current_pepper),
+                        password_history::created_at.eq(now),
+                    ))
+                    .execute(conn)?;
+            }
+            let kept: Vec<Id> = password_history::table
+                .filter(password_history::user_id.eq(Id(id)))
+                .order_by((
+                    password_history::created_at.desc(),
+                    password_history::id.desc(),
+                ))
+                .limit(history_size)
+                .select(password_history::id)
+                .load::<Uuid>(conn)?
+                .into_iter()
+                .map(Id)
+                .collect();
+            diesel::delete(
+                password_history::table
+                    .filter(password_history::user_id.eq(Id(id)))
+                    .filter(password_history::id.ne_all(kept)),
+            )
+            .execute(conn)?;
+
+            diesel::update(users::table.filter(users::id.eq(Id(id))))
+                .set((
+                    users::password_hash.eq(new_password_hash),
+                    users::password_pepper_version.eq(pepper_version),
+                    users::password_changed_at.eq(now),
+                    users::updated_at.eq(now),
+                ))
+                .execute(conn)?;
+
+            diesel::delete(refresh_tokens::table.filter(refresh_tokens::user_id.eq(Id(id))))
+                .execute(conn)?;
+            Ok(())
+        })?;
+
+        Ok(())
+    }
+
+    fn update(&self, id: Uuid, changes: &UpdateUser) -> Result<User, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        diesel::update(users::table.filter(users::id.eq(Id(id))))
+            .set((
+                changes.email_verified.map(|v| users::email_verified.eq(v)),
+                changes.is_active.map(|v| users::is_active.eq(v)),
+                changes.is_admin.map(|v| users::is_admin.eq(v)),
+                changes.last_login_at.map(|v| users::last_login_at.eq(v)),
+                changes
+                    .deletion_scheduled_at
+                    .map(|v| users::deletion_scheduled_at.eq(v)),
+                users::updated_at.eq(Utc::now()),
+            ))
+            .returning(USER_COLUMNS)
+            .get_result::<User>(&mut conn)
+            .map_err(Into::into)
+    }
+
+    fn delete(&self, id: Uuid) -> Result<(), RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        diesel::delete(users::table.filter(users::id.eq(Id(id)))).execute(&mut conn)?;
+
+        Ok(())
+    }
+
+    fn find_due_for_deletion(
+        &self,
+        now: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<User>, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        users::table
+            .filter(users::deletion_scheduled_at.le(now))
+            .order_by(users::deletion_scheduled_at.asc())
+            .limit(limit)
+            .select(USER_COLUMNS)
+            .load::<User>(&mut conn)
+            .map_err(Into::into)
+    }
+
+    fn update_lockout(
+        &self,
+        id: Uuid,
+        f: &mut dyn FnMut(LockoutState) -> LockoutState,
+    ) -> Result<LockoutState, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        conn.immediate_transaction::<_, diesel::result::Error, _>(|conn| {
+            let current = users::table
+                .filter(users::id.eq(Id(id)))
+                .select((
+                    users::failed_login_count,
+                    users::lockout_level,
+                    users::locked_until,
+                    users::locked_permanently,
+                    users::last_failed_login_at,
+                ))
+                .first::<LockoutState>(conn)?;
+            let next = f(current);
+            write_lockout(conn, id, &next)?;
+            Ok(next)
+        })
+        .map_err(Into::into)
+    }
+
+    fn reset_lockout(&self, id: Uuid) -> Result<(), RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        if write_lockout(&mut conn, id, &LockoutState::default())? == 0 {
+            return Err(RepositoryError::NotFound("User not found".to_string()));
+        }
+        Ok(())
+    }
+
+    fn purge(&self, id: Uuid) -> Result<Uuid, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+        let pseudonym = Uuid::new_v4();
+
+        conn.immediate_transaction::<_, diesel::result::Error, _>(|conn| {
+            diesel::update(login_attempts::table.filter(login_attempts::user_id.eq(Id(id))))
+                .set((
+                    login_attempts::user_agent.eq(None::<String>),
+                    login_attempts::ip_address.eq(None::<String>),
+                    login_attempts::email_hash.eq(None::<String>),
+                ))
+                .execute(conn)?;
+
+            // Le trigger append-only n'accepte ces mises à jour que pour un compte inscrit
+            // dans `audit_purged_users`, retiré avant la fin de la transaction
+            diesel::insert_into(audit_purged_users::table)
+                .values(audit_purged_users::user_id.eq(Id(id)))
+                .execute(conn)?;
+            diesel::update(
+                audit_events::table.filter(
+                    audit_events::actor_id
+                        .eq(Id(id))
+                        .or(audit_events::target_id.eq(Id(id))),
+                ),
+            )
+            .set((
+                audit_events::ip_address.eq(None::<String>),
+                audit_events::user_agent.eq(None::<String>),
+            ))
+            .execute(conn)?;
+            diesel::update(
+                audit_events::table.filter(
+                    audit_events::actor_id
+                        .eq(Id(id))
+                        .or(audit_events::target_id.eq(Id(id)))
+                        .and(json_type(audit_events::metadata, "$.email_hash").is_not_null()),
+                ),
+            )
+            .set(audit_events::metadata.eq(json_set(
+                audit_events::metadata,
+                "$.email_hash",
+                None::<String>,
+            )))
+            .execute(conn)?;
+            diesel::update(audit_events::table.filter(audit_events::actor_id.eq(Id(id))))
+                .set(audit_events::actor_id.eq(Id(pseudonym)))
+                .execute(conn)?;
+            diesel::update(audit_events::table.filter(audit_events::target_id.eq(Id(id))))
+                .set(audit_events::target_id.eq(Id(pseudonym)))
+                .execute(conn)?;
+            diesel::delete(
+                audit_purged_users::table.filter(audit_purged_users::user_id.eq(Id(id))),
+            )
+            .execute(conn)?;
+
+            diesel::delete(users::table.filter(users::id.eq(Id(id)))).execute(conn)?;
+            Ok(())
+        })?;
+
+        Ok(pseudonym)
+    }
+}
+
+/// Writes the lockout columns of a user, returning the number of rows updated.
+fn write_lockout(
+    conn: &mut SqliteConnection,
+    id: Uuid,
+    state: &LockoutState,
+) -> Result<usize, diesel::result::Error> {
+    diesel::update(users::table.filter(users::id.eq(Id(id))))
+        .set((
+            users::failed_login_count.eq(state.failed_login_count),
+            users::lockout_level.eq(state.lockout_level),
+            users::locked_until.eq(state.locked_until),
+            users::locked_permanently.eq(state.locked_permanently),
+            users::last_failed_login_at.eq(state.last_failed_login_at),
+            users::updated_at.eq(Utc::now()),
+        ))
+        .execute(conn)
+}
+
+impl RefreshTokenStore for SqliteStore {
+    fn create(&self, new_refresh_token: &NewRefreshToken) -> Result<RefreshToken, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+        let now = Utc::now();
+
+        diesel::insert_into(refresh_tokens::table)
+            .values((
+                refresh_tokens::id.eq(Id(Uuid::new_v4())),
+                refresh_tokens::user_id.eq(Id(new_refresh_token.user_id)),
+                refresh_tokens::token_hash.eq(&new_refresh_token.token_hash),
+                refresh_tokens::expires_at.eq(new_refresh_token.expires_at),
+                refresh_tokens::created_at.eq(now),
+                refresh_tokens::updated_at.eq(now),
+                refresh_tokens::risk_flagged.eq(new_refresh_token.risk_flagged),
+            ))
+            .get_result::<RefreshToken>(&mut conn)
+            .map_err(Into::into)
+    }
+
+    fn find_by_hash(&self, hash: &str) -> Result<Option<RefreshToken>, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        refresh_tokens::table
+            .filter(refresh_tokens::token_hash.eq(hash))
+            .filter(refresh_tokens::expires_at.gt(Utc::now()))
+            .first::<RefreshToken>(&mut conn)
+            .optional()
+            .map_err(Into::into)
+    }
+
+    fn find_by_user(&self, user_id: Uuid) -> Result<Vec<RefreshToken>, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        refresh_tokens::table
+            .filter(refresh_tokens::user_id.eq(Id(user_id)))
+            .order_by(refresh_tokens::created_at.desc())
+            .load::<RefreshToken>(&mut conn)
+            .map_err(Into::into)
+    }
+
+    fn delete(&self, id: Uuid) -> Result<(), RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        diesel::delete(refresh_tokens::table.filter(refresh_tokens::id.eq(Id(id))))
+            .execute(&mut conn)?;
+        Ok(())
+    }
+
+    fn delete_by_user(&self, user_id: Uuid) -> Result<(), RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        diesel::delete(refresh_tokens::table.filter(refresh_tokens::user_id.eq(Id(user_id))))
+            .execute(&mut conn)?;
+        Ok(())
+    }
+
+    fn delete_expired(&self, before: DateTime<Utc>) -> Result<usize, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        diesel::delete(refresh_tokens::table.filter(refresh_tokens::expires_at.lt(before)))
+            .execute(&mut conn)
+            .map_err(Into::into)
+    }
+}
+
+impl LoginAttemptStore for SqliteStore {
+    fn create(&self, new_attempt: &NewLoginAttempt) -> Result<LoginAttempt, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        diesel::insert_into(login_attempts::table)
+            .values((
+                login_attempts::id.eq(Id(Uuid::new_v4())),
+                login_attempts::user_id.eq(new_attempt.user_id.map(Id)),
+                login_attempts::success.eq(new_attempt.success),
+                login_attempts::attempted_at.eq(Utc::now()),
+                login_attempts::user_agent.eq(&new_attempt.user_agent),
+                login_attempts::ip_address.eq(&new_attempt.ip_address),
+                login_attempts::email_hash.eq(&new_attempt.email_hash),
+                login_attempts::failure_reason.eq(&new_attempt.failure_reason),
+                login_attempts::request_id.eq(&new_attempt.request_id),
+            ))
+            .get_result::<LoginAttempt>(&mut conn)
+            .map_err(Into::into)
+    }
+
+    fn find_all_by_user(&self, user_id: Uuid) -> Result<Vec<LoginAttempt>, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        login_attempts::table
+            .filter(login_attempts::user_id.eq(Id(user_id)))
+            .order_by(login_attempts::attempted_at.desc())
+            .load::<LoginAttempt>(&mut conn)
+            .map_err(Into::into)
+    }
+
+    fn find_by_id(&self, id: Uuid) -> Result<Option<LoginAttempt>, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        login_attempts::table
+            .filter(login_attempts::id.eq(Id(id)))
+            .first::<LoginAttempt>(&mut conn)
+            .optional()
+            .map_err(Into::into)
+    }
+
+    fn find_page_by_user(
+        &self,
+        user_id: Uuid,
+        cursor: Option<HistoryCursor>,
+        limit: i64,
+    ) -> Result<Vec<LoginAttempt>, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+        let query = login_attempts::table
+            .filter(login_attempts::user_id.eq(Id(user_id)))
+            .into_boxed();
+        load_attempt_page(&mut conn, query, cursor, limit)
+    }
+
+    fn find_page_by_email_hash(
+        &self,
+        email_hash: &str,
+        cursor: Option<HistoryCursor>,
+        limit: i64,
+    ) -> Result<Vec<LoginAttempt>, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+        let query = login_attempts::table
+            .filter(login_attempts::email_hash.eq(email_hash.to_string()))
+            .into_boxed();
+        load_attempt_page(&mut conn, query, cursor, limit)
+    }
+    fn delete_before(&self, before: DateTime<Utc>) -> Result<usize, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        diesel::delete(login_attempts::table.filter(login_attempts::attempted_at.lt(before)))
+            .execute(&mut conn)
+            .map_err(Into::into)
+    }
+}
+
+impl UserIdentityStore for SqliteStore {
+    fn create(&self, new_identity: &NewUserIdentity) -> Result<UserIdentity, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        diesel::insert_into(user_identities::table)
+            .values((
+                user_identities::id.eq(Id(Uuid::new_v4())),
+                user_identities::user_id.eq(Id(new_identity.user_id)),
+                user_identities::provider.eq(&new_identity.provider),
+                user_identities::provider_user_id.eq(&new_identity.provider_user_id),
+                user_identities::email.eq(&new_identity.email),
+                user_identities::created_at.eq(Utc::now()),
+            ))
+            .get_result::<UserIdentity>(&mut conn)
+            .map_err(Into::into)
+    }
+
+    fn find_by_user(&self, user_id: Uuid) -> Result<Vec<UserIdentity>, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        user_identities::table
+            .filter(user_identities::user_id.eq(Id(user_id)))
+            .order_by(user_identities::created_at.asc())
+            .load::<UserIdentity>(&mut conn)
+            .map_err(Into::into)
+    }
+}
+
+impl PasswordHistoryStore for SqliteStore {
+    fn find_recent(
+        &self,
+        user_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<PasswordHistoryEntry>, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        password_history::table
+            .filter(password_history::user_id.eq(Id(user_id)))
+            .order_by((
+                password_history::created_at.desc(),
+                password_history::id.desc(),
+            ))
+            .limit(limit)
+            .load::<PasswordHistoryEntry>(&mut conn)
+            .map_err(Into::into)
+    }
+}
+
+impl UserTokenStore for SqliteStore {
+    fn create(&self, new_token: &NewUserToken) -> Result<UserToken, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        diesel::insert_into(user_tokens::table)
+            .values((
+                user_tokens::id.eq(Id(Uuid::new_v4())),
+                user_tokens::user_id.eq(Id(new_token.user_id)),
+                user_tokens::purpose.eq(&new_token.purpose),
+                user_tokens::token_hash.eq(&new_token.token_hash),
+                user_tokens::expires_at.eq(new_token.expires_at),
+                user_tokens::created_at.eq(Utc::now()),
+            ))
+            .get_result::<UserToken>(&mut conn)
+            .map_err(Into::into)
+    }
+
+    fn consume(
+        &self,
+        token_hash: &str,
+        purpose: TokenPurpose,
+        now: DateTime<Utc>,
+    ) -> Result<Option<UserToken>, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        diesel::update(
+            user_tokens::table
+                .filter(user_tokens::token_hash.eq(token_hash))
+                .filter(user_tokens::purpose.eq(purpose.as_str()))
+                .filter(user_tokens::used_at.is_null())
+                .filter(user_tokens::expires_at.gt(now)),
+        )
+        .set(user_tokens::used_at.eq(now))
+        .get_result::<UserToken>(&mut conn)
+        .optional()
+        .map_err(Into::into)
+    }
+}
+
+impl KnownDeviceStore for SqliteStore {
+    fn find_by_user(&self, user_id: Uuid) -> Result<Vec<KnownDevice>, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        known_devices::table
+            .filter(known_devices::user_id.eq(Id(user_id)))
+            .order_by(known_devices::last_seen_at.desc())
+            .load::<KnownDevice>(&mut conn)
+            .map_err(Into::into)
+    }
+
+    fn touch(&self, device: &NewKnownDevice) -> Result<KnownDevice, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+        let now = Utc::now();
+
+        diesel::insert_into(known_devices::table)
+            .values((
+                known_devices::id.eq(Id(Uuid::new_v4())),
+                known_devices::user_id.eq(Id(device.user_id)),
+                known_devices::fingerprint.eq(&device.fingerprint),
+                known_devices::browser.eq(&device.browser),
+                known_devices::os.eq(&device.os),
+                known_devices::ip_prefix.eq(&device.ip_prefix),
+                known_devices::first_seen_at.eq(now),
+                known_devices::last_seen_at.eq(now),
+            ))
+            .on_conflict((known_devices::user_id, known_devices::fingerprint))
+            .do_update()
+            .set(known_devices::last_seen_at.eq(now))
+            .get_result::<KnownDevice>(&mut conn)
+            .map_err(Into::into)
+    }
+}
+
+impl AuditEventStore for SqliteStore {
+    fn create(&self, new_event: &NewAuditEvent) -> Result<AuditEvent, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        diesel::insert_into(audit_events::table)
+            .values((
+                audit_events::id.eq(Id(Uuid::new_v4())),
+                audit_events::occurred_at.eq(Utc::now()),
+                audit_events::event_type.eq(&new_event.event_type),
+                audit_events::actor_id.eq(new_event.actor_id.map(Id)),
+                audit_events::target_id.eq(new_event.target_id.map(Id)),
+                audit_events::ip_address.eq(&new_event.ip_address),
+                audit_events::user_agent.eq(&new_event.user_agent),
+                audit_events::request_id.eq(&new_event.request_id),
+                audit_events::metadata.eq(&new_event.metadata),
+            ))
+            .get_result::<AuditEvent>(&mut conn)
+            .map_err(Into::into)
+    }
+
+    fn find_page(
+        &self,
+        filter: &AuditEventFilter,
+        cursor: Option<AuditCursor>,
+        limit: i64,
+    ) -> Result<Vec<AuditEvent>, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        let mut query = audit_events::table.into_boxed();
+        if let Some(event_type) = &filter.event_type {
+            query = query.filter(audit_events::event_type.eq(event_type.clone()));
+        }
+        if let Some(actor_id) = filter.actor_id {
+            query = query.filter(audit_events::actor_id.eq(Id(actor_id)));
+        }
+        if let Some(target_id) = filter.target_id {
+            query = query.filter(audit_events::target_id.eq(Id(target_id)));
+        }
+        if let Some(from) = filter.from {
+            query = query.filter(audit_events::occurred_at.ge(from));
+        }
+        if let Some(to) = filter.to {
+            query = query.filter(audit_events::occurred_at.lt(to));
+        }
+        if let Some((occurred_at, id)) = cursor {
+            query = query.filter(
+                audit_events::occurred_at
+                    .lt(occurred_at)
+                    .or(audit_events::occurred_at
+                        .eq(occurred_at)
+                        .and(audit_events::id.lt(Id(id)))),
+            );
+        }
+
+        query
+            .order_by((audit_events::occurred_at.desc(), audit_events::id.desc()))
+            .limit(limit)
+            .load::<AuditEvent>(&mut conn)
+            .map_err(Into::into)
+    }
+}
+
+impl RateLimitStore for SqliteStore {
+    fn get(
+        &self,
+        key: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Option<RateLimitBucket>, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        rate_limit_buckets::table
+            .filter(rate_limit_buckets::key.eq(key))
+            .filter(rate_limit_buckets::window_start.gt(since))
+            .first::<RateLimitBucket>(&mut conn)
+            .optional()
+            .map_err(Into::into)
+    }
+
+    fn increment(
+        &self,
+        key: &str,
+        now: DateTime<Utc>,
+        since: DateTime<Utc>,
+    ) -> Result<RateLimitBucket, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        conn.immediate_transaction::<_, diesel::result::Error, _>(|conn| {
+            let current = rate_limit_buckets::table
+                .filter(rate_limit_buckets::key.eq(key))
+                .first::<RateLimitBucket>(conn)
+                .optional()?;
+            let next = match current {
+                Some(bucket) if bucket.window_start > since => RateLimitBucket {
+                    hits: bucket.hits + 1,
+                    ..bucket
+                },
+                _ => RateLimitBucket {
+                    key: key.to_string(),
+                    window_start: now,
+                    hits: 1,
+                },
+            };
+            diesel::replace_into(rate_limit_buckets::table)
+                .values((
+                    rate_limit_buckets::key.eq(&next.key),
+                    rate_limit_buckets::window_start.eq(next.window_start),
+                    rate_limit_buckets::hits.eq(next.hits),
+                ))
+                .execute(conn)?;
+            Ok(next)
+        })
+        .map_err(Into::into)
+    }
+
+    fn reset(&self, key: &str) -> Result<(), RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        diesel::delete(rate_limit_buckets::table.filter(rate_limit_buckets::key.eq(key)))
+            .execute(&mut conn)?;
+        Ok(())
+    }
+
+    fn delete_expired(&self, before: DateTime<Utc>) -> Result<usize, RepositoryError> {
+        let mut conn = get_connection(&self.pool)?;
+
+        diesel::delete(
+            rate_limit_buckets::table.filter(rate_limit_buckets::window_start.le(before)),
+        )
+        .execute(&mut conn)
+        .map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::test_pool;
+    use chrono::Duration;
+
+    #[test]
+    fn rate_limit_increment_counts_then_restarts_expired_window() {
+        let store = SqliteStore::new(test_pool());
+        let start = Utc::now() - Duration::minutes(30);
+        let old_since = start - Duration::minutes(15);
+        assert_eq!(store.increment("ip:a", start, old_since).unwrap().hits, 1);
+        assert_eq!(store.increment("ip:a", start, old_since).unwrap().hits, 2);
+
+        let now = Utc::now();
+        let since = now - Duration::minutes(15);
+        assert!(store.get("ip:a", since).unwrap().is_none());
+        let bucket = store.increment("ip:a", now, since).unwrap();
+        assert_eq!(bucket.hits, 1);
+        assert_eq!(store.get("ip:a", since).unwrap(), Some(bucket));
+
+        store.reset("ip:a").unwrap();
+        assert!(store.get("ip:a", since).unwrap().is_none());
+    }
+
+    #[test]
+    fn delete_expired_buckets_keeps_fresh_windows() {
+        let store = SqliteStore::new(test_pool());
+        let now = Utc::now();
+        let long_ago = now - Duration::days(2);
+        store
+            .increment("old", long_ago, long_ago - Duration::minutes(15))
+            .unwrap();
+        store
+            .increment("fresh", now, now - Duration::minutes(15))
+            .unwrap();
+
+        assert_eq!(
+            RateLimitStore::delete_expired(&store, now - Duration::days(1)).unwrap(),
+            1
+        );
+        assert!(
+            store
+                .get("fresh", now - Duration::minutes(15))
+                .unwrap()
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn delete_before_prunes_old_login_attempts() {
+        let pool = test_pool();
+        let store = SqliteStore::new(pool.clone());
+        let old = LoginAttemptStore::create(&store, &NewLoginAttempt::default()).unwrap();
+        let fresh = LoginAttemptStore::create(&store, &NewLoginAttempt::default()).unwrap();
+        diesel::update(login_attempts::table.filter(login_attempts::id.eq(Id(old.id))))
+            .set(login_attempts::attempted_at.eq(Utc::now() - Duration::days(400)))
+            .execute(&mut pool.get().unwrap())
+            .unwrap();
+
+        assert_eq!(
+            store
+                .delete_before(Utc::now() - Duration::days(365))
+                .unwrap(),
+            1
+        );
+        assert!(
+            LoginAttemptStore::find_by_id(&store, old.id)
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            LoginAttemptStore::find_by_id(&store, fresh.id)
+                .unwrap()
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn audit_events_cannot_be_deleted() {
+        let pool = test_pool();
+        let store = SqliteStore::new(pool.clone());
+        let event = AuditEventStore::create(
+            &store,
+            &NewAuditEvent {
+                event_type: "test_event".to_string(),
+                metadata: serde_json::json!({}),
+                ..NewAuditEvent::default()
+            },
+        )
+        .unwrap();
+
+        let mut conn = get_connection(&pool).unwrap();
+        let deleted = diesel::delete(audit_events::table.filter(audit_events::id.eq(Id(event.id))))
+            .execute(&mut conn);
+        assert!(deleted.is_err(), "audit_events must be append-only");
+    }
+
+    #[test]
+    fn updates_without_updated_at_are_stamped_by_trigger() {
+        let pool = test_pool();
+        let store = SqliteStore::new(pool.clone());
+        let user = UserStore::create(
+            &store,
+            &NewUser {
+                email: "trigger@example.com".to_string(),
+                username: "trigger".to_string(),
+                password_hash: None,
+                username_canonical: "trigger".to_string(),
+                password_pepper_version: None,
+            },
+        )
+        .unwrap();
+
+        let mut conn = get_connection(&pool).unwrap();
+        diesel::update(users::table.filter(users::id.eq(Id(user.id))))
+            .set(users::is_admin.eq(true))
+            .execute(&mut conn)
+            .unwrap();
+        let updated = UserStore::find_by_id(&store, user.id).unwrap().unwrap();
+        assert!(updated.is_admin);
+        assert_ne!(updated.updated_at, user.updated_at);
+    }
+}
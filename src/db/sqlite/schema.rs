@@ -0,0 +1,176 @@
+// Mirrors `crate::db::schema` for `migrations-sqlite/`. Written by hand: UUIDs are
+// stored as text (`UuidText`) and timestamps as UTC text (`TimestamptzSqlite`).
+
+diesel::table! {
+    use diesel::sql_types::{Json, Nullable, Text, TimestamptzSqlite};
+    use crate::db::sqlite::UuidText;
+
+    audit_events (id) {
+        id -> UuidText,
+        occurred_at -> TimestamptzSqlite,
+        event_type -> Text,
+        actor_id -> Nullable<UuidText>,
+        target_id -> Nullable<UuidText>,
+        ip_address -> Nullable<Text>,
+        user_agent -> Nullable<Text>,
+        request_id -> Nullable<Text>,
+        metadata -> Json,
+    }
+}
+
+diesel::table! {
+    use crate::db::sqlite::UuidText;
+
+    audit_purged_users (user_id) {
+        user_id -> UuidText,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::{Nullable, Text, TimestamptzSqlite};
+    use crate::db::sqlite::UuidText;
+
+    known_devices (id) {
+        id -> UuidText,
+        user_id -> UuidText,
+        fingerprint -> Text,
+        browser -> Nullable<Text>,
+        os -> Nullable<Text>,
+        ip_prefix -> Nullable<Text>,
+        first_seen_at -> TimestamptzSqlite,
+        last_seen_at -> TimestamptzSqlite,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::{Bool, Nullable, Text, TimestamptzSqlite};
+    use crate::db::sqlite::UuidText;
+
+    login_attempts (id) {
+        id -> UuidText,
+        user_id -> Nullable<UuidText>,
+        success -> Bool,
+        attempted_at -> TimestamptzSqlite,
+        user_agent -> Nullable<Text>,
+        ip_address -> Nullable<Text>,
+        email_hash -> Nullable<Text>,
+        failure_reason -> Nullable<Text>,
+        request_id -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::{Integer, Nullable, Text, TimestamptzSqlite};
+    use crate::db::sqlite::UuidText;
+
+    password_history (id) {
+        id -> UuidText,
+        user_id -> UuidText,
+        password_hash -> Text,
+        password_pepper_version -> Nullable<Integer>,
+        created_at -> TimestamptzSqlite,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::{Integer, Text, TimestamptzSqlite};
+
+    rate_limit_buckets (key) {
+        key -> Text,
+        window_start -> TimestamptzSqlite,
+        hits -> Integer,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::{Bool, Text, TimestamptzSqlite};
+    use crate::db::sqlite::UuidText;
+
+    refresh_tokens (id) {
+        id -> UuidText,
+        user_id -> UuidText,
+        token_hash -> Text,
+        expires_at -> TimestamptzSqlite,
+        created_at -> TimestamptzSqlite,
+        updated_at -> TimestamptzSqlite,
+        risk_flagged -> Bool,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::{Nullable, Text, TimestamptzSqlite};
+    use crate::db::sqlite::UuidText;
+
+    user_identities (id) {
+        id -> UuidText,
+        user_id -> UuidText,
+        provider -> Text,
+        provider_user_id -> Text,
+        email -> Nullable<Text>,
+        created_at -> Nullable<TimestamptzSqlite>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::{Nullable, Text, TimestamptzSqlite};
+    use crate::db::sqlite::UuidText;
+
+    user_tokens (id) {
+        id -> UuidText,
+        user_id -> UuidText,
+        purpose -> Text,
+        token_hash -> Text,
+        expires_at -> TimestamptzSqlite,
+        used_at -> Nullable<TimestamptzSqlite>,
+        created_at -> TimestamptzSqlite,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::{Bool, Integer, Nullable, Text, TimestamptzSqlite};
+    use crate::db::sqlite::UuidText;
+
+    users (id) {
+        id -> UuidText,
+        email -> Text,
+        username -> Text,
+        password_hash -> Nullable<Text>,
+        email_verified -> Bool,
+        is_active -> Bool,
+        is_admin -> Bool,
+        created_at -> TimestamptzSqlite,
+        updated_at -> TimestamptzSqlite,
+        last_login_at -> Nullable<TimestamptzSqlite>,
+        username_canonical -> Text,
+        deletion_scheduled_at -> Nullable<TimestamptzSqlite>,
+        failed_login_count -> Integer,
+        lockout_level -> Integer,
+        locked_until -> Nullable<TimestamptzSqlite>,
+        locked_permanently -> Bool,
+        last_failed_login_at -> Nullable<TimestamptzSqlite>,
+        password_pepper_version -> Nullable<Integer>,
+        password_changed_at -> Nullable<TimestamptzSqlite>,
+        /// Unicode lowercase of `email`, the unique key (`SQLite`'s `lower()` is ASCII only)
+        email_lower -> Text,
+    }
+}
+
+diesel::joinable!(known_devices -> users (user_id));
+diesel::joinable!(login_attempts -> users (user_id));
+diesel::joinable!(password_history -> users (user_id));
+diesel::joinable!(refresh_tokens -> users (user_id));
+diesel::joinable!(user_identities -> users (user_id));
+diesel::joinable!(user_tokens -> users (user_id));
+
+diesel::allow_tables_to_appear_in_same_query!(
+    audit_events,
+    audit_purged_users,
+    known_devices,
+    login_attempts,
+    password_history,
+    rate_limit_buckets,
+    refresh_tokens,
+    user_identities,
+    user_tokens,
+    users,
+);
@@ -0,0 +1,978 @@
+//! Storage traits implemented by each backend, and the bundle handed to the service.
+//!
+//! The Postgres implementations live in [`crate::db::repositories`], the in-memory one
+//! in [`crate::db::memory`] and the `SQLite` one in `crate::db::sqlite` (`--features sqlite`).
+
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::db::DbPool;
+use crate::db::error::RepositoryError;
+use crate::db::memory::MemoryStore;
+use crate::db::models::audit_event::{AuditEvent, NewAuditEvent};
+use crate::db::models::known_device::{KnownDevice, NewKnownDevice};
+use crate::db::models::login_attempt::{LoginAttempt, NewLoginAttempt};
+use crate::db::models::password_history::PasswordHistoryEntry;
+use crate::db::models::refresh_token::{NewRefreshToken, RefreshToken};
+use crate::db::models::user::{LockoutState, NewUser, UpdateUser, User};
+use crate::db::models::user_identity::{NewUserIdentity, UserIdentity};
+use crate::db::models::user_token::{NewUserToken, TokenPurpose, UserToken};
+use crate::db::repositories::audit_event_repository::{
+    AuditCursor, AuditEventFilter, AuditEventRepository,
+};
+use crate::db::repositories::known_device_repository::KnownDeviceRepository;
+use crate::db::repositories::login_attempt_repository::{HistoryCursor, LoginAttemptRepository};
+use crate::db::repositories::password_history_repository::PasswordHistoryRepository;
+use crate::db::repositories::refresh_token_repository::RefreshTokenRepository;
+use crate::db::repositories::user_identity_repository::UserIdentityRepository;
+use crate::db::repositories::user_repository::UserRepository;
+use crate::db::repositories::user_token_repository::UserTokenRepository;
+
+/// User accounts (`users`).
+pub trait UserStore: Send + Sync {
+    /// Finds a user by email address, ignoring case.
+    fn find_by_email(&self, email: &str) -> Result<Option<User>, RepositoryError>;
+
+    fn find_by_username_canonical(&self, canonical: &str) -> Result<Option<User>, RepositoryError>;
+
+    fn find_by_id(&self, id: Uuid) -> Result<Option<User>, RepositoryError>;
+
+    /// Inserts a user; email (case-insensitive) and canonical username are unique.
+    fn create(&self, new_user: &NewUser) -> Result<User, RepositoryError>;
+
+    /// Replaces the password hash without touching history or sessions (rehash at login).
+    fn update_password(
+        &self,
+        id: Uuid,
+        new_password_hash: &str,
+        pepper_version: Option<i32>,
+    ) -> Result<(), RepositoryError>;
+
+    /// Sets a password chosen by the user, atomically: the current hash moves to the
+    /// password history (trimmed to `history_size`), `password_changed_at` is reset and
+    /// every refresh token of the user is revoked.
+    fn change_password(
+        &self,
+        id: Uuid,
+        new_password_hash: &str,
+        pepper_version: Option<i32>,
+        history_size: i64,
+    ) -> Result<(), RepositoryError>;
+
+    fn update(&self, id: Uuid, changes: &UpdateUser) -> Result<User, RepositoryError>;
+
+    fn delete(&self, id: Uuid) -> Result<(), RepositoryError>;
+
+    /// Accounts whose deletion date is at or before `now`, oldest first.
+    fn find_due_for_deletion(
+        &self,
+        now: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<User>, RepositoryError>;
+
+    /// Applies `f` to the lockout state atomically, so concurrent failed logins
+    /// cannot lose updates. Returns the state written.
+    fn update_lockout(
+        &self,
+        id: Uuid,
+        f: &mut dyn FnMut(LockoutState) -> LockoutState,
+    ) -> Result<LockoutState, RepositoryError>;
+
+    /// Clears any lockout. Fails with `NotFound` for an unknown user.
+    fn reset_lockout(&self, id: Uuid) -> Result<(), RepositoryError>;
+
+    /// Deletes a user while keeping their login attempts, detached and stripped of
+    /// user agent and IP address. In the same transaction, their id is replaced in the
+    /// audit log by a fresh pseudonym, returned, and IP and user agent are cleared there
+    /// too.
+    fn purge(&self, id: Uuid) -> Result<Uuid, RepositoryError>;
+
+    fn update_last_login(&self, id: Uuid) -> Result<(), RepositoryError> {
+        let changes = UpdateUser {
+            last_login_at: Some(Some(Utc::now())),
+            ..UpdateUser::default()
+        };
+        self.update(id, &changes)?;
+        Ok(())
+    }
+
+    /// Schedules the account for deletion at `at`.
+    fn schedule_deletion(&self, id: Uuid, at: DateTime<Utc>) -> Result<User, RepositoryError> {
+        let changes = UpdateUser {
+            deletion_scheduled_at: Some(Some(at)),
+            ..UpdateUser::default()
+        };
+        self.update(id, &changes)
+    }
+
+    fn cancel_deletion(&self, id: Uuid) -> Result<(), RepositoryError> {
+        let changes = UpdateUser {
+            deletion_scheduled_at: Some(None),
+            ..UpdateUser::default()
+        };
+        self.update(id, &changes)?;
+        Ok(())
+    }
+}
+
+/// Sessions (`refresh_tokens`).
+pub trait RefreshTokenStore: Send + Sync {
+    fn create(&self, new_refresh_token: &NewRefreshToken) -> Result<RefreshToken, RepositoryError>;
+
+    /// Finds an unexpired token by hash.
+    fn find_by_hash(&self, hash: &str) -> Result<Option<RefreshToken>, RepositoryError>;
+
+    /// Every session of a user, newest first.
+    fn find_by_user(&self, user_id: Uuid) -> Result<Vec<RefreshToken>, RepositoryError>;
+
+    fn delete(&self, id: Uuid) -> Result<(), RepositoryError>;
+
+    fn delete_by_user(&self, user_id: Uuid) -> Result<(), RepositoryError>;
+
+    /// Deletes the sessions that expired before `before`; returns how many were deleted.
+    fn delete_expired(&self, before: DateTime<Utc>) -> Result<usize, RepositoryError>;
+}
+
+/// Login history (`login_attempts`).
+pub trait LoginAttemptStore: Send + Sync {
+    fn create(&self, new_attempt: &NewLoginAttempt) -> Result<LoginAttempt, RepositoryError>;
+
+    /// Every attempt of a user, newest first.
+    fn find_all_by_user(&self, user_id: Uuid) -> Result<Vec<LoginAttempt>, RepositoryError>;
+
+    fn find_by_id(&self, id: Uuid) -> Result<Option<LoginAttempt>, RepositoryError>;
+
+    /// Page of a user's attempts, newest first, strictly after `cursor`.
+    fn find_page_by_user(
+        &self,
+        user_id: Uuid,
+        cursor: Option<HistoryCursor>,
+        limit: i64,
+    ) -> Result<Vec<LoginAttempt>, RepositoryError>;
+
+    /// Page of attempts against an email hash, newest first, strictly after `cursor`.
+    fn find_page_by_email_hash(
+        &self,
+        email_hash: &str,
+        cursor: Option<HistoryCursor>,
+        limit: i64,
+    ) -> Result<Vec<LoginAttempt>, RepositoryError>;
+
+    /// Deletes the attempts made before `before`; returns how many were deleted.
+    fn delete_before(&self, before: DateTime<Utc>) -> Result<usize, RepositoryError>;
+}
+
+/// External identities linked to accounts (`user_identities`).
+pub trait UserIdentityStore: Send + Sync {
+    /// Links an identity; a provider account links to at most one user.
+    fn create(&self, new_identity: &NewUserIdentity) -> Result<UserIdentity, RepositoryError>;
+
+    fn find_by_user(&self, user_id: Uuid) -> Result<Vec<UserIdentity>, RepositoryError>;
+}
+
+/// Previous password hashes (`password_history`).
+pub trait PasswordHistoryStore: Send + Sync {
+    /// The user's `limit` most recent previous passwords, newest first.
+    fn find_recent(
+        &self,
+        user_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<PasswordHistoryEntry>, RepositoryError>;
+}
+
+/// Single-use emailed tokens (`user_tokens`).
+pub trait UserTokenStore: Send + Sync {
+    fn create(&self, new_token: &NewUserToken) -> Result<UserToken, RepositoryError>;
+
+    /// Atomically marks a valid token (unused, unexpired, right purpose) as used.
+    /// Returns `None` otherwise, without telling the cases apart.
+    fn consume(
+        &self,
+        token_hash: &str,
+        purpose: TokenPurpose,
+        now: DateTime<Utc>,
+    ) -> Result<Option<UserToken>, RepositoryError>;
+}
+
+/// Devices accounts logged in from (`known_devices`).
+pub trait KnownDeviceStore: Send + Sync {
+    /// The user's devices, most recently seen first.
+    fn find_by_user(&self, user_id: Uuid) -> Result<Vec<KnownDevice>, RepositoryError>;
+
+    /// Inserts the device on first sight, bumps `last_seen_at` afterwards.
+    fn touch(&self, device: &NewKnownDevice) -> Result<KnownDevice, RepositoryError>;
+}
+
+/// Append-only security audit log (`audit_events`).
+pub trait AuditEventStore: Send + Sync {
+    fn create(&self, new_event: &NewAuditEvent) -> Result<AuditEvent, RepositoryError>;
+
+    /// Page of matching events, newest first, strictly after `cursor`.
+    fn find_page(
+        &self,
+        filter: &AuditEventFilter,
+        cursor: Option<AuditCursor>,
+        limit: i64,
+    ) -> Result<Vec<AuditEvent>, RepositoryError>;
+}
+
+/// Every store the service needs, shared by the handlers through `AppState`.
+#[derive(Clone)]
+pub struct Repositories {
+    pub users: Arc<dyn UserStore>,
+    pub refresh_tokens: Arc<dyn RefreshTokenStore>,
+    pub login_attempts: Arc<dyn LoginAttemptStore>,
+    pub identities: Arc<dyn UserIdentityStore>,
+    pub password_history: Arc<dyn PasswordHistoryStore>,
+    pub user_tokens: Arc<dyn UserTokenStore>,
+    pub known_devices: Arc<dyn KnownDeviceStore>,
+    pub audit_events: Arc<dyn AuditEventStore>,
+}
+
+impl Repositories {
+    /// Postgres repositories sharing `pool`.
+    pub fn postgres(pool: &DbPool) -> Self {
+        Self {
+            users: Arc::new(UserRepository::new(pool.clone())),
+            refresh_tokens: Arc::new(RefreshTokenRepository::new(pool.clone())),
+            login_attempts: Arc::new(LoginAttemptRepository::new(pool.clone())),
+            identities: Arc::new(UserIdentityRepository::new(pool.clone())),
+            password_history: Arc::new(PasswordHistoryRepository::new(pool.clone())),
+            user_tokens: Arc::new(UserTokenRepository::new(pool.clone())),
+            known_devices: Arc::new(KnownDeviceRepository::new(pool.clone())),
+            audit_events: Arc::new(AuditEventRepository::new(pool.clone())),
+        }
+    }
+
+    /// Fresh, empty in-memory stores sharing one set of tables (`STORAGE=memory`).
+    pub fn memory() -> Self {
+        let store = MemoryStore::default();
+        Self {
+            users: Arc::new(store.clone()),
+            refresh_tokens: Arc::new(store.clone()),
+            login_attempts: Arc::new(store.clone()),
+            identities: Arc::new(store.clone()),
+            password_history: Arc::new(store.clone()),
+            user_tokens: Arc::new(store.clone()),
+            known_devices: Arc::new(store.clone()),
+            audit_events: Arc::new(store),
+        }
+    }
+
+    /// `SQLite` stores sharing `pool` (`STORAGE=sqlite`).
+    #[cfg(feature = "sqlite")]
+    pub fn sqlite(pool: &crate::db::sqlite::SqlitePool) -> Self {
+        let store = crate::db::sqlite::SqliteStore::new(pool.clone());
+        Self {
+            users: Arc::new(store.clone()),
+            refresh_tokens: Arc::new(store.clone()),
+            login_attempts: Arc::new(store.clone()),
+            identities: Arc::new(store.clone()),
+            password_history: Arc::new(store.clone()),
+            user_tokens: Arc::new(store.clone()),
+            known_devices: Arc::new(store.clone()),
+            audit_events: Arc::new(store),
+        }
+    }
+}
+
+/// In-memory repositories of the calling test, with no database or environment
+/// variable: every call from the same thread shares one store, and each test runs on a
+/// thread of its own.
+#[cfg(test)]
+pub fn test_repositories() -> Repositories {
+    thread_local! {
+        static REPOSITORIES: Repositories = Repositories::memory();
+    }
+    REPOSITORIES.with(Clone::clone)
+}
+
+/// Postgres repositories on the shared test pool (`TEST_DATABASE_URL`), for the checks
+/// specific to that backend.
+#[cfg(test)]
+pub fn postgres_test_repositories() -> Repositories {
+    Repositories::postgres(&crate::db::connection::test_pool())
+}
+
+/// Behaviour every backend must share, run against each of them with
+/// [`store_conformance_tests!`]. Data is unique per call so that backends sharing a
+/// database across tests stay isolated.
+#[cfg(test)]
+pub mod conformance {
+    use chrono::{Duration, Utc};
+    use uuid::Uuid;
+
+    use super::Repositories;
+    use crate::db::error::RepositoryError;
+    use crate::db::models::audit_event::NewAuditEvent;
+    use crate::db::models::known_device::NewKnownDevice;
+    use crate::db::models::login_attempt::NewLoginAttempt;
+    use crate::db::models::refresh_token::NewRefreshToken;
+    use crate::db::models::user::{LockoutState, NewUser, UpdateUser, User};
+    use crate::db::models::user_identity::NewUserIdentity;
+    use crate::db::models::user_token::{NewUserToken, TokenPurpose};
+    use crate::db::repositories::audit_event_repository::AuditEventFilter;
+
+    fn new_user_data() -> NewUser {
+        let unique = Uuid::new_v4().simple().to_string();
+        NewUser {
+            email: format!("conformance+{unique}@example.com"),
+            username: format!("Conf_{}", &unique[..16]),
+            password_hash: Some("hash_0".to_string()),
+            username_canonical: format!("conf_{}", &unique[..16]),
+            password_pepper_version: None,
+        }
+    }
+
+    fn create_user(repos: &Repositories) -> User {
+        repos.users.create(&new_user_data()).expect("create user")
+    }
+
+    fn session(user_id: Uuid, expires_in: Duration) -> NewRefreshToken {
+        NewRefreshToken {
+            user_id,
+            token_hash: Uuid::new_v4().simple().to_string(),
+            expires_at: Utc::now() + expires_in,
+            risk_flagged: false,
+        }
+    }
+
+    pub fn users_are_unique_by_email_ignoring_case(repos: &Repositories) {
+        let user = create_user(repos);
+        let duplicate = NewUser {
+            email: user.email.to_uppercase(),
+            ..new_user_data()
+        };
+
+        match repos.users.create(&duplicate) {
+            Err(RepositoryError::UniqueViolation { constraint }) => {
+                assert_eq!(constraint, "users_email_lower_key");
+            }
+            other => panic!("expected a unique violation, got {other:?}"),
+        }
+        let found = repos
+            .users
+            .find_by_email(&user.email.to_uppercase())
+            .unwrap()
+            .expect("lookup ignores case");
+        assert_eq!(found.id, user.id);
+
+        repos.users.delete(user.id).unwrap();
+    }
+
+    pub fn users_are_unique_by_canonical_username(repos: &Repositories) {
+        let user = create_user(repos);
+        let duplicate = NewUser {
+            username_canonical: user.username_canonical.clone(),
+            ..new_user_data()
+        };
+
+        match repos.users.create(&duplicate) {
+            // Le service s'appuie sur le nom de la contrainte pour distinguer les cas
+            Err(RepositoryError::UniqueViolation { constraint }) => {
+                assert_eq!(constraint, "users_username_canonical_key");
+            }
+            other => panic!("expected a unique violation, got {other:?}"),
+        }
+
+        repos.users.delete(user.id).unwrap();
+    }
+
+    pub fn user_defaults_and_updates(repos: &Repositories) {
+        let user = create_user(repos);
+        assert!(user.is_active);
+        assert!(!user.is_admin);
+        assert!(!user.email_verified);
+        assert!(user.password_changed_at.is_some());
+        assert_eq!(user.lockout_state(), LockoutState::default());
+
+        let updated = repos
+            .users
+            .update(
+                user.id,
+                &UpdateUser {
+                    is_admin: Some(true),
+                    ..UpdateUser::default()
+                },
+            )
+            .unwrap();
+        assert!(updated.is_admin);
+        assert!(updated.updated_at >= user.updated_at);
+
+        let at = Utc::now() - Duration::minutes(1);
+        repos.users.schedule_deletion(user.id, at).unwrap();
+        let due = repos.users.find_due_for_deletion(Utc::now(), 1000).unwrap();
+        assert!(due.iter().any(|u| u.id == user.id));
+        repos.users.cancel_deletion(user.id).unwrap();
+        let found = repos.users.find_by_id(user.id).unwrap().unwrap();
+        assert!(found.deletion_scheduled_at.is_none());
+
+        repos.users.delete(user.id).unwrap();
+        assert!(matches!(
+            repos.users.update(
+                user.id,
+                &UpdateUser {
+                    is_active: Some(false),
+                    ..UpdateUser::default()
+                }
+            ),
+            Err(RepositoryError::NotFound(_))
+        ));
+    }
+
+    pub fn lockout_updates_are_applied_and_reset(repos: &Repositories) {
+        let user = create_user(repos);
+
+        let written = repos
+            .users
+            .update_lockout(user.id, &mut |state| LockoutState {
+                failed_login_count: state.failed_login_count + 2,
+                locked_permanently: true,
+                ..state
+            })
+            .unwrap();
+        assert_eq!(written.failed_login_count, 2);
+        let found = repos.users.find_by_id(user.id).unwrap().unwrap();
+        assert_eq!(found.lockout_state(), written);
+
+        repos.users.reset_lockout(user.id).unwrap();
+        let found = repos.users.find_by_id(user.id).unwrap().unwrap();
+        assert_eq!(found.lockout_state(), LockoutState::default());
+
+        repos.users.delete(user.id).unwrap();
+        assert!(matches!(
+            repos.users.reset_lockout(user.id),
+            Err(RepositoryError::NotFound(_))
+        ));
+    }
+
+    pub fn expired_sessions_are_not_found(repos: &Repositories) {
+        let user = create_user(repos);
+        let live = repos
+            .refresh_tokens
+            .create(&session(user.id, Duration::days(1)))
+            .unwrap();
+        let expired = repos
+            .refresh_tokens
+            .create(&session(user.id, -Duration::minutes(1)))
+            .unwrap();
+
+        assert!(
+            repos
+                .refresh_tokens
+                .find_by_hash(&live.token_hash)
+                .unwrap()
+                .is_some()
+        );
+        assert!(
+            repos
+                .refresh_tokens
+                .find_by_hash(&expired.token_hash)
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!(repos.refresh_tokens.find_by_user(user.id).unwrap().len(), 2);
+
+        let duplicate = NewRefreshToken {
+            token_hash: live.token_hash.clone(),
+            ..session(user.id, Duration::days(1))
+        };
+        assert!(matches!(
+            repos.refresh_tokens.create(&duplicate),
+            Err(RepositoryError::UniqueViolation { .. })
+        ));
+
+        repos.refresh_tokens.delete(live.id).unwrap();
+        assert_eq!(repos.refresh_tokens.find_by_user(user.id).unwrap().len(), 1);
+        repos.users.delete(user.id).unwrap();
+    }
+
+    pub fn expired_sessions_are_purged(repos: &Repositories) {
+        let user = create_user(repos);
+        let live = repos
+            .refresh_tokens
+            .create(&session(user.id, Duration::days(1)))
+            .unwrap();
+        let recent = repos
+            .refresh_tokens
+            .create(&session(user.id, -Duration::minutes(1)))
+            .unwrap();
+        repos
+            .refresh_tokens
+            .create(&session(user.id, -Duration::days(2)))
+            .unwrap();
+
+        // La base peut être partagée : seules les sessions de ce test sont comptées
+        let purged = repos
+            .refresh_tokens
+            .delete_expired(Utc::now() - Duration::days(1))
+            .unwrap();
+        assert!(purged >= 1);
+        let mut remaining: Vec<_> = repos
+            .refresh_tokens
+            .find_by_user(user.id)
+            .unwrap()
+            .into_iter()
+            .map(|t| t.id)
+            .collect();
+        remaining.sort();
+        let mut expected = vec![live.id, recent.id];
+        expected.sort();
+        assert_eq!(remaining, expected);
+        repos.users.delete(user.id).unwrap();
+    }
+
+    pub fn rows_require_an_existing_user(repos: &Repositories) {
+        let missing = Uuid::new_v4();
+
+        assert!(matches!(
+            repos
+                .refresh_tokens
+                .create(&session(missing, Duration::days(1))),
+            Err(RepositoryError::ForeignKeyViolation(_))
+        ));
+        assert!(matches!(
+            repos.login_attempts.create(&NewLoginAttempt {
+                user_id: Some(missing),
+                ..NewLoginAttempt::default()
+            }),
+            Err(RepositoryError::ForeignKeyViolation(_))
+        ));
+        assert!(matches!(
+            repos.user_tokens.create(&NewUserToken {
+                user_id: missing,
+                purpose: TokenPurpose::AccountUnlock.as_str().to_string(),
+                token_hash: Uuid::new_v4().simple().to_string(),
+                expires_at: Utc::now() + Duration::hours(1),
+            }),
+            Err(RepositoryError::ForeignKeyViolation(_))
+        ));
+    }
+
+    pub fn purging_a_user_keeps_anonymous_login_history(repos: &Repositories) {
+        let user = create_user(repos);
+        repos
+            .refresh_tokens
+            .create(&session(user.id, Duration::days(1)))
+            .unwrap();
+        let attempt = repos
+            .login_attempts
+            .create(&NewLoginAttempt {
+                user_id: Some(user.id),
+                success: true,
+                user_agent: Some("curl/8.0".to_string()),
+                ip_address: Some("203.0.113.7".to_string()),
+                ..NewLoginAttempt::default()
+            })
+            .unwrap();
+
+        repos.users.purge(user.id).unwrap();
+
+        assert!(repos.users.find_by_id(user.id).unwrap().is_none());
+        assert!(
+            repos
+                .refresh_tokens
+                .find_by_user(user.id)
+                .unwrap()
+                .is_empty()
+        );
+        let kept = repos
+            .login_attempts
+            .find_by_id(attempt.id)
+            .unwrap()
+            .expect("attempt survives the account");
+        assert!(kept.user_id.is_none());
+        assert!(kept.user_agent.is_none());
+        assert!(kept.ip_address.is_none());
+        assert!(kept.success);
+    }
+
+    pub fn purging_a_user_anonymises_their_audit_events(repos: &Repositories) {
+        let user = create_user(repos);
+        let admin = create_user(repos);
+        let record = |actor_id, target_id| {
+            repos
+                .audit_events
+                .create(&NewAuditEvent {
+                    event_type: "purge_test".to_string(),
+                    actor_id,
+                    target_id,
+                    ip_address: Some("203.0.113.7".to_string()),
+                    user_agent: Some("curl/8.0".to_string()),
+                    request_id: Some("req-1".to_string()),
+                    metadata: serde_json::json!({ "n": 1 }),
+                })
+                .unwrap();
+        };
+        record(Some(user.id), Some(user.id));
+        record(Some(admin.id), Some(user.id));
+        record(Some(admin.id), None);
+
+        let pseudonym = repos.users.purge(user.id).unwrap();
+
+        let page =
+            |filter: AuditEventFilter| repos.audit_events.find_page(&filter, None, 10).unwrap();
+        for filter in [
+            AuditEventFilter {
+                actor_id: Some(user.id),
+                ..AuditEventFilter::default()
+            },
+            AuditEventFilter {
+                target_id: Some(user.id),
+                ..AuditEventFilter::default()
+            },
+        ] {
+            assert!(page(filter).is_empty());
+        }
+        let anonymised = page(AuditEventFilter {
+            target_id: Some(pseudonym),
+            ..AuditEventFilter::default()
+        });
+        assert_eq!(anonymised.len(), 2);
+        for event in &anonymised {
+            assert!(event.ip_address.is_none());
+            assert!(event.user_agent.is_none());
+            assert_eq!(event.request_id.as_deref(), Some("req-1"));
+            assert_eq!(event.metadata["n"], 1);
+        }
+        assert!(anonymised.iter().any(|e| e.actor_id == Some(pseudonym)));
+        assert!(anonymised.iter().any(|e| e.actor_id == Some(admin.id)));
+
+        let untouched = page(AuditEventFilter {
+            actor_id: Some(admin.id),
+            ..AuditEventFilter::default()
+        });
+        let unrelated = untouched.iter().find(|e| e.target_id.is_none()).unwrap();
+        assert_eq!(unrelated.ip_address.as_deref(), Some("203.0.113.7"));
+        assert_eq!(unrelated.user_agent.as_deref(), Some("curl/8.0"));
+    }
+
+    pub fn purging_a_user_forgets_their_email_hash(repos: &Repositories) {
+        let user = create_user(repos);
+        let other = create_user(repos);
+        let email_hash = Uuid::new_v4().simple().to_string();
+        for user_id in [user.id, other.id] {
+            repos
+                .login_attempts
+                .create(&NewLoginAttempt {
+                    user_id: Some(user_id),
+                    email_hash: Some(email_hash.clone()),
+                    ..NewLoginAttempt::default()
+                })
+                .unwrap();
+            repos
+                .audit_events
+                .create(&NewAuditEvent {
+                    event_type: "login_failed".to_string(),
+                    target_id: Some(user_id),
+                    metadata: serde_json::json!({
+                        "reason": "invalid_password",
+                        "email_hash": email_hash,
+                    }),
+                    ..NewAuditEvent::default()
+                })
+                .unwrap();
+        }
+
+        let pseudonym = repos.users.purge(user.id).unwrap();
+
+        let attempts = repos
+            .login_attempts
+            .find_page_by_email_hash(&email_hash, None, 10)
+            .unwrap();
+        assert_eq!(attempts.len(), 1);
+        assert_eq!(attempts[0].user_id, Some(other.id));
+
+        let events_of = |target_id| {
+            repos
+                .audit_events
+                .find_page(
+                    &AuditEventFilter {
+                        target_id: Some(target_id),
+                        ..AuditEventFilter::default()
+                    },
+                    None,
+                    10,
+                )
+                .unwrap()
+        };
+        let anonymised = events_of(pseudonym);
+        assert_eq!(anonymised.len(), 1);
+        assert!(anonymised[0].metadata["email_hash"].is_null());
+        assert_eq!(anonymised[0].metadata["reason"], "invalid_password");
+        assert_eq!(events_of(other.id)[0].metadata["email_hash"], email_hash);
+
+        repos.users.delete(other.id).unwrap();
+    }
+
+    pub fn change_password_keeps_history_and_revokes_sessions(repos: &Repositories) {
+        let user = create_user(repos);
+        repos
+            .refresh_tokens
+            .create(&session(user.id, Duration::days(1)))
+            .unwrap();
+
+        for i in 1..=3 {
+            repos
+                .users
+                .change_password(user.id, &format!("hash_{i}"), Some(1), 2)
+                .unwrap();
+        }
+
+        let found = repos.users.find_by_id(user.id).unwrap().unwrap();
+        assert_eq!(found.password_hash.as_deref(), Some("hash_3"));
+        assert_eq!(found.password_pepper_version, Some(1));
+        assert!(
+            repos
+                .refresh_tokens
+                .find_by_user(user.id)
+                .unwrap()
+                .is_empty()
+        );
+        let history: Vec<String> = repos
+            .password_history
+            .find_recent(user.id, 10)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.password_hash)
+            .collect();
+        assert_eq!(history, ["hash_2", "hash_1"]);
+
+        repos.users.delete(user.id).unwrap();
+        assert!(
+            repos
+                .password_history
+                .find_recent(user.id, 10)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    pub fn user_tokens_are_consumed_once_before_expiry(repos: &Repositories) {
+        let user = create_user(repos);
+        let token = |expires_at| NewUserToken {
+            user_id: user.id,
+            purpose: TokenPurpose::AccountUnlock.as_str().to_string(),
+            token_hash: Uuid::new_v4().simple().to_string(),
+            expires_at,
+        };
+        let valid = repos
+            .user_tokens
+            .create(&token(Utc::now() + Duration::hours(1)))
+            .unwrap();
+        let expired = repos
+            .user_tokens
+            .create(&token(Utc::now() - Duration::hours(1)))
+            .unwrap();
+        let now = Utc::now();
+
+        assert!(
+            repos
+                .user_tokens
+                .consume(&valid.token_hash, TokenPurpose::SessionRevoke, now)
+                .unwrap()
+                .is_none(),
+            "wrong purpose"
+        );
+        let consumed = repos
+            .user_tokens
+            .consume(&valid.token_hash, TokenPurpose::AccountUnlock, now)
+            .unwrap()
+            .expect("valid token");
+        assert_eq!(consumed.user_id, user.id);
+        assert!(consumed.used_at.is_some());
+        assert!(
+            repos
+                .user_tokens
+                .consume(&valid.token_hash, TokenPurpose::AccountUnlock, now)
+                .unwrap()
+                .is_none(),
+            "already used"
+        );
+        assert!(
+            repos
+                .user_tokens
+                .consume(&expired.token_hash, TokenPurpose::AccountUnlock, now)
+                .unwrap()
+                .is_none(),
+            "expired"
+        );
+
+        repos.users.delete(user.id).unwrap();
+    }
+
+    pub fn identities_link_a_provider_account_once(repos: &Repositories) {
+        let user = create_user(repos);
+        let other = create_user(repos);
+        let identity = NewUserIdentity {
+            user_id: user.id,
+            provider: "discord".to_string(),
+            provider_user_id: Uuid::new_v4().to_string(),
+            email: None,
+        };
+        repos.identities.create(&identity).unwrap();
+
+        assert!(matches!(
+            repos.identities.create(&NewUserIdentity {
+                user_id: other.id,
+                ..identity
+            }),
+            Err(RepositoryError::UniqueViolation { constraint })
+                if constraint == "user_identities_provider_provider_user_id_key"
+        ));
+        assert_eq!(repos.identities.find_by_user(user.id).unwrap().len(), 1);
+
+        repos.users.delete(user.id).unwrap();
+        repos.users.delete(other.id).unwrap();
+    }
+
+    pub fn login_attempts_page_newest_first(repos: &Repositories) {
+        let email_hash = Uuid::new_v4().simple().to_string();
+        let mut inserted: Vec<Uuid> = (0..5)
+            .map(|_| {
+                repos
+                    .login_attempts
+                    .create(&NewLoginAttempt {
+                        email_hash: Some(email_hash.clone()),
+                        ..NewLoginAttempt::default()
+                    })
+                    .unwrap()
+                    .id
+            })
+            .collect();
+
+        let mut seen = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = repos
+                .login_attempts
+                .find_page_by_email_hash(&email_hash, cursor, 2)
+                .unwrap();
+            let Some(last) = page.last() else { break };
+            cursor = Some((last.attempted_at, last.id));
+            seen.extend(page);
+        }
+
+        let keys: Vec<_> = seen.iter().map(|a| (a.attempted_at, a.id)).collect();
+        assert!(keys.windows(2).all(|w| w[0] > w[1]), "newest first");
+        let mut seen: Vec<Uuid> = seen.into_iter().map(|a| a.id).collect();
+        seen.sort();
+        inserted.sort();
+        assert_eq!(seen, inserted);
+    }
+
+    pub fn known_devices_are_upserted(repos: &Repositories) {
+        let user = create_user(repos);
+        let device = NewKnownDevice {
+            user_id: user.id,
+            fingerprint: "fp".to_string(),
+            browser: Some("Firefox".to_string()),
+            os: None,
+            ip_prefix: None,
+        };
+
+        let first = repos.known_devices.touch(&device).unwrap();
+        let again = repos.known_devices.touch(&device).unwrap();
+
+        assert_eq!(first.id, again.id);
+        assert_eq!(again.first_seen_at, first.first_seen_at);
+        assert!(again.last_seen_at >= first.last_seen_at);
+        assert_eq!(repos.known_devices.find_by_user(user.id).unwrap().len(), 1);
+
+        repos.users.delete(user.id).unwrap();
+        assert!(
+            repos
+                .known_devices
+                .find_by_user(user.id)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    pub fn audit_events_are_filtered_and_paged(repos: &Repositories) {
+        let target = Uuid::new_v4();
+        for event_type in ["login", "logout", "login"] {
+            repos
+                .audit_events
+                .create(&NewAuditEvent {
+                    event_type: event_type.to_string(),
+                    target_id: Some(target),
+                    metadata: serde_json::json!({ "n": 1 }),
+                    ..NewAuditEvent::default()
+                })
+                .unwrap();
+        }
+        let filter = AuditEventFilter {
+            event_type: Some("login".to_string()),
+            target_id: Some(target),
+            ..AuditEventFilter::default()
+        };
+
+        let first = repos.audit_events.find_page(&filter, None, 1).unwrap();
+        assert_eq!(first.len(), 1);
+        let cursor = first.last().map(|e| (e.occurred_at, e.id));
+        let rest = repos.audit_events.find_page(&filter, cursor, 10).unwrap();
+        assert_eq!(rest.len(), 1);
+        assert!(rest[0].occurred_at <= first[0].occurred_at);
+        assert_eq!(rest[0].metadata["n"], 1);
+
+        let future = AuditEventFilter {
+            from: Some(Utc::now() + Duration::hours(1)),
+            ..filter
+        };
+        assert!(
+            repos
+                .audit_events
+                .find_page(&future, None, 10)
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
+
+/// Generates one `#[test]` per [`conformance`] case against the repositories
+/// built by `$repositories` (evaluated once per test).
+#[cfg(test)]
+macro_rules! store_conformance_tests {
+    ($repositories:expr) => {
+        $crate::db::store::store_conformance_tests!(
+            $repositories;
+            users_are_unique_by_email_ignoring_case,
+            users_are_unique_by_canonical_username,
+            user_defaults_and_updates,
+            lockout_updates_are_applied_and_reset,
+            expired_sessions_are_not_found,
+            expired_sessions_are_purged,
+            rows_require_an_existing_user,
+            purging_a_user_keeps_anonymous_login_history,
+            purging_a_user_anonymises_their_audit_events,
+            purging_a_user_forgets_their_email_hash,
+            change_password_keeps_history_and_revokes_sessions,
+            user_tokens_are_consumed_once_before_expiry,
+            identities_link_a_provider_account_once,
+            login_attempts_page_newest_first,
+            known_devices_are_upserted,
+            audit_events_are_filtered_and_paged,
+        );
+    };
+    ($repositories:expr; $($case:ident),+ $(,)?) => {
+        $(
+            #[test]
+            fn $case() {
+                $crate::db::store::conformance::$case(&$repositories);
+            }
+        )+
+    };
+}
+
+#[cfg(test)]
+pub(crate) use store_conformance_tests;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    store_conformance_tests!(postgres_test_repositories());
+}
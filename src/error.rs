@@ -1,11 +1,12 @@
 // src/error.rs
 
-use auth_manager_api::ErrorResponse;
+use auth_manager_api::{ErrorResponse, PasswordViolation};
 use axum::{
     Json,
-    http::StatusCode,
+    http::{HeaderValue, StatusCode, header},
     response::{IntoResponse, Response},
 };
+use chrono::{DateTime, SecondsFormat, Utc};
 
 #[derive(Debug, thiserror::Error)]
 pub enum AppError {
@@ -20,16 +21,26 @@ pub enum AppError {
     // === Erreurs d'Authentification ===
     #[error("Invalid password")]
     InvalidPassword,
+    /// Échec de connexion générique, sans révéler si l'email existe
+    #[error("Invalid email or password")]
+    InvalidCredentials,
     #[error("Email already exists")]
     UserAlreadyExists,
     #[error("Invalid refresh token")]
     InvalidRefreshToken,
     #[error("Refresh token expired")]
     RefreshTokenExpired,
+    #[error("Username already taken")]
+    UsernameTaken,
     #[error("Invalid email format")]
     InvalidEmail,
-    #[error("Password too weak: {0}")]
-    WeakPassword(String),
+    #[error("Disposable email domains are not allowed")]
+    DisposableEmail,
+    #[error("Invalid username: {0}")]
+    InvalidUsername(String),
+    /// Règles de la politique de mot de passe non respectées
+    #[error("Password too weak: {}", violation_messages(.0))]
+    WeakPassword(Vec<PasswordViolation>),
 
     // === Erreurs de Hashing/Cryptographie ===
     #[error("Password hashing failed: {0}")]
@@ -48,8 +59,19 @@ pub enum AppError {
     // === Erreurs métier ===
     #[error("Unauthorized: {0}")]
     UnauthorizedAction(String),
-    #[error("Too many attempts: {0}")]
-    TooManyAttempts(String),
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+    /// Mot de passe expiré : seul le changement de mot de passe est autorisé
+    #[error("Password change required")]
+    PasswordChangeRequired,
+    /// Action confirmée par le mot de passe actuel, sur un compte qui n'en a pas
+    #[error("Password not set")]
+    PasswordNotSet,
+    /// Compte verrouillé ; `unlock_at` vaut `None` pour un verrouillage définitif
+    #[error("Too many attempts")]
+    TooManyAttempts { unlock_at: Option<DateTime<Utc>> },
+    #[error("Rate limited, retry after {retry_after_secs}s")]
+    RateLimited { retry_after_secs: u64 },
 
     // === Erreurs internes ===
     #[error("Internal server error: {0}")]
@@ -64,18 +86,50 @@ impl IntoResponse for AppError {
             tracing::error!(error_code, %status, detail, "Internal server error");
         }
 
+        // Date de déverrouillage exacte, lisible par les clients
+        let details = match &self {
+            AppError::TooManyAttempts {
+                unlock_at: Some(at),
+            } => Some(at.to_rfc3339_opts(SecondsFormat::Secs, true)),
+            _ => None,
+        };
+
+        let violations = match &self {
+            AppError::WeakPassword(violations) => Some(violations.clone()),
+            _ => None,
+        };
+
         let body = Json(ErrorResponse {
             error: error_code.to_string(),
             message,
-            details: None,
+            details,
+            violations,
         });
 
-        (status, body).into_response()
+        let mut response = (status, body).into_response();
+        let retry_after_secs = match self {
+            AppError::RateLimited { retry_after_secs } => Some(retry_after_secs),
+            AppError::TooManyAttempts {
+                unlock_at: Some(at),
+            } => Some(
+                u64::try_from((at - Utc::now()).num_seconds())
+                    .unwrap_or(0)
+                    .max(1),
+            ),
+            _ => None,
+        };
+        if let Some(secs) = retry_after_secs {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(secs));
+        }
+        response
     }
 }
 
 impl AppError {
     /// Récupère les informations d'erreur formatées pour la réponse HTTP
+    #[allow(clippy::too_many_lines)]
     fn get_error_info(&self) -> (StatusCode, &'static str, String, Option<String>) {
         match self {
             // 404 Not Found
@@ -91,6 +145,12 @@ impl AppError {
                 "Email already exists".to_string(),
                 None,
             ),
+            AppError::UsernameTaken => (
+                StatusCode::CONFLICT,
+                "USERNAME_EXISTS",
+                "Username already taken".to_string(),
+                None,
+            ),
 
             // 401 Unauthorized
             AppError::InvalidPassword => (
@@ -99,6 +159,12 @@ impl AppError {
                 "Invalid password".to_string(),
                 None,
             ),
+            AppError::InvalidCredentials => (
+                StatusCode::UNAUTHORIZED,
+                "INVALID_CREDENTIALS",
+                "Invalid email or password".to_string(),
+                None,
+            ),
             AppError::InvalidRefreshToken => (
                 StatusCode::UNAUTHORIZED,
                 "INVALID_TOKEN",
@@ -109,6 +175,21 @@ impl AppError {
                 (StatusCode::UNAUTHORIZED, "UNAUTHORIZED", msg.clone(), None)
             }
 
+            // 403 Forbidden
+            AppError::Forbidden(msg) => (StatusCode::FORBIDDEN, "FORBIDDEN", msg.clone(), None),
+            AppError::PasswordChangeRequired => (
+                StatusCode::FORBIDDEN,
+                "PASSWORD_CHANGE_REQUIRED",
+                "Your password has expired and must be changed".to_string(),
+                None,
+            ),
+            AppError::PasswordNotSet => (
+                StatusCode::FORBIDDEN,
+                "PASSWORD_NOT_SET",
+                "This action must be confirmed with the account password, and this account has none. Account deletion can be confirmed by email instead; otherwise ask an administrator to set one.".to_string(),
+                None,
+            ),
+
             // 400 Bad Request
             AppError::RefreshTokenExpired => (
                 StatusCode::BAD_REQUEST,
@@ -122,9 +203,24 @@ impl AppError {
                 "Invalid email format".to_string(),
                 None,
             ),
-            AppError::WeakPassword(msg) => {
-                (StatusCode::BAD_REQUEST, "WEAK_PASSWORD", msg.clone(), None)
-            }
+            AppError::DisposableEmail => (
+                StatusCode::BAD_REQUEST,
+                "DISPOSABLE_EMAIL",
+                "Disposable email domains are not allowed".to_string(),
+                None,
+            ),
+            AppError::InvalidUsername(msg) => (
+                StatusCode::BAD_REQUEST,
+                "INVALID_USERNAME",
+                msg.clone(),
+                None,
+            ),
+            AppError::WeakPassword(violations) => (
+                StatusCode::BAD_REQUEST,
+                "WEAK_PASSWORD",
+                violation_messages(violations),
+                None,
+            ),
             AppError::ValidationError(msg) => (
                 StatusCode::BAD_REQUEST,
                 "VALIDATION_ERROR",
@@ -142,10 +238,22 @@ impl AppError {
             ),
 
             // 429 Too Many Requests
-            AppError::TooManyAttempts(msg) => (
+            AppError::TooManyAttempts { unlock_at } => (
                 StatusCode::TOO_MANY_REQUESTS,
                 "TOO_MANY_ATTEMPTS",
-                msg.clone(),
+                match unlock_at {
+                    Some(at) => format!(
+                        "Account temporarily locked after too many failed attempts. Try again after {}.",
+                        at.to_rfc3339_opts(SecondsFormat::Secs, true)
+                    ),
+                    None => "Account locked after too many failed attempts. Use the unlock link sent by email or contact support.".to_string(),
+                },
+                None,
+            ),
+            AppError::RateLimited { retry_after_secs } => (
+                StatusCode::TOO_MANY_REQUESTS,
+                "RATE_LIMITED",
+                format!("Too many login attempts. Try again in {retry_after_secs} seconds."),
                 None,
             ),
 
@@ -206,9 +314,8 @@ impl AppError {
         AppError::UnauthorizedAction(msg.into())
     }
 
-    #[allow(dead_code)]
-    pub fn too_many_attempts(msg: impl Into<String>) -> Self {
-        AppError::TooManyAttempts(msg.into())
+    pub fn forbidden(msg: impl Into<String>) -> Self {
+        AppError::Forbidden(msg.into())
     }
 
     pub fn token_generation_failed(msg: impl Into<String>) -> Self {
@@ -222,6 +329,15 @@ impl AppError {
     }
 }
 
+/// Messages des règles non respectées, séparés par des points-virgules
+fn violation_messages(violations: &[PasswordViolation]) -> String {
+    violations
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
 // === Conversions automatiques depuis d'autres types d'erreurs ===
 
 // Depuis RepositoryError
@@ -229,7 +345,9 @@ impl From<crate::db::error::RepositoryError> for AppError {
     fn from(err: crate::db::error::RepositoryError) -> Self {
         match err {
             crate::db::error::RepositoryError::NotFound(msg) => AppError::not_found(msg),
-            crate::db::error::RepositoryError::UniqueViolation(msg) => AppError::duplicate(msg),
+            crate::db::error::RepositoryError::UniqueViolation { constraint } => {
+                AppError::duplicate(format!("unique constraint {constraint}"))
+            }
             crate::db::error::RepositoryError::PoolError(msg)
             | crate::db::error::RepositoryError::ForeignKeyViolation(msg)
             | crate::db::error::RepositoryError::DatabaseError(msg) => AppError::database(msg),
@@ -244,6 +362,23 @@ impl From<crate::auth::password::PasswordError> for AppError {
     }
 }
 
+// Depuis EmailError
+impl From<crate::auth::email::EmailError> for AppError {
+    fn from(err: crate::auth::email::EmailError) -> Self {
+        match err {
+            crate::auth::email::EmailError::DisposableDomain => AppError::DisposableEmail,
+            _ => AppError::InvalidEmail,
+        }
+    }
+}
+
+// Depuis UsernameError
+impl From<crate::auth::username::UsernameError> for AppError {
+    fn from(err: crate::auth::username::UsernameError) -> Self {
+        AppError::InvalidUsername(err.to_string())
+    }
+}
+
 // Depuis JwtError
 impl From<crate::auth::jwt::JwtError> for AppError {
     fn from(err: crate::auth::jwt::JwtError) -> Self {
@@ -317,6 +452,14 @@ mod tests {
         );
     }
 
+    #[test]
+    fn invalid_credentials_does_not_reveal_which_part_failed() {
+        let (status, code, message, _) = AppError::InvalidCredentials.get_error_info();
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(code, "INVALID_CREDENTIALS");
+        assert_eq!(message, "Invalid email or password");
+    }
+
     #[test]
     fn validation_error_maps_to_400_status() {
         assert_eq!(
@@ -336,15 +479,77 @@ mod tests {
     #[test]
     fn too_many_attempts_maps_to_429_status() {
         assert_eq!(
-            AppError::too_many_attempts("limit exceeded").status_code(),
+            AppError::TooManyAttempts { unlock_at: None }.status_code(),
             StatusCode::TOO_MANY_REQUESTS
         );
     }
 
+    #[tokio::test]
+    async fn too_many_attempts_reports_unlock_time() {
+        let unlock_at = Utc::now() + chrono::Duration::minutes(15);
+        let response = AppError::TooManyAttempts {
+            unlock_at: Some(unlock_at),
+        }
+        .into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(response.headers().contains_key(header::RETRY_AFTER));
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let error: ErrorResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            error.details.as_deref(),
+            Some(
+                unlock_at
+                    .to_rfc3339_opts(SecondsFormat::Secs, true)
+                    .as_str()
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn weak_password_lists_failed_rules() {
+        let response = AppError::WeakPassword(vec![
+            PasswordViolation::TooShort { min_length: 8 },
+            PasswordViolation::ContainsUserInfo,
+        ])
+        .into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let error: ErrorResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error.error, "WEAK_PASSWORD");
+        assert_eq!(
+            error.message,
+            "Password must be at least 8 characters; Password must not contain your email or username"
+        );
+        assert_eq!(error.violations.map(|v| v.len()), Some(2));
+    }
+
+    #[test]
+    fn invalid_username_maps_to_400_status() {
+        let err = AppError::from(crate::auth::username::UsernameError::Reserved);
+        assert_eq!(err.status_code(), StatusCode::BAD_REQUEST);
+        assert_eq!(err.to_string(), "Invalid username: Username is reserved");
+    }
+
     #[test]
     fn not_found_into_response_sets_404_status() {
         let err = AppError::not_found("User");
         let response = err.into_response();
         assert_eq!(response.status(), StatusCode::NOT_FOUND);
     }
+
+    #[test]
+    fn rate_limited_into_response_sets_retry_after() {
+        let response = AppError::RateLimited {
+            retry_after_secs: 42,
+        }
+        .into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "42");
+    }
 }
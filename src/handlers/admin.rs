@@ -0,0 +1,160 @@
+use auth_manager_api::{
+    AuditEventPage, AuditEventQuery, AuditEventType, LoginAttemptsByEmailQuery, LoginHistoryPage,
+    LoginHistoryQuery,
+};
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, HeaderValue, header};
+use axum::response::{IntoResponse, Response};
+use uuid::Uuid;
+
+use crate::auth::audit::{self, Audit};
+use crate::auth::client_info::ClientInfo;
+use crate::auth::extractors::AdminClaims;
+use crate::blocking;
+use crate::error::AppError;
+use crate::response::AppResponse;
+use crate::state::AppState;
+
+/// GET /admin/users/:id/login-history
+/// Historique des connexions d'un utilisateur quelconque
+pub async fn get_user_login_history(
+    State(state): State<AppState>,
+    Path(user_id): Path<Uuid>,
+    admin: AdminClaims,
+    client: ClientInfo,
+    Query(query): Query<LoginHistoryQuery>,
+) -> Result<AppResponse<LoginHistoryPage>, AppError> {
+    tracing::info!(admin_id = %admin.sub, %user_id, "Admin viewed login history");
+    let page = blocking::run(move || {
+        let page =
+            state
+                .auth_service
+                .login_history(user_id, query.cursor.as_deref(), query.limit)?;
+        Audit::new(AuditEventType::AdminLoginHistoryViewed)
+            .actor(admin.sub)
+            .target(user_id)
+            .client(&client)
+            .record(state.repositories.audit_events.as_ref());
+        Ok(page)
+    })
+    .await?;
+    Ok(AppResponse::ok(page))
+}
+
+/// GET /admin/login-attempts?email_hash=... | ?email=...
+/// Tentatives de connexion sur un email, y compris les emails sans compte
+pub async fn get_login_attempts_by_email(
+    State(state): State<AppState>,
+    admin: AdminClaims,
+    client: ClientInfo,
+    Query(query): Query<LoginAttemptsByEmailQuery>,
+) -> Result<AppResponse<LoginHistoryPage>, AppError> {
+    tracing::info!(admin_id = %admin.sub, "Admin searched login attempts by email");
+    let page = blocking::run(move || {
+        let (page, email_hash) = state.auth_service.login_attempts_by_email(
+            query.email_hash.as_deref(),
+            query.email.as_deref(),
+            query.cursor.as_deref(),
+            query.limit,
+        )?;
+        // L'email en clair n'est jamais journalisé, seulement l'empreinte cherchée
+        Audit::new(AuditEventType::AdminLoginAttemptsSearched)
+            .actor(admin.sub)
+            .client(&client)
+            .metadata(serde_json::json!({ "email_hash": email_hash }))
+            .record(state.repositories.audit_events.as_ref());
+        Ok(page)
+    })
+    .await?;
+    Ok(AppResponse::ok(page))
+}
+
+/// POST /admin/users/:id/unlock
+/// Lève le verrouillage d'un compte (y compris définitif)
+pub async fn unlock_user(
+    State(state): State<AppState>,
+    Path(user_id): Path<Uuid>,
+    admin: AdminClaims,
+    client: ClientInfo,
+) -> Result<AppResponse<serde_json::Value>, AppError> {
+    blocking::run(move || {
+        state.auth_service.unlock_account(user_id)?;
+        tracing::info!(admin_id = %admin.sub, %user_id, "Admin unlocked account");
+        Audit::new(AuditEventType::AccountUnlocked)
+            .actor(admin.sub)
+            .target(user_id)
+            .client(&client)
+            .metadata(serde_json::json!({ "method": "admin" }))
+            .record(state.repositories.audit_events.as_ref());
+        Ok(())
+    })
+    .await?;
+    Ok(AppResponse::ok(serde_json::json!({
+        "message": "Account unlocked"
+    })))
+}
+
+/// GET /admin/audit-events
+/// Journal d'audit filtré et paginé, du plus récent au plus ancien
+pub async fn get_audit_events(
+    State(state): State<AppState>,
+    admin: AdminClaims,
+    client: ClientInfo,
+    Query(query): Query<AuditEventQuery>,
+) -> Result<AppResponse<AuditEventPage>, AppError> {
+    let page = blocking::run(move || {
+        let page = audit::find_page(state.repositories.audit_events.as_ref(), &query)?;
+        Audit::new(AuditEventType::AdminAuditLogViewed)
+            .actor(admin.sub)
+            .client(&client)
+            .metadata(filter_metadata(&query))
+            .record(state.repositories.audit_events.as_ref());
+        Ok(page)
+    })
+    .await?;
+    Ok(AppResponse::ok(page))
+}
+
+/// GET /admin/audit-events/export
+/// Export JSON Lines du journal d'audit ; `X-Next-Cursor` indique la suite s'il y en a une
+pub async fn export_audit_events(
+    State(state): State<AppState>,
+    admin: AdminClaims,
+    client: ClientInfo,
+    Query(query): Query<AuditEventQuery>,
+) -> Result<Response, AppError> {
+    let (body, next_cursor) = blocking::run(move || {
+        let export = audit::export_json_lines(state.repositories.audit_events.as_ref(), &query)?;
+        Audit::new(AuditEventType::AdminAuditLogExported)
+            .actor(admin.sub)
+            .client(&client)
+            .metadata(filter_metadata(&query))
+            .record(state.repositories.audit_events.as_ref());
+        Ok(export)
+    })
+    .await?;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/x-ndjson"),
+    );
+    if let Some(cursor) = next_cursor {
+        headers.insert(
+            "x-next-cursor",
+            HeaderValue::from_str(&cursor).map_err(|_| AppError::internal("Invalid cursor"))?,
+        );
+    }
+    Ok((headers, body).into_response())
+}
+
+/// Filtre d'une consultation du journal d'audit, tel qu'il est journalisé à son tour
+fn filter_metadata(query: &AuditEventQuery) -> serde_json::Value {
+    serde_json::json!({
+        "event_type": query.event_type,
+        "actor_id": query.actor_id,
+        "target_id": query.target_id,
+        "from": query.from,
+        "to": query.to,
+    })
+}
@@ -3,103 +3,158 @@
 use std::sync::Arc;
 
 use auth_manager_api::{
-    LoginRequest, PublicLoginResponse, RefreshTokenRequest, RefreshTokenResponse, RegisterRequest,
-    UserResponse,
+    AccountDeletionResponse, ConfirmAccountDeletionRequest, LoginRequest, PasswordPolicy,
+    PublicLoginResponse, RefreshTokenRequest, RefreshTokenResponse, RegisterRequest,
+    RevokeSessionsRequest, UnlockAccountRequest,
 };
 use axum::{
     Json,
-    extract::Extension,
-    http::{HeaderMap, HeaderValue},
+    extract::State,
+    http::{HeaderMap, header},
+    response::{IntoResponse, Response},
 };
 
-use crate::auth::extractors::AuthClaims;
-use crate::auth::services::AuthService;
+use crate::auth::client_info::ClientInfo;
+use crate::auth::cookie::CookieConfig;
+use crate::auth::extractors::PasswordChangeClaims;
+use crate::auth::services::{AuthService, Registration};
+use crate::blocking;
 use crate::error::AppError;
 use crate::response::AppResponse;
 
 /// POST /auth/register
 /// Inscription d'un nouvel utilisateur
+///
+/// En mode anti-énumération, la réponse est toujours `202` (email existant ou non)
 pub async fn register(
+    State(auth_service): State<Arc<AuthService>>,
+    client: ClientInfo,
     Json(payload): Json<RegisterRequest>,
-) -> Result<AppResponse<UserResponse>, AppError> {
-    let user = AuthService::register(payload)?;
-    Ok(AppResponse::created(user))
+) -> Result<Response, AppError> {
+    let registration =
+        blocking::run_hashing(move || auth_service.register(&payload, &client)).await?;
+    Ok(match registration {
+        Registration::Created(user) => AppResponse::created(user).into_response(),
+        Registration::Accepted => AppResponse::accepted(serde_json::json!({
+            "message": "Registration received, check your email to continue"
+        }))
+        .into_response(),
+    })
+}
+
+/// GET /auth/password-policy
+/// Règles appliquées aux nouveaux mots de passe, pour la validation côté client
+pub async fn get_password_policy(
+    State(auth_service): State<Arc<AuthService>>,
+) -> AppResponse<PasswordPolicy> {
+    AppResponse::ok(auth_service.password_policy().clone())
 }
 
 /// POST /auth/login
 /// Connexion d'un utilisateur
 pub async fn login(
-    Extension(auth_service): Extension<Arc<AuthService>>,
-    headers: HeaderMap,
+    State(auth_service): State<Arc<AuthService>>,
+    State(cookies): State<Arc<CookieConfig>>,
+    client: ClientInfo,
     Json(payload): Json<LoginRequest>,
 ) -> Result<AppResponse<PublicLoginResponse>, AppError> {
-    // Récupère le User-Agent s'il existe
-    let user_agent = headers
-        .get("user-agent")
-        .and_then(|h| h.to_str().ok())
-        .map(str::to_owned);
+    let (response, refresh_hash) =
+        blocking::run_hashing(move || auth_service.login(&payload, &client)).await?;
 
-    let (response, refresh_hash) = auth_service.login(&payload, user_agent)?;
-
-    // Refresh token hash en cookie HttpOnly uniquement — jamais dans le body
-    let cookie_val = format!(
-        "refresh_token={refresh_hash}; HttpOnly; Secure; SameSite=None; Path=/auth/refresh"
-    );
-    let mut out_headers = HeaderMap::new();
-    out_headers.insert(
-        axum::http::header::SET_COOKIE,
-        HeaderValue::from_str(&cookie_val)
-            .map_err(|_| AppError::internal("Failed to set cookie"))?,
-    );
-
-    Ok(AppResponse::ok(PublicLoginResponse::from(response)).with_headers(out_headers))
+    Ok(AppResponse::ok(PublicLoginResponse::from(response))
+        .with_headers(refresh_cookie(&cookies, &refresh_hash)?))
 }
 
 /// POST /auth/refresh
 /// Rafraîchissement des tokens
 pub async fn refresh_token(
-    Extension(auth_service): Extension<Arc<AuthService>>,
+    State(auth_service): State<Arc<AuthService>>,
+    State(cookies): State<Arc<CookieConfig>>,
     headers: HeaderMap,
 ) -> Result<AppResponse<RefreshTokenResponse>, AppError> {
     // Read refresh_token hash from Cookie header
-    let raw_cookie = headers
-        .get(axum::http::header::COOKIE)
-        .and_then(|h| h.to_str().ok())
-        .ok_or_else(|| AppError::validation("Missing Cookie header"))?;
+    if !headers.contains_key(header::COOKIE) {
+        return Err(AppError::validation("Missing Cookie header"));
+    }
+    let refresh_hash = cookies
+        .find(&headers)
+        .ok_or_else(|| AppError::validation(format!("Missing {} cookie", cookies.name)))?;
 
-    let refresh_hash = raw_cookie
-        .split(';')
-        .find_map(|kv| {
-            let mut it = kv.trim().splitn(2, '=');
-            match (it.next(), it.next()) {
-                (Some("refresh_token"), Some(v)) => Some(v.trim().to_string()),
-                _ => None,
-            }
+    let (response, new_refresh_hash) = blocking::run(move || {
+        auth_service.refresh_token(&RefreshTokenRequest {
+            refresh_token: refresh_hash,
         })
-        .ok_or_else(|| AppError::validation("Missing refresh_token cookie"))?;
+    })
+    .await?;
 
-    let (response, new_refresh_hash) = auth_service.refresh_token(&RefreshTokenRequest {
-        refresh_token: refresh_hash,
-    })?;
+    Ok(AppResponse::ok(response).with_headers(refresh_cookie(&cookies, &new_refresh_hash)?))
+}
 
-    let cookie_val = format!(
-        "refresh_token={new_refresh_hash}; HttpOnly; Secure; SameSite=None; Path=/auth/refresh"
-    );
+/// En-tête `Set-Cookie` du refresh token.
+/// Refresh token hash en cookie `HttpOnly` uniquement — jamais dans le body
+pub(crate) fn refresh_cookie(
+    cookies: &CookieConfig,
+    refresh_hash: &str,
+) -> Result<HeaderMap, AppError> {
     let mut out_headers = HeaderMap::new();
     out_headers.insert(
-        axum::http::header::SET_COOKIE,
-        HeaderValue::from_str(&cookie_val)
-            .map_err(|_| AppError::internal("Failed to set cookie"))?,
+        header::SET_COOKIE,
+        cookies.refresh_cookie(refresh_hash).header_value()?,
     );
-
-    Ok(AppResponse::ok(response).with_headers(out_headers))
+    Ok(out_headers)
 }
 
 /// POST /auth/logout
-/// Déconnexion (optionnel)
-pub async fn logout(claims: AuthClaims) -> Result<AppResponse<serde_json::Value>, AppError> {
-    AuthService::logout(claims.sub)?;
+/// Déconnexion (optionnel), possible aussi avec un mot de passe expiré
+pub async fn logout(
+    State(auth_service): State<Arc<AuthService>>,
+    claims: PasswordChangeClaims,
+    client: ClientInfo,
+) -> Result<AppResponse<serde_json::Value>, AppError> {
+    blocking::run(move || auth_service.logout(claims.sub, &client)).await?;
     Ok(AppResponse::ok(serde_json::json!({
         "message": "Logged out successfully"
     })))
 }
+
+/// POST /auth/unlock
+/// Déverrouille un compte via le lien reçu par email
+pub async fn unlock_account(
+    State(auth_service): State<Arc<AuthService>>,
+    client: ClientInfo,
+    Json(payload): Json<UnlockAccountRequest>,
+) -> Result<AppResponse<serde_json::Value>, AppError> {
+    blocking::run(move || auth_service.unlock_account_with_token(&payload.token, &client)).await?;
+    Ok(AppResponse::ok(serde_json::json!({
+        "message": "Account unlocked"
+    })))
+}
+
+/// POST /auth/not-me
+/// Lien « ce n'était pas moi » d'un email de connexion suspecte : révoque toutes les sessions
+pub async fn not_me(
+    State(auth_service): State<Arc<AuthService>>,
+    client: ClientInfo,
+    Json(payload): Json<RevokeSessionsRequest>,
+) -> Result<AppResponse<serde_json::Value>, AppError> {
+    blocking::run(move || auth_service.revoke_sessions_with_token(&payload.token, &client)).await?;
+    Ok(AppResponse::ok(serde_json::json!({
+        "message": "All sessions have been signed out, please change your password"
+    })))
+}
+
+/// POST /auth/confirm-deletion
+/// Lien de l'email de confirmation : programme la suppression d'un compte sans mot de passe
+pub async fn confirm_account_deletion(
+    State(auth_service): State<Arc<AuthService>>,
+    client: ClientInfo,
+    Json(payload): Json<ConfirmAccountDeletionRequest>,
+) -> Result<AppResponse<AccountDeletionResponse>, AppError> {
+    let deletion_scheduled_at = blocking::run(move || {
+        auth_service.confirm_account_deletion_with_token(&payload.token, &client)
+    })
+    .await?;
+    Ok(AppResponse::accepted(AccountDeletionResponse {
+        deletion_scheduled_at,
+    }))
+}
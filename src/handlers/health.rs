@@ -1,9 +1,27 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+
 use crate::response::AppResponse;
+use crate::shutdown::Shutdown;
 
 /// GET /health
 /// Simple healthcheck endpoint
-pub async fn health() -> AppResponse<serde_json::Value> {
+///
+/// Pendant l'arrêt gracieux, répond `503` avec `"status": "draining"` pour que le
+/// load balancer cesse d'envoyer du trafic
+pub async fn health(State(shutdown): State<Shutdown>) -> Response {
+    if shutdown.is_draining() {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            AppResponse::ok(serde_json::json!({ "status": "draining" })),
+        )
+            .into_response();
+    }
     AppResponse::ok(serde_json::json!({
         "status": "ok"
     }))
+    .into_response()
 }
@@ -1,35 +1,54 @@
-use axum::{Json, extract::Path};
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+};
 use uuid::Uuid;
 
-use crate::auth::extractors::AuthClaims;
+use crate::auth::client_info::ClientInfo;
+use crate::auth::cookie::CookieConfig;
+use crate::auth::extractors::{AuthClaims, PasswordChangeClaims};
 use crate::auth::services::AuthService;
+use crate::blocking;
 use crate::error::AppError;
+use crate::handlers::auth::refresh_cookie;
 use crate::response::AppResponse;
-use auth_manager_api::{ChangePasswordRequest, UserResponse};
+use auth_manager_api::{
+    AccountDeletionResponse, AccountExport, ChangePasswordRequest, DeleteAccountRequest,
+    LoginHistoryPage, LoginHistoryQuery, UserResponse,
+};
 
 /// GET /users/me
 /// Récupère le profil de l'utilisateur courant
-pub async fn get_current_user(claims: AuthClaims) -> Result<AppResponse<UserResponse>, AppError> {
-    let user = AuthService::get_current_user(claims.sub)?;
+pub async fn get_current_user(
+    State(auth_service): State<Arc<AuthService>>,
+    claims: AuthClaims,
+) -> Result<AppResponse<UserResponse>, AppError> {
+    let user = blocking::run(move || auth_service.get_current_user(claims.sub)).await?;
     Ok(AppResponse::ok(user))
 }
 
 /// GET /users/:id
 /// Récupère un utilisateur par son ID
 pub async fn get_user_by_id(
+    State(auth_service): State<Arc<AuthService>>,
     Path(user_id): Path<Uuid>,
     _claims: AuthClaims,
 ) -> Result<AppResponse<UserResponse>, AppError> {
-    let user = AuthService::get_user_by_id(user_id)?;
+    let user = blocking::run(move || auth_service.get_user_by_id(user_id)).await?;
     Ok(AppResponse::ok(user))
 }
 
 /// DELETE /users/:id
-/// Supprime un utilisateur
+/// Programme la suppression du compte (délai de grâce, annulable en se reconnectant)
 pub async fn delete_user(
+    State(auth_service): State<Arc<AuthService>>,
     Path(user_id): Path<Uuid>,
     claims: AuthClaims,
-) -> Result<AppResponse<()>, AppError> {
+    client: ClientInfo,
+    Json(payload): Json<DeleteAccountRequest>,
+) -> Result<AppResponse<AccountDeletionResponse>, AppError> {
     // Vérifier que l'utilisateur supprime son propre compte
     if claims.sub != user_id {
         return Err(AppError::unauthorized(
@@ -37,15 +56,67 @@ pub async fn delete_user(
         ));
     }
 
-    AuthService::delete_user(user_id)?;
-    Ok(AppResponse::no_content())
+    let deletion_scheduled_at = blocking::run_hashing(move || {
+        auth_service.request_account_deletion(user_id, &payload.password, &client)
+    })
+    .await?;
+    Ok(AppResponse::accepted(AccountDeletionResponse {
+        deletion_scheduled_at,
+    }))
+}
+
+/// POST /users/:id/deletion-confirmation
+/// Compte sans mot de passe : envoie par email un lien confirmant la suppression
+pub async fn send_deletion_confirmation(
+    State(auth_service): State<Arc<AuthService>>,
+    Path(user_id): Path<Uuid>,
+    claims: AuthClaims,
+) -> Result<AppResponse<serde_json::Value>, AppError> {
+    if claims.sub != user_id {
+        return Err(AppError::unauthorized(
+            "You can only delete your own account",
+        ));
+    }
+
+    blocking::run(move || auth_service.send_account_deletion_confirmation(user_id)).await?;
+    Ok(AppResponse::accepted(serde_json::json!({
+        "message": "Check your email to confirm the deletion"
+    })))
+}
+
+/// GET /users/me/export
+/// Exporte toutes les données de l'utilisateur courant (RGPD)
+pub async fn export_current_user(
+    State(auth_service): State<Arc<AuthService>>,
+    claims: AuthClaims,
+) -> Result<AppResponse<AccountExport>, AppError> {
+    let export = blocking::run(move || auth_service.export_account(claims.sub)).await?;
+    Ok(AppResponse::ok(export))
+}
+
+/// GET /users/me/login-history
+/// Historique paginé des connexions de l'utilisateur courant
+pub async fn get_login_history(
+    State(auth_service): State<Arc<AuthService>>,
+    claims: AuthClaims,
+    Query(query): Query<LoginHistoryQuery>,
+) -> Result<AppResponse<LoginHistoryPage>, AppError> {
+    let page = blocking::run(move || {
+        auth_service.login_history(claims.sub, query.cursor.as_deref(), query.limit)
+    })
+    .await?;
+    Ok(AppResponse::ok(page))
 }
 
 /// POST /users/:id/change-password
-/// Change le mot de passe de l'utilisateur
+/// Change le mot de passe de l'utilisateur (y compris expiré) et révoque ses autres
+/// sessions ; la session courante reçoit un nouveau refresh token
 pub async fn change_password(
+    State(auth_service): State<Arc<AuthService>>,
+    State(cookies): State<Arc<CookieConfig>>,
     Path(user_id): Path<Uuid>,
-    claims: AuthClaims,
+    claims: PasswordChangeClaims,
+    client: ClientInfo,
     Json(payload): Json<ChangePasswordRequest>,
 ) -> Result<AppResponse<serde_json::Value>, AppError> {
     // Vérifier que l'utilisateur change son propre password
@@ -55,8 +126,17 @@ pub async fn change_password(
         ));
     }
 
-    AuthService::change_password(user_id, &payload.old_password, &payload.new_password)?;
+    let refresh_hash = blocking::run_hashing(move || {
+        auth_service.change_password(
+            user_id,
+            &payload.old_password,
+            &payload.new_password,
+            &client,
+        )
+    })
+    .await?;
     Ok(AppResponse::ok(serde_json::json!({
         "message": "Password changed successfully"
-    })))
+    }))
+    .with_headers(refresh_cookie(&cookies, &refresh_hash)?))
 }
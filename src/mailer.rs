@@ -0,0 +1,65 @@
+// src/mailer.rs
+
+/// A plain-text transactional email.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmailMessage {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("Failed to send email: {0}")]
+pub struct MailError(pub String);
+
+/// Outgoing email transport.
+///
+/// Sending is synchronous and best effort from the caller's point of view:
+/// services log failures rather than failing the request.
+pub trait Mailer: Send + Sync {
+    /// Sends `message`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`MailError`] if the transport rejects the message.
+    fn send(&self, message: &EmailMessage) -> Result<(), MailError>;
+}
+
+/// Writes emails to the log instead of sending them (local development, and the
+/// default until a real transport is configured).
+#[derive(Debug, Default)]
+pub struct LogMailer;
+
+impl Mailer for LogMailer {
+    fn send(&self, message: &EmailMessage) -> Result<(), MailError> {
+        tracing::info!(
+            to = %message.to,
+            subject = %message.subject,
+            "📧 Email (not sent, log mailer):\n{}",
+            message.body
+        );
+        Ok(())
+    }
+}
+
+/// Keeps sent emails in memory so tests can inspect them.
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub struct MemoryMailer {
+    sent: std::sync::Mutex<Vec<EmailMessage>>,
+}
+
+#[cfg(test)]
+impl MemoryMailer {
+    pub fn sent(&self) -> Vec<EmailMessage> {
+        self.sent.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+impl Mailer for MemoryMailer {
+    fn send(&self, message: &EmailMessage) -> Result<(), MailError> {
+        self.sent.lock().unwrap().push(message.clone());
+        Ok(())
+    }
+}
@@ -1,35 +1,149 @@
 mod app;
 mod auth;
+mod blocking;
+mod cli;
 mod config;
+mod cors;
 mod db;
 mod error;
 mod handlers;
+mod mailer;
+mod maintenance;
 mod response;
+mod shutdown;
+mod state;
+
+use std::future::IntoFuture;
+use std::sync::Arc;
 
 use app::build_router;
+use auth::rate_limit::{PostgresRateLimitStore, RateLimitStore};
+use clap::Parser;
+use cli::{Cli, Command};
 use config::Config;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-pub fn setup_logging() {
+/// Logs go to stdout for the service; a one-shot command keeps stdout for its result
+/// and only reports warnings, on stderr.
+pub fn setup_logging(serving: bool) {
     let filter = tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| {
         // Si RUST_LOG n'est pas défini, utiliser ces règles par défaut
-        tracing_subscriber::EnvFilter::new(
-            "info,auth_manager=debug,hyper_util=warn,tower_http=info",
-        )
+        tracing_subscriber::EnvFilter::new(if serving {
+            "info,auth_manager=debug,hyper_util=warn,tower_http=info"
+        } else {
+            "warn"
+        })
     });
 
-    tracing_subscriber::registry()
-        .with(filter)
-        .with(tracing_subscriber::fmt::layer())
-        .init();
+    let registry = tracing_subscriber::registry().with(filter);
+    if serving {
+        registry.with(tracing_subscriber::fmt::layer()).init();
+    } else {
+        registry
+            .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
+            .init();
+    }
+}
+
+type Storage = (db::store::Repositories, Option<Arc<dyn RateLimitStore>>);
+
+/// Initializes storage (database connection pool unless running in memory), and the
+/// database-backed login counters when `RATE_LIMIT_STORE` asks for them.
+/// Refuses to go on while the schema is missing migrations.
+fn open_storage(config: &Config) -> anyhow::Result<Storage> {
+    match config.storage {
+        config::StorageBackend::Postgres => {
+            tracing::info!("🔌 Initializing database connection pool...");
+            let pool = db::connection::build_pool(&config.database_url).inspect_err(|e| {
+                tracing::error!("❌ Failed to initialize database connection pool: {:#}", e);
+                tracing::error!("   This is usually caused by:");
+                tracing::error!("   1. DATABASE_URL is incorrect");
+                tracing::error!("   2. Database is not accessible from Lambda");
+                tracing::error!("   3. Credentials are invalid");
+                tracing::error!("   4. SSL/TLS issues");
+            })?;
+            tracing::info!("✅ Database connection pool initialized");
+            prepare_schema(
+                config,
+                || db::migrations::migrate_postgres(&pool),
+                || db::migrations::check_postgres(&pool),
+            )?;
+            Ok((
+                db::store::Repositories::postgres(&pool),
+                Some(Arc::new(PostgresRateLimitStore::new(pool))),
+            ))
+        }
+        #[cfg(feature = "sqlite")]
+        config::StorageBackend::Sqlite => {
+            tracing::info!("🔌 Opening SQLite database...");
+            let pool = db::sqlite::build_pool(&config.database_url).inspect_err(|e| {
+                tracing::error!("❌ Failed to open SQLite database: {:#}", e);
+            })?;
+            tracing::info!("✅ SQLite database opened");
+            prepare_schema(
+                config,
+                || db::migrations::migrate_sqlite(&pool),
+                || db::migrations::check_sqlite(&pool),
+            )?;
+            Ok((
+                db::store::Repositories::sqlite(&pool),
+                Some(Arc::new(db::sqlite::SqliteStore::new(pool))),
+            ))
+        }
+        config::StorageBackend::Memory => {
+            tracing::warn!("⚠️  In-memory storage: all data is lost when the server stops");
+            Ok((db::store::Repositories::memory(), None))
+        }
+    }
+}
+
+/// Applies pending migrations when `MIGRATE_ON_STARTUP` is set, then checks that none is left.
+fn prepare_schema(
+    config: &Config,
+    migrate: impl FnOnce() -> anyhow::Result<Vec<String>>,
+    check: impl FnOnce() -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    if config.migrate_on_startup {
+        log_applied(&migrate()?);
+    }
+    check().inspect_err(|e| tracing::error!("❌ {:#}", e))
+}
+
+/// `auth-manager migrate`: applies the pending migrations, then exits.
+fn migrate(config: &Config) -> anyhow::Result<()> {
+    let applied = match config.storage {
+        config::StorageBackend::Postgres => {
+            db::migrations::migrate_postgres(&db::connection::build_pool(&config.database_url)?)?
+        }
+        #[cfg(feature = "sqlite")]
+        config::StorageBackend::Sqlite => {
+            db::migrations::migrate_sqlite(&db::sqlite::build_pool(&config.database_url)?)?
+        }
+        config::StorageBackend::Memory => {
+            tracing::info!("In-memory storage has no migrations to run");
+            return Ok(());
+        }
+    };
+    log_applied(&applied);
+    Ok(())
+}
+
+fn log_applied(applied: &[String]) {
+    if applied.is_empty() {
+        tracing::info!("✅ Database schema is up to date");
+    } else {
+        tracing::info!("✅ Applied migrations: {}", applied.join(", "));
+    }
 }
 
 // ----------------- Main -----------------
 
 #[tokio::main]
 async fn main() -> Result<(), lambda_http::Error> {
+    let cli = Cli::parse();
+
     // Initialize logging for all environments
-    setup_logging();
+    setup_logging(cli.serves());
     tracing::info!("🚀 Starting auth-manager...");
 
     // Load configuration (auto-détecte l'environnement)
@@ -38,23 +152,110 @@ async fn main() -> Result<(), lambda_http::Error> {
         .inspect_err(|e| tracing::error!("❌ Failed to load configuration: {:#}", e))?;
     tracing::info!("✅ Configuration loaded successfully");
 
-    // Initialize database connection pool
-    tracing::info!("🔌 Initializing database connection pool...");
-    db::connection::init_pool_with_url(&config.database_url).inspect_err(|e| {
-        tracing::error!("❌ Failed to initialize database connection pool: {:#}", e);
-        tracing::error!("   This is usually caused by:");
-        tracing::error!("   1. DATABASE_URL is incorrect");
-        tracing::error!("   2. Database is not accessible from Lambda");
-        tracing::error!("   3. Credentials are invalid");
-        tracing::error!("   4. SSL/TLS issues");
-    })?;
-    tracing::info!("✅ Database connection pool initialized");
-
-    // Create JWT manager
+    if cli.print_config {
+        cli::print_config(&config).print(cli.json);
+        return Ok(());
+    }
+
+    let report = match cli.command {
+        None | Some(Command::Serve) => return serve(config).await,
+        Some(Command::Migrate) => {
+            migrate(&config).inspect_err(|e| tracing::error!("❌ Migration failed: {:#}", e))?;
+            return Ok(());
+        }
+        Some(Command::Config) => cli::print_config(&config),
+        Some(Command::RotateJwtSecret) => cli::rotate_jwt_secret(config.jwt_expiration_hours),
+        Some(Command::Maintenance) => {
+            let (repositories, database_rate_limits) = open_storage(&config)?;
+            let auth_service = build_auth_service(
+                &config,
+                jwt_manager(&config),
+                &repositories,
+                database_rate_limits,
+            )?;
+            cli::maintenance_report(&maintenance::run(&auth_service, &config.maintenance))
+        }
+        Some(Command::Admin(command)) => {
+            let (repositories, database_rate_limits) = open_storage(&config)?;
+            let jwt_manager = jwt_manager(&config);
+            let auth_service =
+                build_auth_service(&config, jwt_manager, &repositories, database_rate_limits)?
+                    // Une commande d'administration doit savoir si le compte a été créé
+                    .with_enumeration_protection(false);
+            cli::run_admin(command, &auth_service, &repositories)?
+        }
+    };
+    report.print(cli.json);
+    Ok(())
+}
+
+fn jwt_manager(config: &Config) -> auth::jwt::JwtManager {
     let jwt_manager = auth::jwt::JwtManager::new(&config.jwt_secret, config.jwt_expiration_hours);
+    match &config.jwt_secret_previous {
+        Some(previous) => jwt_manager.with_previous_secret(previous),
+        None => jwt_manager,
+    }
+}
+
+/// Configures password hashing and builds the auth service from the configuration.
+fn build_auth_service(
+    config: &Config,
+    jwt_manager: auth::jwt::JwtManager,
+    repositories: &db::store::Repositories,
+    database_rate_limits: Option<Arc<dyn RateLimitStore>>,
+) -> anyhow::Result<auth::services::AuthService> {
+    auth::password::PasswordManager::configure(config.argon2, config.password_peppers.clone())
+        .inspect_err(|e| tracing::error!("❌ Invalid password hashing settings: {:#}", e))?;
+    if let Some(max_concurrent) = config.password_hash_concurrency {
+        blocking::limit_hashing(max_concurrent);
+    }
+
+    let email_policy = config
+        .email_policy()
+        .inspect_err(|e| tracing::error!("❌ Failed to load email policy: {:#}", e))?;
+    let breach_checker = config
+        .breach_checker()
+        .inspect_err(|e| tracing::error!("❌ Failed to load breached passwords: {:#}", e))?;
+    let mut auth_service = auth::services::AuthService::new(jwt_manager, repositories.clone())
+        .with_email_policy(email_policy)
+        .with_rate_limiter(config.rate_limiter(database_rate_limits))
+        .with_enumeration_protection(config.enumeration_protection)
+        .with_lockout_policy(config.lockout_policy.clone())
+        .with_password_policy(config.password_policy.clone())
+        .with_password_history(config.password_history_size)
+        .with_password_max_age(config.password_max_age)
+        .with_risk_engine(config.risk_engine())
+        .with_mailer(Arc::new(mailer::LogMailer), config.frontend_url.clone());
+    if let Some(checker) = breach_checker {
+        auth_service = auth_service.with_breach_checker(checker);
+    }
+    Ok(auth_service)
+}
+
+/// Runs the HTTP server locally, the Lambda handler on AWS.
+async fn serve(config: Config) -> Result<(), lambda_http::Error> {
+    let (repositories, database_rate_limits) = open_storage(&config)?;
+
+    // Create JWT manager and auth service
+    let jwt_manager = jwt_manager(&config);
+    let auth_service = build_auth_service(
+        &config,
+        jwt_manager.clone(),
+        &repositories,
+        database_rate_limits,
+    )?;
 
     // Build router
-    let app = build_router(jwt_manager);
+    let shutdown = shutdown::Shutdown::default();
+    let state = state::AppState::new(auth_service, jwt_manager, repositories)
+        .with_cookie_config(config.cookie.clone())
+        .with_shutdown(shutdown.clone());
+    let auth_service = Arc::clone(&state.auth_service);
+    let app = build_router(
+        state,
+        config.trusted_proxies.clone(),
+        config.cors_origins.clone(),
+    );
 
     // Run server based on environment (Local → HTTP server, Dev/Prod → Lambda)
     if !config.is_local() {
@@ -62,14 +263,47 @@ async fn main() -> Result<(), lambda_http::Error> {
             "☁️  Running in AWS Lambda mode ({})",
             config.environment.as_str()
         );
-        lambda_http::run(app).await
+        match config.lambda_handler {
+            config::LambdaHandler::Http => lambda_http::run(app).await,
+            config::LambdaHandler::Maintenance => {
+                let settings = config.maintenance;
+                lambda_http::lambda_runtime::run(lambda_http::service_fn(move |event| {
+                    maintenance::handle_scheduled_event(Arc::clone(&auth_service), settings, event)
+                }))
+                .await
+            }
+        }
     } else {
         tracing::info!("💻 Running in local HTTP server mode");
         let addr = format!("{}:{}", config.server_host, config.server_port);
         let listener = tokio::net::TcpListener::bind(&addr).await?;
         tracing::info!("🌐 Server listening on http://{}", addr);
-        axum::serve(listener, app).await?;
+        tokio::spawn(shutdown.clone().on_signal());
+        let maintenance_job =
+            maintenance::spawn(auth_service, config.maintenance, shutdown.clone());
+
+        // ConnectInfo fournit l'adresse du pair à l'extracteur `ClientInfo`
+        let server = axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+        )
+        .with_graceful_shutdown(shutdown.listener_closed());
+        // Tâche de maintenance en cours, puis travaux bloquants qui tiennent une connexion
+        let background = async {
+            if let Some(job) = maintenance_job
+                && let Err(e) = job.await
+            {
+                tracing::error!("Maintenance job failed: {e}");
+            }
+            blocking::drain().await;
+        };
+        shutdown
+            .run_server(config.shutdown, server.into_future(), background)
+            .await?;
 
+        // Plus aucune tâche ne tient de connexion : le pool se ferme avec les dernières
+        // références aux repositories, à la sortie de `main`
+        tracing::info!("👋 Server stopped");
         Ok(())
     }
 }
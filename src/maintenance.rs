@@ -0,0 +1,300 @@
+//! Tâche de maintenance : purge des sessions expirées, des tentatives de connexion
+//! au-delà de la rétention, des compteurs de rate limit clos et des comptes dont la
+//! suppression est due.
+//!
+//! En mode serveur elle tourne sur un intervalle Tokio ([`spawn`]), jusqu'à l'arrêt du
+//! serveur ; sur Lambda, une
+//! fonction dédiée (`LAMBDA_HANDLER=maintenance`) la déclenche sur un événement planifié
+//! ([`handle_scheduled_event`]). `auth-manager maintenance` la lance une fois.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use lambda_http::LambdaEvent;
+use serde::Serialize;
+use tokio::task::JoinHandle;
+
+use crate::auth::services::AuthService;
+use crate::error::AppError;
+use crate::shutdown::Shutdown;
+
+/// `CloudWatch` namespace of the metrics emitted on Lambda.
+const METRICS_NAMESPACE: &str = "AuthManager";
+
+/// Schedule and retention periods of the maintenance job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaintenanceConfig {
+    /// Time between two runs in server mode (`None`: no background task)
+    pub interval: Option<Duration>,
+    /// How long expired sessions are kept (still listed in the account export)
+    pub expired_session_retention: chrono::Duration,
+    /// How long login attempts are kept (login history, admin search)
+    pub login_attempt_retention: chrono::Duration,
+}
+
+impl Default for MaintenanceConfig {
+    fn default() -> Self {
+        Self {
+            interval: Some(Duration::from_secs(3600)),
+            expired_session_retention: chrono::Duration::zero(),
+            login_attempt_retention: chrono::Duration::days(90),
+        }
+    }
+}
+
+/// Rows deleted by one run. A failed step counts zero and increments `failed_steps`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct MaintenanceReport {
+    pub expired_sessions: usize,
+    pub login_attempts: usize,
+    pub rate_limit_buckets: usize,
+    pub purged_accounts: usize,
+    pub failed_steps: usize,
+}
+
+impl MaintenanceReport {
+    /// Logs the counts. On Lambda, also prints them in the `CloudWatch` embedded metric
+    /// format, which turns the log line into metrics without an API call.
+    pub fn emit_metrics(&self, lambda: bool) {
+        tracing::info!(
+            expired_sessions = self.expired_sessions,
+            login_attempts = self.login_attempts,
+            rate_limit_buckets = self.rate_limit_buckets,
+            purged_accounts = self.purged_accounts,
+            failed_steps = self.failed_steps,
+            "🧹 Maintenance run finished"
+        );
+        if lambda {
+            println!("{}", self.to_emf(Utc::now().timestamp_millis()));
+        }
+    }
+
+    /// The report as an embedded metric format document.
+    fn to_emf(&self, timestamp_ms: i64) -> serde_json::Value {
+        let names = [
+            "expired_sessions",
+            "login_attempts",
+            "rate_limit_buckets",
+            "purged_accounts",
+            "failed_steps",
+        ];
+        let mut document = serde_json::json!({
+            "_aws": {
+                "Timestamp": timestamp_ms,
+                "CloudWatchMetrics": [{
+                    "Namespace": METRICS_NAMESPACE,
+                    "Dimensions": [["Job"]],
+                    "Metrics": names
+                        .iter()
+                        .map(|name| serde_json::json!({ "Name": name, "Unit": "Count" }))
+                        .collect::<Vec<_>>(),
+                }],
+            },
+            "Job": "maintenance",
+        });
+        if let (Some(document), serde_json::Value::Object(counts)) =
+            (document.as_object_mut(), serde_json::json!(self))
+        {
+            document.extend(counts);
+        }
+        document
+    }
+}
+
+/// Runs every step once. A failing step is logged and the others still run.
+pub fn run(service: &AuthService, config: &MaintenanceConfig) -> MaintenanceReport {
+    let mut report = MaintenanceReport::default();
+    let mut step = |name: &str, result: Result<usize, AppError>| match result {
+        Ok(count) => count,
+        Err(e) => {
+            tracing::error!("Maintenance step {name} failed: {e}");
+            report.failed_steps += 1;
+            0
+        }
+    };
+
+    let expired_sessions = step(
+        "expired_sessions",
+        service.purge_expired_sessions(config.expired_session_retention),
+    );
+    let login_attempts = step(
+        "login_attempts",
+        service.prune_login_attempts(config.login_attempt_retention),
+    );
+    let rate_limit_buckets = step("rate_limit_buckets", service.prune_rate_limits());
+    let purged_accounts = step("purged_accounts", service.purge_due_deletions());
+
+    MaintenanceReport {
+        expired_sessions,
+        login_attempts,
+        rate_limit_buckets,
+        purged_accounts,
+        ..report
+    }
+}
+
+/// Runs the job on the blocking pool.
+async fn run_blocking(
+    service: Arc<AuthService>,
+    config: MaintenanceConfig,
+) -> Result<MaintenanceReport, AppError> {
+    crate::blocking::run(move || Ok(run(&service, &config))).await
+}
+
+/// Starts the periodic task of the HTTP server (first run right away). It stops once
+/// `shutdown` begins, after the run in progress. Returns `None` when
+/// `MAINTENANCE_INTERVAL_SECS=0`.
+pub fn spawn(
+    service: Arc<AuthService>,
+    config: MaintenanceConfig,
+    shutdown: Shutdown,
+) -> Option<JoinHandle<()>> {
+    let every = config.interval?;
+    tracing::info!("🧹 Maintenance job scheduled every {}s", every.as_secs());
+    Some(tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(every);
+        // Après une pause (machine en veille), une seule exécution de rattrapage
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                biased;
+                () = shutdown.wait() => break,
+                _ = ticker.tick() => {}
+            }
+            match run_blocking(Arc::clone(&service), config).await {
+                Ok(report) => report.emit_metrics(false),
+                Err(e) => tracing::error!("Maintenance run failed: {e}"),
+            }
+        }
+    }))
+}
+
+/// Lambda handler for the scheduled (`EventBridge`) event; the event body is ignored.
+///
+/// # Errors
+///
+/// Returns an error if the run itself could not be executed; failed steps are only
+/// counted in the report.
+pub async fn handle_scheduled_event(
+    service: Arc<AuthService>,
+    config: MaintenanceConfig,
+    _event: LambdaEvent<serde_json::Value>,
+) -> Result<MaintenanceReport, lambda_http::Error> {
+    let report = run_blocking(service, config).await?;
+    report.emit_metrics(true);
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::jwt::JwtManager;
+    use crate::db::models::login_attempt::NewLoginAttempt;
+    use crate::db::models::refresh_token::NewRefreshToken;
+    use crate::db::models::user::NewUser;
+    use crate::db::store::test_repositories;
+
+    #[test]
+    fn run_purges_expired_sessions_and_keeps_recent_attempts() {
+        let repositories = test_repositories();
+        let service = AuthService::new(JwtManager::new("secret_key", 1), repositories.clone());
+        let user = repositories
+            .users
+            .create(&NewUser {
+                email: "keeper@example.com".to_string(),
+                username: "keeper".to_string(),
+                password_hash: None,
+                username_canonical: "keeper".to_string(),
+                password_pepper_version: None,
+            })
+            .unwrap();
+        for hours in [-2, 2] {
+            repositories
+                .refresh_tokens
+                .create(&NewRefreshToken {
+                    user_id: user.id,
+                    token_hash: format!("hash{hours}"),
+                    expires_at: Utc::now() + chrono::Duration::hours(hours),
+                    risk_flagged: false,
+                })
+                .unwrap();
+        }
+        repositories
+            .login_attempts
+            .create(&NewLoginAttempt {
+                user_id: Some(user.id),
+                ..NewLoginAttempt::default()
+            })
+            .unwrap();
+
+        let report = run(&service, &MaintenanceConfig::default());
+
+        assert_eq!(
+            report,
+            MaintenanceReport {
+                expired_sessions: 1,
+                ..MaintenanceReport::default()
+            }
+        );
+        assert_eq!(
+            repositories
+                .refresh_tokens
+                .find_by_user(user.id)
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(
+            repositories
+                .login_attempts
+                .find_all_by_user(user.id)
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn emf_document_carries_every_count() {
+        let report = MaintenanceReport {
+            expired_sessions: 3,
+            login_attempts: 7,
+            ..MaintenanceReport::default()
+        };
+        let emf = report.to_emf(1_700_000_000_000);
+
+        assert_eq!(
+            emf["_aws"]["CloudWatchMetrics"][0]["Namespace"],
+            METRICS_NAMESPACE
+        );
+        assert_eq!(
+            emf["_aws"]["CloudWatchMetrics"][0]["Metrics"]
+                .as_array()
+                .unwrap()
+                .len(),
+            5
+        );
+        assert_eq!(emf["expired_sessions"], 3);
+        assert_eq!(emf["login_attempts"], 7);
+        assert_eq!(emf["failed_steps"], 0);
+    }
+
+    #[tokio::test]
+    async fn spawned_job_stops_on_shutdown() {
+        let service = AuthService::new(JwtManager::new("secret_key", 1), test_repositories());
+        let shutdown = Shutdown::default();
+        let job = spawn(
+            Arc::new(service),
+            MaintenanceConfig::default(),
+            shutdown.clone(),
+        )
+        .unwrap();
+
+        shutdown.begin();
+        tokio::time::timeout(Duration::from_secs(5), job)
+            .await
+            .expect("the job should stop once shutdown begins")
+            .unwrap();
+    }
+}
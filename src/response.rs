@@ -68,10 +68,6 @@ where
     }
 
     /// 202 Accepted with data
-    #[expect(
-        dead_code,
-        reason = "Provided for HTTP completeness; no handler uses 202 yet"
-    )]
     pub fn accepted(data: T) -> Self {
         Self::new(ApiResponse::accepted(data))
     }
@@ -79,6 +75,13 @@ where
 
 impl AppResponse<()> {
     /// 204 No Content
+    #[cfg_attr(
+        not(test),
+        expect(
+            dead_code,
+            reason = "Provided for HTTP completeness; no handler uses 204 yet"
+        )
+    )]
     pub fn no_content() -> Self {
         Self::new(ApiResponse::no_content())
     }
@@ -0,0 +1,255 @@
+//! Arrêt gracieux du serveur local, sur SIGTERM ou SIGINT :
+//!
+//! 1. `/health` répond `draining` mais le serveur continue de répondre pendant
+//!    `SERVER_SHUTDOWN_DELAY_SECS`, le temps que le load balancer le retire ;
+//! 2. le listener est fermé : plus aucune nouvelle connexion ;
+//! 3. les requêtes en cours, puis la tâche de maintenance et les travaux bloquants, se
+//!    terminent avant une même échéance (`SERVER_SHUTDOWN_TIMEOUT_SECS`), après quoi le
+//!    pool de connexions est fermé.
+
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::watch;
+use tokio::time::Instant;
+
+/// Timing of the stop sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShutdownConfig {
+    /// Time spent still serving with `/health` at `draining`, before the listener closes
+    pub pre_stop_delay: Duration,
+    /// Time left, once the listener is closed, for in-flight requests and background jobs
+    pub drain_timeout: Duration,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self {
+            pre_stop_delay: Duration::ZERO,
+            drain_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Phase {
+    Serving,
+    /// `/health` reports `draining`, requests are still accepted
+    Draining,
+    /// The listener is closed
+    Stopping,
+}
+
+/// Shared shutdown state: cheap to clone, moves forward only.
+#[derive(Debug, Clone)]
+pub struct Shutdown(Arc<watch::Sender<Phase>>);
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self(Arc::new(watch::Sender::new(Phase::Serving)))
+    }
+}
+
+impl Shutdown {
+    /// Starts draining; later calls do nothing.
+    pub fn begin(&self) {
+        self.advance(Phase::Draining);
+    }
+
+    pub fn is_draining(&self) -> bool {
+        *self.0.borrow() >= Phase::Draining
+    }
+
+    /// Resolves once draining has started.
+    pub async fn wait(&self) {
+        self.reached(Phase::Draining).await;
+    }
+
+    /// Resolves once the listener must close, for `with_graceful_shutdown`.
+    pub fn listener_closed(&self) -> impl Future<Output = ()> + Send + 'static {
+        self.reached(Phase::Stopping)
+    }
+
+    fn advance(&self, phase: Phase) {
+        self.0.send_if_modified(|current| {
+            let moved = *current < phase;
+            *current = (*current).max(phase);
+            moved
+        });
+    }
+
+    fn reached(&self, phase: Phase) -> impl Future<Output = ()> + Send + 'static {
+        let mut current = self.0.subscribe();
+        async move {
+            // Le `Sender` vit aussi longtemps que le `Shutdown` : l'attente ne peut échouer
+            let _ = current.wait_for(|current| *current >= phase).await;
+        }
+    }
+
+    /// Starts draining on the first SIGTERM (container stop) or SIGINT (Ctrl+C).
+    pub async fn on_signal(self) {
+        let name = signal().await;
+        tracing::info!("🛑 {name} received, draining connections");
+        self.begin();
+    }
+
+    /// Runs `server` (built with `with_graceful_shutdown(shutdown.listener_closed())`)
+    /// through the stop sequence, then `background`. Connections and background jobs
+    /// share one deadline: whatever is left at `drain_timeout` is dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns the server's error if it stops on its own.
+    pub async fn run_server<S, B>(
+        &self,
+        config: ShutdownConfig,
+        server: S,
+        background: B,
+    ) -> std::io::Result<()>
+    where
+        S: Future<Output = std::io::Result<()>>,
+        B: Future<Output = ()>,
+    {
+        let mut server = std::pin::pin!(server);
+        tokio::select! {
+            result = &mut server => return result,
+            () = self.wait() => {}
+        }
+        // Le serveur continue de répondre (et `/health` de dire `draining`) le temps que
+        // le load balancer cesse d'envoyer du trafic
+        tokio::select! {
+            result = &mut server => return result,
+            () = tokio::time::sleep(config.pre_stop_delay) => {}
+        }
+
+        tracing::info!("🚪 Closing the listener, draining in-flight requests");
+        self.advance(Phase::Stopping);
+        let deadline = Instant::now() + config.drain_timeout;
+        match tokio::time::timeout_at(deadline, &mut server).await {
+            Ok(result) => result?,
+            Err(_) => {
+                tracing::warn!("⏱️  Drain timeout reached, closing the remaining connections");
+            }
+        }
+        if tokio::time::timeout_at(deadline, background).await.is_err() {
+            tracing::warn!("⏱️  Background jobs still running at the drain timeout");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+async fn signal() -> &'static str {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    match signal(SignalKind::terminate()) {
+        Ok(mut sigterm) => tokio::select! {
+            _ = sigterm.recv() => "SIGTERM",
+            _ = tokio::signal::ctrl_c() => "SIGINT",
+        },
+        Err(e) => {
+            tracing::warn!("⚠️  Cannot listen for SIGTERM: {e}");
+            let _ = tokio::signal::ctrl_c().await;
+            "SIGINT"
+        }
+    }
+}
+
+#[cfg(not(unix))]
+async fn signal() -> &'static str {
+    let _ = tokio::signal::ctrl_c().await;
+    "Ctrl+C"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::Router;
+    use axum::routing::get;
+    use std::future::IntoFuture;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    /// Sends `GET <path>` on a new connection and returns the raw response
+    async fn get_raw(addr: std::net::SocketAddr, path: &str) -> std::io::Result<String> {
+        let mut stream = TcpStream::connect(addr).await?;
+        stream
+            .write_all(
+                format!("GET {path} HTTP/1.1\r\nHost: test\r\nConnection: close\r\n\r\n")
+                    .as_bytes(),
+            )
+            .await?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await?;
+        Ok(response)
+    }
+
+    #[tokio::test]
+    async fn wait_resolves_once_draining_starts() {
+        let shutdown = Shutdown::default();
+        let waiter = tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move { shutdown.wait().await }
+        });
+        assert!(!shutdown.is_draining());
+
+        shutdown.begin();
+        tokio::time::timeout(Duration::from_secs(1), waiter)
+            .await
+            .expect("wait() should resolve after begin()")
+            .unwrap();
+        assert!(shutdown.is_draining());
+        // Déjà en cours : l'attente se termine immédiatement
+        shutdown.wait().await;
+    }
+
+    #[tokio::test]
+    async fn stop_sequence_serves_draining_health_then_shares_one_deadline() {
+        let shutdown = Shutdown::default();
+        let app = Router::new()
+            .route("/health", get(crate::handlers::health::health))
+            .route(
+                "/slow",
+                get(|| async {
+                    tokio::time::sleep(Duration::from_secs(30)).await;
+                    "done"
+                }),
+            )
+            .with_state(shutdown.clone());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = axum::serve(listener, app)
+            .with_graceful_shutdown(shutdown.listener_closed())
+            .into_future();
+        let config = ShutdownConfig {
+            pre_stop_delay: Duration::from_millis(300),
+            drain_timeout: Duration::from_millis(300),
+        };
+        let sequence = tokio::spawn({
+            let shutdown = shutdown.clone();
+            // Une tâche de fond qui ne finit jamais : elle n'a que le reste de l'échéance
+            async move {
+                shutdown
+                    .run_server(config, server, std::future::pending())
+                    .await
+            }
+        });
+        // Une requête en cours qui dépasse l'échéance
+        let slow = tokio::spawn(get_raw(addr, "/slow"));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let started = Instant::now();
+        shutdown.begin();
+        let health = get_raw(addr, "/health").await.unwrap();
+        assert!(health.starts_with("HTTP/1.1 503"), "{health}");
+        assert!(health.contains("draining"), "{health}");
+
+        sequence.await.unwrap().unwrap();
+        let elapsed = started.elapsed();
+        assert!(elapsed >= Duration::from_millis(550), "{elapsed:?}");
+        assert!(elapsed < Duration::from_millis(850), "{elapsed:?}");
+        assert!(get_raw(addr, "/health").await.is_err());
+        slow.abort();
+    }
+}
@@ -0,0 +1,83 @@
+// src/state.rs
+
+use std::sync::Arc;
+
+use axum::extract::FromRef;
+
+use crate::auth::cookie::CookieConfig;
+use crate::auth::jwt::JwtManager;
+use crate::auth::services::AuthService;
+use crate::db::store::Repositories;
+use crate::shutdown::Shutdown;
+
+/// État partagé par tous les handlers (axum `State`).
+///
+/// Les extracteurs n'en prennent que la partie utile via `FromRef`
+/// (`JwtManager` pour les claims, `Repositories` pour le contrôle admin,
+/// `CookieConfig` pour le cookie du refresh token, `Shutdown` pour `/health`).
+#[derive(Clone)]
+pub struct AppState {
+    pub auth_service: Arc<AuthService>,
+    pub jwt_manager: JwtManager,
+    pub repositories: Repositories,
+    pub cookies: Arc<CookieConfig>,
+    pub shutdown: Shutdown,
+}
+
+impl AppState {
+    pub fn new(
+        auth_service: AuthService,
+        jwt_manager: JwtManager,
+        repositories: Repositories,
+    ) -> Self {
+        Self {
+            auth_service: Arc::new(auth_service),
+            jwt_manager,
+            repositories,
+            cookies: Arc::new(CookieConfig::default()),
+            shutdown: Shutdown::default(),
+        }
+    }
+
+    /// Attributs du cookie du refresh token (par défaut : `refresh_token`, `Path=/auth/refresh`)
+    pub fn with_cookie_config(mut self, cookies: CookieConfig) -> Self {
+        self.cookies = Arc::new(cookies);
+        self
+    }
+
+    /// Signal d'arrêt partagé avec le serveur (`/health` répond alors `draining`)
+    pub fn with_shutdown(mut self, shutdown: Shutdown) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+}
+
+impl FromRef<AppState> for Arc<AuthService> {
+    fn from_ref(state: &AppState) -> Self {
+        Arc::clone(&state.auth_service)
+    }
+}
+
+impl FromRef<AppState> for JwtManager {
+    fn from_ref(state: &AppState) -> Self {
+        state.jwt_manager.clone()
+    }
+}
+
+impl FromRef<AppState> for Repositories {
+    fn from_ref(state: &AppState) -> Self {
+        state.repositories.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<CookieConfig> {
+    fn from_ref(state: &AppState) -> Self {
+        Arc::clone(&state.cookies)
+    }
+}
+
+impl FromRef<AppState> for Shutdown {
+    fn from_ref(state: &AppState) -> Self {
+        state.shutdown.clone()
+    }
+}